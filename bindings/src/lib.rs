@@ -0,0 +1,8 @@
+//! Links the JavaScript bindings (wasm feature) or the C functions (ffi feature) of
+//! eliassam_chess_lib into a shared library. Build it from this directory with
+//! `wasm-pack build --target web --features wasm` or `cargo build --release --features ffi`.
+
+#[cfg(feature = "ffi")]
+pub use eliassam_chess_lib::ffi::*;
+#[cfg(feature = "wasm")]
+pub use eliassam_chess_lib::wasm::*;
@@ -0,0 +1,291 @@
+//! Play chess in the terminal, against a friend or the engine:
+//!
+//!     cargo run --release --example play
+//!
+//! Type a move in standard algebraic notation ("Nf3", "exd5", "O-O", "e8=Q") or coordinate
+//! notation ("g1f3", "e7e8q"), or one of the commands that "help" lists.
+
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use eliassam_chess_lib::engine::find_best_move_timed;
+use eliassam_chess_lib::{Color, Game, GameState, Move, PieceRole};
+
+const HELP: &str = "\
+Moves: Nf3, exd5, O-O, e8=Q or g1f3, e7e8q
+Commands:
+  moves <square>   the squares the piece on the square can move to
+  undo             take back the last move
+  fen              print the position as a FEN string
+  load <fen>       set up the position of a FEN string
+  engine <ms>      let the engine reply to every move, thinking <ms> milliseconds
+  engine off       stop the engine
+  resign           give up the game
+  help             print this
+  quit             leave";
+
+/// A line typed by the player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    /// A move in either notation, checked against the position when it is played.
+    Move(String),
+    Moves(String),
+    Undo,
+    Fen,
+    Load(String),
+    /// Let the engine reply with this much time per move, or stop it with None.
+    Engine(Option<Duration>),
+    Resign,
+    Help,
+    Quit,
+}
+
+/// Reads a line typed by the player. Anything that isn't a command is taken to be a move.
+fn parse_command(line: &str) -> Result<Command, String> {
+    let line = line.trim();
+    let (word, argument) = line.split_once(' ').map_or((line, ""), |(word, argument)| (word, argument.trim()));
+    let needs_argument = |what: &str| -> Result<String, String> {
+        if argument.is_empty() {return Err(format!("{} needs {}", word, what));}
+        return Ok(argument.to_string());
+    };
+    return match word {
+        "" => Err("type a move, or help".to_string()),
+        "moves" => Ok(Command::Moves(needs_argument("a square, like moves e2")?)),
+        "undo" => Ok(Command::Undo),
+        "fen" => Ok(Command::Fen),
+        "load" => Ok(Command::Load(needs_argument("a FEN string")?)),
+        "engine" => match needs_argument("a time in milliseconds, or off")?.as_str() {
+            "off" => Ok(Command::Engine(None)),
+            millis => millis.parse::<u64>()
+                .map(|millis| Command::Engine(Some(Duration::from_millis(millis))))
+                .map_err(|_| format!("{} isn't a number of milliseconds", millis))
+        },
+        "resign" => Ok(Command::Resign),
+        "help" => Ok(Command::Help),
+        "quit" | "exit" => Ok(Command::Quit),
+        _ if argument.is_empty() => Ok(Command::Move(word.to_string())),
+        _ => Err(format!("unknown command {}, type help for the commands", word))
+    };
+}
+
+/// Finds the legal move written in standard algebraic or coordinate notation. Check and mate
+/// signs are optional, and "0-0" works for castling as well.
+fn find_move(game: &Game, text: &str) -> Result<Move, String> {
+    let strip = |san: &str| -> String {san.trim_end_matches(['+', '#', '!', '?']).replace('0', "O")};
+    let wanted = strip(text);
+    let moves = game.get_all_possible_moves();
+    if let Some(mv) = moves.iter().find(|mv| mv.to_string() == text.to_lowercase()) {return Ok(*mv);}
+    if let Some(mv) = moves.iter().find(|mv| game.to_san(mv).is_some_and(|san| strip(&san) == wanted)) {return Ok(*mv);}
+    if game.get_game_state() == GameState::Checkmate || game.get_game_state() == GameState::Stalemate {
+        return Err("the game is over".to_string());
+    }
+    return Err(format!("{} isn't a legal move here", text));
+}
+
+/// Draws the board with Unicode pieces, White at the bottom.
+fn render(game: &Game) -> String {
+    let mut text = String::new();
+    for (row, squares) in game.get_board().iter().enumerate() {
+        let symbols: Vec<String> = squares.iter().map(|piece| match piece {
+            Some(piece) => symbol(piece.role, piece.color).to_string(),
+            None => "·".to_string()
+        }).collect();
+        text.push_str(&format!("{} {}\n", 8 - row, symbols.join(" ")));
+    }
+    text.push_str("  a b c d e f g h");
+    return text;
+}
+
+fn symbol(role: PieceRole, color: Color) -> char {
+    let symbols = match color {
+        Color::White => ['♙', '♖', '♘', '♗', '♕', '♔'],
+        Color::Black => ['♟', '♜', '♞', '♝', '♛', '♚']
+    };
+    return symbols[match role {
+        PieceRole::Pawn => 0,
+        PieceRole::Rook => 1,
+        PieceRole::Knight => 2,
+        PieceRole::Bishop => 3,
+        PieceRole::Queen => 4,
+        PieceRole::King => 5
+    }];
+}
+
+/// What happened after a move, or an empty string if the game goes on without check.
+fn state_message(game: &Game) -> String {
+    let mover = if game.get_turn_color() == Color::White {"Black"} else {"White"};
+    return match game.get_game_state() {
+        GameState::Checkmate => format!("Checkmate, {} wins", mover),
+        GameState::Stalemate => "Stalemate, the game is drawn".to_string(),
+        GameState::Check => "Check".to_string(),
+        GameState::InProgress => String::new()
+    };
+}
+
+/// The game being played and who plays it.
+struct Session {
+    game: Game,
+    /// How long the engine thinks about its replies, if it plays.
+    engine_time: Option<Duration>,
+    /// The color the engine plays, which is the color that didn't ask for it.
+    engine_color: Color,
+    over: bool,
+}
+
+impl Session {
+    /// Runs the command and returns what to print, or an error to print.
+    fn run(&mut self, command: Command) -> Result<String, String> {
+        return match command {
+            Command::Move(text) => {
+                if self.over {return Err("the game is over, load a position or quit".to_string());}
+                let mv = find_move(&self.game, &text)?;
+                let san = self.game.to_san(&mv).unwrap();
+                self.game.play_move(&mv).unwrap();
+                let mut output = format!("{}\n{}", san, state_message(&self.game));
+                if self.engine_time.is_some() && self.game.get_turn_color() == self.engine_color {
+                    output.push_str(&self.engine_reply());
+                }
+                self.check_over();
+                Ok(format!("{}\n{}", output.trim_end(), render(&self.game)))
+            }
+            Command::Moves(square) => {
+                let targets = self.game.get_possible_moves(&square).ok_or(format!("there is no piece that can move on {}", square))?;
+                if targets.is_empty() {return Ok(format!("the piece on {} can't move", square));}
+                Ok(targets.join(" "))
+            }
+            Command::Undo => {
+                self.game.undo().ok_or("there is no move to take back")?;
+                // take back the reply of the engine and the move it replied to together
+                if self.engine_time.is_some() && self.game.get_turn_color() == self.engine_color {self.game.undo();}
+                self.over = false;
+                Ok(render(&self.game))
+            }
+            Command::Fen => Ok(self.game.get_fen()),
+            Command::Load(fen) => {
+                self.game = Game::from_fen(&fen).map_err(|error| error.to_string())?;
+                self.over = false;
+                self.check_over();
+                Ok(format!("{}\n{}", render(&self.game), state_message(&self.game)).trim_end().to_string())
+            }
+            Command::Engine(time) => {
+                self.engine_time = time;
+                if time.is_none() {return Ok("the engine stopped playing".to_string());}
+                self.engine_color = self.game.get_turn_color().opposite();
+                Ok(format!("the engine plays {:?}", self.engine_color))
+            }
+            Command::Resign => {
+                if self.over {return Err("the game is over already".to_string());}
+                self.over = true;
+                Ok(format!("{:?} resigns, {:?} wins", self.game.get_turn_color(), self.game.get_turn_color().opposite()))
+            }
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => Ok(String::new())
+        };
+    }
+
+    /// Lets the engine move and returns what it played.
+    fn engine_reply(&mut self) -> String {
+        let result = find_best_move_timed(&self.game, self.engine_time.unwrap());
+        let Some(mv) = result.best_move else {return String::new()};
+        let san = self.game.to_san(&mv).unwrap();
+        self.game.play_move(&mv).unwrap();
+        return format!("\nThe engine plays {}\n{}", san, state_message(&self.game));
+    }
+
+    fn check_over(&mut self) {
+        self.over = matches!(self.game.get_game_state(), GameState::Checkmate | GameState::Stalemate);
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut session = Session { game: Game::new(), engine_time: None, engine_color: Color::Black, over: false };
+    let mut stdout = io::stdout();
+    println!("{}\n\n{}", HELP, render(&session.game));
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("{} to move> ", if session.game.get_turn_color() == Color::White {"White"} else {"Black"});
+        stdout.flush()?;
+        let Some(line) = lines.next() else {return Ok(())};
+        let command = match parse_command(&line?) {
+            Ok(command) => command,
+            Err(message) => {println!("{}", message); continue;}
+        };
+        if command == Command::Quit {return Ok(());}
+        match session.run(command) {
+            Ok(output) => println!("{}", output),
+            Err(message) => println!("{}", message)
+        }
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{find_move, parse_command, render, Command, Session};
+    use eliassam_chess_lib::{Color, Game};
+
+    fn session() -> Session {
+        return Session { game: Game::new(), engine_time: None, engine_color: Color::Black, over: false };
+    }
+
+    //check that commands and moves are told apart and bad arguments are errors
+    #[test]
+    fn check_parse_command() {
+        assert_eq!(parse_command(" e4 "), Ok(Command::Move("e4".to_string())));
+        assert_eq!(parse_command("moves  e2"), Ok(Command::Moves("e2".to_string())));
+        assert_eq!(parse_command("engine 500"), Ok(Command::Engine(Some(Duration::from_millis(500)))));
+        assert_eq!(parse_command("engine off"), Ok(Command::Engine(None)));
+        assert_eq!(parse_command("load 8/8/8/8/8/8/8/K1k5 w - - 0 1"), Ok(Command::Load("8/8/8/8/8/8/8/K1k5 w - - 0 1".to_string())));
+        assert_eq!(parse_command("undo"), Ok(Command::Undo));
+        assert!(parse_command("engine fast").is_err());
+        assert!(parse_command("moves").is_err());
+        assert!(parse_command("").is_err());
+        assert!(parse_command("castle kingside").is_err());
+    }
+
+    //check that moves are found in both notations, with or without check signs
+    #[test]
+    fn check_find_move() {
+        let mut game1 = Game::new();
+        assert_eq!(find_move(&game1, "Nf3"), find_move(&game1, "g1f3"));
+        assert!(find_move(&game1, "Nf4").is_err());
+        for mv in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6"] {
+            game1.play_move(&find_move(&game1, mv).unwrap()).unwrap();
+        }
+        assert_eq!(find_move(&game1, "0-0"), find_move(&game1, "O-O"));
+        assert_eq!(find_move(&game1, "O-O").unwrap().to_string(), "e1g1");
+        assert_eq!(find_move(&game1, "Bxf7+"), find_move(&game1, "Bxf7"));
+        assert_eq!(find_move(&game1, "E1G1").unwrap().to_string(), "e1g1");
+    }
+
+    //check a game with undo, an illegal move and the engine finding a mate
+    #[test]
+    fn check_session() {
+        let mut session1 = session();
+        assert!(session1.run(Command::Move("e5".to_string())).is_err());
+        assert!(session1.run(Command::Move("e4".to_string())).unwrap().starts_with("e4\n"));
+        assert_eq!(session1.run(Command::Moves("e7".to_string())), Ok("e6 e5".to_string()));
+        assert!(session1.run(Command::Undo).is_ok());
+        assert_eq!(session1.game.get_fen(), Game::new().get_fen());
+        assert!(session1.run(Command::Undo).is_err());
+        assert!(session1.run(Command::Load("not a fen".to_string())).is_err());
+        // the engine mates after 1.f3 e5 2.g4
+        for mv in ["f3", "e5"] {
+            session1.run(Command::Move(mv.to_string())).unwrap();
+        }
+        session1.run(Command::Engine(Some(Duration::from_millis(200)))).unwrap();
+        let output = session1.run(Command::Move("g4".to_string())).unwrap();
+        assert!(output.contains("The engine plays Qh4#\nCheckmate, Black wins"), "{}", output);
+        assert!(session1.run(Command::Move("a3".to_string())).is_err());
+        assert!(session1.run(Command::Resign).is_err());
+        assert!(render(&session1.game).starts_with("8 ♜ ♞ ♝ · ♚ ♝ ♞ ♜"));
+    }
+}
@@ -0,0 +1,10 @@
+//! A UCI engine to play against in a chess GUI: point the GUI at the binary of
+//! `cargo build --release --example uci`.
+
+use std::io;
+
+use eliassam_chess_lib::engine::{uci, AlphaBetaEngine};
+
+fn main() -> io::Result<()> {
+    uci::run(io::stdin().lock(), io::stdout(), AlphaBetaEngine::default())
+}
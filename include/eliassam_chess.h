@@ -0,0 +1,36 @@
+/*
+ * The C functions of eliassam_chess_lib, built with `cargo build --release --features ffi` in
+ * the bindings directory into target/release/libeliassam_chess.so (.dylib on macOS, .dll on
+ * Windows).
+ *
+ * A game made by chess_game_new belongs to the caller until it is given back to
+ * chess_game_free, exactly once. The other functions only borrow it for the call. Strings
+ * are NUL-terminated UTF-8; strings going in are only read during the call, and strings
+ * coming out are copied into a buffer of the caller.
+ *
+ * The functions return CHESS_OK or a count on success and a negative status code on failure.
+ */
+
+#ifndef ELIASSAM_CHESS_H
+#define ELIASSAM_CHESS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#define CHESS_OK 0
+#define CHESS_ERR_NULL (-1)
+#define CHESS_ERR_INVALID (-2)
+#define CHESS_ERR_ILLEGAL (-3)
+#define CHESS_ERR_BUFFER (-4)
+#define CHESS_ERR_PANIC (-5)
+
+typedef struct ChessGame ChessGame;
+
+ChessGame *chess_game_new(void);
+void chess_game_free(ChessGame *game);
+int32_t chess_load_fen(ChessGame *game, const char *fen);
+int32_t chess_make_move(ChessGame *game, const char *from, const char *to, char promo);
+int32_t chess_get_fen(const ChessGame *game, char *buf, size_t len);
+int32_t chess_legal_moves(const ChessGame *game, const char *square, char *buf, size_t len);
+
+#endif
@@ -0,0 +1,88 @@
+use std::thread;
+
+use crate::{ChessError, Game, GameState};
+
+/// Options for analyze_fens.
+#[derive(Clone, Copy, Debug)]
+pub struct AnalysisOptions {
+    /// Number of threads to spread the positions over. 0 and 1 both mean that the positions are
+    /// analysed on the calling thread.
+    pub threads: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> AnalysisOptions {
+        AnalysisOptions { threads: 1 }
+    }
+}
+
+/// The result of analysing a single position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalysisRow {
+    pub fen: String,
+    pub legal_moves: usize,
+    pub state: GameState,
+    /// Material balance in centipawns, positive if White has more material.
+    pub material: i32,
+}
+
+/// Analyses every FEN string and returns one result per string, in the same order as the input.
+/// A FEN string that can't be parsed gives an Err for that row without affecting the others.
+pub fn analyze_fens(fens: impl Iterator<Item = String>, opts: AnalysisOptions) -> Vec<Result<AnalysisRow, ChessError>> {
+    let fens: Vec<String> = fens.collect();
+    let threads = opts.threads.clamp(1, fens.len().max(1));
+    if threads == 1 {
+        return fens.iter().map(|fen| analyze_fen(fen)).collect();
+    }
+
+    // every thread analyses a contiguous chunk, so putting the chunks back together keeps the order
+    let chunk_size = fens.len().div_ceil(threads);
+    return thread::scope(|scope| {
+        let handles: Vec<_> = fens.chunks(chunk_size).map(|chunk| {
+            scope.spawn(move || chunk.iter().map(|fen| analyze_fen(fen)).collect::<Vec<_>>())
+        }).collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    });
+}
+
+fn analyze_fen(fen: &str) -> Result<AnalysisRow, ChessError> {
+    let game = Game::from_fen(fen)?;
+    return Ok(AnalysisRow {
+        fen: fen.to_string(),
+        legal_moves: game.get_all_possible_moves().len(),
+        state: game.get_game_state(),
+        material: game.material_balance(),
+    });
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_fens, AnalysisOptions};
+    use crate::{ChessError, FenError, FenField, GameState};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/batch_analysis.fen");
+
+    //check that the rows come back in order, with an error only for the bad FEN
+    #[test]
+    fn check_analyze_fens() {
+        for threads in [1, 2, 4] {
+            let rows = analyze_fens(FIXTURE.lines().map(|line| line.to_string()), AnalysisOptions { threads });
+            assert_eq!(rows.len(), 6);
+            assert_eq!(rows[0].as_ref().unwrap().legal_moves, 20);
+            assert_eq!(rows[0].as_ref().unwrap().material, 0);
+            assert_eq!(rows[1].as_ref().unwrap().legal_moves, 48);
+            let error = FenError { message: "invalid piece character 'X'".to_string(), field: Some(FenField::Placement), position: 42 };
+            assert_eq!(rows[2], Err(ChessError::InvalidFen(error)));
+            assert_eq!(rows[3].as_ref().unwrap().state, GameState::Checkmate);
+            assert_eq!(rows[4].as_ref().unwrap().state, GameState::Stalemate);
+            assert_eq!(rows[5].as_ref().unwrap().material, 500);
+            for (row, fen) in rows.iter().zip(FIXTURE.lines()) {
+                if let Ok(row) = row {assert_eq!(row.fen, fen);}
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::vec::Vec;
+
+use crate::{parse_square, Game, PieceRole, Square};
+
+/// A legal move of a piece with what it does, see Game::annotated_moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AnnotatedMove {
+    /// The square the piece moves to, for castling in Chess960 the square of the rook.
+    pub to: Square,
+    /// Whether the move takes a piece, also en passant.
+    pub is_capture: bool,
+    /// Whether the move checks the other king, for a promotion when promoting to a queen.
+    pub gives_check: bool,
+    pub is_castle: bool,
+    /// Whether the pawn promotes and the player has to choose a piece.
+    pub needs_promotion: bool,
+}
+
+impl Game {
+    /// Lists the legal moves of the piece on the square, e.g. "g1", with whether they take, check,
+    /// castle or promote, so that the squares can be highlighted differently. Like
+    /// get_possible_moves every promotion square is listed once. Only the player to move has
+    /// legal moves, so the list is empty for the pieces of the other player, an empty square or
+    /// a string that isn't a square.
+    pub fn annotated_moves(&self, square: &str) -> Vec<AnnotatedMove> {
+        let Some(from) = parse_square(square).filter(|_| square.len() == 2) else {return Vec::new()};
+        return self.legal_moves().iter()
+            .filter(|mv| mv.from.index() == from && !mv.is_drop() && (mv.promotion.is_none() || mv.promotion == Some(PieceRole::Queen)))
+            .map(|&mv| AnnotatedMove {
+                to: mv.to,
+                is_capture: self.position.is_capture(mv),
+                // the check is looked for on a copy of the position, which is cheap
+                gives_check: self.position.gives_check(mv),
+                is_castle: self.position.castling_rook(mv).is_some(),
+                needs_promotion: mv.promotion.is_some()
+            })
+            .collect();
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::AnnotatedMove;
+    use crate::{Game, Square};
+
+    fn annotated(game: &Game, from: &str, to: &str) -> AnnotatedMove {
+        let to = Square::from_algebraic(to).unwrap();
+        return game.annotated_moves(from).into_iter().find(|mv| mv.to == to).unwrap();
+    }
+
+    //check a knight with a capture and checks, a pawn that promotes, castling and en passant
+    #[test]
+    fn check_annotated_moves() {
+        let game1 = Game::from_fen("4k3/1P6/8/2p5/4N3/8/8/R3K3 w Q - 0 1").unwrap();
+        assert_eq!(game1.annotated_moves("e4").len(), game1.get_possible_moves("e4").unwrap().len());
+        let capture = annotated(&game1, "e4", "c5");
+        assert!(capture.is_capture && !capture.gives_check && !capture.is_castle && !capture.needs_promotion);
+        for square in ["d6", "f6"] {
+            let check = annotated(&game1, "e4", square);
+            assert!(check.gives_check && !check.is_capture);
+        }
+        let quiet = annotated(&game1, "e4", "g3");
+        assert!(!quiet.is_capture && !quiet.gives_check);
+        // the pawn is listed once for its four promotions, and the queen checks along the rank
+        let pawn = game1.annotated_moves("b7");
+        assert_eq!(pawn.len(), 1);
+        assert!(pawn[0].needs_promotion && !pawn[0].is_capture && pawn[0].gives_check);
+        let castle = annotated(&game1, "e1", "c1");
+        assert!(castle.is_castle && !castle.is_capture);
+        assert!(!annotated(&game1, "e1", "d1").is_castle);
+        // the other player, an empty square and a bad square have no moves
+        assert!(game1.annotated_moves("e8").is_empty());
+        assert!(game1.annotated_moves("e5").is_empty());
+        assert!(game1.annotated_moves("e44").is_empty());
+        // en passant takes on an empty square
+        let game1 = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(annotated(&game1, "e5", "d6").is_capture);
+        assert!(!annotated(&game1, "e5", "e6").is_capture);
+    }
+}
@@ -0,0 +1,88 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::{ChessError, Color, Game};
+
+impl Game {
+    /// Gets the board as 8 rows of 8 characters, the FEN letters of the pieces (capitals for
+    /// White) and '.' for empty squares. Row 0 is rank 8 and column 0 the a-file, as in
+    /// get_board, so `to_char_board()[7][4]` is e1.
+    pub fn to_char_board(&self) -> [[char; 8]; 8] {
+        let mut board = [['.'; 8]; 8];
+        for (index, square) in board.as_flattened_mut().iter_mut().enumerate() {
+            if let Some(piece) = self.position.piece_at(index) {*square = piece.letter();}
+        }
+        return board;
+    }
+
+    /// Creates a game from a board of characters ordered as in to_char_board, with '.' or ' '
+    /// for empty squares. Without castling rights (in FEN, e.g. "KQkq") no one can castle, and
+    /// without an en passant square (e.g. "e3") there is none. The position is checked like one
+    /// read by from_fen, and a character that isn't a piece gives ChessError::InvalidPosition.
+    pub fn from_char_board(board: [[char; 8]; 8], turn: Color, castling: Option<&str>, en_passant: Option<&str>) -> Result<Game, ChessError> {
+        let mut placement = String::new();
+        for (row_index, row) in board.iter().enumerate() {
+            let mut empty_squares = 0;
+            for (column_index, &square) in row.iter().enumerate() {
+                match square {
+                    '.' | ' ' => {
+                        empty_squares += 1;
+                        continue;
+                    }
+                    'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => (),
+                    _ => return Err(ChessError::InvalidPosition(format!("invalid piece character '{}' in row {} column {}", square, row_index, column_index)))
+                }
+                if empty_squares > 0 {placement.push_str(&empty_squares.to_string()); empty_squares = 0;}
+                placement.push(square);
+            }
+            if empty_squares > 0 {placement.push_str(&empty_squares.to_string());}
+            if row_index < 7 {placement.push('/');}
+        }
+        let turn = if turn == Color::White {"w"} else {"b"};
+        return Game::from_fen(&format!("{} {} {} {} 0 1", placement, turn, castling.unwrap_or("-"), en_passant.unwrap_or("-")));
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::random_games;
+    use crate::{ChessError, Color, Game};
+
+    //check the orientation of the board and that it reads back to the same position
+    #[test]
+    fn check_char_board() {
+        let board = Game::new().to_char_board();
+        assert_eq!(board[0], ['r', 'n', 'b', 'q', 'k', 'b', 'n', 'r']);
+        assert_eq!((board[7][4], board[4][4]), ('K', '.'));
+        let game1 = Game::from_char_board(board, Color::White, Some("KQkq"), None).unwrap();
+        assert_eq!(game1.get_fen(), Game::new().get_fen());
+        // no castling unless it is given
+        assert_eq!(Game::from_char_board(board, Color::White, None, None).unwrap().get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+        for game1 in random_games(20, 0x3C6EF372FE94F82B) {
+            let fen = game1.get_fen();
+            let fields: Vec<&str> = fen.split(' ').collect();
+            let en_passant = Some(fields[3]).filter(|&square| square != "-");
+            let read = Game::from_char_board(game1.to_char_board(), game1.get_turn_color(), Some(fields[2]), en_passant).unwrap();
+            assert_eq!(read.to_char_board(), game1.to_char_board());
+            assert_eq!(read.get_fen().split(' ').take(4).collect::<Vec<_>>(), fields[0..4]);
+        }
+    }
+
+    //check that a character that isn't a piece and a broken position are refused
+    #[test]
+    fn check_char_board_invalid() {
+        let mut board = Game::new().to_char_board();
+        board[4][2] = ' ';
+        assert!(Game::from_char_board(board, Color::Black, None, None).is_ok());
+        board[4][2] = 'x';
+        assert_eq!(Game::from_char_board(board, Color::White, None, None).err(), Some(ChessError::InvalidPosition("invalid piece character 'x' in row 4 column 2".to_string())));
+        board[4][2] = '3';
+        assert!(Game::from_char_board(board, Color::White, None, None).is_err());
+        let board = Game::new().to_char_board();
+        assert!(matches!(Game::from_char_board(board, Color::White, Some("KQkq"), Some("e9")), Err(ChessError::InvalidFen(_))));
+    }
+}
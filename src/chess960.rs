@@ -0,0 +1,159 @@
+use alloc::format;
+
+use crate::{ChessError, Game, PieceRole, Variant, XorShift};
+
+/// The number of Chess960 starting positions.
+const POSITIONS: u16 = 960;
+
+/// Where the two knights go among the five squares left after the bishops and the queen, for
+/// every remainder of the numbering scheme.
+const KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+/// The back row of the Chess960 starting position with the number, from the a-file to the
+/// h-file, in the standard numbering scheme (the one of Reinhard Scharnagl).
+fn back_row_960(position_number: u16) -> [PieceRole; 8] {
+    let mut row: [Option<PieceRole>; 8] = [None; 8];
+    let mut n = position_number as usize;
+    // the light-squared bishop on b, d, f or h and the dark-squared one on a, c, e or g
+    row[n % 4 * 2 + 1] = Some(PieceRole::Bishop);
+    n /= 4;
+    row[n % 4 * 2] = Some(PieceRole::Bishop);
+    n /= 4;
+    // the queen on one of the six squares left
+    let empty = |row: &[Option<PieceRole>; 8], nth: usize| -> usize {row.iter().enumerate().filter(|(_, role)| role.is_none()).nth(nth).unwrap().0};
+    let queen = empty(&row, n % 6);
+    row[queen] = Some(PieceRole::Queen);
+    n /= 6;
+    // the knights on two of the five squares left, both counted before either is placed
+    let (first, second) = KNIGHTS[n];
+    let (first, second) = (empty(&row, first), empty(&row, second));
+    row[first] = Some(PieceRole::Knight);
+    row[second] = Some(PieceRole::Knight);
+    // the king between the rooks on the three squares left
+    for role in [PieceRole::Rook, PieceRole::King, PieceRole::Rook] {
+        let square = empty(&row, 0);
+        row[square] = Some(role);
+    }
+    return row.map(|role| role.unwrap());
+}
+
+impl Game {
+    /// Creates a game from the Chess960 (Fischer random chess) starting position with the number,
+    /// from 0 to 959 in the standard numbering scheme, where 518 is the normal starting position.
+    /// Black mirrors the back row of White. Castling works from every starting position, by
+    /// moving the king onto its own rook or with O-O and O-O-O, and the king and the rook end on
+    /// the squares they do in normal chess.
+    pub fn new_960(position_number: u16) -> Result<Game, ChessError> {
+        if position_number >= POSITIONS {return Err(ChessError::InvalidPosition(format!("there is no Chess960 position {}", position_number)));}
+        return Ok(Game::with_back_row(back_row_960(position_number), Variant::Chess960));
+    }
+
+    /// Creates a game from a Chess960 starting position chosen by the generator, every one of
+    /// them equally likely.
+    pub fn new_960_random(rng: &mut XorShift) -> Game {
+        return Game::with_back_row(back_row_960(rng.next_index(POSITIONS as usize) as u16), Variant::Chess960);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::back_row_960;
+    use crate::tests::coordinate_move;
+    use crate::{ChessError, Game, GameState, PieceRole, Variant, XorShift};
+
+    //check that position 518 is the normal starting position
+    #[test]
+    fn check_960_standard() {
+        let game1 = Game::new_960(518).unwrap();
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+        assert_eq!(game1.get_board(), Game::new().get_board());
+        assert_eq!(game1.variant(), Variant::Chess960);
+        assert_eq!(game1.perft(3), 8902);
+        // position 0 from the numbering scheme
+        assert_eq!(Game::new_960(0).unwrap().get_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1");
+        assert!(matches!(Game::new_960(960), Err(ChessError::InvalidPosition(_))));
+    }
+
+    //check that every position has the bishops on opposite colors and the king between the rooks
+    #[test]
+    fn check_960_all() {
+        let mut rows = HashSet::new();
+        for number in 0..960 {
+            let row = back_row_960(number);
+            let files = |role: PieceRole| -> Vec<usize> {(0..8).filter(|&file| row[file] == role).collect()};
+            let (bishops, rooks, king) = (files(PieceRole::Bishop), files(PieceRole::Rook), files(PieceRole::King));
+            assert_eq!((bishops.len(), rooks.len(), king.len()), (2, 2, 1), "{}", number);
+            assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", number);
+            assert!(rooks[0] < king[0] && king[0] < rooks[1], "{}", number);
+            assert_eq!((files(PieceRole::Queen).len(), files(PieceRole::Knight).len()), (1, 2), "{}", number);
+            rows.insert(row);
+        }
+        // every number gives its own position
+        assert_eq!(rows.len(), 960);
+        // the positions are mirrored and playable
+        let mut game1 = Game::new_960_random(&mut XorShift::new(7));
+        assert_eq!(game1.get_fen(), "nrbqkrnb/pppppppp/8/8/8/8/PPPPPPPP/NRBQKRNB w FBfb - 0 1");
+        // the knight in the corner has one move
+        assert_eq!(game1.get_all_possible_moves().len(), 19);
+        game1.make_move("e2", "e4").unwrap();
+        assert_eq!(Game::from_fen(&game1.get_fen()).unwrap().get_fen(), game1.get_fen());
+        assert!(game1.validate_position().is_ok());
+    }
+
+    // the position after castling by the king on the first square taking the rook on the second
+    fn castle(fen: &str, king_takes_rook: &str) -> Option<String> {
+        let mut game1 = Game::from_fen(fen).unwrap();
+        game1.play_move(&coordinate_move(king_takes_rook))?;
+        return Some(game1.get_fen());
+    }
+
+    //check that the king and the rook end on the squares of normal chess, also when they don't move
+    #[test]
+    fn check_960_castling() {
+        // the king on g1 stays where it is
+        assert_eq!(castle("1r4kr/8/8/8/8/8/8/R5KR w HAhb - 0 1", "g1h1").unwrap(), "1r4kr/8/8/8/8/8/8/R4RK1 b hb - 1 1");
+        assert_eq!(castle("1r4kr/8/8/8/8/8/8/R5KR w HAhb - 0 1", "g1a1").unwrap(), "1r4kr/8/8/8/8/8/8/2KR3R b hb - 1 1");
+        // the rook on f1 and d1 stays where it is
+        assert_eq!(castle("4k3/8/8/8/8/8/8/3RKR2 w FD - 0 1", "e1f1").unwrap(), "4k3/8/8/8/8/8/8/3R1RK1 b - - 1 1");
+        assert_eq!(castle("4k3/8/8/8/8/8/8/3RKR2 w FD - 0 1", "e1d1").unwrap(), "4k3/8/8/8/8/8/8/2KR1R2 b - - 1 1");
+        // the king and the rook swap squares
+        assert_eq!(castle("2rk4/8/8/8/8/8/8/4K3 b c - 0 1", "d8c8").unwrap(), "2kr4/8/8/8/8/8/8/4K3 w - - 1 2");
+        // every square the king or the rook passes or ends on must be empty
+        assert_eq!(castle("4k3/8/8/8/8/8/8/1RK2N2 w B - 0 1", "c1b1"), Some("4k3/8/8/8/8/8/8/2KR1N2 b - - 1 1".to_string()));
+        assert_eq!(castle("4k3/8/8/8/8/8/8/1RKN4 w B - 0 1", "c1b1"), None);
+        assert_eq!(castle("4k3/8/8/8/8/8/8/5KNR w H - 0 1", "f1h1"), None);
+        // the king must not be in check or pass through an attacked square, but the rook may
+        assert_eq!(castle("4k3/8/8/8/8/8/8/1RK4r w B - 0 1", "c1b1"), None);
+        assert_eq!(castle("2r1k3/8/8/8/8/8/8/1R1K4 w B - 0 1", "d1b1"), None);
+        assert!(castle("1r2k3/8/8/8/8/8/8/1R1K4 w B - 0 1", "d1b1").is_some());
+        // a standard game doesn't castle by taking the rook
+        assert_eq!(castle("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1h1"), None);
+    }
+
+    //check castling in SAN, with make_move, undo and the piece ids
+    #[test]
+    fn check_960_castling_moves() {
+        let mut game1 = Game::from_fen("1r4kr/8/8/8/8/8/8/R5KR w HAhb - 0 1").unwrap();
+        let king_side = coordinate_move("g1h1");
+        assert_eq!(game1.to_san(&king_side).unwrap(), "O-O");
+        assert_eq!(game1.to_san(&coordinate_move("g1a1")).unwrap(), "O-O-O");
+        let rook_id = game1.get_piece("h1").unwrap().id();
+        let result = game1.play_move(&king_side).unwrap();
+        assert_eq!(result.rook_move, Some(coordinate_move("h1f1")));
+        assert_eq!(game1.get_piece("f1").unwrap().id(), rook_id);
+        assert!(game1.validate_position().is_ok());
+        game1.undo().unwrap();
+        assert_eq!(game1.get_fen(), "1r4kr/8/8/8/8/8/8/R5KR w HAhb - 0 1");
+        assert_eq!(game1.make_move("g1", "a1"), Some(GameState::InProgress));
+        assert_eq!(game1.get_piece("c1").unwrap().role, PieceRole::King);
+        // the perft numbers of a known Chess960 position
+        let game1 = Game::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").unwrap();
+        assert_eq!((game1.perft(1), game1.perft(2), game1.perft(3)), (21, 528, 12189));
+    }
+}
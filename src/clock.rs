@@ -0,0 +1,406 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::engine::time::Instant;
+use crate::event_log::LogEntry;
+use crate::{ChessError, EndReason, Color, Game, GameOutcome, GameState, Move, MoveResult, PieceRole};
+
+/// How much time the players get.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// The whole game in the time.
+    SuddenDeath(Duration),
+    /// The base time, and the increment added after every move (Fischer).
+    Increment { base: Duration, increment: Duration },
+    /// The base time, and a delay at the start of every move before the clock starts to run
+    /// (simple or US delay). Unused delay is lost.
+    Delay { base: Duration, delay: Duration },
+}
+
+impl TimeControl {
+    fn base(&self) -> Duration {
+        return match *self {
+            TimeControl::SuddenDeath(base) | TimeControl::Increment { base, .. } | TimeControl::Delay { base, .. } => base,
+        };
+    }
+}
+
+/// Where a Clock gets the time from.
+pub trait TimeSource: Send + Sync {
+    /// The time since some fixed moment, which never goes backwards.
+    fn now(&self) -> Duration;
+}
+
+/// The time of the system's monotonic clock (JavaScript's clock in the browser), what Clock::new
+/// and the event log of a game use.
+pub struct MonotonicTime {
+    start: Instant,
+}
+
+impl MonotonicTime {
+    pub fn new() -> MonotonicTime {
+        return MonotonicTime { start: Instant::now() };
+    }
+
+    /// A time source that is at the time already, as if it had been made that long ago.
+    pub(crate) fn since(time: Duration) -> MonotonicTime {
+        let now = Instant::now();
+        return MonotonicTime { start: now.checked_sub(time).unwrap_or(now) };
+    }
+}
+
+impl Default for MonotonicTime {
+    fn default() -> MonotonicTime {
+        MonotonicTime::new()
+    }
+}
+
+impl TimeSource for MonotonicTime {
+    fn now(&self) -> Duration {
+        return self.start.elapsed();
+    }
+}
+
+/// A time source that only moves when it is told to, for tests. Clones share the time, so a
+/// clone can be given to a Clock and the original moved forward.
+#[derive(Clone, Debug, Default)]
+pub struct MockTime {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl MockTime {
+    pub fn new() -> MockTime {
+        return MockTime::default();
+    }
+
+    /// Moves the time forward.
+    pub fn advance(&self, time: Duration) {
+        *self.now.lock().unwrap() += time;
+    }
+}
+
+impl TimeSource for MockTime {
+    fn now(&self) -> Duration {
+        return *self.now.lock().unwrap();
+    }
+}
+
+/// A chess clock: the time each player has left, and whose clock is running.
+pub struct Clock {
+    control: TimeControl,
+    /// The time left for White and Black when their turn started.
+    remaining: [Duration; 2],
+    /// The player whose clock is running, and when their turn started.
+    running: Option<(Color, Duration)>,
+    source: Box<dyn TimeSource>,
+}
+
+impl Clock {
+    /// A clock with the base time of the time control for both players, neither running,
+    /// using the system's clock.
+    pub fn new(control: TimeControl) -> Clock {
+        return Clock::with_time_source(control, MonotonicTime::new());
+    }
+
+    /// A clock that gets the time from the source, like a MockTime in tests.
+    pub fn with_time_source(control: TimeControl, source: impl TimeSource + 'static) -> Clock {
+        let base = control.base();
+        return Clock { control, remaining: [base, base], running: None, source: Box::new(source) };
+    }
+
+    /// A clock with the time each player has left, neither running, using the system's clock,
+    /// e.g. for a restored game.
+    pub(crate) fn with_remaining(control: TimeControl, remaining: [Duration; 2]) -> Clock {
+        return Clock { control, remaining, running: None, source: Box::new(MonotonicTime::new()) };
+    }
+
+    pub fn time_control(&self) -> TimeControl {
+        return self.control;
+    }
+
+    /// The player whose clock is running, if any.
+    pub fn running(&self) -> Option<Color> {
+        return self.running.map(|(color, _)| color);
+    }
+
+    /// Starts the clock of the player, stopping the other clock without charging or adding
+    /// anything to it.
+    pub fn start_turn(&mut self, color: Color) {
+        self.running = Some((color, self.source.now()));
+    }
+
+    /// Stops the clock of the player at the end of their move, charging the time of the move
+    /// (less the delay) and adding the increment if they still have time left. Does nothing if
+    /// the clock of the player isn't running.
+    pub fn end_turn(&mut self, color: Color) {
+        if self.running() != Some(color) {return;}
+        let left = self.remaining(color);
+        self.remaining[index(color)] = match self.control {
+            TimeControl::Increment { increment, .. } if !left.is_zero() => left + increment,
+            _ => left
+        };
+        self.running = None;
+    }
+
+    /// The time the player has left, counting the running turn if it is theirs. Never below
+    /// zero.
+    pub fn remaining(&self, color: Color) -> Duration {
+        let remaining = self.remaining[index(color)];
+        return match self.running {
+            Some((running, start)) if running == color => {
+                let mut used = self.source.now().saturating_sub(start);
+                if let TimeControl::Delay { delay, .. } = self.control {used = used.saturating_sub(delay);}
+                remaining.saturating_sub(used)
+            }
+            _ => remaining
+        };
+    }
+
+    /// True if the player has run out of time.
+    pub fn flagged(&self, color: Color) -> bool {
+        return self.remaining(color).is_zero();
+    }
+}
+
+impl Game {
+    /// Ends the game if a player has run out of time on the clock, the player to move first,
+    /// and returns the result: a loss for that player, or a draw if their opponent doesn't
+    /// have the pieces to checkmate with (see has_mating_material). No moves can be made in the
+    /// game after that, like after Game::resign. Returns None if nobody has run out of time or
+    /// the game was already over.
+    pub fn check_flag(&mut self, clock: &Clock) -> Option<GameOutcome> {
+        if self.outcome().is_some() {return None;}
+        let turn = self.get_turn_color();
+        let flagged = [turn, turn.opposite()].into_iter().find(|&color| clock.flagged(color))?;
+        self.flagged = Some(flagged);
+        self.log(flagged, LogEntry::Flagged);
+        let outcome = if self.has_mating_material(flagged.opposite()) {GameOutcome::Win(flagged.opposite())} else {GameOutcome::Draw};
+        return Some(self.end(outcome, EndReason::Timeout));
+    }
+
+    /// The player who ran out of time, if the game ended that way (see check_flag).
+    pub fn flagged(&self) -> Option<Color> {
+        return self.flagged;
+    }
+}
+
+fn index(color: Color) -> usize {
+    return if color == Color::White {0} else {1};
+}
+
+/// A game with a clock that switches sides with every move: making a move ends the turn of
+/// the player who made it and starts the turn of the other, and a move that ends the game
+/// stops the clock.
+pub struct ClockedGame {
+    game: Game,
+    clock: Clock,
+}
+
+impl ClockedGame {
+    /// Starts the clock of the player to move, unless the game is already over.
+    pub fn new(game: Game, mut clock: Clock) -> ClockedGame {
+        if game.outcome().is_none() {clock.start_turn(game.get_turn_color());}
+        return ClockedGame { game, clock };
+    }
+
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    pub fn clock(&self) -> &Clock {
+        return &self.clock;
+    }
+
+    /// Game::check_flag with the clock of the game, stopping the clock if it ends the game.
+    pub fn check_flag(&mut self) -> Option<GameOutcome> {
+        let outcome = self.game.check_flag(&self.clock)?;
+        self.clock.running = None;
+        return Some(outcome);
+    }
+
+    /// Game::make_move, switching the clock if the move was made. A player who has run out
+    /// of time can't move, see check_flag.
+    pub fn make_move(&mut self, from: &str, to: &str) -> Option<GameState> {
+        self.check_flag();
+        let mover = self.game.get_turn_color();
+        let state = self.game.make_move(from, to)?;
+        self.switch(mover);
+        return Some(state);
+    }
+
+    /// Game::play_move, switching the clock if the move was made.
+    pub fn play_move(&mut self, mv: &Move) -> Option<MoveResult> {
+        self.check_flag();
+        let mover = self.game.get_turn_color();
+        let result = self.game.play_move(mv)?;
+        self.switch(mover);
+        return Some(result);
+    }
+
+    /// Game::set_premove for the game. A premove that is played takes no time off the clock
+    /// of the player, but does get the increment.
+    pub fn set_premove(&mut self, color: Color, from: &str, to: &str, promotion: Option<PieceRole>) -> Result<(), ChessError> {
+        return self.game.set_premove(color, from, to, promotion);
+    }
+
+    fn switch(&mut self, mover: Color) {
+        self.clock.end_turn(mover);
+        if self.game.outcome().is_none() {self.clock.start_turn(mover.opposite());}
+        // the opponent's premove was played at once, which ends their turn too
+        if self.game.get_turn_color() == mover {self.switch(mover.opposite());}
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, ClockedGame, MockTime, TimeControl};
+    use crate::{Color, EndReason, Game, GameOutcome};
+
+    fn seconds(seconds: u64) -> Duration {
+        return Duration::from_secs(seconds);
+    }
+
+    //check that the increment is only added when a move is completed
+    #[test]
+    fn check_clock_increment() {
+        let time = MockTime::new();
+        let mut clock = Clock::with_time_source(TimeControl::Increment { base: seconds(60), increment: seconds(2) }, time.clone());
+        // ending a turn that isn't running changes nothing
+        clock.end_turn(Color::White);
+        assert_eq!(clock.remaining(Color::White), seconds(60));
+        clock.start_turn(Color::White);
+        time.advance(seconds(5));
+        assert_eq!(clock.remaining(Color::White), seconds(55));
+        clock.end_turn(Color::White);
+        assert_eq!(clock.remaining(Color::White), seconds(57));
+        assert_eq!(clock.running(), None);
+        // time passes for nobody while no clock runs
+        time.advance(seconds(100));
+        assert_eq!(clock.remaining(Color::White), seconds(57));
+        assert_eq!(clock.remaining(Color::Black), seconds(60));
+        // a player whose time ran out gets no increment
+        clock.start_turn(Color::Black);
+        time.advance(seconds(61));
+        clock.end_turn(Color::Black);
+        assert_eq!(clock.remaining(Color::Black), Duration::ZERO);
+    }
+
+    //check that unused delay is lost instead of adding up
+    #[test]
+    fn check_clock_delay() {
+        let time = MockTime::new();
+        let mut clock = Clock::with_time_source(TimeControl::Delay { base: seconds(60), delay: seconds(3) }, time.clone());
+        for _ in 0..5 {
+            clock.start_turn(Color::White);
+            time.advance(seconds(1));
+            clock.end_turn(Color::White);
+        }
+        assert_eq!(clock.remaining(Color::White), seconds(60));
+        clock.start_turn(Color::White);
+        time.advance(seconds(10));
+        assert_eq!(clock.remaining(Color::White), seconds(53));
+    }
+
+    //check that the time left goes down and never up during a turn
+    #[test]
+    fn check_clock_monotonic() {
+        let time = MockTime::new();
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(seconds(10)), time.clone());
+        clock.start_turn(Color::Black);
+        let mut last = clock.remaining(Color::Black);
+        for _ in 0..30 {
+            time.advance(Duration::from_millis(500));
+            let remaining = clock.remaining(Color::Black);
+            assert!(remaining <= last);
+            last = remaining;
+        }
+        assert_eq!(last, Duration::ZERO);
+        assert_eq!(clock.remaining(Color::White), seconds(10));
+    }
+
+    //check that moves switch the clock of a game and the end of the game stops it
+    #[test]
+    fn check_clocked_game() {
+        let time = MockTime::new();
+        let clock = Clock::with_time_source(TimeControl::Increment { base: seconds(60), increment: seconds(1) }, time.clone());
+        let mut game1 = ClockedGame::new(Game::new(), clock);
+        assert_eq!(game1.clock().running(), Some(Color::White));
+        time.advance(seconds(4));
+        assert!(game1.make_move("e2", "e5").is_none());
+        assert_eq!(game1.clock().running(), Some(Color::White));
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4")] {
+            game1.make_move(from, to).unwrap();
+            time.advance(seconds(4));
+        }
+        assert_eq!(game1.clock().remaining(Color::White), seconds(54));
+        assert_eq!(game1.clock().running(), Some(Color::Black));
+        game1.make_move("d8", "h4").unwrap();
+        assert_eq!(game1.clock().running(), None);
+        assert_eq!(game1.clock().remaining(Color::Black), seconds(54));
+        // a premove switches the clock back at once
+        let clock = Clock::with_time_source(TimeControl::Increment { base: seconds(60), increment: seconds(1) }, time.clone());
+        let mut game1 = ClockedGame::new(Game::new(), clock);
+        game1.set_premove(Color::Black, "e7", "e5", None).unwrap();
+        time.advance(seconds(4));
+        game1.make_move("e2", "e4").unwrap();
+        assert_eq!(game1.clock().running(), Some(Color::White));
+        assert_eq!(game1.clock().remaining(Color::White), seconds(57));
+        assert_eq!(game1.clock().remaining(Color::Black), seconds(61));
+    }
+
+    //check that running out of time loses, unless the opponent can't checkmate
+    #[test]
+    fn check_flag() {
+        let time = MockTime::new();
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(seconds(10)), time.clone());
+        let mut game1 = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+        clock.start_turn(Color::Black);
+        time.advance(seconds(9));
+        assert!(!clock.flagged(Color::Black));
+        assert_eq!(game1.check_flag(&clock), None);
+        time.advance(seconds(1));
+        assert!(clock.flagged(Color::Black));
+        // the opponent has a rook
+        assert_eq!(game1.check_flag(&clock), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(game1.flagged(), Some(Color::Black));
+        assert_eq!(game1.end_reason(), Some(EndReason::Timeout));
+        assert!(game1.make_move("e8", "d8").is_none());
+        // a flag is only called once
+        assert_eq!(game1.check_flag(&clock), None);
+        // the opponent only has a king
+        let mut game1 = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(seconds(10)), time.clone());
+        clock.start_turn(Color::White);
+        time.advance(seconds(11));
+        assert_eq!(game1.check_flag(&clock), Some(GameOutcome::Draw));
+        assert_eq!(game1.end_reason(), Some(EndReason::Timeout));
+        // a game that is already over can't be lost on time
+        let mut game1 = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(seconds(10)), time.clone());
+        clock.start_turn(Color::Black);
+        time.advance(seconds(11));
+        assert_eq!(game1.check_flag(&clock), None);
+        assert_eq!(game1.flagged(), None);
+        assert_eq!(game1.end_reason(), Some(EndReason::Checkmate));
+    }
+
+    //check that a clocked game can't go on after a flag
+    #[test]
+    fn check_clocked_game_flag() {
+        let time = MockTime::new();
+        let clock = Clock::with_time_source(TimeControl::SuddenDeath(seconds(10)), time.clone());
+        let mut game1 = ClockedGame::new(Game::new(), clock);
+        game1.make_move("e2", "e4").unwrap();
+        time.advance(seconds(30));
+        assert!(game1.make_move("e7", "e5").is_none());
+        assert_eq!(game1.game().flagged(), Some(Color::Black));
+        assert_eq!(game1.clock().running(), None);
+        assert_eq!(game1.check_flag(), None);
+    }
+}
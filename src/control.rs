@@ -0,0 +1,205 @@
+use alloc::vec::Vec;
+
+use crate::{move_okay, Color, Game, Piece, PieceRole, Square, BISHOP_DIRECTIONS, KNIGHT_OFFSETS, QUEEN_DIRECTIONS, ROOK_DIRECTIONS};
+
+/// How many pieces of each color attack a square, see Game::control_map.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SquareControl {
+    pub white: u8,
+    pub black: u8,
+}
+
+impl SquareControl {
+    /// The attackers of the color.
+    pub fn attackers(&self, color: Color) -> u8 {
+        return if color == Color::White {self.white} else {self.black};
+    }
+
+    /// The color with more attackers on the square, or None if they are as many.
+    pub fn controlled_by(&self) -> Option<Color> {
+        return match self.white.cmp(&self.black) {
+            core::cmp::Ordering::Greater => Some(Color::White),
+            core::cmp::Ordering::Less => Some(Color::Black),
+            core::cmp::Ordering::Equal => None
+        };
+    }
+}
+
+impl Game {
+    /// Counts the white and black attackers of every square, indexed like the board (a8 is 0
+    /// and h1 is 63), e.g. to shade the squares of an analysis board by who controls them. A
+    /// square counts as attacked whatever stands on it, so the pieces that defend their own
+    /// pieces are counted too. Pawns attack diagonally forward, kings count, and sliding
+    /// pieces stop at the first piece in their way, so a rook behind another rook isn't
+    /// counted. Pins and check don't matter. The map is made in one pass over the pieces.
+    pub fn control_map(&self) -> [SquareControl; 64] {
+        let mut map = [SquareControl::default(); 64];
+        for index in 0..64 {
+            let Some(piece) = self.position.piece_at(index) else {continue};
+            attacks(piece.role, piece.color, index, &self.position.board, |target| {
+                let control = &mut map[target];
+                if piece.color == Color::White {control.white += 1;} else {control.black += 1;}
+            });
+        }
+        return map;
+    }
+}
+
+impl Piece {
+    /// The squares the piece would attack from the square in the position of the game, e.g. to
+    /// show a student what a piece covers. Whose turn it is, pins and check don't matter, and
+    /// pawns only attack diagonally forward. A sliding piece attacks up to and including the
+    /// first piece in its way, whatever its color. What stands on the square itself is
+    /// ignored, so the piece doesn't have to be there. The squares are in the order of their
+    /// indexes, a8 first.
+    pub fn attacks_from(&self, square: Square, occupancy: &Game) -> Vec<Square> {
+        let mut squares: Vec<Square> = Vec::new();
+        attacks(self.role, self.color, square.index(), &occupancy.position.board, |target| squares.push(Square(target as u8)));
+        squares.sort();
+        return squares;
+    }
+}
+
+/// The squares a piece of the role and color attacks from the square on an empty board, see
+/// Piece::attacks_from.
+pub fn empty_board_attacks(role: PieceRole, color: Color, square: Square) -> Vec<Square> {
+    let mut squares: Vec<Square> = Vec::new();
+    attacks(role, color, square.index(), &[0; 64], |target| squares.push(Square(target as u8)));
+    squares.sort();
+    return squares;
+}
+
+/// Calls attack with the index of every square a piece of the role and color on the index
+/// attacks on the board. Pawns attack diagonally forward, and sliding pieces stop at the first
+/// piece in their way.
+fn attacks(role: PieceRole, color: Color, index: usize, board: &[u8; 64], mut attack: impl FnMut(usize)) {
+    let (row, column) = ((index / 8) as i8, (index % 8) as i8);
+    let mut attack = |row: i8, column: i8| attack((row * 8 + column) as usize);
+    match role {
+        PieceRole::Pawn => {
+            let forward = if color == Color::White {-1} else {1};
+            for column_offset in [-1, 1] {
+                if move_okay(row + forward, column + column_offset) {attack(row + forward, column + column_offset);}
+            }
+        }
+        PieceRole::Knight | PieceRole::King => {
+            let offsets = if role == PieceRole::Knight {KNIGHT_OFFSETS} else {QUEEN_DIRECTIONS};
+            for (row_offset, column_offset) in offsets {
+                if move_okay(row + row_offset, column + column_offset) {attack(row + row_offset, column + column_offset);}
+            }
+        }
+        _ => {
+            let directions: &[(i8, i8)] = match role {
+                PieceRole::Rook => &ROOK_DIRECTIONS,
+                PieceRole::Bishop => &BISHOP_DIRECTIONS,
+                _ => &QUEEN_DIRECTIONS
+            };
+            for (row_offset, column_offset) in directions {
+                let (mut r, mut c) = (row + row_offset, column + column_offset);
+                while move_okay(r, c) {
+                    attack(r, c);
+                    if board[(r * 8 + c) as usize] != 0 {break;}
+                    r += row_offset;
+                    c += column_offset;
+                }
+            }
+        }
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{empty_board_attacks, SquareControl};
+    use crate::tests::{coordinate_move, random_games};
+    use crate::{Color, Game, Piece, PieceRole, Square};
+
+    fn control(game: &Game, square: &str) -> SquareControl {
+        return game.control_map()[Square::from_algebraic(square).unwrap().index()];
+    }
+
+    //check the counts of the start position and how 1.e4 changes them
+    #[test]
+    fn check_control_map() {
+        let mut game1 = Game::new();
+        // nothing reaches the fourth rank yet, while e3 is covered by two pawns
+        assert_eq!(control(&game1, "d4"), SquareControl { white: 0, black: 0 });
+        assert_eq!(control(&game1, "e3"), SquareControl { white: 2, black: 0 });
+        assert_eq!(control(&game1, "f3"), SquareControl { white: 3, black: 0 });
+        // the pieces defend each other, but the rook in the corner has no one behind it
+        assert_eq!(control(&game1, "d1"), SquareControl { white: 1, black: 0 });
+        assert_eq!(control(&game1, "a1"), SquareControl { white: 0, black: 0 });
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        assert_eq!(control(&game1, "d5"), SquareControl { white: 1, black: 0 });
+        assert_eq!(control(&game1, "f5"), SquareControl { white: 1, black: 0 });
+        // the empty e2 is covered by the queen, king, bishop and knight
+        assert_eq!(control(&game1, "e2"), SquareControl { white: 4, black: 0 });
+        assert_eq!(control(&game1, "e2").controlled_by(), Some(Color::White));
+        game1.play_move(&coordinate_move("d7d5")).unwrap();
+        assert_eq!(control(&game1, "d5"), SquareControl { white: 1, black: 1 });
+        assert_eq!(control(&game1, "d5").controlled_by(), None);
+        let empty = Game::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(empty.control_map(), [SquareControl::default(); 64]);
+    }
+
+    //check that a square is attacked exactly when it has attackers in the map
+    #[test]
+    fn check_control_map_attacks() {
+        for game1 in random_games(20, 0x510E527FADE682D1) {
+            let map = game1.control_map();
+            for (index, control) in map.iter().enumerate() {
+                for color in [Color::White, Color::Black] {
+                    assert_eq!(control.attackers(color) > 0, game1.position.is_attacked(index, color), "{} {}", game1.get_fen(), index);
+                }
+            }
+        }
+    }
+
+    fn squares(names: &[&str]) -> Vec<Square> {
+        let mut squares: Vec<Square> = names.iter().map(|name| Square::from_algebraic(name).unwrap()).collect();
+        squares.sort();
+        return squares;
+    }
+
+    //check the attacks of a knight, a blocked rook and the pawns of both colors
+    #[test]
+    fn check_attacks_from() {
+        let d4 = Square::from_algebraic("d4").unwrap();
+        assert_eq!(empty_board_attacks(PieceRole::Knight, Color::White, d4), squares(&["b3", "b5", "c2", "c6", "e2", "e6", "f3", "f5"]));
+        // the rook stops on the piece on a4, whoever it belongs to
+        let game1 = Game::from_fen("4k3/8/8/8/p7/8/8/R3K3 w - - 0 1").unwrap();
+        let rook = game1.get_piece("a1").unwrap();
+        assert_eq!(rook.attacks_from(Square::from_algebraic("a1").unwrap(), &game1), squares(&["a2", "a3", "a4", "b1", "c1", "d1", "e1"]));
+        assert_eq!(empty_board_attacks(PieceRole::Rook, Color::White, Square::from_algebraic("a1").unwrap()).len(), 14);
+        // the piece doesn't have to stand on the square, and the king on e1 blocks too
+        let rook = Piece::new(PieceRole::Rook, Color::Black, false);
+        assert_eq!(rook.attacks_from(Square::from_algebraic("b1").unwrap(), &game1), squares(&["a1", "c1", "d1", "e1", "b2", "b3", "b4", "b5", "b6", "b7", "b8"]));
+        // pawns only attack diagonally forward, which differs by color
+        assert_eq!(empty_board_attacks(PieceRole::Pawn, Color::White, d4), squares(&["c5", "e5"]));
+        assert_eq!(empty_board_attacks(PieceRole::Pawn, Color::Black, d4), squares(&["c3", "e3"]));
+        assert_eq!(empty_board_attacks(PieceRole::Pawn, Color::White, Square::from_algebraic("a2").unwrap()), squares(&["b3"]));
+        assert_eq!(empty_board_attacks(PieceRole::Queen, Color::White, d4).len(), 27);
+        assert_eq!(empty_board_attacks(PieceRole::King, Color::Black, Square::from_algebraic("h8").unwrap()), squares(&["g8", "g7", "h7"]));
+    }
+
+    //check that the attacks of every piece are the squares the control map counts
+    #[test]
+    fn check_attacks_from_control_map() {
+        for game1 in random_games(10, 0x9B05688C2B3E6C1F) {
+            let mut counts = [0u8; 64];
+            for index in 0..64 {
+                let Some(piece) = game1.position.piece_at(index) else {continue};
+                for target in piece.attacks_from(Square(index as u8), &game1) {
+                    counts[target.index()] += 1;
+                }
+            }
+            let map = game1.control_map();
+            for index in 0..64 {
+                assert_eq!(counts[index], map[index].white + map[index].black, "{} {}", game1.get_fen(), index);
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Color, Game, Move, Piece, PieceRole, Position, Square, Variant};
+
+/// The roles that can be in a pocket, in the order of the counts of Position::pockets.
+pub(crate) const POCKET_ROLES: [PieceRole; 5] = [PieceRole::Pawn, PieceRole::Knight, PieceRole::Bishop, PieceRole::Rook, PieceRole::Queen];
+
+/// The index of the role in the counts of a pocket. Kings are never taken in Crazyhouse.
+pub(crate) fn pocket_slot(role: PieceRole) -> usize {
+    return POCKET_ROLES.iter().position(|&pocket_role| pocket_role == role).unwrap();
+}
+
+impl Position {
+    /// Returns the legal drops of the player to move. A drop can't leave the own king in check
+    /// unless it was already, so only then are the drops tried out.
+    pub(crate) fn drop_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        if self.variant != Variant::Crazyhouse {return moves;}
+        let in_check = self.in_check(self.turn);
+        for (slot, &role) in POCKET_ROLES.iter().enumerate() {
+            if self.pockets[self.turn as usize][slot] == 0 {continue;}
+            for to in 0..64 {
+                // pawns can't be dropped on the first or last rank
+                if self.board[to] != 0 || (role == PieceRole::Pawn && (to / 8 == 0 || to / 8 == 7)) {continue;}
+                let mv = Move::new_drop(role, Square(to as u8));
+                if in_check {
+                    let mut position_copy = *self;
+                    position_copy.apply_move(mv);
+                    if position_copy.in_check(self.turn) {continue;}
+                }
+                moves.push(mv);
+            }
+        }
+        return moves;
+    }
+
+    /// Puts a piece from the pocket of the player to move on the board, see Move::new_drop.
+    pub(crate) fn apply_drop(&mut self, mv: Move) {
+        let role = mv.promotion.unwrap();
+        self.pockets[self.turn as usize][pocket_slot(role)] -= 1;
+        // a pawn dropped on its starting row can still double step
+        let starting_row = if self.turn == Color::White {6} else {1};
+        let has_moved = role != PieceRole::Pawn || mv.to.index() / 8 != starting_row;
+        self.set_piece(mv.to.index(), Some(Piece::new(role, self.turn, has_moved)));
+        self.halfmove += 1;
+        self.ep_square = None;
+        if self.turn == Color::Black {self.fullmove += 1;}
+        self.turn = self.turn.opposite();
+    }
+
+    /// Puts the piece on the index into the pocket of the player to move, who takes it. A
+    /// promoted piece goes back into the pocket as a pawn.
+    pub(crate) fn pocket_capture(&mut self, index: usize) {
+        let piece = self.piece_at(index).unwrap();
+        let role = if self.promoted >> index & 1 == 1 {PieceRole::Pawn} else {piece.role};
+        self.pockets[self.turn as usize][pocket_slot(role)] += 1;
+        self.promoted &= !(1 << index);
+    }
+
+    /// Writes the pockets for FEN, e.g. "[QNp]": White's pieces in capitals, then Black's.
+    pub(crate) fn pocket_fen(&self) -> String {
+        let mut fen = String::from("[");
+        for color in [Color::White, Color::Black] {
+            for (slot, &role) in POCKET_ROLES.iter().enumerate().rev() {
+                let letter = match role {
+                    PieceRole::Pawn => 'p',
+                    PieceRole::Knight => 'n',
+                    PieceRole::Bishop => 'b',
+                    PieceRole::Rook => 'r',
+                    _ => 'q'
+                };
+                for _ in 0..self.pockets[color as usize][slot] {
+                    fen.push(if color == Color::White {letter.to_ascii_uppercase()} else {letter});
+                }
+            }
+        }
+        fen.push(']');
+        return fen;
+    }
+}
+
+impl Game {
+    /// Get how many pieces of the role the player has in their pocket to drop, in Crazyhouse.
+    pub fn pocket_count(&self, color: Color, role: PieceRole) -> u8 {
+        if role == PieceRole::King {return 0;}
+        return self.position.pockets[color as usize][pocket_slot(role)];
+    }
+
+    /// The id for a piece that is dropped, one more than any id given out so far, so that the
+    /// ids of taken pieces aren't used again.
+    pub(crate) fn next_id(&self) -> u8 {
+        let on_board = self.ids.iter().copied().max().unwrap_or(0);
+        let taken = self.history.iter().filter_map(|entry| entry.result.captured).map(|piece| piece.id).max().unwrap_or(0);
+        return on_board.max(taken).saturating_add(1);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, GameState, Move, PieceRole, Square, Variant};
+
+    fn drop(role: PieceRole, square: &str) -> Move {
+        return Move::new_drop(role, Square::from_algebraic(square).unwrap());
+    }
+
+    //check a game with captures into the pockets, drops and a promoted piece going back as a pawn
+    #[test]
+    fn check_crazyhouse_game() {
+        let mut game1 = Game::new_variant(Variant::Crazyhouse);
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1");
+        for mv in ["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!((game1.pocket_count(Color::White, PieceRole::Pawn), game1.pocket_count(Color::Black, PieceRole::Pawn)), (1, 1));
+        // a pawn can be dropped on any empty square but the first and last rank
+        let drops = game1.get_all_possible_moves().into_iter().filter(|mv| mv.is_drop()).count();
+        assert_eq!(drops, 32);
+        assert!(!game1.is_legal_move(&drop(PieceRole::Pawn, "e8")));
+        let result = game1.play_move(&drop(PieceRole::Pawn, "d5")).unwrap();
+        assert_eq!(result.piece.id(), 33);
+        assert_eq!(game1.pocket_count(Color::White, PieceRole::Pawn), 0);
+        assert_eq!(game1.get_fen(), "rnb1kbnr/ppp1pppp/8/q2P4/8/2N5/PPPP1PPP/R1BQKBNR[p] b KQkq - 3 4");
+        game1.undo().unwrap();
+        assert_eq!(game1.pocket_count(Color::White, PieceRole::Pawn), 1);
+        // a promoted queen that is taken goes into the pocket as a pawn
+        let mut game1 = Game::from_fen("1r2k3/P2n4/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+        assert_eq!(game1.variant(), Variant::Crazyhouse);
+        game1.play_move(&coordinate_move("a7b8q")).unwrap();
+        assert_eq!(game1.get_fen(), "1Q~2k3/3n4/8/8/8/8/8/4K3[R] b - - 0 1");
+        game1.play_move(&coordinate_move("d7b8")).unwrap();
+        assert_eq!(game1.get_fen(), "1n2k3/8/8/8/8/8/8/4K3[Rp] w - - 0 2");
+        assert_eq!(Game::from_fen(&game1.get_fen()).unwrap().get_fen(), game1.get_fen());
+        assert_eq!(Game::from_fen("1Q~2k3/3n4/8/8/8/8/8/4K3[R] b - - 0 1").unwrap().get_fen(), "1Q~2k3/3n4/8/8/8/8/8/4K3[R] b - - 0 1");
+    }
+
+    //check that a drop can block a check that would otherwise be mate, and how drops are written
+    #[test]
+    fn check_crazyhouse_drop_blocks_mate() {
+        let fen = "k7/8/8/8/8/8/PP6/K6r w - - 0 1";
+        assert_eq!(Game::from_fen_variant(fen, Variant::Crazyhouse).unwrap().get_game_state(), GameState::Checkmate);
+        let mut game1 = Game::from_fen("k7/8/8/8/8/8/PP6/K6r[N] w - - 0 1").unwrap();
+        assert_eq!(game1.get_game_state(), GameState::Check);
+        let moves: Vec<String> = game1.get_all_possible_moves().iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(moves, ["N@b1", "N@c1", "N@d1", "N@e1", "N@f1", "N@g1"]);
+        assert_eq!(game1.to_san(&drop(PieceRole::Knight, "b1")).unwrap(), "N@b1");
+        assert_eq!(game1.play_move(&drop(PieceRole::Knight, "c1")).unwrap().state, GameState::InProgress);
+        assert!(game1.validate_position().is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Game, Move, Piece, Position, Square};
+
+/// What happened on a square between two positions, see Game::diff.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SquareChange {
+    /// A piece stands on the square that was empty.
+    Appeared { square: Square, piece: Piece },
+    /// The square is empty where a piece stood.
+    Disappeared { square: Square, piece: Piece },
+    /// Another piece stands on the square, of another role or color.
+    Changed { square: Square, before: Piece, after: Piece },
+}
+
+/// The differences between two positions, see Game::diff.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PositionDiff {
+    /// The squares that changed, in board order (a8 first).
+    pub squares: Vec<SquareChange>,
+    pub turn_changed: bool,
+    pub castling_changed: bool,
+    pub en_passant_changed: bool,
+    /// Whether the halfmove clock or the fullmove number changed.
+    pub clocks_changed: bool,
+    /// The legal move that leads from the first position to the second, if there is one.
+    pub inferred_move: Option<Move>,
+}
+
+impl PositionDiff {
+    /// Returns true if the positions are the same.
+    pub fn is_empty(&self) -> bool {
+        return self.squares.is_empty() && !self.turn_changed && !self.castling_changed && !self.en_passant_changed && !self.clocks_changed;
+    }
+}
+
+impl Game {
+    /// Lists what changed from this position to the other one, e.g. to animate a jump between
+    /// two positions: the squares where a piece appeared, disappeared or was replaced by one of
+    /// another role or color, and whether the turn, the castling rights, the en passant square
+    /// or the clocks changed. Pieces are compared by role and color only, and the pieces of the
+    /// changes are those of the two games, with their ids. If one legal move leads from this
+    /// position to the other, it is inferred, also for castling and en passant.
+    pub fn diff(&self, other: &Game) -> PositionDiff {
+        let mut squares: Vec<SquareChange> = Vec::new();
+        for index in 0..64 {
+            let square = Square(index as u8);
+            match (self.piece_with_id(index), other.piece_with_id(index)) {
+                (None, Some(piece)) => squares.push(SquareChange::Appeared { square, piece }),
+                (Some(piece), None) => squares.push(SquareChange::Disappeared { square, piece }),
+                (Some(before), Some(after)) if (before.role, before.color) != (after.role, after.color) => squares.push(SquareChange::Changed { square, before, after }),
+                _ => ()
+            }
+        }
+        let fields = |game: &Game| -> Vec<String> {game.get_fen().split(' ').map(String::from).collect()};
+        let (before, after) = (fields(self), fields(other));
+        // the move whose position has the same pieces, turn and castling rights as the other,
+        // which changes at most four squares (castling). Pieces are compared by role and color,
+        // since whether a piece other than a king or rook has moved isn't part of the position.
+        // The en passant square isn't compared, since a FEN often leaves it out after a double
+        // step when no capture is possible.
+        let target = &other.position;
+        let same_pieces = |position: &Position| -> bool {
+            (0..64).all(|index| position.piece_at(index).map(|piece| (piece.role, piece.color)) == target.piece_at(index).map(|piece| (piece.role, piece.color)))
+                && position.promoted == target.promoted && position.pockets == target.pockets
+        };
+        let candidates = if squares.is_empty() || squares.len() > 4 {&[][..]} else {&self.legal_moves()[..]};
+        let inferred_move = candidates.iter().copied().find(|&mv| {
+            let mut position = self.position;
+            position.apply_move(mv);
+            return same_pieces(&position) && position.turn == target.turn && position.castling_fen() == target.castling_fen();
+        });
+        return PositionDiff {
+            squares,
+            turn_changed: before[1] != after[1],
+            castling_changed: before[2] != after[2],
+            en_passant_changed: before[3] != after[3],
+            clocks_changed: before[4..6] != after[4..6],
+            inferred_move
+        };
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::SquareChange;
+    use crate::tests::coordinate_move;
+    use crate::{Game, PieceRole, Square};
+
+    fn square(name: &str) -> Square {
+        return Square::from_algebraic(name).unwrap();
+    }
+
+    //check the diff of a normal move and of an en passant capture
+    #[test]
+    fn check_diff_move() {
+        let game1 = Game::new();
+        let mut game2 = game1.clone();
+        game2.play_move(&coordinate_move("g1f3")).unwrap();
+        let diff = game1.diff(&game2);
+        let knight = game1.get_piece("g1").unwrap();
+        assert_eq!(diff.squares, vec![
+            SquareChange::Appeared { square: square("f3"), piece: game2.get_piece("f3").unwrap() },
+            SquareChange::Disappeared { square: square("g1"), piece: knight },
+        ]);
+        assert_eq!(diff.inferred_move, Some(coordinate_move("g1f3")));
+        assert!(diff.turn_changed && !diff.castling_changed && !diff.en_passant_changed && diff.clocks_changed);
+        assert!(game1.diff(&game1).is_empty());
+        // en passant changes three squares
+        let game1 = Game::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
+        let mut game2 = game1.clone();
+        game2.play_move(&coordinate_move("d5e6")).unwrap();
+        let diff = game1.diff(&game2);
+        assert_eq!(diff.squares.len(), 3);
+        assert_eq!(diff.inferred_move, Some(coordinate_move("d5e6")));
+        // a double step is found from a FEN without the en passant square too
+        let game2 = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(Game::new().diff(&game2).inferred_move, Some(coordinate_move("e2e4")));
+        let game2 = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(Game::new().diff(&game2).inferred_move, Some(coordinate_move("e2e4")));
+    }
+
+    //check the diff of castling and of a capture with promotion
+    #[test]
+    fn check_diff_castling() {
+        let game1 = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let mut game2 = game1.clone();
+        game2.play_move(&coordinate_move("e1g1")).unwrap();
+        let diff = game1.diff(&game2);
+        assert_eq!(diff.squares.len(), 4);
+        assert_eq!(diff.inferred_move, Some(coordinate_move("e1g1")));
+        assert!(diff.castling_changed);
+        let game1 = Game::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut game2 = game1.clone();
+        game2.play_move(&coordinate_move("a7b8n")).unwrap();
+        let diff = game1.diff(&game2);
+        assert!(matches!(diff.squares[0], SquareChange::Changed { before, after, .. } if before.role == PieceRole::Rook && after.role == PieceRole::Knight));
+        assert_eq!(diff.inferred_move, Some(coordinate_move("a7b8n")));
+    }
+
+    //check that unrelated positions list every change without a move
+    #[test]
+    fn check_diff_unrelated() {
+        let game1 = Game::new();
+        let game2 = Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 10 40").unwrap();
+        let diff = game1.diff(&game2);
+        assert_eq!(diff.squares.len(), 30);
+        assert!(diff.squares.iter().all(|change| matches!(change, SquareChange::Disappeared { .. })));
+        assert_eq!(diff.inferred_move, None);
+        assert!(diff.turn_changed && diff.castling_changed && !diff.en_passant_changed && diff.clocks_changed);
+        // a position a move away by the other player has no move either
+        let mut game2 = Game::new();
+        game2.play_move(&coordinate_move("e2e4")).unwrap();
+        game2.play_move(&coordinate_move("e7e5")).unwrap();
+        assert_eq!(game1.diff(&game2).inferred_move, None);
+        assert_eq!(game1.diff(&game2).squares.len(), 4);
+    }
+}
@@ -0,0 +1,126 @@
+use super::search::Score;
+use crate::{Color, GameOutcome};
+
+/// When an Adjudicator ends a game early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdjudicationRules {
+    /// A player resigns when its own score has been below -resign_score centipawns ...
+    pub resign_score: i32,
+    /// ... for this many of its moves in a row. 0 turns resigning off.
+    pub resign_moves: u32,
+    /// The game is drawn when the score of every move has been within draw_score centipawns
+    /// of 0 ...
+    pub draw_score: i32,
+    /// ... for this many moves in a row (of either player), without a pawn move. 0 turns
+    /// draws off.
+    pub draw_moves: u32,
+    /// Only moves after this move number count for draws.
+    pub draw_from_move: u32,
+}
+
+impl Default for AdjudicationRules {
+    fn default() -> AdjudicationRules {
+        AdjudicationRules { resign_score: 800, resign_moves: 5, draw_score: 30, draw_moves: 20, draw_from_move: 40 }
+    }
+}
+
+/// Ends engine games whose result is clear, from the scores the engines give their moves.
+/// Record every move of the game, in order.
+#[derive(Debug, Clone)]
+pub struct Adjudicator {
+    rules: AdjudicationRules,
+    /// How many moves in a row White and Black have scored below -resign_score.
+    losing_moves: [u32; 2],
+    /// How many moves in a row have scored close to 0.
+    drawn_moves: u32,
+}
+
+impl Adjudicator {
+    pub fn new(rules: AdjudicationRules) -> Adjudicator {
+        return Adjudicator { rules, losing_moves: [0; 2], drawn_moves: 0 };
+    }
+
+    /// Records a move by `mover` with move number `fullmove`, and the score the mover's engine
+    /// gave it from its own point of view (None if it doesn't score moves, which breaks every
+    /// run). Returns the result if the game is adjudicated with this move.
+    pub fn record_move(&mut self, mover: Color, fullmove: u32, pawn_move: bool, score: Option<Score>) -> Option<GameOutcome> {
+        // mates count as far beyond any threshold
+        let centipawns = score.map(|score| match score {
+            Score::Centipawns(centipawns) => centipawns,
+            Score::Mate(moves) => if moves > 0 {i32::MAX} else {-i32::MAX}
+        });
+        let losing = &mut self.losing_moves[mover as usize];
+        match centipawns {
+            Some(centipawns) if centipawns < -self.rules.resign_score => *losing += 1,
+            _ => *losing = 0
+        }
+        if self.rules.resign_moves > 0 && *losing >= self.rules.resign_moves {return Some(GameOutcome::Win(mover.opposite()));}
+        match centipawns {
+            Some(centipawns) if centipawns.abs() < self.rules.draw_score && fullmove > self.rules.draw_from_move && !pawn_move => self.drawn_moves += 1,
+            _ => self.drawn_moves = 0
+        }
+        if self.rules.draw_moves > 0 && self.drawn_moves >= self.rules.draw_moves {return Some(GameOutcome::Draw);}
+        return None;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{AdjudicationRules, Adjudicator};
+    use crate::engine::{GameOutcome, Score};
+    use crate::Color;
+
+    //check that a player resigns exactly after the configured number of losing moves
+    #[test]
+    fn check_resign_adjudication() {
+        let rules = AdjudicationRules { resign_score: 800, resign_moves: 3, ..AdjudicationRules::default() };
+        let mut adjudicator = Adjudicator::new(rules);
+        // White's scores, Black always says it's winning
+        let white_scores = [-900, -850, -700, -810, -900, -1000];
+        for (move_number, &score) in white_scores.iter().enumerate() {
+            let result = adjudicator.record_move(Color::White, move_number as u32 + 1, false, Some(Score::Centipawns(score)));
+            // -700 breaks the run, so the third losing move in a row is the sixth move
+            assert_eq!(result, if move_number == 5 {Some(GameOutcome::Win(Color::Black))} else {None});
+            assert_eq!(adjudicator.record_move(Color::Black, move_number as u32 + 1, false, Some(Score::Centipawns(900))), None);
+        }
+        // getting mated counts, not scoring doesn't
+        let mut adjudicator = Adjudicator::new(rules);
+        assert_eq!(adjudicator.record_move(Color::Black, 1, false, Some(Score::Mate(-5))), None);
+        assert_eq!(adjudicator.record_move(Color::Black, 2, false, None), None);
+        assert_eq!(adjudicator.record_move(Color::Black, 3, false, Some(Score::Mate(-4))), None);
+        assert_eq!(adjudicator.record_move(Color::Black, 4, false, Some(Score::Mate(-3))), None);
+        assert_eq!(adjudicator.record_move(Color::Black, 5, false, Some(Score::Mate(-2))), Some(GameOutcome::Win(Color::White)));
+    }
+
+    //check that a draw is adjudicated exactly after the configured number of quiet moves after move 40
+    #[test]
+    fn check_draw_adjudication() {
+        let rules = AdjudicationRules { draw_score: 30, draw_moves: 6, draw_from_move: 40, ..AdjudicationRules::default() };
+        let mut adjudicator = Adjudicator::new(rules);
+        let mut adjudicated_at = None;
+        for ply in 0..40 {
+            let (mover, fullmove) = (if ply % 2 == 0 {Color::White} else {Color::Black}, 36 + ply / 2);
+            // a pawn move on move 42 starts the count again
+            let pawn_move = fullmove == 42 && mover == Color::White;
+            if let Some(result) = adjudicator.record_move(mover, fullmove, pawn_move, Some(Score::Centipawns(10))) {
+                assert_eq!(result, GameOutcome::Draw);
+                adjudicated_at = Some((fullmove, mover));
+                break;
+            }
+        }
+        // the moves of Black on move 42 to White on move 45 make 6
+        assert_eq!(adjudicated_at, Some((45, Color::White)));
+        // a score that isn't close to 0 breaks the run
+        let mut adjudicator = Adjudicator::new(rules);
+        for (ply, score) in [10, -20, 29, 30, 0, 5, -5, 10, 15, -29, 0].into_iter().enumerate() {
+            let mover = if ply % 2 == 0 {Color::White} else {Color::Black};
+            let result = adjudicator.record_move(mover, 50 + ply as u32 / 2, false, Some(Score::Centipawns(score)));
+            assert_eq!(result, if ply == 9 {Some(GameOutcome::Draw)} else {None});
+            if result.is_some() {break;}
+        }
+    }
+}
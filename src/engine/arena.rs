@@ -0,0 +1,166 @@
+use super::adjudicate::{AdjudicationRules, Adjudicator};
+use super::backend::{Engine, SearchLimits};
+use crate::{Color, Game, GameOutcome, GameState, Move, PieceRole};
+
+/// Why a game between engines ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    /// 100 plies without a capture or a pawn move.
+    FiftyMoves,
+    /// The same position for the third time.
+    Repetition,
+    /// An engine gave no move or an illegal move, and lost.
+    Forfeit,
+    /// The Adjudicator ended the game, see AdjudicationRules.
+    Adjudication,
+    /// The ply limit was reached, so the game has no result.
+    PlyLimit,
+}
+
+/// A game played by play_game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    /// The names of the engines.
+    pub white: String,
+    pub black: String,
+    /// The FEN of the position the game started from.
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    /// The result, or None if the game reached the ply limit.
+    pub outcome: Option<GameOutcome>,
+    pub termination: Termination,
+}
+
+impl GameRecord {
+    /// Writes the game in Portable Game Notation (PGN), with the seven tags every PGN game
+    /// has (the event and round as given), the start position if the game didn't start from
+    /// the start position, how it ended and the moves in standard algebraic notation.
+    pub fn to_pgn(&self, event: &str, round: u32) -> String {
+        let result = match self.outcome {
+            Some(GameOutcome::Win(Color::White)) => "1-0",
+            Some(GameOutcome::Win(Color::Black)) => "0-1",
+            Some(GameOutcome::Draw) => "1/2-1/2",
+            None => "*"
+        };
+        let termination = match self.termination {
+            Termination::Adjudication => "adjudication",
+            Termination::Forfeit => "rules infraction",
+            Termination::PlyLimit => "unterminated",
+            _ => "normal"
+        };
+        let mut pgn = format!(
+            "[Event \"{}\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"{}\"]\n[White \"{}\"]\n[Black \"{}\"]\n[Result \"{}\"]\n",
+            event, round, self.white, self.black, result
+        );
+        let start = Game::from_fen(&self.start_fen).unwrap_or_default();
+        if self.start_fen != Game::new().get_fen() {pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", self.start_fen));}
+        pgn.push_str(&format!("[Termination \"{}\"]\n\n", termination));
+        // the move numbers go before White's moves, and before Black's first if Black starts
+        let mut tokens: Vec<String> = Vec::new();
+        let (mut fullmove, mut turn) = (start.position.fullmove, start.get_turn_color());
+        for (ply, san) in start.position.san_line(&self.moves).into_iter().enumerate() {
+            if turn == Color::White {tokens.push(format!("{}.", fullmove));}
+            else if ply == 0 {tokens.push(format!("{}...", fullmove));}
+            tokens.push(san);
+            if turn == Color::Black {fullmove += 1;}
+            turn = turn.opposite();
+        }
+        tokens.push(result.to_string());
+        // lines of at most 80 characters
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {line.push(' ');}
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        return pgn;
+    }
+}
+
+/// Lets two engines play a game from the start position, each choosing its moves within the
+/// limits, until the game ends by the rules or after `max_plies` moves. An engine that gives
+/// no move or an illegal move loses the game. With adjudication rules the game also ends when
+/// the scores of the engines make the result clear.
+pub fn play_game(white: &mut dyn Engine, black: &mut dyn Engine, start: &Game, limits: &SearchLimits, max_plies: u32, adjudication: Option<AdjudicationRules>) -> GameRecord {
+    let mut game = start.clone();
+    let mut moves: Vec<Move> = Vec::new();
+    let mut adjudicator = adjudication.map(Adjudicator::new);
+    let (outcome, termination) = loop {
+        let turn = game.get_turn_color();
+        match game.get_game_state() {
+            GameState::Checkmate => break (Some(GameOutcome::Win(turn.opposite())), Termination::Checkmate),
+            GameState::Stalemate => break (Some(GameOutcome::Draw), Termination::Stalemate),
+            _ => ()
+        }
+        if game.get_halfmove() >= 100 {break (Some(GameOutcome::Draw), Termination::FiftyMoves);}
+        if game.is_threefold_repetition() {break (Some(GameOutcome::Draw), Termination::Repetition);}
+        if moves.len() as u32 >= max_plies {break (None, Termination::PlyLimit);}
+        let engine: &mut dyn Engine = if turn == Color::White {&mut *white} else {&mut *black};
+        let chosen = match engine.choose_move(&game, limits) {
+            Some(chosen) if game.is_legal_move(&chosen.mv) => chosen,
+            _ => break (Some(GameOutcome::Win(turn.opposite())), Termination::Forfeit)
+        };
+        let fullmove = game.position.fullmove;
+        let result = game.play_move(&chosen.mv).unwrap();
+        moves.push(result.mv);
+        let adjudicated = adjudicator.as_mut().and_then(|adjudicator| {
+            adjudicator.record_move(turn, fullmove, result.piece.role == PieceRole::Pawn, chosen.score)
+        });
+        // a move that ends the game by the rules decides it
+        if adjudicated.is_some() && !game.get_all_possible_moves().is_empty() {break (adjudicated, Termination::Adjudication);}
+    };
+    return GameRecord { white: white.name().to_string(), black: black.name().to_string(), start_fen: start.get_fen(), moves, outcome, termination };
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{play_game, Termination};
+    use crate::engine::AdjudicationRules;
+    use crate::engine::{AlphaBetaEngine, Engine, SearchLimits};
+    use crate::tests::FirstMoveEngine;
+    use crate::{Color, Game, GameOutcome};
+
+    //check that engines behind the trait play games to the end
+    #[test]
+    fn check_play_game() {
+        let mut strong: Box<dyn Engine> = Box::new(AlphaBetaEngine::default());
+        let mut weak: Box<dyn Engine> = Box::new(FirstMoveEngine);
+        let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let record = play_game(strong.as_mut(), weak.as_mut(), &game1, &SearchLimits::depth(2), 100, None);
+        assert_eq!(record.outcome, Some(GameOutcome::Win(Color::White)));
+        assert_eq!(record.termination, Termination::Checkmate);
+        assert_eq!((record.white.as_str(), record.black.as_str(), record.moves.len()), ("alpha-beta", "first move", 1));
+        // the first legal move engine against itself ends by the rules or the ply limit
+        let record = play_game(&mut FirstMoveEngine, &mut FirstMoveEngine, &Game::new(), &SearchLimits::depth(1), 200, None);
+        let mut game1 = Game::new();
+        for mv in &record.moves {
+            assert!(game1.play_move(mv).is_some());
+        }
+        assert!(record.moves.len() <= 200);
+        assert_eq!(record.outcome.is_none(), record.termination == Termination::PlyLimit);
+    }
+
+    //check that the adjudicator ends a lost game early
+    #[test]
+    fn check_play_game_adjudication() {
+        // the first legal move engine doesn't score, so only the alpha-beta engine can resign
+        let game1 = Game::from_fen("4k3/8/8/8/8/8/8/q3K3 w - - 0 1").unwrap();
+        let rules = AdjudicationRules { resign_score: 800, resign_moves: 2, ..AdjudicationRules::default() };
+        let record = play_game(&mut AlphaBetaEngine::default(), &mut FirstMoveEngine, &game1, &SearchLimits::depth(2), 100, Some(rules));
+        assert_eq!(record.outcome, Some(GameOutcome::Win(Color::Black)));
+        assert_eq!(record.termination, Termination::Adjudication);
+        assert_eq!(record.moves.len(), 3);
+    }
+}
@@ -0,0 +1,396 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use super::params::EvalParams;
+use super::search::{find_best_move_controlled, find_best_move_limited, score_moves_signalled, Score, SearchInfo, SearchResult, StopSignal};
+use super::time::Instant;
+use crate::{Color, Game, Move, XorShift};
+
+/// The highest skill level, which plays the best move found.
+pub const MAX_SKILL: u8 = 20;
+
+/// How long an engine may think about a move. The engine stops at whichever limit it reaches
+/// first, so at least one should be set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    /// The most plies to search, for engines that search to a depth.
+    pub depth: Option<u32>,
+    /// The most time to think.
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    /// Search `depth` plies deep, however long it takes.
+    pub fn depth(depth: u32) -> SearchLimits {
+        return SearchLimits { depth: Some(depth), time: None };
+    }
+
+    /// Think for `time`.
+    pub fn time(time: Duration) -> SearchLimits {
+        return SearchLimits { depth: None, time: Some(time) };
+    }
+}
+
+/// The move an engine chose, with what it knows about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineMove {
+    pub mv: Move,
+    /// The score of the move from the point of view of the player whose turn it is, if the
+    /// engine scores moves.
+    pub score: Option<Score>,
+    /// The moves both players are expected to make, starting with mv. Engines that don't look
+    /// ahead give only mv.
+    pub pv: Vec<Move>,
+}
+
+/// What a protocol like UCI gives an engine while it thinks, see Engine::search: when to stop,
+/// where the progress goes and how many lines the GUI wants to see.
+pub struct SearchControl<'a> {
+    signal: &'a StopSignal,
+    info: &'a mut dyn FnMut(&SearchInfo),
+    multipv: usize,
+}
+
+impl<'a> SearchControl<'a> {
+    pub(super) fn new(signal: &'a StopSignal, info: &'a mut dyn FnMut(&SearchInfo), multipv: usize) -> SearchControl<'a> {
+        return SearchControl { signal, info, multipv: multipv.max(1) };
+    }
+
+    /// Whether the engine has to stop thinking and answer with the best move it has: the GUI
+    /// said "stop", or the time is up.
+    pub fn should_stop(&self) -> bool {
+        if self.signal.stop.load(Ordering::Relaxed) {return true;}
+        return self.signal.deadline.lock().unwrap().is_some_and(|deadline| Instant::now() >= deadline);
+    }
+
+    /// The time left to think, or None if the engine thinks until it is told to stop, like
+    /// while pondering or with "go infinite".
+    pub fn remaining(&self) -> Option<Duration> {
+        return self.signal.deadline.lock().unwrap().map(|deadline| deadline - Instant::now());
+    }
+
+    /// Shows the progress of the search in the GUI.
+    pub fn report(&mut self, info: &SearchInfo) {
+        (self.info)(info);
+    }
+
+    /// How many of the best lines the GUI wants reported, each starting with a different move
+    /// and told apart by SearchInfo::multipv. An engine that only finds one reports one.
+    pub fn multipv(&self) -> usize {
+        return self.multipv;
+    }
+}
+
+/// A way of choosing moves. The engines of the library implement it, and so can engines outside
+/// of it, so that a caller (like play_game or uci::run) can use any of them, also as a
+/// Box<dyn Engine>.
+pub trait Engine {
+    /// Chooses a move for the player whose turn it is within the limits. Returns None if the
+    /// game is over.
+    fn choose_move(&mut self, game: &Game, limits: &SearchLimits) -> Option<EngineMove>;
+
+    /// The name of the engine, e.g. to show in a GUI or a PGN header.
+    fn name(&self) -> &str;
+
+    /// Chooses a move like choose_move, for a protocol that can stop the engine at any time
+    /// and shows its progress. The time limit is in the control, since while pondering it
+    /// isn't known yet when the search starts, so the limits only have the depth. By default
+    /// this is choose_move with the time left, which can't be stopped early and reports
+    /// nothing.
+    fn search(&mut self, game: &Game, limits: &SearchLimits, control: &mut SearchControl) -> Option<EngineMove> {
+        return self.choose_move(game, &SearchLimits { time: control.remaining().or(limits.time), ..*limits });
+    }
+
+    /// Plays at the skill level (0 to MAX_SKILL) and with the contempt for draws in
+    /// centipawns (see EvalParams::contempt) that a GUI set. By default both are ignored, for
+    /// engines that have neither.
+    fn configure(&mut self, _skill: u8, _contempt: i32) {}
+}
+
+/// A boxed engine is an engine too, so that e.g. uci::run can serve one chosen at run time.
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn choose_move(&mut self, game: &Game, limits: &SearchLimits) -> Option<EngineMove> {
+        return (**self).choose_move(game, limits);
+    }
+
+    fn name(&self) -> &str {
+        return (**self).name();
+    }
+
+    fn search(&mut self, game: &Game, limits: &SearchLimits, control: &mut SearchControl) -> Option<EngineMove> {
+        return (**self).search(game, limits, control);
+    }
+
+    fn configure(&mut self, skill: u8, contempt: i32) {
+        (**self).configure(skill, contempt);
+    }
+}
+
+/// Varies the first moves of an AlphaBetaEngine, so that its games don't all start the same
+/// way without an opening book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpeningVariety {
+    /// The number of plies from the start of the game, counted by the move number, in which
+    /// the engine varies its moves.
+    pub plies: u32,
+    /// How many centipawns worse than the best a move may score and still be picked. 0 turns
+    /// the variety off.
+    pub margin: i32,
+}
+
+/// The settings of an AlphaBetaEngine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineOptions {
+    /// How well the engine plays, from 0 to MAX_SKILL. Below MAX_SKILL the engine searches
+    /// less deep and picks at random among the moves close to the best, more so the lower the
+    /// skill.
+    pub skill: u8,
+    /// The seed of the random choices, so the same seed makes the same choices.
+    pub seed: u64,
+    /// The weights the engine evaluates positions with, and its contempt for draws.
+    pub eval: EvalParams,
+    /// At the highest skill, picks at random among the moves close to the best in the first
+    /// plies of the game, with the seed. None, the default, always plays the best move.
+    pub opening_variety: Option<OpeningVariety>,
+}
+
+impl Default for EngineOptions {
+    fn default() -> EngineOptions {
+        EngineOptions { skill: MAX_SKILL, seed: 1, eval: EvalParams::DEFAULT, opening_variety: None }
+    }
+}
+
+/// The alpha-beta search of find_best_move_timed as an Engine, playing at the skill level of
+/// its options.
+#[derive(Debug, Clone)]
+pub struct AlphaBetaEngine {
+    options: EngineOptions,
+    rng: XorShift,
+}
+
+impl AlphaBetaEngine {
+    pub fn new(options: EngineOptions) -> AlphaBetaEngine {
+        return AlphaBetaEngine { options, rng: XorShift::new(options.seed) };
+    }
+
+    /// Chooses a move below the highest skill: every move is scored by a search that gets
+    /// deeper with the skill, and one of the best few moves that aren't much worse than the
+    /// best is picked at random. The lower the skill, the more moves and the bigger the margin,
+    /// and the more often the best move is left out if it is a capture. Moves that get mated
+    /// are only played if every move does. If the signal stops the search, the pick is among
+    /// the moves scored so far, or the first legal move if there are none.
+    fn choose_weaker_move(&mut self, game: &Game, limits: &SearchLimits, signal: Option<&StopSignal>) -> Option<EngineMove> {
+        let handicap = (MAX_SKILL - self.options.skill.min(MAX_SKILL)) as i32;
+        let depth = limits.depth.unwrap_or(u32::MAX).min(2 + self.options.skill as u32 / 7).max(1);
+        let mut scores = score_moves_signalled(game, depth, &self.options.eval, signal);
+        if scores.is_empty() {
+            // the game is over, or the search was stopped before any move was scored
+            let &mv = game.legal_moves().first()?;
+            return Some(EngineMove { mv, score: None, pv: vec![mv] });
+        }
+        // sort_by_key is stable, so equal moves keep the generation order
+        scores.sort_by_key(|&(_, score)| -score);
+        let best = scores[0].1;
+        let margin = 15 * handicap;
+        let count = 1 + handicap as usize / 3;
+        let gets_mated = |score: i32| matches!(Score::from_search_score(score), Score::Mate(moves) if moves < 0);
+        let mut candidates: Vec<(Move, i32)> = scores.iter().copied()
+            .take(count)
+            .filter(|&(_, score)| score >= best - margin && (!gets_mated(score) || gets_mated(best)))
+            .collect();
+        // now and then the best capture is overlooked
+        if candidates.len() > 1 && game.position.is_capture(candidates[0].0) && self.rng.next_index(40) < handicap as usize {
+            candidates.remove(0);
+        }
+        let (mv, score) = candidates[self.rng.next_index(candidates.len())];
+        return Some(EngineMove { mv, score: Some(Score::from_search_score(score)), pv: vec![mv] });
+    }
+
+    /// Picks at random among the moves that score at most the margin of the variety below the
+    /// best, in a search as deep as the one that found the best move, if the game is still in
+    /// the plies of the variety. Returns None to play the best move, also if the signal stops
+    /// the search before any move is scored.
+    fn choose_varied_move(&mut self, game: &Game, depth: u32, signal: Option<&StopSignal>) -> Option<(Move, i32)> {
+        let variety = self.options.opening_variety.filter(|variety| variety.margin > 0)?;
+        let ply = 2 * (game.position.fullmove - 1) + (game.position.turn == Color::Black) as u32;
+        if ply >= variety.plies {return None;}
+        let scores = score_moves_signalled(game, depth.max(1), &self.options.eval, signal);
+        let best = scores.iter().map(|&(_, score)| score).max()?;
+        let candidates: Vec<(Move, i32)> = scores.into_iter().filter(|&(_, score)| score >= best - variety.margin).collect();
+        return Some(candidates[self.rng.next_index(candidates.len())]);
+    }
+
+    /// The move of a search at the highest skill, or a varied move in the first plies.
+    fn play_best_move(&mut self, game: &Game, result: SearchResult, mut pv: Vec<Move>, signal: Option<&StopSignal>) -> Option<EngineMove> {
+        let (mut mv, mut score) = (result.best_move?, result.score);
+        if let Some(varied) = self.choose_varied_move(game, result.depth, signal) {(mv, score) = varied;}
+        // a move from an unfinished iteration, or a varied move, has no line yet
+        if pv.first() != Some(&mv) {pv = vec![mv];}
+        return Some(EngineMove { mv, score: Some(Score::from_search_score(score)), pv });
+    }
+}
+
+impl Default for AlphaBetaEngine {
+    fn default() -> AlphaBetaEngine {
+        AlphaBetaEngine::new(EngineOptions::default())
+    }
+}
+
+impl Engine for AlphaBetaEngine {
+    fn choose_move(&mut self, game: &Game, limits: &SearchLimits) -> Option<EngineMove> {
+        if self.options.skill < MAX_SKILL {return self.choose_weaker_move(game, limits, None);}
+        let (result, pv) = find_best_move_limited(game, limits.depth, limits.time, &self.options.eval);
+        return self.play_best_move(game, result, pv, None);
+    }
+
+    fn name(&self) -> &str {
+        return "alpha-beta";
+    }
+
+    /// Searches until the control says to stop, reporting every finished depth with as many
+    /// lines as the GUI wants. Below the highest skill the move comes from the short search of
+    /// the skill, which isn't worth reporting.
+    fn search(&mut self, game: &Game, limits: &SearchLimits, control: &mut SearchControl) -> Option<EngineMove> {
+        let signal = control.signal;
+        if self.options.skill < MAX_SKILL {return self.choose_weaker_move(game, limits, Some(signal));}
+        let multipv = control.multipv;
+        let (result, pv) = find_best_move_controlled(game, limits.depth, &self.options.eval, signal, multipv, |info| control.report(info));
+        return self.play_best_move(game, result, pv, Some(signal));
+    }
+
+    fn configure(&mut self, skill: u8, contempt: i32) {
+        self.options.skill = skill.min(MAX_SKILL);
+        self.options.eval.contempt = contempt;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{AlphaBetaEngine, EngineOptions, OpeningVariety, MAX_SKILL};
+    use crate::engine::search::{find_best_move_limited, score_moves};
+    use crate::engine::{solve_mate, Engine, EngineMove, EvalParams, Score, SearchLimits};
+    use crate::tests::{coordinate_move, random_games};
+    use crate::{Game, Move};
+
+    //check that the highest skill plays the same moves as the search
+    #[test]
+    fn check_max_skill() {
+        let mut engine = AlphaBetaEngine::new(EngineOptions { skill: MAX_SKILL, seed: 5, ..EngineOptions::default() });
+        for game1 in random_games(30, 0x6A09E667F3BCC908) {
+            let chosen = engine.choose_move(&game1, &SearchLimits::depth(3)).map(|chosen| chosen.mv);
+            assert_eq!(chosen, find_best_move_limited(&game1, Some(3), None, &EvalParams::DEFAULT).0.best_move);
+        }
+    }
+
+    //check that the lowest skill plays legal moves that don't allow a mate in one if it can
+    #[test]
+    fn check_min_skill() {
+        let mut engine = AlphaBetaEngine::new(EngineOptions { skill: 0, seed: 5, ..EngineOptions::default() });
+        let allows_mate = |game: &Game, mv| -> bool {
+            let mut game = game.clone();
+            game.play_move(&mv).unwrap();
+            return solve_mate(&game, 1).is_some();
+        };
+        for game1 in random_games(100, 0xBB67AE8584CAA73B) {
+            let chosen = match engine.choose_move(&game1, &SearchLimits::depth(20)) {
+                Some(chosen) => chosen.mv,
+                None => {assert!(game1.get_all_possible_moves().is_empty()); continue;}
+            };
+            assert!(game1.is_legal_move(&chosen));
+            if allows_mate(&game1, chosen) {
+                assert!(game1.get_all_possible_moves().into_iter().all(|mv| allows_mate(&game1, mv)));
+            }
+        }
+        // 7 of the 16 moves allow Rd1#
+        let game1 = Game::from_fen("3r2k1/5ppp/8/8/8/2N5/5PPP/6K1 w - - 0 1").unwrap();
+        for _ in 0..20 {
+            let chosen = engine.choose_move(&game1, &SearchLimits::depth(20)).unwrap().mv;
+            assert!(!allows_mate(&game1, chosen));
+        }
+    }
+
+    //check that a low skill varies its moves, the same way for the same seed
+    #[test]
+    fn check_skill_seeded() {
+        let moves = |seed: u64| -> Vec<String> {
+            let mut engine = AlphaBetaEngine::new(EngineOptions { skill: 2, seed, ..EngineOptions::default() });
+            return (0..10).map(|_| engine.choose_move(&Game::new(), &SearchLimits::depth(20)).unwrap().mv.to_string()).collect();
+        };
+        assert_eq!(moves(11), moves(11));
+        let mut distinct = moves(11);
+        distinct.sort();
+        distinct.dedup();
+        assert!(distinct.len() > 1);
+    }
+
+    // the moves of a game the engine plays against itself from the start position
+    fn self_play(options: EngineOptions, plies: usize) -> Vec<Move> {
+        let mut engine = AlphaBetaEngine::new(options);
+        let mut game1 = Game::new();
+        let mut moves = Vec::new();
+        for _ in 0..plies {
+            let mv = engine.choose_move(&game1, &SearchLimits::depth(2)).unwrap().mv;
+            game1.play_move(&mv).unwrap();
+            moves.push(mv);
+        }
+        return moves;
+    }
+
+    //check that opening variety is off by default, repeats with the same seed and varies with another
+    #[test]
+    fn check_opening_variety() {
+        assert_eq!(EngineOptions::default().opening_variety, None);
+        let varied = |seed: u64| EngineOptions { seed, opening_variety: Some(OpeningVariety { plies: 6, margin: 100 }), ..EngineOptions::default() };
+        assert_eq!(self_play(varied(3), 8), self_play(varied(3), 8));
+        // the games of different seeds part within the plies of the variety
+        let games: Vec<Vec<Move>> = (1..=4).map(|seed| self_play(varied(seed), 6)).collect();
+        assert!(games.iter().any(|game| game != &games[0]));
+        // a margin of 0 plays like the engine without variety
+        let deterministic = self_play(EngineOptions::default(), 8);
+        for seed in 1..=4 {
+            let options = EngineOptions { seed, opening_variety: Some(OpeningVariety { plies: 6, margin: 0 }), ..EngineOptions::default() };
+            assert_eq!(self_play(options, 8), deterministic);
+        }
+    }
+
+    //check that a varied move is never worse than the margin, and that the best move is played after the plies
+    #[test]
+    fn check_opening_variety_margin() {
+        let mut engine = AlphaBetaEngine::new(EngineOptions { seed: 9, opening_variety: Some(OpeningVariety { plies: 4, margin: 30 }), ..EngineOptions::default() });
+        let mut game1 = Game::new();
+        for ply in 0..8 {
+            let mv = engine.choose_move(&game1, &SearchLimits::depth(2)).unwrap().mv;
+            let scores = score_moves(&game1, 2, &EvalParams::DEFAULT);
+            let best = scores.iter().map(|&(_, score)| score).max().unwrap();
+            let score = scores.iter().find(|&&(other, _)| other == mv).unwrap().1;
+            assert!(score >= best - 30, "ply {}", ply);
+            if ply >= 4 {assert_eq!(Some(mv), find_best_move_limited(&game1, Some(2), None, &EvalParams::DEFAULT).0.best_move);}
+            game1.play_move(&mv).unwrap();
+        }
+    }
+
+    //check that contempt declines a repetition in an equal position, and negative contempt takes it
+    #[test]
+    fn check_contempt() {
+        let mut game1 = Game::new();
+        for mv in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let choose = |game: &Game, contempt: i32| -> EngineMove {
+            let mut engine = AlphaBetaEngine::new(EngineOptions { eval: EvalParams { contempt, ..EvalParams::DEFAULT }, ..EngineOptions::default() });
+            return engine.choose_move(game, &SearchLimits::depth(3)).unwrap();
+        };
+        assert_ne!(choose(&game1, 100).mv, coordinate_move("f3g1"));
+        let seeking = choose(&game1, -100);
+        assert_eq!(seeking.mv, coordinate_move("f3g1"));
+        assert_eq!(seeking.score, Some(Score::Centipawns(100)));
+        // black seeks the draw too, since contempt is for the player whose turn it is, but
+        // black is a move ahead and needs more contempt to give that up
+        game1.play_move(&coordinate_move("f3g1")).unwrap();
+        assert_eq!(choose(&game1, -200).mv, coordinate_move("f6g8"));
+    }
+}
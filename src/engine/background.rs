@@ -0,0 +1,157 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use super::params::EvalParams;
+use super::search::{find_best_move_controlled, SearchInfo, SearchResult, StopSignal};
+use crate::Game;
+
+/// The settings of an analysis started with Analysis::start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnalysisOptions {
+    /// The deepest depth to search, or None to search until stopped.
+    pub max_depth: Option<u32>,
+    /// The parameters of the evaluation.
+    pub params: EvalParams,
+}
+
+/// Analysis of a position on a background thread, e.g. for an analysis board that shows the
+/// engine's opinion while the user looks at the position. See Analysis::start.
+#[derive(Debug)]
+pub struct Analysis;
+
+impl Analysis {
+    /// Starts searching a copy of the game on a background thread, one depth deeper at a time
+    /// until max_depth (or a forced mate) is reached, or forever if there is no max_depth. The
+    /// progress is sent to the handle every time a depth is finished. Every analysis has its
+    /// own thread and copy of the game, so several can run at once, and a new one can be
+    /// started before the old one is stopped.
+    pub fn start(game: &Game, options: AnalysisOptions) -> AnalysisHandle {
+        let signal = Arc::new(StopSignal::default());
+        let finished = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let game = game.clone();
+        let thread = {
+            let (signal, finished) = (Arc::clone(&signal), Arc::clone(&finished));
+            thread::spawn(move || {
+                // the handle may already be gone, and then nobody reads the progress
+                let (result, _) = find_best_move_controlled(&game, options.max_depth, &options.params, &signal, 1, |info| {let _ = sender.send(info.clone());});
+                finished.store(true, Ordering::Release);
+                result
+            })
+        };
+        return AnalysisHandle { signal, thread: Some(thread), receiver, finished };
+    }
+}
+
+/// A running analysis. Dropping the handle stops the search and waits for the thread, so no
+/// thread is left running.
+#[derive(Debug)]
+pub struct AnalysisHandle {
+    signal: Arc<StopSignal>,
+    thread: Option<JoinHandle<SearchResult>>,
+    receiver: Receiver<SearchInfo>,
+    finished: Arc<AtomicBool>,
+}
+
+impl AnalysisHandle {
+    /// The progress sent since the last call, one SearchInfo per finished depth, the
+    /// shallowest first. Doesn't wait for more.
+    pub fn updates(&self) -> Vec<SearchInfo> {
+        return self.receiver.try_iter().collect();
+    }
+
+    /// Whether the search has ended by itself, at max_depth or at a forced mate. A finished
+    /// analysis still has to be stopped to get its result.
+    pub fn is_finished(&self) -> bool {
+        return self.finished.load(Ordering::Acquire);
+    }
+
+    /// Stops the search and returns the best move of the deepest finished depth, which takes a
+    /// few milliseconds at most. The result has no move if the game is over.
+    pub fn stop(mut self) -> SearchResult {
+        self.signal.stop.store(true, Ordering::Relaxed);
+        return self.thread.take().unwrap().join().unwrap();
+    }
+
+    fn halt(&mut self) {
+        self.signal.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            // the result of an abandoned analysis isn't needed, and neither is a panic in it
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for AnalysisHandle {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::{Analysis, AnalysisOptions};
+    use crate::Game;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    //check that stopping after 50ms returns a legal move quickly, also with another analysis running
+    #[test]
+    fn check_analysis_stop() {
+        let game1 = Game::new();
+        let other = Analysis::start(&Game::from_fen(KIWIPETE).unwrap(), AnalysisOptions::default());
+        let handle = Analysis::start(&game1, AnalysisOptions::default());
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        let result = handle.stop();
+        assert!(start.elapsed() < Duration::from_millis(10));
+        assert!(game1.is_legal_move(&result.best_move.unwrap()));
+        assert!(result.depth >= 1);
+        assert!(other.stop().best_move.is_some());
+        // and a finished game has nothing to analyse
+        let mate = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(Analysis::start(&mate, AnalysisOptions::default()).stop().best_move, None);
+    }
+
+    //check that dropping the handle ends the thread, and that an analysis to a max depth finishes by itself
+    #[test]
+    fn check_analysis_drop() {
+        let handle = Analysis::start(&Game::from_fen(KIWIPETE).unwrap(), AnalysisOptions::default());
+        let finished = Arc::clone(&handle.finished);
+        thread::sleep(Duration::from_millis(50));
+        assert!(!finished.load(Ordering::Acquire));
+        drop(handle);
+        assert!(finished.load(Ordering::Acquire));
+        let handle = Analysis::start(&Game::new(), AnalysisOptions { max_depth: Some(2), ..AnalysisOptions::default() });
+        while !handle.is_finished() {thread::sleep(Duration::from_millis(1));}
+        let depths: Vec<u32> = handle.updates().iter().map(|info| info.depth).collect();
+        assert_eq!(depths, [1, 2]);
+        assert_eq!(handle.stop().depth, 2);
+    }
+
+    //check that the analysis of a tactical position goes deeper over time
+    #[test]
+    fn check_analysis_deepens() {
+        let handle = Analysis::start(&Game::from_fen(KIWIPETE).unwrap(), AnalysisOptions::default());
+        let mut depths = Vec::new();
+        for _ in 0..10 {
+            thread::sleep(Duration::from_millis(30));
+            depths.extend(handle.updates().iter().map(|info| info.depth));
+        }
+        let result = handle.stop();
+        assert!(depths.len() >= 2);
+        assert!(depths.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(result.depth >= *depths.last().unwrap());
+    }
+}
@@ -0,0 +1,221 @@
+use super::kpk::{self, Wdl};
+use crate::{Color, Game, PieceRole, Position, Square, Variant};
+
+/// What the evaluation adds for the winner of a recognized won endgame, so that the engine
+/// heads for it. It is far below the mate scores.
+const KNOWN_WIN: i32 = 2_000;
+
+/// An ending whose result is known from the position alone, see Game::recognize_endgame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndgameClass {
+    /// King and pawn against king, won by the side with the pawn or drawn (None).
+    KingPawnKing(Option<Color>),
+    /// King, bishop and knight against king, won by the side with the pieces.
+    BishopKnightKing(Color),
+    /// King, bishop and pawns on a rook file against king, where the bishop can't cover the
+    /// promotion square and the defending king has reached that corner: a draw.
+    WrongBishop,
+}
+
+impl EndgameClass {
+    /// The color that wins the ending, or None if it is a draw.
+    pub fn winner(&self) -> Option<Color> {
+        match *self {
+            EndgameClass::KingPawnKing(winner) => winner,
+            EndgameClass::BishopKnightKing(winner) => Some(winner),
+            EndgameClass::WrongBishop => None
+        }
+    }
+}
+
+impl Game {
+    /// Recognizes a few endings whose result is known without searching them, and returns
+    /// None for everything else, including the positions of these endings that the rules
+    /// below don't decide:
+    ///
+    /// - king and pawn against king is looked up in a bitbase (see kpk::probe), so every
+    ///   position of it is decided, won or drawn, unless it is stalemate.
+    /// - king, bishop and knight against king is won, unless the defending king can take a
+    ///   piece right away.
+    /// - king, bishop and pawns on one rook file against king is drawn if the bishop doesn't
+    ///   move on the color of the promotion square and the defending king stands on it or
+    ///   beside it.
+    ///
+    /// The evaluation scores the drawn endings as draws and gives a bonus to the winner of
+    /// the won ones.
+    pub fn recognize_endgame(&self) -> Option<EndgameClass> {
+        return self.position.recognize_endgame();
+    }
+}
+
+/// The row and column of the index as seen by the color, with its pawns moving towards row 0.
+fn relative(index: usize, color: Color) -> (i32, i32) {
+    let row = (index / 8) as i32;
+    return (if color == Color::White {row} else {7 - row}, (index % 8) as i32);
+}
+
+/// The number of king moves between two squares.
+fn distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    return (a.0 - b.0).abs().max((a.1 - b.1).abs());
+}
+
+impl Position {
+    pub(crate) fn recognize_endgame(&self) -> Option<EndgameClass> {
+        // the endgames are about a king each, which the variants don't always have
+        if self.variant != Variant::Standard && self.variant != Variant::Chess960 {return None;}
+        let key = self.material_key();
+        for attacker in [Color::White, Color::Black] {
+            let count = |color: Color, role: PieceRole| -> u8 {key.count(color, role)};
+            let defender = attacker.opposite();
+            let bare_king = [PieceRole::Queen, PieceRole::Rook, PieceRole::Bishop, PieceRole::Knight, PieceRole::Pawn].iter().all(|&role| count(defender, role) == 0);
+            if !bare_king || count(attacker, PieceRole::Queen) + count(attacker, PieceRole::Rook) > 0 {continue;}
+            match (count(attacker, PieceRole::Bishop), count(attacker, PieceRole::Knight), count(attacker, PieceRole::Pawn)) {
+                (0, 0, 1) => return self.king_pawn_king(attacker),
+                (1, 1, 0) => return self.bishop_knight_king(attacker),
+                (1, 0, pawns) if pawns > 0 => return self.wrong_bishop(attacker),
+                _ => ()
+            }
+        }
+        return None;
+    }
+
+    fn king_pawn_king(&self, attacker: Color) -> Option<EndgameClass> {
+        // stalemate is for the rules to decide
+        if self.legal_moves().is_empty() {return None;}
+        let pawn = self.squares_of(PieceRole::Pawn, Some(attacker)).next()?.index();
+        // the bitbase has the pawn of White, so for Black the board is turned from the 1st to
+        // the 8th rank
+        let flip = if attacker == Color::White {0} else {56};
+        let square = |index: usize| Square((index ^ flip) as u8);
+        let turn = if self.turn == attacker {Color::White} else {Color::Black};
+        return match kpk::probe(square(self.king_square(attacker)?), square(pawn), square(self.king_square(attacker.opposite())?), turn) {
+            Wdl::Draw => Some(EndgameClass::KingPawnKing(None)),
+            Wdl::Win | Wdl::Loss => Some(EndgameClass::KingPawnKing(Some(attacker)))
+        };
+    }
+
+    fn bishop_knight_king(&self, attacker: Color) -> Option<EndgameClass> {
+        let defending_king = self.king_square(attacker.opposite())?;
+        if self.legal_moves().is_empty() {return None;}
+        if self.turn != attacker {
+            // a piece beside the king that its own pieces don't protect is lost
+            for (index, _) in self.occupied().filter(|(_, piece)| piece.color == attacker && piece.role != PieceRole::King) {
+                let beside = distance(relative(index, Color::White), relative(defending_king, Color::White)) == 1;
+                if beside && self.attackers_of(index, attacker).is_empty() {return None;}
+            }
+        }
+        return Some(EndgameClass::BishopKnightKing(attacker));
+    }
+
+    fn wrong_bishop(&self, attacker: Color) -> Option<EndgameClass> {
+        let mut pawn_columns = self.squares_of(PieceRole::Pawn, None).map(|square| square.index() % 8);
+        let column = pawn_columns.next()?;
+        if (column != 0 && column != 7) || pawn_columns.any(|other| other != column) {return None;}
+        let bishop = self.squares_of(PieceRole::Bishop, None).next()?;
+        let promotion = if attacker == Color::White {column} else {56 + column};
+        if bishop.color() == Square(promotion as u8).color() {return None;}
+        let defending_king = self.king_square(attacker.opposite())?;
+        if distance(relative(defending_king, Color::White), relative(promotion, Color::White)) > 1 {return None;}
+        return Some(EndgameClass::WrongBishop);
+    }
+
+    /// What a recognized won endgame adds to the evaluation from White's point of view.
+    pub(crate) fn endgame_bonus(&self, class: EndgameClass) -> i32 {
+        let winner = match class.winner() {
+            Some(winner) => winner,
+            None => return 0
+        };
+        let mut bonus = KNOWN_WIN;
+        if let EndgameClass::BishopKnightKing(_) = class {
+            // the mate can only be forced in a corner of the color of the bishop, so drive the
+            // defending king there and bring the own king close
+            let bishop = self.squares_of(PieceRole::Bishop, None).next().unwrap();
+            let defending_king = relative(self.king_square(winner.opposite()).unwrap(), Color::White);
+            let attacking_king = relative(self.king_square(winner).unwrap(), Color::White);
+            // a8 and h1 are light
+            let corners = if bishop.color() == Color::White {[(0, 0), (7, 7)]} else {[(0, 7), (7, 0)]};
+            let corner_distance = corners.iter().map(|&(row, column)| (defending_king.0 - row).abs() + (defending_king.1 - column).abs()).min().unwrap();
+            bonus -= 20 * corner_distance + 5 * distance(defending_king, attacking_king);
+        }
+        return if winner == Color::White {bonus} else {-bonus};
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::EndgameClass;
+    use crate::engine::find_best_move;
+    use crate::tests::flip_fen;
+    use crate::{Color, Game};
+
+    fn recognize(fen: &str) -> Option<EndgameClass> {
+        return Game::from_fen(fen).unwrap().recognize_endgame();
+    }
+
+    //check that king and pawn against king is won from a key square with either side to move,
+    //and a race is drawn only when the defender moves first
+    #[test]
+    fn check_king_pawn_king() {
+        let won = Some(EndgameClass::KingPawnKing(Some(Color::White)));
+        let drawn = Some(EndgameClass::KingPawnKing(None));
+        assert_eq!(recognize("8/8/4k3/8/4K3/8/4P3/8 w - - 0 1"), won);
+        assert_eq!(recognize("8/8/4k3/8/4K3/8/4P3/8 b - - 0 1"), won);
+        assert_eq!(recognize(&flip_fen("8/8/4k3/8/4K3/8/4P3/8 b - - 0 1")), Some(EndgameClass::KingPawnKing(Some(Color::Black))));
+        // the king outside the square only catches the rook pawn if it moves first
+        assert_eq!(recognize("8/8/8/5k2/P7/8/8/7K w - - 0 1"), won);
+        assert_eq!(recognize("8/8/8/5k2/P7/8/8/7K b - - 0 1"), drawn);
+        // a king in front of a rook pawn can't be driven away
+        assert_eq!(recognize("k7/8/1K6/P7/8/8/8/8 w - - 0 1"), drawn);
+        // a pawn the king can take
+        assert_eq!(recognize("8/8/8/8/8/3k4/4P3/6K1 b - - 0 1"), drawn);
+        // the kings close to each other and the pawn, which the bitbase decides too
+        assert_eq!(recognize("8/8/8/4k3/8/4K3/4P3/8 w - - 0 1"), drawn);
+        assert_eq!(recognize(&flip_fen("8/8/8/4k3/8/4K3/4P3/8 w - - 0 1")), drawn);
+        // stalemate
+        assert_eq!(recognize("k7/P7/1K6/8/8/8/8/8 b - - 0 1"), None);
+    }
+
+    //check that bishop and knight win unless a piece hangs to the king
+    #[test]
+    fn check_bishop_knight_king() {
+        assert_eq!(recognize("8/8/3k4/8/8/8/8/2B1KN2 w - - 0 1"), Some(EndgameClass::BishopKnightKing(Color::White)));
+        assert_eq!(recognize("8/8/3k4/8/8/8/8/2B1KN2 b - - 0 1"), Some(EndgameClass::BishopKnightKing(Color::White)));
+        assert_eq!(recognize("8/8/3k4/4N3/8/8/8/2B1K3 b - - 0 1"), None);
+        // a rook more isn't this ending
+        assert_eq!(recognize("8/8/3k4/8/8/8/8/R1B1KN2 w - - 0 1"), None);
+    }
+
+    //check that the defending king in the corner the bishop can't cover draws
+    #[test]
+    fn check_wrong_bishop() {
+        // a8 is light, the bishop on e5 is dark
+        assert_eq!(recognize("k7/8/8/P3B3/P7/8/8/4K3 w - - 0 1"), Some(EndgameClass::WrongBishop));
+        assert_eq!(recognize("1k6/8/8/P3B3/8/8/8/4K3 b - - 0 1"), Some(EndgameClass::WrongBishop));
+        // the bishop on d5 covers a8
+        assert_eq!(recognize("1k6/8/8/P2B4/8/8/8/4K3 w - - 0 1"), None);
+        // the king is too far from the corner
+        assert_eq!(recognize("8/8/3k4/P3B3/8/8/8/4K3 w - - 0 1"), None);
+        assert_eq!(Game::from_fen("k7/8/8/P3B3/P7/8/8/4K3 w - - 0 1").unwrap().evaluate(), 0);
+    }
+
+    //check that the engine sees the drawn race as a draw and heads for the corner
+    #[test]
+    fn check_endgame_search() {
+        let game1 = Game::from_fen("8/8/8/5k2/P7/8/8/7K b - - 0 1").unwrap();
+        let (_, score) = find_best_move(&game1, 3).unwrap();
+        assert_eq!(score, 0);
+        let game1 = Game::from_fen("8/8/8/5k2/P7/8/8/7K w - - 0 1").unwrap();
+        let (_, score) = find_best_move(&game1, 3).unwrap();
+        assert!(score > 1000, "{}", score);
+        // the opposition decides, which the bitbase knows without searching to the promotion
+        let game1 = Game::from_fen("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1").unwrap();
+        assert_eq!(find_best_move(&game1, 3).unwrap().1, 0);
+        let game1 = Game::from_fen("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1").unwrap();
+        let (_, score) = find_best_move(&game1, 3).unwrap();
+        assert!(score < -1000, "{}", score);
+    }
+}
@@ -0,0 +1,186 @@
+use super::params::EvalParams;
+use crate::{move_okay, Color, Game, GameState, Piece, PieceRole, Position, BISHOP_DIRECTIONS, KNIGHT_OFFSETS, QUEEN_DIRECTIONS, ROOK_DIRECTIONS};
+
+/// The score of a checkmate. A search finding a mate further away scores it MATE minus the
+/// number of plies to the mate, so faster mates are preferred.
+pub const MATE: i32 = 100_000;
+
+/// The phase of a position with all the knights, bishops, rooks and queens, see Position::phase.
+pub(crate) const MAX_PHASE: i32 = 24;
+
+/// How much a piece of the role counts towards the phase.
+fn phase_weight(role: PieceRole) -> i32 {
+    match role {
+        PieceRole::Knight | PieceRole::Bishop => 1,
+        PieceRole::Rook => 2,
+        PieceRole::Queen => 4,
+        PieceRole::Pawn | PieceRole::King => 0
+    }
+}
+
+impl Game {
+    /// Evaluates the position in centipawns from White's point of view: material plus a small
+    /// bonus for every move the knights, bishops, rooks and queens can make (more for the
+    /// minor pieces), a bonus for the bishop pair, the pawn structure (doubled, isolated and
+    /// passed pawns), a bonus or penalty for the square every piece stands on and, while there
+    /// are pieces to attack with, the safety of the kings. Checkmate gives MATE (or -MATE if
+    /// White is mated), while stalemate and the fifty-move rule give 0.
+    pub fn evaluate(&self) -> i32 {
+        return self.evaluate_with(&EvalParams::DEFAULT);
+    }
+
+    /// Like evaluate, but with the weights of the parameters instead of the default ones.
+    pub fn evaluate_with(&self, params: &EvalParams) -> i32 {
+        match self.state {
+            GameState::Checkmate => return if self.position.turn == Color::White {-MATE} else {MATE},
+            GameState::Stalemate => return 0,
+            _ => ()
+        }
+        if self.position.halfmove >= 100 {return 0;}
+        return self.position.evaluate(params);
+    }
+
+    /// Counts the moves the knights, bishops, rooks and queens of the color can make, to an
+    /// empty square or onto an enemy piece. Moves that would leave the king in check count
+    /// too (so both colors can be counted, whoever's turn it is), while pawn and king moves
+    /// don't. Active pieces have many moves and passive pieces few.
+    pub fn mobility(&self, color: Color) -> u32 {
+        return self.position.mobility(color);
+    }
+}
+
+impl Position {
+    /// Static evaluation of the position from White's point of view, without looking at
+    /// checkmate or stalemate. Does not allocate, so it can be called at every search leaf.
+    pub(crate) fn evaluate(&self, params: &EvalParams) -> i32 {
+        // a recognized drawn ending is a draw whatever the material
+        let endgame = self.recognize_endgame();
+        if endgame.is_some_and(|class| class.winner().is_none()) {return 0;}
+        let mut score: i32 = 0;
+        for (index, &byte) in self.board.iter().enumerate() {
+            let piece = match Piece::from_u8(byte) {
+                Some(piece) => piece,
+                None => continue
+            };
+            let piece_score = params.value(piece.role) + params.mobility(piece.role) * self.piece_mobility(index, piece) as i32;
+            score += if piece.color == Color::White {piece_score} else {-piece_score};
+        }
+        if self.has_bishop_pair(Color::White) {score += params.bishop_pair;}
+        if self.has_bishop_pair(Color::Black) {score -= params.bishop_pair;}
+        score += self.cached_pawn_structure(params);
+        score += self.king_safety(params);
+        score += self.piece_square_score(params);
+        if let Some(class) = endgame {score += self.endgame_bonus(class);}
+        return score;
+    }
+
+    /// Returns how far the position is from an endgame, from MAX_PHASE with all the knights,
+    /// bishops, rooks and queens on the board (or more, after promotions) to 0 with none.
+    pub(crate) fn phase(&self) -> i32 {
+        return (self.side_phase(Color::White) + self.side_phase(Color::Black)).min(MAX_PHASE);
+    }
+
+    /// Returns how far the pieces of one color are from an endgame, from MAX_PHASE / 2 with
+    /// all the knights, bishops, rooks and queens (or more, after promotions) to 0 with none.
+    pub(crate) fn side_phase(&self, color: Color) -> i32 {
+        return self.board.iter().filter_map(|&byte| Piece::from_u8(byte)).filter(|piece| piece.color == color).map(|piece| phase_weight(piece.role)).sum();
+    }
+
+    /// Counts the moves of the knights, bishops, rooks and queens of the color, see Game::mobility.
+    pub(crate) fn mobility(&self, color: Color) -> u32 {
+        let mut count = 0;
+        for (index, &byte) in self.board.iter().enumerate() {
+            if let Some(piece) = Piece::from_u8(byte).filter(|piece| piece.color == color) {
+                count += self.piece_mobility(index, piece);
+            }
+        }
+        return count;
+    }
+
+    /// Counts the squares a knight, bishop, rook or queen can move to, ignoring whether the move
+    /// would leave the king in check. Pawns and kings always count 0.
+    pub(crate) fn piece_mobility(&self, index: usize, piece: Piece) -> u32 {
+        let (row, column) = ((index / 8) as i8, (index % 8) as i8);
+        // a square can be moved to if it's empty or holds an enemy piece
+        let reachable = |row: i8, column: i8| -> bool {
+            match Piece::from_u8(self.board[(row * 8 + column) as usize]) {
+                Some(other) => other.color != piece.color,
+                None => true
+            }
+        };
+        let mut count: u32 = 0;
+        let directions: &[(i8, i8)] = match piece.role {
+            PieceRole::Knight => {
+                for (row_offset, column_offset) in KNIGHT_OFFSETS {
+                    if move_okay(row + row_offset, column + column_offset) && reachable(row + row_offset, column + column_offset) {count += 1;}
+                }
+                return count;
+            }
+            PieceRole::Bishop => &BISHOP_DIRECTIONS,
+            PieceRole::Rook => &ROOK_DIRECTIONS,
+            PieceRole::Queen => &QUEEN_DIRECTIONS,
+            PieceRole::Pawn | PieceRole::King => return 0
+        };
+        for &(row_offset, column_offset) in directions {
+            let (mut r, mut c) = (row + row_offset, column + column_offset);
+            while move_okay(r, c) {
+                if reachable(r, c) {count += 1;}
+                if self.board[(r * 8 + c) as usize] != 0 {break;}
+                r += row_offset;
+                c += column_offset;
+            }
+        }
+        return count;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::MATE;
+    use crate::tests::{flip_fen, random_games};
+    use crate::{Color, Game};
+
+    //check that the start position is equal and that material counts
+    #[test]
+    fn check_evaluate_material() {
+        assert_eq!(Game::new().evaluate(), 0);
+        let game1 = Game::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert!(game1.evaluate() > 800);
+    }
+
+    //check the scores of checkmate, stalemate and the fifty-move rule
+    #[test]
+    fn check_evaluate_terminal() {
+        let game1 = Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(game1.evaluate(), -MATE);
+        let game2 = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game2.evaluate(), 0);
+        let game3 = Game::from_fen("7k/8/6K1/8/8/8/8/Q7 b - - 100 80").unwrap();
+        assert_eq!(game3.evaluate(), 0);
+    }
+
+    //check that both colors start with the same mobility and that opening the position adds to it
+    #[test]
+    fn check_mobility() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.mobility(Color::White), 4);
+        assert_eq!(game1.mobility(Color::Black), 4);
+        game1.make_move("e2", "e4");
+        // the bishop and the queen can move now, and the knight on g1 to e2
+        assert_eq!(game1.mobility(Color::White), 5 + 5 + 4);
+        assert_eq!(game1.mobility(Color::Black), 4);
+    }
+
+    //check that swapping the colors of a position negates the score
+    #[test]
+    fn check_evaluate_mirror_symmetry() {
+        for game in random_games(100, 0x9E3779B97F4A7C15) {
+            let flipped = Game::from_fen(&flip_fen(&game.get_fen())).unwrap();
+            assert_eq!(game.evaluate(), -flipped.evaluate(), "{}", game.get_fen());
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use super::eval::MAX_PHASE;
+use super::params::EvalParams;
+use crate::{Color, PieceRole, Position};
+
+impl Position {
+    /// Scores the safety of both kings from White's point of view. The danger of a king is
+    /// scaled by the pieces the opponent has left to attack it with, so that it fades out in
+    /// the endgame.
+    pub(crate) fn king_safety(&self, params: &EvalParams) -> i32 {
+        let scaled_danger = |color: Color| -> i32 {
+            // one side has half of the phase of all the pieces
+            return self.king_danger(color, params) * self.side_phase(color.opposite()) / (MAX_PHASE / 2);
+        };
+        return scaled_danger(Color::Black) - scaled_danger(Color::White);
+    }
+
+    /// Counts the penalty of the king of `color` in centipawns: defects in the pawn shield of
+    /// a castled king, open and semi-open files at or beside the king, and the enemy pieces
+    /// that attack the squares around the king and the three squares in front of those.
+    fn king_danger(&self, color: Color, params: &EvalParams) -> i32 {
+        let king = match self.king_square(color) {
+            Some(king) => king,
+            None => return 0
+        };
+        let (row, column) = ((king / 8) as i8, (king % 8) as i8);
+        // rows in front of the king are towards row 0 for White
+        let forward: i8 = if color == Color::White {-1} else {1};
+        let is_pawn = |row: i8, column: i8, color: Color| -> bool {
+            (0..8).contains(&row) && self.piece_at((row * 8 + column) as usize).is_some_and(|piece| piece.role == PieceRole::Pawn && piece.color == color)
+        };
+        let files = (column - 1).max(0)..=(column + 1).min(7);
+        let mut danger = 0;
+        // a king is castled if it stands on its first rank beside the center files
+        let back_row = if color == Color::White {7} else {0};
+        if row == back_row && column != 3 && column != 4 {
+            for c in files.clone() {
+                if is_pawn(row + forward, c, color) {continue;}
+                danger += if is_pawn(row + 2 * forward, c, color) {params.shield_advanced} else {params.shield_missing};
+            }
+        }
+        for c in files.clone() {
+            if (0..8).any(|r| is_pawn(r, c, color)) {continue;}
+            danger += if (0..8).any(|r| is_pawn(r, c, color.opposite())) {params.semi_open_file} else {params.open_file};
+        }
+        // the king zone, the rows around the king and the row two in front of it
+        for r in [row - 1, row, row + 1, row + 2 * forward] {
+            if !(0..8).contains(&r) {continue;}
+            for c in files.clone() {
+                if (r, c) == (row, column) {continue;}
+                self.for_each_attacker((r * 8 + c) as usize, color.opposite(), |attacker| {
+                    danger += params.king_attack(self.piece_at(attacker).unwrap().role);
+                });
+            }
+        }
+        return danger;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::EvalParams;
+    use crate::Game;
+
+    //check that pushing the pawns in front of a castled king costs a meaningful amount
+    #[test]
+    fn check_king_safety() {
+        let intact = Game::from_fen("r2q1rk1/ppp2ppp/2n2n2/8/8/2N2N2/PPP2PPP/R2Q1RK1 w - - 0 1").unwrap();
+        let pushed = Game::from_fen("r2q1rk1/ppp2ppp/2n2n2/8/6PP/2N2N2/PPP2P2/R2Q1RK1 w - - 0 1").unwrap();
+        assert_eq!(intact.position.king_safety(&EvalParams::DEFAULT), 0);
+        let penalty = intact.position.king_safety(&EvalParams::DEFAULT) - pushed.position.king_safety(&EvalParams::DEFAULT);
+        assert!(penalty >= 30, "{}", penalty);
+        assert!(intact.evaluate() - pushed.evaluate() >= 30);
+        // without the pieces the king safety doesn't matter
+        let endgame = Game::from_fen("6k1/ppp2ppp/8/8/6PP/8/PPP2P2/6K1 w - - 0 1").unwrap();
+        assert_eq!(endgame.position.king_safety(&EvalParams::DEFAULT), 0);
+    }
+}
@@ -0,0 +1,338 @@
+use std::sync::OnceLock;
+
+use crate::{Color, Square};
+
+/// The result of a position with the best play of both players, for the player to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+// a position is the player to move, the pawn on one of the 24 squares of the a- to d-file from
+// the 2nd to the 7th rank (a pawn on the e- to h-file is mirrored) and the squares of the kings
+const PAWN_SQUARES: usize = 24;
+const POSITIONS: usize = 2 * PAWN_SQUARES * 64 * 64;
+
+/// One bit for every position, set if White wins it. Generated the first time it is probed.
+static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+/// Looks up king and pawn against king: White has the king and the pawn, Black the king. The
+/// bitbase is generated the first time it is probed, which takes a moment, and is exact:
+/// every position is either won by White or drawn. A position that can't occur, like kings
+/// beside each other, a pawn on the 1st or 8th rank or the player who isn't to move in check,
+/// probes as a draw.
+pub fn probe(white_king: Square, pawn: Square, black_king: Square, side_to_move: Color) -> Wdl {
+    let (mut white_king, mut pawn, mut black_king) = (white_king.index(), pawn.index(), black_king.index());
+    if !(1..=6).contains(&(pawn / 8)) {return Wdl::Draw;}
+    // the a- to d-file are enough, since the board is the same mirrored from the a- to the h-file
+    if pawn % 8 > 3 {
+        (white_king, pawn, black_king) = (white_king ^ 7, pawn ^ 7, black_king ^ 7);
+    }
+    let index = position_index(side_to_move, white_king, pawn, black_king);
+    let won = BITBASE.get_or_init(generate)[index / 64] >> (index % 64) & 1 == 1;
+    return match (won, side_to_move) {
+        (false, _) => Wdl::Draw,
+        (true, Color::White) => Wdl::Win,
+        (true, Color::Black) => Wdl::Loss
+    };
+}
+
+/// What is known about a position while the bitbase is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// The position can't occur.
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+/// A position of the bitbase, with board indices (a8 is 0) and the pawn on the a- to d-file.
+#[derive(Debug, Clone, Copy)]
+struct KpkPosition {
+    turn: Color,
+    white_king: usize,
+    pawn: usize,
+    black_king: usize,
+}
+
+fn position_index(turn: Color, white_king: usize, pawn: usize, black_king: usize) -> usize {
+    let pawn = (pawn / 8 - 1) * 4 + pawn % 8;
+    return (((turn == Color::Black) as usize * PAWN_SQUARES + pawn) * 64 + white_king) * 64 + black_king;
+}
+
+fn decode(index: usize) -> KpkPosition {
+    let pawn = index / (64 * 64) % PAWN_SQUARES;
+    return KpkPosition {
+        turn: if index / (64 * 64 * PAWN_SQUARES) == 0 {Color::White} else {Color::Black},
+        white_king: index / 64 % 64,
+        pawn: (pawn / 4 + 1) * 8 + pawn % 4,
+        black_king: index % 64,
+    };
+}
+
+/// The number of king moves between two squares.
+fn distance(a: usize, b: usize) -> usize {
+    return (a / 8).abs_diff(b / 8).max((a % 8).abs_diff(b % 8));
+}
+
+/// The squares a king on the square can move to.
+fn king_moves(square: usize) -> impl Iterator<Item = usize> {
+    return (0..64).filter(move |&other| distance(square, other) == 1);
+}
+
+/// Whether the white pawn attacks the square, which is one row closer to row 0.
+fn pawn_attacks(pawn: usize, square: usize) -> bool {
+    return square / 8 + 1 == pawn / 8 && (square % 8).abs_diff(pawn % 8) == 1;
+}
+
+/// Whether a queen on the square attacks the target, with the white king as the only piece in
+/// the way.
+fn queen_attacks(from: usize, white_king: usize, target: usize) -> bool {
+    for (row_step, column_step) in [(0, 1), (1, 0), (0, -1), (-1, 0), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+        let (mut row, mut column) = ((from / 8) as i32 + row_step, (from % 8) as i32 + column_step);
+        while (0..8).contains(&row) && (0..8).contains(&column) {
+            let square = (row * 8 + column) as usize;
+            if square == target {return true;}
+            if square == white_king {break;}
+            (row, column) = (row + row_step, column + column_step);
+        }
+    }
+    return false;
+}
+
+/// Whether White wins at once by promoting the pawn on the 7th rank to a queen: the queen
+/// must not be lost to the king, and Black must be in check or have a move. King and queen
+/// against king is always won then. No position where the queen stalemates is won by
+/// promoting to a rook instead, so the other promotions aren't looked at.
+fn promotion_wins(position: &KpkPosition) -> bool {
+    let KpkPosition { white_king, pawn, black_king, .. } = *position;
+    let promotion = pawn - 8;
+    if promotion == white_king || promotion == black_king {return false;}
+    if distance(black_king, promotion) == 1 && distance(white_king, promotion) > 1 {return false;}
+    // a defended queen can't be taken, and the king isn't in the way of the queen once it moves
+    let escapes = king_moves(black_king).any(|square| square != promotion && distance(square, white_king) > 1 && !queen_attacks(promotion, white_king, square));
+    return escapes || queen_attacks(promotion, white_king, black_king);
+}
+
+/// What is known about the position before looking at the positions it leads to.
+fn initial_state(position: &KpkPosition) -> State {
+    let KpkPosition { turn, white_king, pawn, black_king } = *position;
+    if white_king == black_king || white_king == pawn || black_king == pawn || distance(white_king, black_king) < 2 {return State::Invalid;}
+    // Black can't be in check with White to move
+    if turn == Color::White && pawn_attacks(pawn, black_king) {return State::Invalid;}
+    if turn == Color::White && pawn / 8 == 1 && promotion_wins(position) {return State::Win;}
+    return State::Unknown;
+}
+
+/// Decides the position from the positions it leads to, if enough of them are decided. White
+/// wins if one move wins and draws if every move draws, while Black draws if one move draws
+/// and loses if every move loses. A promotion that doesn't win at once loses the new piece or
+/// stalemates, which is a draw.
+fn classify(states: &[State], position: &KpkPosition) -> State {
+    let KpkPosition { turn, white_king, pawn, black_king } = *position;
+    if turn == Color::White {
+        let mut every_move_draws = true;
+        let king_moves = king_moves(white_king).filter(|&square| square != pawn && distance(square, black_king) > 1).map(|square| (square, pawn));
+        // one or two squares forward, if they are empty
+        let forward = pawn - 8;
+        let single = (pawn / 8 > 1 && forward != white_king && forward != black_king).then_some((white_king, forward));
+        let double = single.filter(|_| pawn / 8 == 6 && forward - 8 != white_king && forward - 8 != black_king).map(|_| (white_king, forward - 8));
+        for (king, pawn) in king_moves.chain(single).chain(double) {
+            match states[position_index(Color::Black, king, pawn, black_king)] {
+                State::Win => return State::Win,
+                State::Draw => (),
+                _ => every_move_draws = false
+            }
+        }
+        return if every_move_draws {State::Draw} else {State::Unknown};
+    }
+    let mut every_move_loses = true;
+    let mut has_move = false;
+    for square in king_moves(black_king).filter(|&square| distance(square, white_king) > 1 && !pawn_attacks(pawn, square)) {
+        has_move = true;
+        // the pawn isn't defended, and king against king is a draw
+        if square == pawn {return State::Draw;}
+        match states[position_index(Color::White, white_king, pawn, square)] {
+            State::Draw => return State::Draw,
+            State::Win => (),
+            _ => every_move_loses = false
+        }
+    }
+    if !has_move {return if pawn_attacks(pawn, black_king) {State::Win} else {State::Draw};}
+    return if every_move_loses {State::Win} else {State::Unknown};
+}
+
+/// Generates the bitbase by retrograde analysis: the positions won by promoting at once are
+/// known, and the others are decided from the positions they lead to, going over them again
+/// and again until nothing changes. The positions left undecided then are those where Black
+/// can always keep White from winning, which are draws.
+fn generate() -> Vec<u64> {
+    let positions: Vec<KpkPosition> = (0..POSITIONS).map(decode).collect();
+    let mut states: Vec<State> = positions.iter().map(initial_state).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..POSITIONS {
+            if states[index] != State::Unknown {continue;}
+            let state = classify(&states, &positions[index]);
+            if state != State::Unknown {
+                states[index] = state;
+                changed = true;
+            }
+        }
+    }
+    let mut bits = vec![0u64; POSITIONS / 64];
+    for (index, _) in states.iter().enumerate().filter(|(_, &state)| state == State::Win) {
+        bits[index / 64] |= 1 << (index % 64);
+    }
+    return bits;
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{probe, Wdl};
+    use crate::{Color, Game, Piece, PieceRole, Position, Square, Variant, XorShift};
+
+    fn probe_fen(fen: &str) -> Wdl {
+        let game1 = Game::from_fen(fen).unwrap();
+        let square = |role: PieceRole, color: Color| game1.position.squares_of(role, Some(color)).next().unwrap();
+        return probe(square(PieceRole::King, Color::White), square(PieceRole::Pawn, Color::White), square(PieceRole::King, Color::Black), game1.get_turn_color());
+    }
+
+    //check known won and drawn positions, with the key squares and the rook pawns
+    #[test]
+    fn check_kpk_probe() {
+        let table = [
+            // the king on a key square wins whoever is to move
+            ("8/8/4k3/8/4K3/8/4P3/8 w - - 0 1", Wdl::Win),
+            ("8/8/4k3/8/4K3/8/4P3/8 b - - 0 1", Wdl::Loss),
+            ("8/8/2k5/8/3K4/8/4P3/8 b - - 0 1", Wdl::Loss),
+            ("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", Wdl::Win),
+            // in front of a pawn on the 4th rank, the player to move loses the opposition
+            ("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1", Wdl::Draw),
+            ("8/4k3/8/4K3/4P3/8/8/8 b - - 0 1", Wdl::Loss),
+            // the king beside a pawn on the 6th rank, where Black to move saves itself by stalemate
+            ("4k3/8/4PK2/8/8/8/8/8 b - - 0 1", Wdl::Draw),
+            ("4k3/8/4PK2/8/8/8/8/8 w - - 0 1", Wdl::Win),
+            // the rule of the square
+            ("8/8/8/5k2/P7/8/8/7K w - - 0 1", Wdl::Win),
+            ("8/8/8/5k2/P7/8/8/7K b - - 0 1", Wdl::Draw),
+            // a king in front of a rook pawn, or shutting the other king in, draws
+            ("k7/8/1K6/P7/8/8/8/8 w - - 0 1", Wdl::Draw),
+            ("8/8/8/8/8/1k6/P7/3K4 w - - 0 1", Wdl::Draw),
+            ("2k5/8/K7/P7/8/8/8/8 b - - 0 1", Wdl::Draw),
+            ("8/1K6/8/P7/8/8/8/2k5 w - - 0 1", Wdl::Win),
+            // stalemate, and a pawn the king takes
+            ("k7/P7/1K6/8/8/8/8/8 b - - 0 1", Wdl::Draw),
+            ("8/8/8/8/8/3k4/4P3/6K1 b - - 0 1", Wdl::Draw),
+            // the pawn promotes, unless the king takes the new queen
+            ("8/2P5/k1K5/8/8/8/8/8 w - - 0 1", Wdl::Win),
+            ("2k5/4P3/8/3K4/8/8/8/8 b - - 0 1", Wdl::Draw),
+        ];
+        for (fen, wdl) in table {
+            assert_eq!(probe_fen(fen), wdl, "{}", fen);
+            // mirrored from the a- to the h-file
+            let mirrored = Game::from_fen(fen).unwrap().mirror_horizontal().get_fen();
+            assert_eq!(probe_fen(&mirrored), wdl, "{}", mirrored);
+        }
+        // a pawn on the 8th rank or kings beside each other can't occur
+        let square = |name: &str| Square::from_algebraic(name).unwrap();
+        assert_eq!(probe(square("e1"), square("e8"), square("a1"), Color::White), Wdl::Draw);
+        assert_eq!(probe(square("e4"), square("b2"), square("e5"), Color::White), Wdl::Draw);
+    }
+
+    /// Solves king and pawn against king positions the slow way, with the move generation of
+    /// the game: every position reachable from them is looked at again and again until nothing
+    /// changes, like the bitbase but with all the rules of chess and every promotion.
+    fn solve(starts: &[Position]) -> Vec<Wdl> {
+        let key = |position: &Position| -> Option<(Color, usize, usize, usize)> {
+            let pawn = position.squares_of(PieceRole::Pawn, Some(Color::White)).next()?.index();
+            return Some((position.turn, position.king_square(Color::White).unwrap(), pawn, position.king_square(Color::Black).unwrap()));
+        };
+        // the positions reachable from the starts, and what their moves lead to: another
+        // position, or a decided result once the pawn is gone
+        let mut positions: Vec<Position> = Vec::new();
+        let mut index_of = std::collections::HashMap::new();
+        let starts: Vec<usize> = starts.iter().map(|&start| *index_of.entry(key(&start).unwrap()).or_insert_with(|| {positions.push(start); positions.len() - 1})).collect();
+        let mut children: Vec<Vec<Result<usize, bool>>> = Vec::new();
+        while children.len() < positions.len() {
+            let position = positions[children.len()];
+            let mut moves = Vec::new();
+            for mv in position.legal_moves() {
+                let mut child = position;
+                child.apply_move(mv);
+                let Some(child_key) = key(&child) else {
+                    // a new queen or rook wins unless it is taken at once or stalemates
+                    let replies = child.legal_moves();
+                    let piece = child.piece_at(mv.to.index()).filter(|piece| piece.color == Color::White);
+                    let kept = piece.is_some_and(|piece| matches!(piece.role, PieceRole::Queen | PieceRole::Rook)) && !replies.iter().any(|reply| reply.to == mv.to);
+                    moves.push(Err(kept && (!replies.is_empty() || child.in_check(Color::Black))));
+                    continue;
+                };
+                let next = *index_of.entry(child_key).or_insert_with(|| {positions.push(child); positions.len() - 1});
+                moves.push(Ok(next));
+            }
+            children.push(moves);
+        }
+        let mut won: Vec<Option<bool>> = vec![None; positions.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for index in 0..positions.len() {
+                if won[index].is_some() {continue;}
+                let mut results = children[index].iter().map(|&child| match child {Ok(next) => won[next], Err(win) => Some(win)});
+                // the player to move picks the result that is best for them
+                let good = positions[index].turn == Color::White;
+                let decided = if children[index].is_empty() {
+                    Some(positions[index].in_check(Color::Black))
+                } else if results.clone().any(|result| result == Some(good)) {
+                    Some(good)
+                } else if results.all(|result| result == Some(!good)) {Some(!good)} else {None};
+                if decided.is_some() {
+                    won[index] = decided;
+                    changed = true;
+                }
+            }
+        }
+        return starts.into_iter().map(|index| match (won[index] == Some(true), positions[index].turn) {
+            (false, _) => Wdl::Draw,
+            (true, Color::White) => Wdl::Win,
+            (true, Color::Black) => Wdl::Loss
+        }).collect();
+    }
+
+    //check that probing agrees with solving 100 random positions the slow way
+    #[test]
+    fn check_kpk_against_search() {
+        let mut rng = XorShift::new(0x510E527FADE682D1);
+        let mut positions: Vec<Position> = Vec::new();
+        while positions.len() < 100 {
+            let (white_king, pawn, black_king) = (rng.next_index(64), 8 + rng.next_index(48), rng.next_index(64));
+            let turn = if rng.next_index(2) == 0 {Color::White} else {Color::Black};
+            let mut position = Position { board: [0; 64], halfmove: 0, fullmove: 1, pawn_key: 0, ep_square: None, turn, variant: Variant::Standard, pockets: [[0; 5]; 2], promoted: 0 };
+            position.set_piece(white_king, Some(Piece::new(PieceRole::King, Color::White, true)));
+            position.set_piece(pawn, Some(Piece::new(PieceRole::Pawn, Color::White, pawn / 8 != 6)));
+            position.set_piece(black_king, Some(Piece::new(PieceRole::King, Color::Black, true)));
+            let kings_apart = (white_king / 8).abs_diff(black_king / 8).max((white_king % 8).abs_diff(black_king % 8)) > 1;
+            if white_king == pawn || black_king == pawn || !kings_apart || position.in_check(turn.opposite()) {continue;}
+            positions.push(position);
+        }
+        let square = |index: usize| Square::from_index(index).unwrap();
+        let mut won = 0;
+        for (position, wdl) in positions.iter().zip(solve(&positions)) {
+            let (white_king, black_king) = (position.king_square(Color::White).unwrap(), position.king_square(Color::Black).unwrap());
+            let pawn = position.squares_of(PieceRole::Pawn, None).next().unwrap();
+            assert_eq!(probe(square(white_king), pawn, square(black_king), position.turn), wdl, "{:?}", (white_king, pawn, black_king, position.turn));
+            if wdl != Wdl::Draw {won += 1;}
+        }
+        // both results are among the positions
+        assert!(won > 10 && won < 90, "{}", won);
+    }
+}
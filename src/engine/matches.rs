@@ -0,0 +1,185 @@
+use std::time::Duration;
+
+use super::adjudicate::AdjudicationRules;
+use super::arena::{play_game, GameRecord};
+use super::backend::{Engine, SearchLimits};
+use crate::{Color, Game, GameOutcome};
+
+/// How run_match plays its games.
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    /// The number of game pairs. The engines play both colors from the same opening in every
+    /// pair, so an opening that favors one color favors both engines once.
+    pub pairs: u32,
+    /// How long the engines think about every move.
+    pub limits: SearchLimits,
+    /// Games still going after this many plies end without a result.
+    pub max_plies: u32,
+    /// The positions the pairs start from, used in turn. Without any every game starts from
+    /// the start position.
+    pub openings: Vec<Game>,
+    /// When the games end early because the result is clear, see play_game.
+    pub adjudication: Option<AdjudicationRules>,
+}
+
+impl Default for MatchOptions {
+    fn default() -> MatchOptions {
+        MatchOptions {
+            pairs: 10,
+            limits: SearchLimits::time(Duration::from_millis(100)),
+            max_plies: 400,
+            openings: Vec::new(),
+            adjudication: Some(AdjudicationRules::default())
+        }
+    }
+}
+
+/// The result of run_match, from the point of view of the engine that had White in the
+/// first game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchResult {
+    pub wins: u32,
+    /// Games without a result (see MatchOptions::max_plies) count as draws.
+    pub draws: u32,
+    pub losses: u32,
+    /// Every game in the order it was played.
+    pub games: Vec<GameRecord>,
+}
+
+impl MatchResult {
+    /// The points scored, a win counting 1 and a draw 1/2.
+    pub fn points(&self) -> f64 {
+        return self.wins as f64 + self.draws as f64 / 2.0;
+    }
+
+    /// An estimate of how much stronger the engine is than its opponent in Elo, and the
+    /// margin of error of the estimate (two standard errors, so the difference lies within
+    /// it in about 95% of matches). A perfect or a zero score gives an infinite difference,
+    /// since it says nothing about how big the difference is, and no games give (0, infinity).
+    pub fn elo_difference(&self) -> (f64, f64) {
+        let games = (self.wins + self.draws + self.losses) as f64;
+        if games == 0.0 {return (0.0, f64::INFINITY);}
+        let score = self.points() / games;
+        let variance = (self.wins as f64 * (1.0 - score).powi(2) + self.draws as f64 * (0.5 - score).powi(2) + self.losses as f64 * score.powi(2)) / games;
+        let error = 2.0 * (variance / games).sqrt();
+        let (low, high) = ((score - error).max(0.0), (score + error).min(1.0));
+        let margin = if score == 0.0 || score == 1.0 {f64::INFINITY} else {(elo(high) - elo(low)) / 2.0};
+        return (elo(score), margin);
+    }
+
+    /// Every game in Portable Game Notation, one after the other, numbered as rounds of the event.
+    pub fn pgn(&self, event: &str) -> String {
+        let games: Vec<String> = self.games.iter().enumerate().map(|(index, game)| game.to_pgn(event, index as u32 + 1)).collect();
+        return games.join("\n");
+    }
+}
+
+/// Plays a match of game pairs between two engines, swapping the colors within every pair:
+/// `white` has White in the first game of a pair and `black` in the second. The pairs start
+/// from the openings of the options in turn. The result counts from the point of view of
+/// `white`.
+pub fn run_match(white: &mut dyn Engine, black: &mut dyn Engine, opts: MatchOptions) -> MatchResult {
+    let mut result = MatchResult { wins: 0, draws: 0, losses: 0, games: Vec::new() };
+    for pair in 0..opts.pairs as usize {
+        let opening = opts.openings.get(pair % opts.openings.len().max(1)).cloned().unwrap_or_default();
+        for first_is_white in [true, false] {
+            let record = if first_is_white {
+                play_game(white, black, &opening, &opts.limits, opts.max_plies, opts.adjudication)
+            } else {
+                play_game(black, white, &opening, &opts.limits, opts.max_plies, opts.adjudication)
+            };
+            let first_color = if first_is_white {Color::White} else {Color::Black};
+            match record.outcome {
+                Some(GameOutcome::Win(color)) if color == first_color => result.wins += 1,
+                Some(GameOutcome::Win(_)) => result.losses += 1,
+                _ => result.draws += 1
+            }
+            result.games.push(record);
+        }
+    }
+    return result;
+}
+
+/// The Elo difference that gives the expected score, between 0 and 1.
+fn elo(score: f64) -> f64 {
+    return -400.0 * (1.0 / score - 1.0).log10();
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{run_match, MatchOptions, MatchResult};
+    use crate::engine::{AlphaBetaEngine, GameOutcome, SearchLimits};
+    use crate::tests::FirstMoveEngine;
+    use crate::{Color, Game};
+
+    // plays the moves of a PGN game, returning the number of plies, or None if a move isn't legal
+    fn replay_pgn(pgn: &str) -> Option<usize> {
+        let fen = pgn.lines().find_map(|line| line.strip_prefix("[FEN \"")).map(|fen| fen.trim_end_matches("\"]"));
+        let mut game1 = fen.map_or(Game::new(), |fen| Game::from_fen(fen).unwrap());
+        let moves = pgn.lines().skip_while(|line| !line.is_empty()).collect::<Vec<&str>>().join(" ");
+        let mut plies = 0;
+        for token in moves.split_whitespace().filter(|token| !token.ends_with('.') && !["1-0", "0-1", "1/2-1/2", "*"].contains(token)) {
+            let mv = game1.get_all_possible_moves().into_iter().find(|mv| game1.to_san(mv).as_deref() == Some(token))?;
+            game1.play_move(&mv).unwrap();
+            plies += 1;
+        }
+        return Some(plies);
+    }
+
+    //check that the engine beats the first legal move engine and the games are written as legal PGN
+    #[test]
+    fn check_run_match() {
+        let opts = MatchOptions {
+            pairs: 2,
+            limits: SearchLimits::time(Duration::from_millis(5)),
+            openings: vec![Game::new(), Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap()],
+            ..MatchOptions::default()
+        };
+        let result = run_match(&mut AlphaBetaEngine::default(), &mut FirstMoveEngine, opts);
+        assert_eq!(result.games.len(), 4);
+        assert!(result.points() >= 3.5, "{:?}", (result.wins, result.draws, result.losses));
+        // the colors are swapped within a pair
+        assert_eq!((result.games[0].white.as_str(), result.games[1].white.as_str()), ("alpha-beta", "first move"));
+        assert_eq!(result.games[2].start_fen, result.games[3].start_fen);
+        let pgn = result.pgn("test");
+        assert_eq!(pgn.matches("[Event \"test\"]").count(), 4);
+        for (game, record) in pgn.split("\n\n[").zip(&result.games) {
+            assert_eq!(replay_pgn(game), Some(record.moves.len()), "{}", game);
+        }
+        assert!(result.elo_difference().0 > 200.0);
+    }
+
+    //check the Elo estimate of some results
+    #[test]
+    fn check_elo_difference() {
+        let result = |wins, draws, losses| MatchResult { wins, draws, losses, games: Vec::new() };
+        let (difference, margin) = result(10, 0, 10).elo_difference();
+        assert_eq!(difference, 0.0);
+        assert!(margin > 100.0 && margin < 300.0, "{}", margin);
+        // 75% is about 191 Elo
+        assert_eq!(result(15, 0, 5).elo_difference().0.round(), 191.0);
+        assert_eq!(result(5, 10, 0).elo_difference().0, result(10, 0, 5).elo_difference().0);
+        // more games make the margin smaller
+        assert!(result(150, 0, 50).elo_difference().1 < result(15, 0, 5).elo_difference().1);
+        assert_eq!(result(4, 0, 0).elo_difference(), (f64::INFINITY, f64::INFINITY));
+        assert_eq!(result(0, 0, 0).elo_difference(), (0.0, f64::INFINITY));
+    }
+
+    //check that a game from a position with Black to move is written with the right move numbers
+    #[test]
+    fn check_match_pgn_black_starts() {
+        let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/r7/6K1 b - - 0 40").unwrap();
+        let opts = MatchOptions { pairs: 1, limits: SearchLimits::depth(2), openings: vec![game1], ..MatchOptions::default() };
+        let result = run_match(&mut AlphaBetaEngine::default(), &mut FirstMoveEngine, opts);
+        assert_eq!(result.games[1].outcome, Some(GameOutcome::Win(Color::Black)));
+        let pgn = result.pgn("black starts");
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"6k1/5ppp/8/8/8/8/r7/6K1 b - - 0 40\"]"), "{}", pgn);
+        assert!(pgn.contains("\n\n40... "), "{}", pgn);
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{Game, Move, Position};
+
+/// Looks for a forced mate for the player whose turn it is within `max_plies` plies, and
+/// returns a mating line: the key move, then the defense that holds out the longest, and so on
+/// until the mate. Every defense is tried, so the mate is certain and not a guess of a
+/// heuristic search. The shortest mate is returned, and None if there is no forced mate within
+/// the bound.
+pub fn solve_mate(game: &Game, max_plies: u32) -> Option<Vec<Move>> {
+    return shortest_mate(&game.position, max_plies);
+}
+
+/// The shortest mating line within `max_plies` plies. Mates always take an odd number of plies,
+/// since the attacker makes the last move.
+pub(super) fn shortest_mate(position: &Position, max_plies: u32) -> Option<Vec<Move>> {
+    return (1..=max_plies).step_by(2).find_map(|plies| attack(position, plies));
+}
+
+/// Returns a mating line if the side to move can force mate in exactly `plies` plies (the
+/// attacker's turn, the "or" nodes of the search).
+fn attack(position: &Position, plies: u32) -> Option<Vec<Move>> {
+    for mv in position.legal_moves() {
+        // a mate in one has to be a check
+        if plies == 1 && !position.gives_check(mv) {continue;}
+        let mut child = *position;
+        child.apply_move(mv);
+        if let Some(mut line) = defend(&child, plies - 1) {
+            line.insert(0, mv);
+            return Some(line);
+        }
+    }
+    return None;
+}
+
+/// Returns the line of the longest defense if every defense of the side to move is mated
+/// within `plies` plies (the defender's turn, the "and" nodes of the search).
+pub(super) fn defend(position: &Position, plies: u32) -> Option<Vec<Move>> {
+    let moves = position.legal_moves();
+    if moves.is_empty() {
+        // checkmate, but stalemate isn't a win
+        return if position.in_check(position.turn) {Some(Vec::new())} else {None};
+    }
+    if plies == 0 {return None;}
+    let mut longest: Vec<Move> = Vec::new();
+    for mv in moves {
+        let mut child = *position;
+        child.apply_move(mv);
+        let mut line = shortest_mate(&child, plies - 1)?;
+        if longest.is_empty() || line.len() + 1 > longest.len() {
+            line.insert(0, mv);
+            longest = line;
+        }
+    }
+    return Some(longest);
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::solve_mate;
+    use crate::Game;
+
+    fn solve(fen: &str, max_plies: u32) -> Option<Vec<String>> {
+        let game1 = Game::from_fen(fen).unwrap();
+        return solve_mate(&game1, max_plies).map(|line| line.iter().map(|mv| mv.to_string()).collect());
+    }
+
+    //check that mates in one, two and three are solved with the shortest mate
+    #[test]
+    fn check_solve_mate() {
+        // back-rank mate in one, also when a longer mate is allowed
+        assert_eq!(solve("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 5).unwrap(), ["a1a8"]);
+        // the queen is given up to clear the back rank: Qd8+ Rxd8 Rxd8#
+        assert_eq!(solve("2r3k1/5ppp/8/8/3Q4/8/5PPP/3R2K1 w - - 0 1", 3).unwrap(), ["d4d8", "c8d8", "d1d8"]);
+        // smothered mate: Qg8+ Rxg8 Nf7#
+        assert_eq!(solve("r6k/6pp/7N/8/2Q5/8/8/6K1 w - - 0 1", 3).unwrap(), ["c4g8", "a8g8", "h6f7"]);
+        // Philidor's legacy: Nh6+ Kh8 Qg8+ Rxg8 Nf7#, where Kf8 is mated sooner by Qf7#
+        assert_eq!(solve("r5k1/5Npp/8/8/2Q5/8/8/6K1 w - - 0 1", 5).unwrap(), ["f7h6", "g8h8", "c4g8", "a8g8", "h6f7"]);
+        // a back-rank mate for black: Qd1+ Rxd1 Rxd1#
+        assert_eq!(solve("3r2k1/5ppp/8/3q4/8/8/5PPP/2R3K1 b - - 0 1", 3).unwrap(), ["d5d1", "c1d1", "d8d1"]);
+    }
+
+    //check that a mate just out of reach is not found
+    #[test]
+    fn check_solve_mate_near_miss() {
+        // the luft on h6 lets the king escape after Qd8+ Rxd8 Rxd8+ Kh7
+        assert_eq!(solve("2r3k1/5pp1/7p/8/3Q4/8/5PPP/3R2K1 w - - 0 1", 3), None);
+        // Philidor's legacy needs five plies
+        assert_eq!(solve("r5k1/5Npp/8/8/2Q5/8/8/6K1 w - - 0 1", 3), None);
+    }
+}
@@ -0,0 +1,258 @@
+use super::backend::{Engine, EngineMove, SearchControl, SearchLimits};
+use super::params::EvalParams;
+use super::time::Instant;
+use crate::rng::XorShift;
+use crate::{Color, Game, Move, Position};
+
+// the number of simulations per move when neither the options nor the limits limit them
+const DEFAULT_SIMULATIONS: u32 = 10_000;
+// how far ahead in material (in centipawns) a player has to be when a playout reaches its ply
+// limit to count as the winner
+const HORIZON_MARGIN: i32 = 300;
+
+/// The settings of an MctsEngine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsOptions {
+    /// The most simulations per move, or 0 to simulate until the time limit is reached (or
+    /// 10000 times without a time limit).
+    pub simulations: u32,
+    /// How many random moves a playout makes before the material decides the result.
+    pub playout_plies: u32,
+    /// How much the engine explores moves that have been simulated less (the constant of UCT).
+    pub exploration: f64,
+    /// The seed of the random moves, so the same seed chooses the same moves.
+    pub seed: u64,
+}
+
+impl Default for MctsOptions {
+    fn default() -> MctsOptions {
+        MctsOptions { simulations: 0, playout_plies: 40, exploration: std::f64::consts::SQRT_2, seed: 1 }
+    }
+}
+
+/// An engine that chooses moves by Monte Carlo tree search instead of alpha-beta search. It
+/// plays many random games (playouts) from the position, and spends more of them on the moves
+/// that have done well so far (UCT). The move that was simulated the most is chosen.
+#[derive(Debug, Clone)]
+pub struct MctsEngine {
+    options: MctsOptions,
+    rng: XorShift,
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Win(Color),
+    Draw,
+}
+
+impl Outcome {
+    /// What the outcome is worth to a player: 1 for a win, 0.5 for a draw and 0 for a loss.
+    fn reward(self, color: Color) -> f64 {
+        match self {
+            Outcome::Win(winner) if winner == color => 1.0,
+            Outcome::Win(_) => 0.0,
+            Outcome::Draw => 0.5
+        }
+    }
+}
+
+/// A position in the search tree.
+struct Node {
+    position: Position,
+    /// The move that led here from the parent, None for the root.
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The legal moves that have no child yet.
+    untried: Vec<Move>,
+    visits: u32,
+    /// The sum of the rewards of the simulations through this node, for the player who made the
+    /// move that led here.
+    reward: f64,
+    /// How the game ended if it is over here.
+    outcome: Option<Outcome>,
+}
+
+impl Node {
+    fn new(position: Position, mv: Option<Move>, parent: Option<usize>) -> Node {
+        let untried = position.legal_moves();
+        let outcome = game_over(&position, &untried);
+        return Node { position, mv, parent, children: Vec::new(), untried, visits: 0, reward: 0.0, outcome };
+    }
+
+    /// True if the player who made the move to this node won with it.
+    fn is_won(&self) -> bool {
+        return self.outcome == Some(Outcome::Win(self.position.turn.opposite()));
+    }
+}
+
+/// The outcome if the game is over, given the legal moves of the position. A playout can't go
+/// on forever, since the fifty-move rule ends it as a draw.
+fn game_over(position: &Position, moves: &[Move]) -> Option<Outcome> {
+    if moves.is_empty() {
+        return Some(if position.in_check(position.turn) {Outcome::Win(position.turn.opposite())} else {Outcome::Draw});
+    }
+    if position.halfmove >= 100 {return Some(Outcome::Draw);}
+    return None;
+}
+
+impl MctsEngine {
+    pub fn new(options: MctsOptions) -> MctsEngine {
+        return MctsEngine { options, rng: XorShift::new(options.seed) };
+    }
+
+    /// Plays random moves until the game is over or the ply limit is reached, where the player
+    /// who is ahead in material by HORIZON_MARGIN wins and the game is a draw otherwise.
+    fn playout(&mut self, mut position: Position) -> Outcome {
+        for _ in 0..self.options.playout_plies {
+            let moves = position.legal_moves();
+            if let Some(outcome) = game_over(&position, &moves) {return outcome;}
+            position.apply_move(moves[self.rng.next_index(moves.len())]);
+        }
+        let score = position.evaluate(&EvalParams::DEFAULT);
+        if score >= HORIZON_MARGIN {return Outcome::Win(Color::White);}
+        if score <= -HORIZON_MARGIN {return Outcome::Win(Color::Black);}
+        return Outcome::Draw;
+    }
+
+    /// Picks the child to follow down the tree: a move that wins on the spot, or the child with
+    /// the best average reward plus the exploration bonus for rarely visited children (UCT).
+    fn select_child(&self, tree: &[Node], index: usize) -> usize {
+        let children = &tree[index].children;
+        if let Some(&won) = children.iter().find(|&&child| tree[child].is_won()) {return won;}
+        let log_visits = (tree[index].visits as f64).ln();
+        let uct = |child: usize| -> f64 {
+            let node = &tree[child];
+            return node.reward / node.visits as f64 + self.options.exploration * (log_visits / node.visits as f64).sqrt();
+        };
+        return children.iter().copied().max_by(|&a, &b| uct(a).total_cmp(&uct(b))).unwrap();
+    }
+
+    /// Runs one simulation: selects a path down the tree, adds a child for an untried move,
+    /// plays out the game from there and adds the result to every node on the path.
+    fn simulate(&mut self, tree: &mut Vec<Node>) {
+        let mut index = 0;
+        while tree[index].outcome.is_none() && tree[index].untried.is_empty() {
+            index = self.select_child(tree, index);
+        }
+        if tree[index].outcome.is_none() {
+            let untried = &mut tree[index].untried;
+            let mv = untried.swap_remove(self.rng.next_index(untried.len()));
+            let mut position = tree[index].position;
+            position.apply_move(mv);
+            tree.push(Node::new(position, Some(mv), Some(index)));
+            let child = tree.len() - 1;
+            tree[index].children.push(child);
+            index = child;
+        }
+        let outcome = match tree[index].outcome {
+            Some(outcome) => outcome,
+            None => self.playout(tree[index].position)
+        };
+        let mut next = Some(index);
+        while let Some(index) = next {
+            let node = &mut tree[index];
+            node.visits += 1;
+            node.reward += outcome.reward(node.position.turn.opposite());
+            next = node.parent;
+        }
+    }
+
+    /// Simulates at most max_simulations times, but at least once, and stops early when
+    /// `stop` says so. Returns the move that wins on the spot or else was simulated the most.
+    fn simulate_until(&mut self, game: &Game, max_simulations: u32, stop: impl Fn() -> bool) -> Option<EngineMove> {
+        let mut tree: Vec<Node> = vec![Node::new(game.position, None, None)];
+        // the root is searched even if the fifty-move rule is reached, as long as there are moves
+        tree[0].outcome = None;
+        if tree[0].untried.is_empty() {return None;}
+        for simulations in 0..max_simulations {
+            // at least one simulation, so every limit gives a move
+            if simulations > 0 && stop() {break;}
+            self.simulate(&mut tree);
+        }
+        // the expected line follows the most simulated moves
+        let mut pv: Vec<Move> = Vec::new();
+        let mut index = 0;
+        while !tree[index].children.is_empty() {
+            let children = &tree[index].children;
+            index = match children.iter().find(|&&child| tree[child].is_won()) {
+                Some(&won) => won,
+                None => *children.iter().max_by_key(|&&child| tree[child].visits).unwrap()
+            };
+            pv.push(tree[index].mv.unwrap());
+        }
+        return Some(EngineMove { mv: pv[0], score: None, pv });
+    }
+}
+
+impl Engine for MctsEngine {
+    /// Simulates until the time limit or the number of simulations of the options is reached,
+    /// and returns the move that wins on the spot or else was simulated the most. The depth
+    /// limit is not used, since the playouts set how far ahead the engine looks.
+    fn choose_move(&mut self, game: &Game, limits: &SearchLimits) -> Option<EngineMove> {
+        let deadline = limits.time.map(|time| Instant::now() + time);
+        let max_simulations = match (self.options.simulations, deadline) {
+            (0, None) => DEFAULT_SIMULATIONS,
+            (0, Some(_)) => u32::MAX,
+            (simulations, _) => simulations
+        };
+        return self.simulate_until(game, max_simulations, || deadline.is_some_and(|deadline| Instant::now() >= deadline));
+    }
+
+    fn name(&self) -> &str {
+        return "mcts";
+    }
+
+    /// Simulates until the control says to stop, or until the number of simulations of the
+    /// options is reached. Without that number the engine simulates on until it is stopped,
+    /// unless the GUI only gave a depth, which an MctsEngine can't search to.
+    fn search(&mut self, game: &Game, limits: &SearchLimits, control: &mut SearchControl) -> Option<EngineMove> {
+        let max_simulations = match self.options.simulations {
+            0 if limits.depth.is_some() && control.remaining().is_none() => DEFAULT_SIMULATIONS,
+            0 => u32::MAX,
+            simulations => simulations
+        };
+        return self.simulate_until(game, max_simulations, || control.should_stop());
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{MctsEngine, MctsOptions};
+    use crate::engine::{AlphaBetaEngine, Engine, SearchLimits};
+    use crate::tests::{coordinate_move, random_games};
+    use crate::Game;
+
+    //check that both engines find a mate in one, the MCTS engine within a few hundred simulations
+    #[test]
+    fn check_mcts_mate_in_one() {
+        let options = MctsOptions { simulations: 300, ..MctsOptions::default() };
+        let mut engines: Vec<Box<dyn Engine>> = vec![Box::new(AlphaBetaEngine::default()), Box::new(MctsEngine::new(options))];
+        let limits = SearchLimits::time(Duration::from_secs(10));
+        for engine in engines.iter_mut() {
+            let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+            assert_eq!(engine.choose_move(&game1, &limits).unwrap().mv, coordinate_move("a1a8"));
+            let game1 = Game::from_fen("3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+            assert_eq!(engine.choose_move(&game1, &limits).unwrap().pv, [coordinate_move("d8d1")]);
+        }
+    }
+
+    //check that the MCTS engine only chooses legal moves, and none when the game is over
+    #[test]
+    fn check_mcts_legal_moves() {
+        let mut engine = MctsEngine::new(MctsOptions { simulations: 20, playout_plies: 10, ..MctsOptions::default() });
+        for game1 in random_games(1000, 0x9E3779B97F4A7C15) {
+            match engine.choose_move(&game1, &SearchLimits::time(Duration::from_secs(10))) {
+                Some(chosen) => assert!(game1.is_legal_move(&chosen.mv)),
+                None => assert!(game1.get_all_possible_moves().is_empty())
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+mod adjudicate;
+mod arena;
+mod backend;
+mod background;
+mod endgame;
+mod eval;
+mod king_safety;
+pub mod kpk;
+mod mate;
+mod matches;
+mod mcts;
+mod ordering;
+mod params;
+mod pawn_hash;
+mod pawns;
+mod phase;
+mod ponder;
+mod pst;
+mod puzzle;
+mod review;
+mod search;
+mod see;
+pub(crate) mod time;
+mod tournament;
+pub mod uci;
+pub mod xboard;
+
+pub use adjudicate::{AdjudicationRules, Adjudicator};
+pub use arena::{play_game, GameRecord, Termination};
+pub use backend::{AlphaBetaEngine, Engine, EngineMove, EngineOptions, OpeningVariety, SearchControl, SearchLimits, MAX_SKILL};
+pub use background::{Analysis, AnalysisHandle, AnalysisOptions};
+pub use endgame::EndgameClass;
+pub use eval::MATE;
+pub use mate::solve_mate;
+pub use matches::{run_match, MatchOptions, MatchResult};
+pub use mcts::{MctsEngine, MctsOptions};
+pub use ordering::order_moves;
+pub use params::EvalParams;
+pub use phase::GamePhase;
+pub use ponder::{start_pondering, PonderHandle};
+pub use puzzle::{validate_puzzle, PuzzleCriteria, PuzzleVerdict};
+pub use review::{analyze_game, analyze_game_with, Judgment, JudgmentThresholds, MoveJudgment};
+pub use search::{analyze, find_best_move, find_best_move_timed, find_best_move_with_info, Line, Score, SearchInfo, SearchLimit, SearchResult};
+pub use tournament::{EngineFactory, Tournament, TournamentOptions, TournamentResult};
+pub use crate::GameOutcome;
@@ -0,0 +1,65 @@
+use crate::{Game, Move, Position};
+
+/// Sorts the moves so that the ones most likely to be good come first, which lets an
+/// alpha-beta search cut off more lines. Captures come first, the most valuable victims first
+/// and of those the least valuable attackers first (MVV-LVA). En passant counts as taking a
+/// pawn, and promotions come before other moves of the same kind. The sort is stable, so moves
+/// that score the same keep their order.
+pub fn order_moves(game: &Game, moves: &mut [Move]) {
+    game.position.order_moves(moves);
+}
+
+impl Position {
+    /// Sorts the moves by their MVV-LVA score, highest first.
+    pub(crate) fn order_moves(&self, moves: &mut [Move]) {
+        moves.sort_by_key(|&mv| -self.mvv_lva_score(mv));
+    }
+
+    /// Scores a move for move ordering: ten times the value of the piece taken minus the value
+    /// of the piece taking it, plus the value of the piece a pawn promotes to. Quiet moves
+    /// score 0, and every capture scores more than that.
+    pub(crate) fn mvv_lva_score(&self, mv: Move) -> i32 {
+        let mut score = 0;
+        if let Some(victim) = self.capture_square(mv).and_then(|index| self.piece_at(index)) {
+            let attacker = self.piece_at(mv.from.index()).unwrap();
+            score += 10 * victim.role.value() - attacker.role.value();
+        }
+        if let Some(role) = mv.promotion {
+            score += role.value();
+        }
+        return score;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::order_moves;
+    use crate::Game;
+
+    //check that the most valuable victim is taken first, by the least valuable attacker
+    #[test]
+    fn check_mvv_lva_order() {
+        let game1 = Game::from_fen("4k3/8/2q2r2/1P1N4/8/2Q5/8/4K3 w - - 0 1").unwrap();
+        let mut moves = game1.get_all_possible_moves();
+        order_moves(&game1, &mut moves);
+        let first_moves: Vec<String> = moves.iter().take(4).map(|mv| mv.to_string()).collect();
+        assert_eq!(first_moves, ["b5c6", "c3c6", "d5f6", "c3f6"]);
+    }
+
+    //check that en passant is ordered as taking a pawn and promotions come before quiet moves
+    #[test]
+    fn check_ordering_en_passant_and_promotion() {
+        let game1 = Game::from_fen("4k3/1P6/8/3Pp3/8/8/8/4K3 w - e6 0 2").unwrap();
+        let mut moves = game1.get_all_possible_moves();
+        order_moves(&game1, &mut moves);
+        let first_moves: Vec<String> = moves.iter().take(3).map(|mv| mv.to_string()).collect();
+        assert_eq!(first_moves, ["b7b8q", "d5e6", "b7b8r"]);
+        // every piece a pawn can promote to is searched, the queen first
+        let promotions: Vec<String> = moves.iter().filter(|mv| mv.promotion.is_some()).map(|mv| mv.to_string()).collect();
+        assert_eq!(promotions, ["b7b8q", "b7b8r", "b7b8b", "b7b8n"]);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::PieceRole;
+
+/// Every weight of the evaluation, so that they can be tuned. The default is what evaluate
+/// uses. All values are in centipawns unless said otherwise. With the serde feature the
+/// parameters can be serialized, to save the results of a tuning run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalParams {
+    /// The material values of the pieces.
+    pub pawn_value: i32,
+    pub knight_value: i32,
+    pub bishop_value: i32,
+    pub rook_value: i32,
+    pub queen_value: i32,
+    /// The bonus for bishops on both light and dark squares, see Game::has_bishop_pair.
+    pub bishop_pair: i32,
+    /// The bonus for every move a piece can make, see Game::mobility.
+    pub knight_mobility: i32,
+    pub bishop_mobility: i32,
+    pub rook_mobility: i32,
+    pub queen_mobility: i32,
+    /// The penalty for every pawn that shares its file with a pawn of its color.
+    pub doubled_pawn: i32,
+    /// The penalty for every pawn without pawns of its color on the files beside it.
+    pub isolated_pawn: i32,
+    /// The bonus for a passed pawn, by how many ranks it has advanced from its starting rank.
+    pub passed_pawn: [i32; 6],
+    /// The bonus for a passed pawn with a pawn of its color beside it or protecting it.
+    pub connected_passer: i32,
+    /// The penalty for every file of the shield of a castled king without a pawn on the two
+    /// ranks in front of it ...
+    pub shield_missing: i32,
+    /// ... and for every file where the pawn has advanced two ranks.
+    pub shield_advanced: i32,
+    /// The penalty for every file at or beside a king without pawns of its color ...
+    pub semi_open_file: i32,
+    /// ... or without any pawns.
+    pub open_file: i32,
+    /// The penalty for every attack of an enemy piece on the squares around a king.
+    pub knight_attack: i32,
+    pub bishop_attack: i32,
+    pub rook_attack: i32,
+    pub queen_attack: i32,
+    /// How much the piece-square tables count, in percent.
+    pub piece_square_scale: i32,
+    /// How many centipawns worse than an equal position a search thinks a draw is (by
+    /// stalemate, repetition or the fifty-move rule). A positive contempt avoids draws and a
+    /// negative one seeks them. Only the search uses it, since evaluate doesn't score draws.
+    pub contempt: i32,
+}
+
+impl EvalParams {
+    /// The default parameters, as a constant.
+    pub const DEFAULT: EvalParams = EvalParams {
+        pawn_value: 100,
+        knight_value: 320,
+        bishop_value: 330,
+        rook_value: 500,
+        queen_value: 900,
+        bishop_pair: 30,
+        knight_mobility: 4,
+        bishop_mobility: 5,
+        rook_mobility: 2,
+        queen_mobility: 1,
+        doubled_pawn: 15,
+        isolated_pawn: 15,
+        passed_pawn: [5, 10, 20, 35, 60, 100],
+        connected_passer: 15,
+        shield_missing: 25,
+        shield_advanced: 10,
+        semi_open_file: 10,
+        open_file: 20,
+        knight_attack: 6,
+        bishop_attack: 6,
+        rook_attack: 9,
+        queen_attack: 15,
+        piece_square_scale: 100,
+        contempt: 0,
+    };
+
+    /// The material value of a role. The king can never be taken, so it is worth 0.
+    pub fn value(&self, role: PieceRole) -> i32 {
+        match role {
+            PieceRole::Pawn => self.pawn_value,
+            PieceRole::Knight => self.knight_value,
+            PieceRole::Bishop => self.bishop_value,
+            PieceRole::Rook => self.rook_value,
+            PieceRole::Queen => self.queen_value,
+            PieceRole::King => 0
+        }
+    }
+
+    /// The bonus for every move of a piece of the role.
+    pub fn mobility(&self, role: PieceRole) -> i32 {
+        match role {
+            PieceRole::Knight => self.knight_mobility,
+            PieceRole::Bishop => self.bishop_mobility,
+            PieceRole::Rook => self.rook_mobility,
+            PieceRole::Queen => self.queen_mobility,
+            PieceRole::Pawn | PieceRole::King => 0
+        }
+    }
+
+    /// The penalty for every attack of a piece of the role on the squares around the king.
+    pub fn king_attack(&self, role: PieceRole) -> i32 {
+        match role {
+            PieceRole::Knight => self.knight_attack,
+            PieceRole::Bishop => self.bishop_attack,
+            PieceRole::Rook => self.rook_attack,
+            PieceRole::Queen => self.queen_attack,
+            PieceRole::Pawn | PieceRole::King => 0
+        }
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> EvalParams {
+        EvalParams::DEFAULT
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::EvalParams;
+    use crate::tests::random_games;
+    use crate::Game;
+
+    //check that the default parameters evaluate like evaluate
+    #[test]
+    fn check_default_params() {
+        for game1 in random_games(50, 0x510E527FADE682D1) {
+            assert_eq!(game1.evaluate_with(&EvalParams::default()), game1.evaluate());
+        }
+    }
+
+    //check that the parameters reach the terms they weigh
+    #[test]
+    fn check_params_reach_terms() {
+        let no_mobility = EvalParams { knight_mobility: 0, bishop_mobility: 0, rook_mobility: 0, queen_mobility: 0, ..EvalParams::DEFAULT };
+        let open = Game::from_fen("rnbqkbnr/pppp1ppp/8/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 2").unwrap();
+        assert_ne!(open.evaluate_with(&no_mobility), open.evaluate());
+        // the pieces of a symmetric locked position can move just as much
+        let locked = Game::from_fen("rnbqkbnr/8/8/pppppppp/PPPPPPPP/8/8/RNBQKBNR w - - 0 1").unwrap();
+        assert_eq!(locked.evaluate_with(&no_mobility), locked.evaluate());
+        // with a knight, since king and pawn against king is a draw whatever the parameters
+        let passer = Game::from_fen("4k3/8/8/3P4/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let no_passers = EvalParams { passed_pawn: [0; 6], ..EvalParams::DEFAULT };
+        assert_eq!(passer.evaluate() - passer.evaluate_with(&no_passers), 35);
+        let attacked = Game::from_fen("6k1/5ppp/8/8/8/8/5PP1/q5K1 w - - 0 1").unwrap();
+        assert_ne!(attacked.evaluate_with(&EvalParams { queen_attack: 0, ..EvalParams::DEFAULT }), attacked.evaluate());
+        let no_tables = EvalParams { piece_square_scale: 0, ..EvalParams::DEFAULT };
+        // the kings cancel out, so only the knight on d4 is left
+        let knight = Game::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(knight.evaluate() - knight.evaluate_with(&no_tables), 20);
+    }
+}
@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+
+use super::params::EvalParams;
+use crate::Position;
+
+// the number of entries of the cache, a power of two so that the low bits of a key pick its entry
+const PAWN_CACHE_SIZE: usize = 4096;
+
+/// The pawn structure scores of recently seen pawn placements, by pawn key, for one set of
+/// parameters. A new entry replaces the old one in its slot.
+struct PawnCache {
+    params: EvalParams,
+    entries: Vec<Option<(u64, i32)>>,
+}
+
+thread_local! {
+    // every thread has its own cache, so searches on different threads don't wait for each other
+    static PAWN_CACHE: RefCell<PawnCache> = RefCell::new(PawnCache { params: EvalParams::DEFAULT, entries: vec![None; PAWN_CACHE_SIZE] });
+}
+
+impl Position {
+    /// Like pawn_structure, but looks the score up in the pawn cache of the thread first. The
+    /// pawn structure only depends on where the pawns are, which changes rarely in a search.
+    pub(crate) fn cached_pawn_structure(&self, params: &EvalParams) -> i32 {
+        return PAWN_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            // scores of other parameters are no use
+            if cache.params != *params {
+                cache.params = *params;
+                cache.entries.fill(None);
+            }
+            let slot = self.pawn_key as usize & (PAWN_CACHE_SIZE - 1);
+            if let Some((key, score)) = cache.entries[slot] {
+                if key == self.pawn_key {return score;}
+            }
+            let score = self.pawn_structure(params);
+            cache.entries[slot] = Some((self.pawn_key, score));
+            return score;
+        });
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::EvalParams;
+    use crate::tests::{coordinate_move, random_games};
+    use crate::{pawn_square_key, Game, Position};
+
+    fn pawn_key_from_scratch(position: &Position) -> u64 {
+        return (0..64).fold(0, |key, index| key ^ pawn_square_key(position.board[index], index));
+    }
+
+    //check that the pawn key is kept up to date and that the cache gives the same scores as no cache
+    #[test]
+    fn check_pawn_cache() {
+        let no_doubled = EvalParams { doubled_pawn: 0, ..EvalParams::DEFAULT };
+        let mut positions = 0;
+        for game1 in random_games(200, 0x9B05688C2B3E6C1F) {
+            let history = game1.history.iter().map(|entry| &entry.position);
+            for position in history.chain([&game1.position]) {
+                assert_eq!(position.pawn_key, pawn_key_from_scratch(position));
+                for params in [EvalParams::DEFAULT, no_doubled] {
+                    assert_eq!(position.cached_pawn_structure(&params), position.pawn_structure(&params));
+                }
+                positions += 1;
+            }
+        }
+        assert!(positions > 2000);
+    }
+
+    //check that a promotion takes the pawn out of the key and the cached score
+    #[test]
+    fn check_pawn_cache_promotion() {
+        let mut game1 = Game::from_fen("1r6/P4k2/8/8/8/8/7p/4K3 w - - 0 1").unwrap();
+        let params = EvalParams::DEFAULT;
+        assert_eq!(game1.position.cached_pawn_structure(&params), game1.position.pawn_structure(&params));
+        for mv in ["a7b8q", "h2h1n"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+            assert_eq!(game1.position.pawn_key, pawn_key_from_scratch(&game1.position));
+            assert_eq!(game1.position.cached_pawn_structure(&params), game1.position.pawn_structure(&params));
+        }
+        assert_eq!(game1.position.pawn_key, 0);
+        assert_eq!(game1.position.cached_pawn_structure(&params), 0);
+    }
+}
@@ -0,0 +1,169 @@
+use super::params::EvalParams;
+use crate::{Color, Game, PieceRole, Position, Square};
+
+impl Game {
+    /// Returns the squares of the passed pawns of the color, from a8 to h1: the pawns without
+    /// enemy pawns ahead of them on their own file or the files beside it. A pawn blocked by an
+    /// enemy piece is still passed, while one blocked by an enemy pawn is not.
+    pub fn passed_pawns(&self, color: Color) -> Vec<Square> {
+        return self.pawn_squares(color, |index| self.position.is_passed_pawn(index, color));
+    }
+
+    /// Returns the squares of the doubled pawns of the color, from a8 to h1: every pawn that
+    /// shares its file with another pawn of its color, so all three of tripled pawns.
+    pub fn doubled_pawns(&self, color: Color) -> Vec<Square> {
+        return self.pawn_squares(color, |index| self.position.is_doubled_pawn(index, color));
+    }
+
+    /// Returns the squares of the isolated pawns of the color, from a8 to h1: the pawns without
+    /// pawns of their color on the files beside them (only the b-file for an a-pawn and the
+    /// g-file for an h-pawn).
+    pub fn isolated_pawns(&self, color: Color) -> Vec<Square> {
+        return self.pawn_squares(color, |index| self.position.is_isolated_pawn(index, color));
+    }
+
+    /// The squares of the pawns of the color that pass the test, from a8 to h1.
+    fn pawn_squares(&self, color: Color, test: impl Fn(usize) -> bool) -> Vec<Square> {
+        return (0..64).filter(|&index| self.position.is_pawn(index, color) && test(index)).map(|index| Square(index as u8)).collect();
+    }
+}
+
+impl Position {
+    fn is_pawn(&self, index: usize, color: Color) -> bool {
+        return self.piece_at(index).is_some_and(|piece| piece.role == PieceRole::Pawn && piece.color == color);
+    }
+
+    /// The files beside the file of the index, one for the a and h files.
+    fn neighbour_columns(index: usize) -> std::ops::RangeInclusive<usize> {
+        let column = index % 8;
+        return column.saturating_sub(1)..=(column + 1).min(7);
+    }
+
+    /// Returns true if no pawn of the opponent of `color` stands ahead of the index, on its file
+    /// or the files beside it.
+    pub(crate) fn is_passed_pawn(&self, index: usize, color: Color) -> bool {
+        let row = index / 8;
+        // white pawns move towards row 0
+        let ahead = if color == Color::White {0..row} else {row + 1..8};
+        for r in ahead {
+            if Position::neighbour_columns(index).any(|c| self.is_pawn(r * 8 + c, color.opposite())) {return false;}
+        }
+        return true;
+    }
+
+    /// Returns true if another pawn of `color` stands on the file of the index.
+    pub(crate) fn is_doubled_pawn(&self, index: usize, color: Color) -> bool {
+        let (row, column) = (index / 8, index % 8);
+        return (0..8).any(|r| r != row && self.is_pawn(r * 8 + column, color));
+    }
+
+    /// Returns true if no pawn of `color` stands on the files beside the index.
+    pub(crate) fn is_isolated_pawn(&self, index: usize, color: Color) -> bool {
+        let column = index % 8;
+        return !Position::neighbour_columns(index).any(|c| c != column && (0..8).any(|r| self.is_pawn(r * 8 + c, color)));
+    }
+
+    /// Returns true if a pawn of `color` stands beside the index or protects it.
+    fn is_connected_pawn(&self, index: usize, color: Color) -> bool {
+        let (row, column) = (index / 8, index % 8);
+        let behind = if color == Color::White {row + 1} else {row.wrapping_sub(1)};
+        return Position::neighbour_columns(index).any(|c| {
+            c != column && (self.is_pawn(row * 8 + c, color) || (behind < 8 && self.is_pawn(behind * 8 + c, color)))
+        });
+    }
+
+    /// Scores the pawns from White's point of view: penalties for doubled and isolated pawns
+    /// and bonuses for passed pawns, more the further they are, and more if they are connected.
+    pub(crate) fn pawn_structure(&self, params: &EvalParams) -> i32 {
+        let mut score: i32 = 0;
+        for index in 0..64 {
+            let color = match self.piece_at(index) {
+                Some(piece) if piece.role == PieceRole::Pawn => piece.color,
+                _ => continue
+            };
+            let mut pawn_score = 0;
+            if self.is_doubled_pawn(index, color) {pawn_score -= params.doubled_pawn;}
+            if self.is_isolated_pawn(index, color) {pawn_score -= params.isolated_pawn;}
+            if self.is_passed_pawn(index, color) {
+                // a FEN can put pawns on the first or last rank
+                let advanced = if color == Color::White {6usize.saturating_sub(index / 8)} else {(index / 8).saturating_sub(1)};
+                pawn_score += params.passed_pawn[advanced.min(5)];
+                if self.is_connected_pawn(index, color) {pawn_score += params.connected_passer;}
+            }
+            score += if color == Color::White {pawn_score} else {-pawn_score};
+        }
+        return score;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::EvalParams;
+    use crate::{Color, Game, Square};
+
+    fn squares(names: &[&str]) -> Vec<Square> {
+        return names.iter().map(|name| Square::from_algebraic(name).unwrap()).collect();
+    }
+
+    //check that protected and blockaded passed pawns are found, but not pawns an enemy pawn can stop
+    #[test]
+    fn check_passed_pawns() {
+        let game1 = Game::from_fen("4k3/p7/8/3P4/2P5/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), squares(&["d5", "c4"]));
+        assert_eq!(game1.passed_pawns(Color::Black), squares(&["a7"]));
+        // a knight in front of the pawn blocks it but doesn't stop it from being passed
+        let game1 = Game::from_fen("4k3/8/3n4/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), squares(&["d5"]));
+        // a pawn in front of it or beside it ahead does
+        let game1 = Game::from_fen("4k3/3p4/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), []);
+        let game1 = Game::from_fen("4k3/1p6/8/P7/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), []);
+        // the a-file only has the b-file beside it
+        let game1 = Game::from_fen("4k3/8/2p5/P7/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), squares(&["a5"]));
+        // enemy pawns beside it but behind it don't count, for either color
+        let game1 = Game::from_fen("4k3/8/8/3P4/2p5/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.passed_pawns(Color::White), squares(&["d5"]));
+        assert_eq!(game1.passed_pawns(Color::Black), squares(&["c4"]));
+    }
+
+    //check that every pawn of doubled and tripled pawns is doubled
+    #[test]
+    fn check_doubled_pawns() {
+        let game1 = Game::from_fen("4k3/8/8/8/2P5/2P1P3/2P1P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.doubled_pawns(Color::White), squares(&["c4", "c3", "e3", "c2", "e2"]));
+        assert_eq!(game1.doubled_pawns(Color::Black), []);
+        // pawns of the other color on the same file don't count
+        let game1 = Game::from_fen("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.doubled_pawns(Color::White), []);
+    }
+
+    //check that isolated pawns are found, also on the rook files and when doubled
+    #[test]
+    fn check_isolated_pawns() {
+        let game1 = Game::from_fen("4k3/8/8/8/8/P3P3/P1P1P2P/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.isolated_pawns(Color::White), squares(&["a3", "e3", "a2", "c2", "e2", "h2"]));
+        // the a-pawns are doubled and isolated, and the b-pawn is beside them
+        let game1 = Game::from_fen("4k3/8/8/8/8/P7/PP6/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.isolated_pawns(Color::White), []);
+        let game1 = Game::from_fen("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.isolated_pawns(Color::White), squares(&["a3", "a2"]));
+        assert_eq!(game1.doubled_pawns(Color::White), squares(&["a3", "a2"]));
+    }
+
+    //check that a protected passed pawn scores well above the same pawns doubled and isolated
+    #[test]
+    fn check_pawn_structure() {
+        let protected = Game::from_fen("6k1/p7/8/3P4/4P3/8/8/6K1 w - - 0 1").unwrap();
+        let crippled = Game::from_fen("6k1/p7/8/8/8/4P3/4P3/6K1 w - - 0 1").unwrap();
+        let structure = |game: &Game| -> i32 {game.position.pawn_structure(&EvalParams::DEFAULT)};
+        let difference = structure(&protected) - structure(&crippled);
+        assert!(difference > 50 && difference < 200, "{}", difference);
+        assert!(protected.evaluate() > crippled.evaluate());
+    }
+}
@@ -0,0 +1,83 @@
+use super::eval::MAX_PHASE;
+use crate::{Game, PieceRole};
+
+// the phase is counted in points: a knight or bishop is 1, a rook 2 and a queen 4, so all the
+// pieces make 24 (MAX_PHASE)
+
+// the opening lasts for the first moves, as long as at most a minor piece has been traded ...
+const OPENING_MOVES: u32 = 10;
+const OPENING_MIN_POINTS: i32 = 22;
+// ... and the endgame starts when this few points are left, or when the queens are gone and
+// at most this many points are left
+const ENDGAME_POINTS: i32 = 8;
+const ENDGAME_POINTS_WITHOUT_QUEENS: i32 = 12;
+
+/// The stage of a game, see Game::phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamePhase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl Game {
+    /// Returns the stage of the game from the knights, bishops, rooks and queens left, counted
+    /// as 1 point for a knight or bishop, 2 for a rook and 4 for a queen (24 at the start):
+    /// the opening lasts the first 10 moves while at least 22 points are left, and the endgame
+    /// starts at 8 points or fewer, or at 12 points or fewer without queens. Everything in
+    /// between is the middlegame.
+    pub fn phase(&self) -> GamePhase {
+        let points = self.position.phase();
+        let queens = (0..64).any(|index| self.position.piece_at(index).is_some_and(|piece| piece.role == PieceRole::Queen));
+        if points <= ENDGAME_POINTS || (!queens && points <= ENDGAME_POINTS_WITHOUT_QUEENS) {return GamePhase::Endgame;}
+        if self.position.fullmove <= OPENING_MOVES && points >= OPENING_MIN_POINTS {return GamePhase::Opening;}
+        return GamePhase::Middlegame;
+    }
+
+    /// Returns how close the game is to a bare endgame, from 0 with all the knights, bishops,
+    /// rooks and queens on the board to 1 with none of them, counted in the points of phase.
+    /// The evaluation blends its middlegame and endgame scores by it.
+    pub fn phase_factor(&self) -> f32 {
+        return (MAX_PHASE - self.position.phase()) as f32 / MAX_PHASE as f32;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::GamePhase;
+    use crate::tests::coordinate_move;
+    use crate::Game;
+
+    //check the phase of the start position and of a rook ending
+    #[test]
+    fn check_phase() {
+        assert_eq!(Game::new().phase(), GamePhase::Opening);
+        assert_eq!(Game::new().phase_factor(), 0.0);
+        let game1 = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(game1.phase(), GamePhase::Endgame);
+        assert!((game1.phase_factor() - 1.0).abs() < 0.1);
+        // all the pieces, but late in the game
+        let game1 = Game::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 20").unwrap();
+        assert_eq!(game1.phase(), GamePhase::Middlegame);
+    }
+
+    //check that the phase factor grows as the pieces are traded in a Scotch game
+    #[test]
+    fn check_phase_factor_monotone() {
+        let mut game1 = Game::new();
+        let moves = ["e2e4", "e7e5", "g1f3", "b8c6", "d2d4", "e5d4", "f3d4", "c6d4", "d1d4", "d8f6", "d4f6", "g8f6", "c1g5", "f8e7", "g5f6", "e7f6", "b1c3", "f6c3", "b2c3"];
+        let mut factors = vec![game1.phase_factor()];
+        for mv in moves {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+            factors.push(game1.phase_factor());
+        }
+        assert!(factors.windows(2).all(|pair| pair[0] <= pair[1]));
+        // the queens, the knights and two bishops are gone
+        assert_eq!(factors.last(), Some(&(14.0 / 24.0)));
+        assert_eq!(game1.phase(), GamePhase::Endgame);
+    }
+}
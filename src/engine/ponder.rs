@@ -0,0 +1,120 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::search::{find_best_move_signalled, SearchResult, StopSignal};
+use super::time::Instant;
+use crate::{Game, Move};
+
+/// A search of the position after the expected reply of the opponent, running on a background
+/// thread while the opponent thinks (pondering). Dropping the handle stops the search and
+/// waits for the thread, so no thread is left running.
+#[derive(Debug)]
+pub struct PonderHandle {
+    signal: Arc<StopSignal>,
+    thread: Option<JoinHandle<SearchResult>>,
+}
+
+/// Starts searching the position after `expected_reply` on a background thread, for as long
+/// as it takes the opponent to move. Call ponder_hit if the opponent made the expected reply,
+/// and stop (or drop the handle) otherwise. If the reply isn't legal there is nothing to
+/// ponder, and ponder_hit returns a result without a move.
+pub fn start_pondering(game: &Game, expected_reply: Move) -> PonderHandle {
+    let signal = Arc::new(StopSignal::default());
+    let mut game = game.clone();
+    let thread = game.play_move(&expected_reply).map(|_| {
+        let signal = Arc::clone(&signal);
+        thread::spawn(move || find_best_move_signalled(&game, &signal))
+    });
+    return PonderHandle { signal, thread };
+}
+
+impl PonderHandle {
+    /// The opponent made the expected reply: keep searching for `budget` more time, like
+    /// find_best_move_timed, and return the result. Everything searched while pondering is
+    /// kept, so this finds a move faster (or deeper) than a search started now.
+    pub fn ponder_hit(mut self, budget: Duration) -> SearchResult {
+        *self.signal.deadline.lock().unwrap() = Some(Instant::now() + budget);
+        return match self.thread.take() {
+            Some(thread) => thread.join().unwrap(),
+            None => SearchResult { best_move: None, score: 0, depth: 0, nodes: 0 }
+        };
+    }
+
+    /// The opponent made another move: stop the search and wait for the thread to finish,
+    /// which takes a few milliseconds at most.
+    pub fn stop(mut self) {
+        self.halt();
+    }
+
+    fn halt(&mut self) {
+        self.signal.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            // the result of an abandoned search isn't needed, and neither is a panic in it
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PonderHandle {
+    fn drop(&mut self) {
+        self.halt();
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::start_pondering;
+    use crate::engine::find_best_move;
+    use crate::tests::coordinate_move;
+    use crate::Game;
+
+    //check that a ponder hit returns a legal move faster than a cold search to the same depth
+    #[test]
+    fn check_ponder_hit() {
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        let handle = start_pondering(&game1, coordinate_move("e7e5"));
+        thread::sleep(Duration::from_millis(300));
+        let start = Instant::now();
+        let result = handle.ponder_hit(Duration::ZERO);
+        let ponder_time = start.elapsed();
+        game1.play_move(&coordinate_move("e7e5")).unwrap();
+        assert!(game1.is_legal_move(&result.best_move.unwrap()));
+        assert!(result.depth >= 2);
+        let start = Instant::now();
+        find_best_move(&game1, result.depth).unwrap();
+        assert!(ponder_time < start.elapsed());
+    }
+
+    //check that stopping a deep ponder search returns quickly, also when the handle is dropped
+    #[test]
+    fn check_ponder_stop() {
+        let game1 = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let handle = start_pondering(&game1, coordinate_move("e2a6"));
+        thread::sleep(Duration::from_millis(200));
+        let start = Instant::now();
+        handle.stop();
+        assert!(start.elapsed() < Duration::from_millis(10));
+        let handle = start_pondering(&game1, coordinate_move("e2a6"));
+        thread::sleep(Duration::from_millis(200));
+        let start = Instant::now();
+        drop(handle);
+        assert!(start.elapsed() < Duration::from_millis(10));
+    }
+
+    //check that an illegal expected reply gives no move
+    #[test]
+    fn check_ponder_illegal_reply() {
+        let handle = start_pondering(&Game::new(), coordinate_move("e7e5"));
+        assert_eq!(handle.ponder_hit(Duration::ZERO).best_move, None);
+    }
+}
@@ -0,0 +1,143 @@
+use super::eval::MAX_PHASE;
+use super::params::EvalParams;
+use crate::{Color, PieceRole, Position};
+
+// The piece-square tables, in centipawns for a white piece on every square, from a8 to h1 like
+// the board. A black piece uses the square mirrored from top to bottom.
+
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+// the king hides behind its pawns while there are pieces to attack it ...
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+// ... and goes to the center in the endgame
+const KING_ENDGAME_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+impl Position {
+    /// Sums the piece-square tables of every piece from White's point of view, blending the
+    /// middlegame and endgame tables of the kings by the phase of the position.
+    pub(crate) fn piece_square_score(&self, params: &EvalParams) -> i32 {
+        let (mut middlegame, mut endgame) = (0, 0);
+        for index in 0..64 {
+            let piece = match self.piece_at(index) {
+                Some(piece) => piece,
+                None => continue
+            };
+            let (square, sign) = if piece.color == Color::White {(index, 1)} else {(index ^ 56, -1)};
+            let (middlegame_score, endgame_score) = match piece.role {
+                PieceRole::Pawn => (PAWN_TABLE[square], PAWN_TABLE[square]),
+                PieceRole::Knight => (KNIGHT_TABLE[square], KNIGHT_TABLE[square]),
+                PieceRole::Bishop => (BISHOP_TABLE[square], BISHOP_TABLE[square]),
+                PieceRole::Rook => (ROOK_TABLE[square], ROOK_TABLE[square]),
+                PieceRole::Queen => (QUEEN_TABLE[square], QUEEN_TABLE[square]),
+                PieceRole::King => (KING_MIDDLEGAME_TABLE[square], KING_ENDGAME_TABLE[square])
+            };
+            middlegame += sign * middlegame_score;
+            endgame += sign * endgame_score;
+        }
+        let phase = self.phase();
+        return (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE * params.piece_square_scale / 100;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::EvalParams;
+    use crate::Game;
+
+    //check that a knight on the rim is worth less than a centralized knight
+    #[test]
+    fn check_piece_square_knight() {
+        let rim = Game::from_fen("4k3/8/8/8/N7/8/8/4K3 w - - 0 1").unwrap();
+        let center = Game::from_fen("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(center.position.piece_square_score(&EvalParams::DEFAULT) - rim.position.piece_square_score(&EvalParams::DEFAULT), 50);
+        assert!(center.evaluate() > rim.evaluate());
+    }
+
+    //check that the king stays home in the middlegame and goes to the center in the endgame
+    #[test]
+    fn check_piece_square_king_phase() {
+        let score = |fen: &str| -> i32 {Game::from_fen(fen).unwrap().position.piece_square_score(&EvalParams::DEFAULT)};
+        let home = score("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let center = score("rnbqkbnr/pppppppp/8/8/4K3/8/PPPPPPPP/RNBQ1BNR w kq - 0 1");
+        assert_eq!(center - home, -40);
+        let home = score("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let center = score("4k3/8/8/8/4K3/8/8/8 w - - 0 1");
+        assert_eq!(center - home, 70);
+    }
+}
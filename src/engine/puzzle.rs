@@ -0,0 +1,142 @@
+use super::mate::{defend, shortest_mate};
+use super::params::EvalParams;
+use super::search::score_moves;
+use crate::{Game, GameState, Move};
+
+/// What validate_puzzle requires of a puzzle that doesn't end in mate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleCriteria {
+    /// How many plies deep every move is searched.
+    pub depth: u32,
+    /// The score in centipawns a solution move has to reach, and every other move has to stay
+    /// below, for the solution to be clearly best.
+    pub threshold: i32,
+}
+
+impl Default for PuzzleCriteria {
+    fn default() -> PuzzleCriteria {
+        PuzzleCriteria { depth: 4, threshold: 300 }
+    }
+}
+
+/// The verdict of validate_puzzle. Plies count from 0, the first move of the solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleVerdict {
+    /// Every solution move is the only good move, and every reply is the best defense.
+    Valid,
+    /// The move of the solution at the ply is not legal.
+    IllegalMove { ply: usize },
+    /// The solution move at the ply doesn't win (see PuzzleCriteria).
+    NotWinning { ply: usize },
+    /// Another move at the ply is as good as the solution move.
+    NotUnique { ply: usize, alternative: Move },
+    /// The reply of the opponent at the ply is not the best defense.
+    WeakDefense { ply: usize, better: Move },
+}
+
+/// Checks that a puzzle has a single solution. The moves of the solution alternate between the
+/// player whose turn it is (the solver) and the opponent. If the solution ends in mate, every
+/// solver move has to be the only move that forces mate as fast, and every reply has to hold
+/// out as long as any other reply. Otherwise the moves are judged by a search: the solver
+/// moves have to be the only moves that reach the threshold of the criteria, and no reply may
+/// score better for the opponent than the reply of the solution.
+pub fn validate_puzzle(game: &Game, solution: &[Move], criteria: PuzzleCriteria) -> PuzzleVerdict {
+    if solution.is_empty() {return PuzzleVerdict::NotWinning { ply: 0 };}
+    // play the whole solution first, to know that it's legal and if it ends in mate
+    let mut games: Vec<Game> = Vec::new();
+    let mut end = game.clone();
+    for (ply, mv) in solution.iter().enumerate() {
+        games.push(end.clone());
+        if end.play_move(mv).is_none() {return PuzzleVerdict::IllegalMove { ply };}
+    }
+    let ends_in_mate = end.get_game_state() == GameState::Checkmate;
+    for (ply, (&mv, game)) in solution.iter().zip(&games).enumerate() {
+        let verdict = if ends_in_mate {
+            check_mate_ply(game, mv, ply, solution.len() - ply)
+        } else {
+            check_scored_ply(game, mv, ply, criteria)
+        };
+        if verdict != PuzzleVerdict::Valid {return verdict;}
+    }
+    return PuzzleVerdict::Valid;
+}
+
+/// Checks the move of a solution that mates in `plies_to_mate` plies from this position.
+fn check_mate_ply(game: &Game, mv: Move, ply: usize, plies_to_mate: usize) -> PuzzleVerdict {
+    let plies_to_mate = plies_to_mate as u32;
+    for other in game.get_all_possible_moves() {
+        if other == mv {continue;}
+        let mut child = game.position;
+        child.apply_move(other);
+        if ply.is_multiple_of(2) {
+            // the solver may not have another move that mates as fast
+            if defend(&child, plies_to_mate - 1).is_some() {return PuzzleVerdict::NotUnique { ply, alternative: other };}
+        } else if shortest_mate(&child, plies_to_mate - 1).is_none() {
+            // the opponent may not have a reply that holds out longer
+            return PuzzleVerdict::WeakDefense { ply, better: other };
+        }
+    }
+    return PuzzleVerdict::Valid;
+}
+
+/// Checks the move of a solution that doesn't end in mate, by scoring every move.
+fn check_scored_ply(game: &Game, mv: Move, ply: usize, criteria: PuzzleCriteria) -> PuzzleVerdict {
+    let scores = score_moves(game, criteria.depth, &EvalParams::DEFAULT);
+    let score = scores.iter().find(|(other, _)| *other == mv).unwrap().1;
+    if ply.is_multiple_of(2) {
+        if score < criteria.threshold {return PuzzleVerdict::NotWinning { ply };}
+        if let Some(&(alternative, _)) = scores.iter().find(|&&(other, other_score)| other != mv && other_score >= criteria.threshold) {
+            return PuzzleVerdict::NotUnique { ply, alternative };
+        }
+    } else if let Some(&(better, _)) = scores.iter().filter(|(_, other_score)| *other_score > score).max_by_key(|(_, other_score)| *other_score) {
+        return PuzzleVerdict::WeakDefense { ply, better };
+    }
+    return PuzzleVerdict::Valid;
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_puzzle, PuzzleCriteria, PuzzleVerdict};
+    use crate::tests::coordinate_move;
+    use crate::{Game, Move};
+
+    fn line(moves: &[&str]) -> Vec<Move> {
+        return moves.iter().map(|notation| coordinate_move(notation)).collect();
+    }
+
+    //check that a mate in two with a single solution and forced replies is valid
+    #[test]
+    fn check_valid_mate_puzzle() {
+        let game1 = Game::from_fen("r6k/6pp/7N/8/2Q5/8/8/6K1 w - - 0 1").unwrap();
+        let verdict = validate_puzzle(&game1, &line(&["c4g8", "a8g8", "h6f7"]), PuzzleCriteria::default());
+        assert_eq!(verdict, PuzzleVerdict::Valid);
+        let verdict = validate_puzzle(&game1, &line(&["c4g8", "h8g8"]), PuzzleCriteria::default());
+        assert_eq!(verdict, PuzzleVerdict::IllegalMove { ply: 1 });
+    }
+
+    //check that a mate with two key moves fails on the first ply
+    #[test]
+    fn check_mate_puzzle_not_unique() {
+        // Rb8# mates as fast as Ra8#
+        let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/RR4K1 w - - 0 1").unwrap();
+        let verdict = validate_puzzle(&game1, &line(&["a1a8"]), PuzzleCriteria::default());
+        assert_eq!(verdict, PuzzleVerdict::NotUnique { ply: 0, alternative: coordinate_move("b1b8") });
+    }
+
+    //check puzzles that are judged by the search
+    #[test]
+    fn check_scored_puzzle() {
+        // only the knight can take the hanging queen
+        let game1 = Game::from_fen("4k3/8/8/8/3q4/5N2/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(validate_puzzle(&game1, &line(&["f3d4"]), PuzzleCriteria::default()), PuzzleVerdict::Valid);
+        assert_eq!(validate_puzzle(&game1, &line(&["e1f1"]), PuzzleCriteria::default()), PuzzleVerdict::NotWinning { ply: 0 });
+        // the queen can be taken by the knight and the rook
+        let game1 = Game::from_fen("4k3/8/8/8/3q4/5N2/8/3RK3 w - - 0 1").unwrap();
+        let verdict = validate_puzzle(&game1, &line(&["f3d4"]), PuzzleCriteria::default());
+        assert_eq!(verdict, PuzzleVerdict::NotUnique { ply: 0, alternative: coordinate_move("d1d4") });
+    }
+}
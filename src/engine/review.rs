@@ -0,0 +1,167 @@
+use super::eval::MATE;
+use super::search::{analyze, score_is_mate, Score, SearchLimit};
+use crate::{Game, GameState, Move};
+
+/// How good a move of a game was, see analyze_game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Judgment {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+/// How many centipawns a move may lose before it is judged an inaccuracy, a mistake or a
+/// blunder. A move that loses less is good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JudgmentThresholds {
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+}
+
+impl Default for JudgmentThresholds {
+    fn default() -> JudgmentThresholds {
+        JudgmentThresholds { inaccuracy: 50, mistake: 100, blunder: 300 }
+    }
+}
+
+/// The review of one move of a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveJudgment {
+    /// The move that was played, also in standard algebraic notation.
+    pub mv: Move,
+    pub san: String,
+    pub judgment: Judgment,
+    /// The centipawns the move lost against the best move, 0 if it lost nothing. Moves that
+    /// change who mates are judged by that instead, and lose 0 or MATE.
+    pub loss: i32,
+    /// The score before and after the move, both from the point of view of the player who
+    /// made it. A move that mates scores Score::Mate(0) after it.
+    pub score_before: Score,
+    pub score_after: Score,
+    /// The move the engine would have played, also in standard algebraic notation.
+    pub best_move: Move,
+    pub best_san: String,
+}
+
+/// Reviews every move of the game, from the position it started from (or was loaded from)
+/// to the current one. Every position is searched within the limit, and a move is judged by
+/// how much the score dropped for the player who made it: the best move of the engine and
+/// moves that lose nothing are the best, and otherwise the default JudgmentThresholds
+/// decide. Missing a forced mate or walking into one is a blunder. The moves come in the
+/// order they were played.
+pub fn analyze_game(game: &Game, budget_per_move: SearchLimit) -> Vec<MoveJudgment> {
+    return analyze_game_with(game, budget_per_move, &JudgmentThresholds::default());
+}
+
+/// Like analyze_game, but with the thresholds instead of the default ones.
+pub fn analyze_game_with(game: &Game, budget_per_move: SearchLimit, thresholds: &JudgmentThresholds) -> Vec<MoveJudgment> {
+    let mut replay = game.clone();
+    let mut moves: Vec<Move> = Vec::new();
+    while let Some(mv) = replay.undo() {
+        moves.push(mv);
+    }
+    moves.reverse();
+    let mut judgments: Vec<MoveJudgment> = Vec::with_capacity(moves.len());
+    // the best move and score of the position before every move, from the point of view of
+    // the player to move
+    let mut best = best_line(&replay, budget_per_move);
+    for mv in moves {
+        let (best_move, before) = best.unwrap();
+        let (san, best_san) = (replay.to_san(&mv).unwrap(), replay.to_san(&best_move).unwrap());
+        replay.play_move(&mv).unwrap();
+        let next = best_line(&replay, budget_per_move);
+        let after = -next.map_or(terminal_score(&replay), |(_, score)| score);
+        let loss = if mv == best_move {0} else {score_loss(before, after)};
+        let judgment = match loss {
+            loss if loss <= 0 => Judgment::Best,
+            loss if loss < thresholds.inaccuracy => Judgment::Good,
+            loss if loss < thresholds.mistake => Judgment::Inaccuracy,
+            loss if loss < thresholds.blunder => Judgment::Mistake,
+            _ => Judgment::Blunder
+        };
+        judgments.push(MoveJudgment {
+            mv,
+            san,
+            judgment,
+            loss: loss.max(0),
+            score_before: Score::from_search_score(before),
+            score_after: Score::from_search_score(after),
+            best_move,
+            best_san
+        });
+        best = next;
+    }
+    return judgments;
+}
+
+/// The best move and its score in the position, or None if the game is over.
+fn best_line(game: &Game, limit: SearchLimit) -> Option<(Move, i32)> {
+    let line = analyze(game, limit, 1).into_iter().next()?;
+    return Some((line.pv[0], line.score));
+}
+
+/// The score of a game that is over for the player whose turn it is.
+fn terminal_score(game: &Game) -> i32 {
+    return if game.get_game_state() == GameState::Checkmate {-MATE} else {0};
+}
+
+/// How much worse `after` is than `before`. A mate for the player counts as more than every
+/// other score and a mate against it as less, however far away it is, so missing a mate or
+/// walking into one loses MATE, while a mate found a few moves later loses nothing.
+fn score_loss(before: i32, after: i32) -> i32 {
+    let outcome = |score: i32| -> i32 {if score_is_mate(score) {score.signum()} else {0}};
+    if outcome(before) != outcome(after) {return if outcome(before) > outcome(after) {MATE} else {-MATE};}
+    if outcome(before) != 0 {return 0;}
+    return before - after;
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_game, analyze_game_with, Judgment, JudgmentThresholds};
+    use crate::engine::{Score, SearchLimit};
+    use crate::tests::coordinate_move;
+    use crate::Game;
+
+    //check that the move that hangs the queen is the only blunder of the game
+    #[test]
+    fn check_analyze_game() {
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "e7e5", "g1f3", "d8g5", "f3g5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let judgments = analyze_game(&game1, SearchLimit::Depth(3));
+        assert_eq!(judgments.len(), 5);
+        let blunders: Vec<usize> = (0..5).filter(|&ply| judgments[ply].judgment == Judgment::Blunder).collect();
+        assert_eq!(blunders, [3]);
+        assert_eq!(judgments[3].san, "Qg5");
+        assert!(judgments[3].loss > 600, "{}", judgments[3].loss);
+        // taking the queen is what the engine would do
+        assert_eq!((judgments[4].judgment, judgments[4].best_san.as_str()), (Judgment::Best, "Nxg5"));
+        // with a threshold above the queen nothing is a blunder
+        let lenient = JudgmentThresholds { blunder: 2000, ..JudgmentThresholds::default() };
+        assert!(analyze_game_with(&game1, SearchLimit::Depth(3), &lenient).iter().all(|judgment| judgment.judgment != Judgment::Blunder));
+    }
+
+    //check that missing a mate in one is a blunder even if the position stays won
+    #[test]
+    fn check_analyze_game_missed_mate() {
+        let mut game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("a1a7")).unwrap();
+        let judgments = analyze_game(&game1, SearchLimit::Depth(2));
+        assert_eq!(judgments.len(), 1);
+        assert_eq!((judgments[0].judgment, judgments[0].best_san.as_str()), (Judgment::Blunder, "Ra8#"));
+        assert_eq!(judgments[0].score_before, Score::Mate(1));
+        // playing the mate is the best move
+        let mut game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("a1a8")).unwrap();
+        let judgments = analyze_game(&game1, SearchLimit::Depth(2));
+        assert_eq!((judgments[0].judgment, judgments[0].score_after), (Judgment::Best, Score::Mate(0)));
+    }
+}
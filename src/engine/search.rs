@@ -0,0 +1,872 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{repetitions, Color, Game, Move, MoveStage, Position};
+
+use super::eval::MATE;
+use super::params::EvalParams;
+use super::time::Instant;
+
+// the deepest iteration of a timed search, so a forced line can't make it loop forever
+const MAX_DEPTH: u32 = 64;
+// how many positions are visited between looks at the clock
+const NODES_PER_CLOCK_CHECK: u64 = 256;
+// ordering scores: captures and promotions first, then the killer moves, then the other quiet
+// moves by their history score, which is kept below the killers
+const CAPTURE_ORDER: i32 = 1 << 25;
+const KILLER_ORDER: i32 = 1 << 24;
+
+/// The result of a timed search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The best move found, or None if the game is over.
+    pub best_move: Option<Move>,
+    /// The score of the best move in centipawns from the point of view of the player whose
+    /// turn it is.
+    pub score: i32,
+    /// The depth of the last iteration that was searched to the end.
+    pub depth: u32,
+    /// The number of positions visited in all iterations.
+    pub nodes: u64,
+}
+
+/// A score as a GUI shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    /// A score in centipawns.
+    Centipawns(i32),
+    /// A forced mate in this many moves, negative if the player whose turn it is gets mated.
+    Mate(i32),
+}
+
+impl Score {
+    /// Converts a search score, where mates are scored MATE minus the number of plies to the mate.
+    pub fn from_search_score(score: i32) -> Score {
+        if !score_is_mate(score) {return Score::Centipawns(score);}
+        let plies = MATE - score.abs();
+        let moves = (plies + 1) / 2;
+        return Score::Mate(if score > 0 {moves} else {-moves});
+    }
+}
+
+/// The progress of a search, as reported to the info callback of find_best_move_with_info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchInfo {
+    /// The depth being searched, or the depth just finished.
+    pub depth: u32,
+    /// The deepest ply reached, including the captures searched at the end of the lines.
+    pub seldepth: u32,
+    /// The score of the best line, from the point of view of the player whose turn it is.
+    pub score: Score,
+    /// The number of positions searched so far.
+    pub nodes: u64,
+    /// The number of positions searched per second.
+    pub nps: u64,
+    /// The time since the search started.
+    pub elapsed: Duration,
+    /// The best line of the deepest finished depth, empty until depth 1 is finished.
+    pub pv: Vec<Move>,
+    /// Which of the best lines score and pv are, 1 for the best. Only a search for several
+    /// lines (MultiPV in the uci module) reports more than the first.
+    pub multipv: usize,
+}
+
+/// How long a search may go on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchLimit {
+    /// Search this many plies deep.
+    Depth(u32),
+    /// Search one ply deeper at a time until the time is used up.
+    Time(Duration),
+}
+
+/// One of the best lines found by analyze.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    /// 1 for the best line, 2 for the second best and so on.
+    pub rank: usize,
+    /// The score of the line in centipawns from the point of view of the player whose turn it is.
+    pub score: i32,
+    /// The moves both players are expected to make, starting with the player whose turn it is
+    /// (the principal variation).
+    pub pv: Vec<Move>,
+    /// The moves of pv in standard algebraic notation.
+    pub san: Vec<String>,
+}
+
+/// Searches every line of legal moves `depth` plies deep and returns the best move with its
+/// score in centipawns from the point of view of the player whose turn it is. Lines that can't
+/// change the result are cut off (alpha-beta pruning), which gives the same move and score as
+/// searching every line. A forced mate scores MATE minus the number of plies to the mate, so
+/// faster mates are preferred. Returns None if the game is over or the depth is 0.
+pub fn find_best_move(game: &Game, depth: u32) -> Option<(Move, i32)> {
+    return SearchContext::new().search_root(game, depth, None);
+}
+
+/// Scores every legal move with a search `depth` plies deep, in centipawns from the point of
+/// view of the player whose turn it is. Unlike find_best_move every score is exact, not only
+/// the score of the best move. Positions are evaluated with the parameters, and draws score
+/// -params.contempt for the player whose turn it is.
+pub(super) fn score_moves(game: &Game, depth: u32, params: &EvalParams) -> Vec<(Move, i32)> {
+    return score_moves_signalled(game, depth, params, None);
+}
+
+/// Like score_moves, but stops when the signal says so, and then leaves out the moves that
+/// weren't scored to the end.
+pub(super) fn score_moves_signalled(game: &Game, depth: u32, params: &EvalParams, signal: Option<&StopSignal>) -> Vec<(Move, i32)> {
+    let mut searcher = SearchContext::new();
+    searcher.params = *params;
+    searcher.signal = signal;
+    searcher.set_root(game);
+    let mut scores = Vec::new();
+    for &mv in game.legal_moves() {
+        let mut child = game.position;
+        child.apply_move(mv);
+        let score = -searcher.search_child(&child, depth.max(1) - 1, 1, -MATE - 1, MATE + 1);
+        if searcher.stopped {break;}
+        scores.push((mv, score));
+    }
+    return scores;
+}
+
+/// Searches one ply deeper at a time (iterative deepening) until the time budget is used up,
+/// and returns the best move of the deepest search that finished. Returns within about 50ms
+/// after the budget, and always with a legal move unless the game is over. The search stops
+/// early if it finds a forced mate.
+pub fn find_best_move_timed(game: &Game, budget: Duration) -> SearchResult {
+    let mut searcher = SearchContext::new();
+    searcher.deadline = Some(Instant::now() + budget);
+    return searcher.iterative_deepening(game);
+}
+
+/// Like find_best_move_timed, but calls `info` with the progress of the search every time a
+/// depth is finished, and also every `info_interval` positions unless it is 0. The callback
+/// only gets to look at the progress, so it can't change the search.
+pub fn find_best_move_with_info(game: &Game, budget: Duration, info_interval: u64, mut info: impl FnMut(&SearchInfo)) -> SearchResult {
+    let mut searcher = SearchContext::new();
+    searcher.deadline = Some(searcher.start + budget);
+    searcher.info = Some(&mut info);
+    searcher.info_interval = info_interval;
+    return searcher.iterative_deepening(game);
+}
+
+/// Like find_best_move_timed, but stops at whichever limit is reached first, and also returns
+/// the principal variation of the deepest finished iteration. Positions are evaluated with
+/// the parameters, and draws score -params.contempt for the player whose turn it is, so a
+/// positive contempt avoids draws and a negative one seeks them.
+pub(super) fn find_best_move_limited(game: &Game, depth: Option<u32>, time: Option<Duration>, params: &EvalParams) -> (SearchResult, Vec<Move>) {
+    let mut searcher = SearchContext::new();
+    searcher.params = *params;
+    searcher.deadline = time.map(|time| searcher.start + time);
+    searcher.max_depth = depth.map_or(MAX_DEPTH, |depth| depth.min(MAX_DEPTH));
+    let result = searcher.iterative_deepening(game);
+    return (result, searcher.best_line);
+}
+
+/// Tells a search running on another thread when to stop.
+#[derive(Debug, Default)]
+pub(super) struct StopSignal {
+    /// Set to stop the search as soon as possible.
+    pub(super) stop: AtomicBool,
+    /// When the search has to stop, once that is known.
+    pub(super) deadline: Mutex<Option<Instant>>,
+}
+
+/// Like find_best_move_timed, but searches until the signal tells it to stop.
+pub(super) fn find_best_move_signalled(game: &Game, signal: &StopSignal) -> SearchResult {
+    let mut searcher = SearchContext::new();
+    searcher.signal = Some(signal);
+    return searcher.iterative_deepening(game);
+}
+
+/// Like find_best_move_signalled, but stops after `depth` plies if given, evaluates with the
+/// parameters and calls `info` with the progress of the search every time a depth is finished.
+/// With a `multipv` above 1 every depth searches that many lines, each starting with a
+/// different move like analyze, and `info` is called once for each of them. Also returns the
+/// principal variation of the deepest finished depth.
+pub(super) fn find_best_move_controlled(game: &Game, depth: Option<u32>, params: &EvalParams, signal: &StopSignal, multipv: usize, mut info: impl FnMut(&SearchInfo)) -> (SearchResult, Vec<Move>) {
+    let mut searcher = SearchContext::new();
+    searcher.params = *params;
+    searcher.signal = Some(signal);
+    searcher.max_depth = depth.map_or(MAX_DEPTH, |depth| depth.clamp(1, MAX_DEPTH));
+    searcher.multipv = multipv.max(1);
+    searcher.info = Some(&mut info);
+    let result = searcher.iterative_deepening(game);
+    return (result, searcher.best_line);
+}
+
+/// Finds the best `multipv` lines, each starting with a different move, sorted by score with
+/// the best first. Every line is searched to the depth of the limit, or with a time limit to
+/// the deepest depth at which all lines were found in time (but at least depth 1). Returns
+/// fewer lines if there are fewer legal moves, and none if the game is over.
+pub fn analyze(game: &Game, limit: SearchLimit, multipv: usize) -> Vec<Line> {
+    let mut searcher = SearchContext::new();
+    let lines = match limit {
+        SearchLimit::Depth(depth) => searcher.search_lines(game, depth, multipv),
+        SearchLimit::Time(budget) => {
+            let start = Instant::now();
+            // depth 1 is always finished, so there is something to return
+            let mut lines = searcher.search_lines(game, 1, multipv);
+            searcher.deadline = Some(start + budget);
+            for depth in 2..=MAX_DEPTH {
+                let deeper_lines = searcher.search_lines(game, depth, multipv);
+                if searcher.stopped {break;}
+                lines = deeper_lines;
+            }
+            lines
+        }
+    };
+    return lines.into_iter().enumerate().map(|(index, (score, pv))| {
+        Line { rank: index + 1, score, san: game.position.san_line(&pv), pv }
+    }).collect();
+}
+
+pub(super) fn score_is_mate(score: i32) -> bool {
+    return score.abs() >= MATE - MAX_DEPTH as i32;
+}
+
+/// The state of a single search. Every search has its own, so searches running at the same
+/// time don't share anything that changes.
+struct SearchContext<'a> {
+    /// The number of positions visited.
+    nodes: u64,
+    /// When the search has to stop, if it is timed.
+    deadline: Option<Instant>,
+    /// The deepest iteration of iterative deepening.
+    max_depth: u32,
+    /// The repetition keys (see Position::repetition_key) of the positions of the game and of
+    /// the line being searched, to score repeated positions as draws.
+    keys: Vec<Position>,
+    /// The weights of the evaluation. The contempt is how much worse than 0 a draw is for the
+    /// player the search is for, who is to move at the root, and how much better for the
+    /// opponent.
+    params: EvalParams,
+    root_color: Color,
+    /// Tells the search when to stop if it is controlled from another thread.
+    signal: Option<&'a StopSignal>,
+    /// When the search started.
+    start: Instant,
+    /// The depth of the current iteration of iterative deepening.
+    depth: u32,
+    /// The deepest ply reached.
+    seldepth: u32,
+    /// The score and principal variation of the last finished iteration.
+    best_score: i32,
+    best_line: Vec<Move>,
+    /// How many lines every iteration searches, each starting with a different move.
+    multipv: usize,
+    /// The score and principal variation of every line of the last finished iteration, if it
+    /// searched more than one.
+    lines: Vec<(i32, Vec<Move>)>,
+    /// Called with the progress of the search, see find_best_move_with_info.
+    info: Option<&'a mut dyn FnMut(&SearchInfo)>,
+    /// How many positions are searched between calls to info, or 0 to only call it when a
+    /// depth is finished.
+    info_interval: u64,
+    /// Set when the deadline has passed. The scores of a stopped search are meaningless.
+    stopped: bool,
+    /// Whether the lines are searched on until there are no captures left (quiescence search).
+    quiescence: bool,
+    /// Whether the moves below the root are sorted before they are searched.
+    ordering: bool,
+    /// Whether quiet moves are sorted by the killer moves and the history table, and not
+    /// only captures by MVV-LVA.
+    quiet_ordering: bool,
+    /// For every ply, the last two quiet moves that cut off the search there (killer moves).
+    /// A move that refutes one line often refutes its siblings too.
+    killers: Vec<[Option<Move>; 2]>,
+    /// For every from and to square (from * 64 + to), how much the quiet move has cut off the
+    /// search, with deeper cutoffs counting more.
+    history: Vec<i32>,
+    /// For every ply, the best line found from the position searched there (the principal
+    /// variation). The line of ply 0 is the best line of the whole search.
+    pv: Vec<Vec<Move>>,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new() -> SearchContext<'a> {
+        SearchContext {
+            nodes: 0,
+            deadline: None,
+            max_depth: MAX_DEPTH,
+            keys: Vec::new(),
+            params: EvalParams::DEFAULT,
+            root_color: Color::White,
+            signal: None,
+            start: Instant::now(),
+            depth: 0,
+            seldepth: 0,
+            best_score: 0,
+            best_line: Vec::new(),
+            multipv: 1,
+            lines: Vec::new(),
+            info: None,
+            info_interval: 0,
+            stopped: false,
+            quiescence: true,
+            ordering: true,
+            quiet_ordering: true,
+            killers: vec![[None; 2]; MAX_DEPTH as usize],
+            history: vec![0; 64 * 64],
+            pv: vec![Vec::new(); MAX_DEPTH as usize + 1],
+        }
+    }
+
+    /// Searches one ply deeper at a time until the search is stopped or max_depth is reached,
+    /// and returns the best move of the deepest search that finished.
+    fn iterative_deepening(&mut self, game: &Game) -> SearchResult {
+        let mut result = SearchResult { best_move: None, score: 0, depth: 0, nodes: 0 };
+        let mut first_move = game.legal_moves().first().copied();
+        if first_move.is_none() {return result;}
+        for depth in 1..=self.max_depth {
+            self.depth = depth;
+            let best = if self.multipv > 1 {self.search_root_lines(game, depth)} else {self.search_root(game, depth, first_move)};
+            if let (Some((_, score)), false) = (best, self.stopped) {
+                self.best_score = score;
+                self.best_line = self.pv[0].clone();
+                self.report_progress();
+            }
+            if self.stopped && result.best_move.is_some() {break;}
+            // an iteration cut short still searched its first moves to the end, which is better
+            // than having no move at all
+            if let Some((mv, score)) = best {
+                result.best_move = Some(mv);
+                result.score = score;
+                result.depth = if self.stopped {depth - 1} else {depth};
+                first_move = Some(mv);
+            }
+            if self.stopped || score_is_mate(result.score) {break;}
+        }
+        if result.best_move.is_none() {
+            // not even one move was searched in time
+            result.best_move = first_move;
+            result.score = side_to_move_score(&game.position, &self.params);
+        }
+        result.nodes = self.nodes;
+        return result;
+    }
+
+    /// Searches the root moves, starting with first_move if given. If the search is stopped,
+    /// the result only covers the moves that were searched to the end. The other root moves
+    /// keep the generation order, so the move chosen among equally good ones doesn't depend
+    /// on the move ordering.
+    fn search_root(&mut self, game: &Game, depth: u32, first_move: Option<Move>) -> Option<(Move, i32)> {
+        let mut moves = game.legal_moves().clone();
+        if let Some(index) = first_move.and_then(|first| moves.iter().position(|&mv| mv == first)) {
+            moves[..=index].rotate_right(1);
+        }
+        self.set_root(game);
+        return self.search_moves(&game.position, moves, depth);
+    }
+
+    /// Searches the best lines of the root like search_lines and returns the first move of the
+    /// best line with its score, leaving the line in the principal variation of ply 0 like
+    /// search_root. The lines are kept for report_progress unless the search was stopped.
+    fn search_root_lines(&mut self, game: &Game, depth: u32) -> Option<(Move, i32)> {
+        let lines = self.search_lines(game, depth, self.multipv);
+        let (score, line) = lines.first()?.clone();
+        self.pv[0] = line;
+        if !self.stopped {self.lines = lines;}
+        return Some((self.pv[0][0], score));
+    }
+
+    /// Searches the given legal moves of the root position and returns the best with its
+    /// score. Its line is left in the principal variation of ply 0.
+    fn search_moves(&mut self, position: &Position, moves: Vec<Move>, depth: u32) -> Option<(Move, i32)> {
+        self.pv[0].clear();
+        if depth == 0 {return None;}
+        let mut best: Option<(Move, i32)> = None;
+        let mut alpha = -MATE - 1;
+        for mv in moves {
+            // make the move on a copy, so unmaking it is just dropping the copy
+            let mut child = *position;
+            child.apply_move(mv);
+            let score = -self.search_child(&child, depth - 1, 1, -MATE - 1, -alpha);
+            if self.stopped {break;}
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((mv, score));
+                alpha = alpha.max(score);
+                self.update_pv(0, mv);
+            }
+        }
+        return best;
+    }
+
+    /// Searches the root once for every line, each time leaving out the first moves of the lines
+    /// found before, and returns the score and principal variation of every line. Stops early
+    /// if the search is stopped.
+    fn search_lines(&mut self, game: &Game, depth: u32, count: usize) -> Vec<(i32, Vec<Move>)> {
+        let mut lines: Vec<(i32, Vec<Move>)> = Vec::new();
+        let mut moves = game.legal_moves().clone();
+        self.set_root(game);
+        while lines.len() < count {
+            let (best_move, score) = match self.search_moves(&game.position, moves.clone(), depth) {
+                Some(best) if !self.stopped => best,
+                _ => break
+            };
+            lines.push((score, self.pv[0].clone()));
+            moves.retain(|&mv| mv != best_move);
+        }
+        return lines;
+    }
+
+    /// Makes the principal variation of the ply the move followed by the line of the next ply.
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        let (line, rest) = self.pv.split_at_mut(ply + 1);
+        line[ply].clear();
+        line[ply].push(mv);
+        line[ply].extend_from_slice(&rest[0]);
+    }
+
+    /// Prepares searching the position of the game: the repetition keys start with the
+    /// positions of the game, and draws are scored for the player whose turn it is.
+    fn set_root(&mut self, game: &Game) {
+        self.keys = game.repetition_keys();
+        self.root_color = game.position.turn;
+    }
+
+    /// The score of a draw from the point of view of the side to move, which is 0 unless the
+    /// search has contempt for draws.
+    fn draw_score(&self, position: &Position) -> i32 {
+        return if position.turn == self.root_color {-self.params.contempt} else {self.params.contempt};
+    }
+
+    /// Searches a position reached by a move of the line, with its repetition key added to the
+    /// keys while it is searched.
+    fn search_child(&mut self, child: &Position, depth: u32, ply: i32, alpha: i32, beta: i32) -> i32 {
+        self.keys.push(child.repetition_key());
+        let score = self.alpha_beta(child, depth, ply, alpha, beta);
+        self.keys.pop();
+        return score;
+    }
+
+    /// Returns the score of the position from the point of view of the side to move. The score
+    /// is exact if it is between alpha and beta, otherwise it is a bound on the exact score
+    /// (fail-soft): at most alpha if no move reached alpha, at least beta if a move reached beta.
+    /// A position that occurred before in the game or the line is scored as a draw, since
+    /// either player can repeat the moves that led back to it, and so is a position where the
+    /// fifty-move rule is reached (unless it is checkmate).
+    fn alpha_beta(&mut self, position: &Position, depth: u32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply as usize].clear();
+        if self.visit(ply) {return 0;}
+        if let Some(score) = self.rule_draw_score(position, ply) {return score;}
+        // a recognized drawn ending needs no search
+        if position.recognize_endgame().is_some_and(|class| class.winner().is_none()) {return self.draw_score(position);}
+        if depth == 0 {
+            return if self.quiescence {self.quiesce(position, ply, alpha, beta)} else {leaf_score(position, ply, &self.params)};
+        }
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            // no legal moves is either checkmate or stalemate
+            return if position.in_check(position.turn) {-(MATE - ply)} else {self.draw_score(position)};
+        }
+        if self.ordering {self.order_moves(position, &mut moves, ply);}
+        let mut best = -MATE - 1;
+        for mv in moves {
+            let mut child = *position;
+            child.apply_move(mv);
+            let score = -self.search_child(&child, depth - 1, ply + 1, -beta, -alpha);
+            if score > best {
+                best = score;
+                if score > alpha {
+                    alpha = score;
+                    self.update_pv(ply as usize, mv);
+                }
+                // the opponent will never allow this position, so the other moves don't matter
+                if alpha >= beta {
+                    if position.mvv_lva_score(mv) == 0 {self.store_cutoff(mv, depth, ply);}
+                    break;
+                }
+            }
+        }
+        return best;
+    }
+
+    /// The score of the position if it is a draw by repetition or the fifty-move rule.
+    fn rule_draw_score(&self, position: &Position, ply: i32) -> Option<i32> {
+        if repetitions(&self.keys, position.halfmove) > 0 {return Some(self.draw_score(position));}
+        if position.halfmove >= 100 {
+            // checkmate on the last move still counts
+            if position.in_check(position.turn) && position.legal_moves().is_empty() {return Some(-(MATE - ply));}
+            return Some(self.draw_score(position));
+        }
+        return None;
+    }
+
+    /// Sorts the moves of a position: captures and promotions by MVV-LVA, then the killer
+    /// moves of the ply, then the other quiet moves by their history score.
+    fn order_moves(&self, position: &Position, moves: &mut [Move], ply: i32) {
+        if !self.quiet_ordering {
+            position.order_moves(moves);
+            return;
+        }
+        let killers = self.killers.get(ply as usize).copied().unwrap_or([None; 2]);
+        moves.sort_by_key(|&mv| {
+            let mvv_lva = position.mvv_lva_score(mv);
+            if mvv_lva > 0 {return -(CAPTURE_ORDER + mvv_lva);}
+            if killers[0] == Some(mv) {return -(KILLER_ORDER + 1);}
+            if killers[1] == Some(mv) {return -KILLER_ORDER;}
+            return -self.history[mv.from.index() * 64 + mv.to.index()];
+        });
+    }
+
+    /// Remembers a quiet move that cut off the search as a killer move of the ply, and adds to
+    /// its history score.
+    fn store_cutoff(&mut self, mv: Move, depth: u32, ply: i32) {
+        if let Some(killers) = self.killers.get_mut(ply as usize) {
+            if killers[0] != Some(mv) {
+                killers[1] = killers[0];
+                killers[0] = Some(mv);
+            }
+        }
+        let history = &mut self.history[mv.from.index() * 64 + mv.to.index()];
+        *history = (*history + (depth * depth) as i32).min(KILLER_ORDER - 1);
+    }
+
+    /// Searches only the captures at the end of a line, until the position is quiet, so the
+    /// line doesn't stop in the middle of an exchange. The side to move may also "stand pat"
+    /// and take the static evaluation instead of capturing. Checks are only looked at to find
+    /// checkmate, since searching every answer to a check makes the search explode. Bounds
+    /// work as in alpha_beta.
+    fn quiesce(&mut self, position: &Position, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.visit(ply) {return 0;}
+        if position.in_check(position.turn) && position.legal_moves().is_empty() {return -(MATE - ply);}
+        let mut best = side_to_move_score(position, &self.params);
+        if best >= beta {return best;}
+        alpha = alpha.max(best);
+        let mut captures = position.stage_moves(MoveStage::Captures);
+        position.order_moves(&mut captures);
+        for mv in captures {
+            // captures that lose material in the exchange can't raise the score
+            if position.see(mv) < 0 {continue;}
+            let mut child = *position;
+            child.apply_move(mv);
+            let score = -self.quiesce(&child, ply + 1, -beta, -alpha);
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {break;}
+            }
+        }
+        return best;
+    }
+
+    /// Counts a visited position at the ply and reports the progress if it is time to. Returns
+    /// true if the search has to stop.
+    fn visit(&mut self, ply: i32) -> bool {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply as u32);
+        if self.info_interval > 0 && self.nodes.is_multiple_of(self.info_interval) {self.report_progress();}
+        return self.out_of_time();
+    }
+
+    /// Calls the info callback, if there is one, with the progress of the search.
+    fn report_progress(&mut self) {
+        if let Some(info) = self.info.as_mut() {
+            let elapsed = self.start.elapsed();
+            let best = [(self.best_score, self.best_line.clone())];
+            let lines = if self.lines.len() > 1 {&self.lines[..]} else {&best[..]};
+            for (index, (score, pv)) in lines.iter().enumerate() {
+                info(&SearchInfo {
+                    depth: self.depth,
+                    seldepth: self.seldepth,
+                    score: Score::from_search_score(*score),
+                    nodes: self.nodes,
+                    nps: (self.nodes as f64 / elapsed.as_secs_f64().max(0.001)) as u64,
+                    elapsed,
+                    pv: pv.clone(),
+                    multipv: index + 1,
+                });
+            }
+        }
+    }
+
+    /// Looks at the clock and the stop signal every few hundred positions, and stops the search
+    /// once the deadline has passed or the signal says so.
+    fn out_of_time(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(NODES_PER_CLOCK_CHECK) {
+            let mut deadline = self.deadline;
+            if let Some(signal) = self.signal {
+                if signal.stop.load(Ordering::Relaxed) {self.stopped = true;}
+                deadline = deadline.or(*signal.deadline.lock().unwrap());
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {self.stopped = true;}
+        }
+        return self.stopped;
+    }
+
+    /// Searches every line without pruning, used to check that alpha-beta finds the same result.
+    #[cfg(test)]
+    fn minimax(&mut self, position: &Position, depth: u32, ply: i32) -> i32 {
+        self.nodes += 1;
+        if let Some(score) = self.rule_draw_score(position, ply) {return score;}
+        if depth == 0 {return self.quiesce(position, ply, -MATE - 1, MATE + 1);}
+        let moves = position.legal_moves();
+        if moves.is_empty() {
+            return if position.in_check(position.turn) {-(MATE - ply)} else {self.draw_score(position)};
+        }
+        let mut best = -MATE - 1;
+        for mv in moves {
+            let mut child = *position;
+            child.apply_move(mv);
+            self.keys.push(child.repetition_key());
+            best = best.max(-self.minimax(&child, depth - 1, ply + 1));
+            self.keys.pop();
+        }
+        return best;
+    }
+}
+
+/// The score of a position at the end of a line without quiescence search, from the point of view of the side to move.
+fn leaf_score(position: &Position, ply: i32, params: &EvalParams) -> i32 {
+    // only positions in check can be mate, so the moves are only generated then
+    if position.in_check(position.turn) && position.legal_moves().is_empty() {return -(MATE - ply);}
+    return side_to_move_score(position, params);
+}
+
+/// The static evaluation from the point of view of the side to move.
+fn side_to_move_score(position: &Position, params: &EvalParams) -> i32 {
+    let score = position.evaluate(params);
+    return if position.turn == Color::White {score} else {-score};
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{analyze, find_best_move, find_best_move_timed, find_best_move_with_info, Score, SearchContext, SearchInfo, SearchLimit};
+    use crate::engine::MATE;
+    use crate::tests::coordinate_move;
+    use crate::{Game, GameState, Move};
+
+    // searches every root move without pruning and returns the first best move, like find_best_move
+    fn minimax_best_move(game: &Game, depth: u32, searcher: &mut SearchContext) -> Option<(Move, i32)> {
+        let mut best: Option<(Move, i32)> = None;
+        searcher.set_root(game);
+        for mv in game.get_all_possible_moves() {
+            let mut child = game.position;
+            child.apply_move(mv);
+            searcher.keys.push(child.repetition_key());
+            let score = -searcher.minimax(&child, depth - 1, 1);
+            searcher.keys.pop();
+            if best.is_none_or(|(_, best_score)| score > best_score) {best = Some((mv, score));}
+        }
+        return best;
+    }
+
+    //check that a hanging queen is taken
+    #[test]
+    fn check_takes_hanging_queen() {
+        let game1 = Game::from_fen("4k3/8/8/8/3q4/5N2/8/4K3 w - - 0 1").unwrap();
+        let (best_move, score) = find_best_move(&game1, 1).unwrap();
+        assert_eq!(best_move.to_string(), "f3d4");
+        assert!(score > 0);
+    }
+
+    //check that a mate in one is found
+    #[test]
+    fn check_finds_mate_in_one() {
+        let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let (best_move, score) = find_best_move(&game1, 2).unwrap();
+        assert_eq!(best_move.to_string(), "a1a8");
+        assert_eq!(score, MATE - 1);
+    }
+
+    //check that the back-rank mate in two is found at depth 3 within a second
+    #[test]
+    fn check_finds_back_rank_mate_in_two() {
+        let game1 = Game::from_fen("2r3k1/5ppp/8/8/3Q4/8/5PPP/3R2K1 w - - 0 1").unwrap();
+        let start = Instant::now();
+        let (best_move, score) = find_best_move(&game1, 3).unwrap();
+        assert_eq!(best_move.to_string(), "d4d8");
+        assert_eq!(score, MATE - 3);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    //check that an underpromotion is found when it is the only move that wins: the knight
+    //forks the king and the queen, while a new queen would only trade itself off
+    #[test]
+    fn check_finds_knight_underpromotion() {
+        let game1 = Game::from_fen("8/4P1k1/3q4/8/8/8/7P/K7 w - - 0 1").unwrap();
+        for depth in [3, 4] {
+            let (best_move, score) = find_best_move(&game1, depth).unwrap();
+            assert_eq!(best_move.to_string(), "e7e8n", "depth {}", depth);
+            assert!(score > 300, "depth {}", depth);
+        }
+    }
+
+    //check that a finished game has no best move
+    #[test]
+    fn check_no_move_when_game_over() {
+        let game1 = Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(find_best_move(&game1, 3), None);
+    }
+
+    //check that alpha-beta gives the same move and score as searching every line
+    #[test]
+    fn check_alpha_beta_matches_minimax() {
+        let fens = [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3",
+            "rnbqkb1r/ppp2ppp/4pn2/3p4/2PP4/2N5/PP2PPPP/R1BQKBNR w KQkq - 2 4",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "2r3k1/5ppp/8/8/3Q4/8/5PPP/3R2K1 w - - 0 1",
+        ];
+        for fen in fens {
+            let game1 = Game::from_fen(fen).unwrap();
+            assert_eq!(find_best_move(&game1, 3), minimax_best_move(&game1, 3, &mut SearchContext::new()), "{}", fen);
+        }
+    }
+
+    //check that alpha-beta visits at least 5 times fewer positions at depth 4
+    #[test]
+    fn check_alpha_beta_node_reduction() {
+        let game1 = Game::new();
+        let mut minimax = SearchContext::new();
+        let mut alpha_beta = SearchContext::new();
+        assert_eq!(alpha_beta.search_root(&game1, 4, None), minimax_best_move(&game1, 4, &mut minimax));
+        assert!(minimax.nodes >= 5 * alpha_beta.nodes);
+    }
+
+    //check that a timed search returns a legal move shortly after its budget
+    #[test]
+    fn check_timed_search_keeps_budget() {
+        let game1 = Game::new();
+        let start = Instant::now();
+        let result = find_best_move_timed(&game1, Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert!(game1.is_legal_move(&result.best_move.unwrap()));
+        assert!(result.depth >= 1);
+        assert!(result.nodes > 0);
+    }
+
+    //check that a timed search finds a mate in one with a tiny budget
+    #[test]
+    fn check_timed_search_finds_mate_in_one() {
+        let game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = find_best_move_timed(&game1, Duration::from_millis(10));
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert_eq!(result.score, MATE - 1);
+    }
+
+    //check that quiescence search stops the search from giving up a rook for a bishop, which
+    //looks good at depth 2 when the search can't see the knight on c3 being taken back
+    #[test]
+    fn check_quiescence_avoids_losing_capture() {
+        let game1 = Game::from_fen("6k1/5ppp/8/p1q5/1b5R/2N5/PP3PPP/4K3 w - - 0 1").unwrap();
+        let mut without_quiescence = SearchContext::new();
+        without_quiescence.quiescence = false;
+        let (best_move, _) = without_quiescence.search_root(&game1, 2, None).unwrap();
+        assert_eq!(best_move.to_string(), "h4b4");
+        let (best_move, _) = find_best_move(&game1, 2).unwrap();
+        assert_ne!(best_move.to_string(), "h4b4");
+    }
+
+    //check that move ordering cuts the positions searched on kiwipete at depth 5 at least 10
+    //times without changing the best move. Both searches are without quiescence search, since
+    //the unordered capture sequences at the leaves would make the unordered search take hours
+    #[test]
+    fn check_ordering_node_reduction() {
+        let game1 = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut ordered = SearchContext::new();
+        let mut unordered = SearchContext::new();
+        ordered.quiescence = false;
+        unordered.quiescence = false;
+        unordered.ordering = false;
+        assert_eq!(ordered.search_root(&game1, 5, None), unordered.search_root(&game1, 5, None));
+        assert!(unordered.nodes >= 10 * ordered.nodes);
+    }
+
+    //check that killer moves and the history table cut the positions searched in a quiet
+    //middlegame at depth 6 without changing the best move
+    #[test]
+    fn check_quiet_ordering_node_reduction() {
+        let game1 = Game::from_fen("r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R1BQ1RK1 w - - 0 8").unwrap();
+        let mut heuristics = SearchContext::new();
+        let mut mvv_lva_only = SearchContext::new();
+        mvv_lva_only.quiet_ordering = false;
+        assert_eq!(heuristics.search_root(&game1, 6, None), mvv_lva_only.search_root(&game1, 6, None));
+        assert!(4 * heuristics.nodes <= 3 * mvv_lva_only.nodes);
+    }
+
+    //check that the lines of a multipv analysis are ranked, sorted and start with different moves
+    #[test]
+    fn check_multipv_lines() {
+        let game1 = Game::new();
+        let lines = analyze(&game1, SearchLimit::Depth(2), 3);
+        assert_eq!(lines.len(), 3);
+        for (index, line) in lines.iter().enumerate() {
+            assert_eq!(line.rank, index + 1);
+            assert_eq!(line.pv.len(), line.san.len());
+            assert_eq!(line.san[0], game1.to_san(&line.pv[0]).unwrap());
+            if index > 0 {
+                assert!(line.score <= lines[index - 1].score);
+                assert!(lines[..index].iter().all(|other| other.pv[0] != line.pv[0]));
+            }
+        }
+        assert_eq!(analyze(&game1, SearchLimit::Time(Duration::from_millis(50)), 3).len(), 3);
+    }
+
+    //check that a mate in one and a mate in two both show up, with the faster mate first
+    #[test]
+    fn check_multipv_mates() {
+        let game1 = Game::from_fen("2r3k1/5ppp/4pP2/8/6Q1/8/3R1PPP/3R2K1 w - - 0 1").unwrap();
+        let lines = analyze(&game1, SearchLimit::Depth(3), 2);
+        assert_eq!(lines[0].score, MATE - 1);
+        assert_eq!(lines[0].san, ["Qxg7#"]);
+        assert_eq!(lines[1].score, MATE - 3);
+        // every move that keeps the threat of Qxg7# mates in two
+        assert_eq!(lines[1].pv.len(), 3);
+        assert_eq!(lines[1].san[2], "Qxg7#");
+    }
+
+    //check that the info callback gets the progress of the search, with the final best move first in the line
+    #[test]
+    fn check_search_info() {
+        let game1 = Game::new();
+        let mut infos: Vec<SearchInfo> = Vec::new();
+        let result = find_best_move_with_info(&game1, Duration::from_millis(100), 5000, |info| infos.push(info.clone()));
+        assert!(infos.iter().any(|info| info.pv.first() == result.best_move.as_ref()));
+        // some of the infos come from the node interval
+        assert!(infos.iter().any(|info| info.nodes % 5000 == 0));
+        for pair in infos.windows(2) {
+            assert!(pair[0].nodes <= pair[1].nodes);
+            assert!(pair[0].depth <= pair[1].depth);
+        }
+        assert!(infos.last().unwrap().depth >= result.depth);
+        assert!(infos.last().unwrap().seldepth >= result.depth);
+    }
+
+    //check that mate scores are converted to moves
+    #[test]
+    fn check_score_from_search_score() {
+        assert_eq!(Score::from_search_score(35), Score::Centipawns(35));
+        assert_eq!(Score::from_search_score(MATE - 1), Score::Mate(1));
+        assert_eq!(Score::from_search_score(MATE - 3), Score::Mate(2));
+        assert_eq!(Score::from_search_score(-(MATE - 2)), Score::Mate(-1));
+    }
+
+    //check that the search knows about repetitions, so a won game makes progress instead of
+    //repeating, and a lost game takes a repetition
+    #[test]
+    fn check_search_repetitions() {
+        // searching 3 plies deep mates without repeating a position three times on the way
+        let mut game1 = Game::from_fen("8/8/8/3k4/8/8/8/2Q1K3 w - - 0 1").unwrap();
+        while game1.get_game_state() != GameState::Checkmate {
+            game1.play_move(&find_best_move(&game1, 3).unwrap().0).unwrap();
+            assert!(!game1.is_threefold_repetition());
+            assert!(game1.history.len() < 100);
+        }
+        // black is a queen and two rooks against a queen down, but can check the king back to h2
+        let mut game1 = Game::from_fen("6k1/RR3ppp/8/Q7/8/7P/5qPK/8 b - - 0 1").unwrap();
+        for mv in ["f2f4", "h2g1", "f4c1", "g1h2"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(find_best_move(&game1, 3), Some((coordinate_move("c1f4"), 0)));
+        assert!(find_best_move(&Game::from_fen(&game1.get_fen()).unwrap(), 3).unwrap().1 < -500);
+    }
+}
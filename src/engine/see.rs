@@ -0,0 +1,133 @@
+use crate::{Color, Game, Move, PieceRole, Position};
+
+// what a king is worth in an exchange, more than everything else together, so that a capture
+// that puts the own king where it can be taken is never worth it
+const KING_VALUE: i32 = 20_000;
+
+/// The value of a role in an exchange.
+fn exchange_value(role: PieceRole) -> i32 {
+    if role == PieceRole::King {return KING_VALUE;}
+    return role.value();
+}
+
+impl Game {
+    /// Returns the static exchange value of a capture in centipawns for the player making it:
+    /// what is won or lost if both players keep taking on the target square with their least
+    /// valuable piece for as long as it pays off. Pieces behind the ones that take (like a
+    /// rook behind a queen) join in when the way is free. En passant takes the pawn beside the
+    /// target square and promotions count the gain of the new piece. Pins are not looked at.
+    /// A negative value means the capture loses material, and a piece that can be taken with
+    /// a positive value is hanging.
+    pub fn see(&self, capture: &Move) -> i32 {
+        return self.position.see(*capture);
+    }
+}
+
+impl Position {
+    /// Returns the indexes of the pieces of color `by` that attack the given index on the
+    /// current board, the sliding pieces only if nothing stands in between.
+    pub(crate) fn attackers_of(&self, index: usize, by: Color) -> Vec<usize> {
+        let mut attackers: Vec<usize> = Vec::new();
+        self.for_each_attacker(index, by, |attacker| attackers.push(attacker));
+        return attackers;
+    }
+
+    /// Calls `found` with the index of every attacker that attackers_of would return, without
+    /// allocating, so that the evaluation can use it. The attackers are found by the same walk
+    /// as Position::is_attacked, see find_attacker.
+    pub(crate) fn for_each_attacker(&self, index: usize, by: Color, mut found: impl FnMut(usize)) {
+        self.find_attacker(index, by, |attacker| {
+            found(attacker);
+            false
+        });
+    }
+
+    /// The static exchange value of the move, see Game::see.
+    pub(crate) fn see(&self, mv: Move) -> i32 {
+        let to = mv.to.index();
+        let piece = match self.piece_at(mv.from.index()) {
+            Some(piece) => piece,
+            None => return 0
+        };
+        // gains[d] is what the player making the d:th capture wins if the exchange stops after it
+        let mut gains: Vec<i32> = vec![self.capture_square(mv).and_then(|index| self.piece_at(index)).map_or(0, |victim| victim.role.value())];
+        let mut board = *self;
+        if let Some(captured) = self.capture_square(mv) {board.board[captured] = 0;}
+        board.board[mv.from.index()] = 0;
+        // the value of the piece standing on the target square, which the next capture takes
+        let mut on_square = match mv.promotion {
+            Some(role) => {
+                gains[0] += role.value() - PieceRole::Pawn.value();
+                role.value()
+            }
+            None => exchange_value(piece.role)
+        };
+        let mut side = piece.color.opposite();
+        // removing the pieces that have taken from the board uncovers the ones behind them
+        while let Some(from) = board.attackers_of(to, side).into_iter().min_by_key(|&index| exchange_value(board.piece_at(index).unwrap().role)) {
+            let attacker = board.piece_at(from).unwrap();
+            let mut gain = on_square - gains[gains.len() - 1];
+            on_square = exchange_value(attacker.role);
+            // a pawn that takes on the last rank promotes to a queen
+            if attacker.role == PieceRole::Pawn && (to / 8 == 0 || to / 8 == 7) {
+                gain += PieceRole::Queen.value() - PieceRole::Pawn.value();
+                on_square = PieceRole::Queen.value();
+            }
+            gains.push(gain);
+            board.board[from] = 0;
+            side = side.opposite();
+        }
+        // every player stops taking when taking would lose more than stopping
+        while gains.len() > 1 {
+            let next = gains.pop().unwrap();
+            let last = gains.len() - 1;
+            gains[last] = -(-gains[last]).max(next);
+        }
+        return gains[0];
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::coordinate_move;
+    use crate::Game;
+
+    fn see(fen: &str, mv: &str) -> i32 {
+        return Game::from_fen(fen).unwrap().see(&coordinate_move(mv));
+    }
+
+    //check that defended and undefended captures are valued by what the exchange wins
+    #[test]
+    fn check_see() {
+        // QxP defended by a pawn loses the queen for a pawn
+        assert_eq!(see("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", "d2d5"), -800);
+        // PxN undefended wins the knight
+        assert_eq!(see("4k3/8/8/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 320);
+        // PxN defended by a pawn still wins a knight for a pawn
+        assert_eq!(see("4k3/8/2p5/3n4/4P3/8/8/4K3 w - - 0 1", "e4d5"), 220);
+        // the king can't take a defended piece
+        assert_eq!(see("4k3/8/8/8/8/5b2/4q3/4K3 w - - 0 1", "e1e2"), 900 - 20_000);
+    }
+
+    //check that a piece behind the one that takes changes the result
+    #[test]
+    fn check_see_x_ray() {
+        // QxR, RxQ, RxR trades the queen for both rooks
+        assert_eq!(see("3r2k1/8/8/3r4/8/3Q4/8/3R2K1 w - - 0 1", "d3d5"), 100);
+        // without the rook behind the queen, black takes back and is ahead
+        assert_eq!(see("3r2k1/8/8/3r4/8/3Q4/8/6K1 w - - 0 1", "d3d5"), -400);
+    }
+
+    //check that en passant takes the pawn beside the square and promotions count the new piece
+    #[test]
+    fn check_see_special_moves() {
+        assert_eq!(see("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2", "d5e6"), 100);
+        // the king takes the new queen, so the rook and the lost pawn remain
+        assert_eq!(see("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q"), 400);
+        assert_eq!(see("3r2k1/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q"), 1300);
+    }
+}
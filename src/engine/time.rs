@@ -0,0 +1,50 @@
+//! The clock the searches and clock::MonotonicTime look at. std::time::Instant panics on wasm32-unknown-unknown, where
+//! there is no system clock, so in the browser the time comes from JavaScript's Date.now()
+//! instead. Everywhere else this is std::time::Instant.
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) use std::time::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub(crate) use self::js::Instant;
+
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod js {
+    use std::ops::{Add, Sub};
+    use std::time::Duration;
+
+    /// A point in time, in milliseconds since 1970 as JavaScript counts them.
+    #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+    pub(crate) struct Instant(f64);
+
+    impl Instant {
+        pub(crate) fn now() -> Instant {
+            return Instant(js_sys::Date::now());
+        }
+
+        pub(crate) fn elapsed(&self) -> Duration {
+            return Instant::now() - *self;
+        }
+
+        pub(crate) fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+            return Some(Instant(self.0 - duration.as_secs_f64() * 1000.0));
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Instant;
+
+        fn add(self, duration: Duration) -> Instant {
+            return Instant(self.0 + duration.as_secs_f64() * 1000.0);
+        }
+    }
+
+    impl Sub for Instant {
+        type Output = Duration;
+
+        // the clock of the computer can be set back, which mustn't make a negative duration
+        fn sub(self, earlier: Instant) -> Duration {
+            return Duration::from_secs_f64(((self.0 - earlier.0) / 1000.0).max(0.0));
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use super::adjudicate::AdjudicationRules;
+use super::arena::{play_game, GameRecord};
+use super::backend::{Engine, SearchLimits};
+use super::matches::MatchOptions;
+use crate::{Color, Game, GameOutcome};
+
+/// Makes a new instance of an engine. Every game of a tournament gets its own, so engines can
+/// keep state between moves and games can run at the same time.
+pub type EngineFactory = Box<dyn Fn() -> Box<dyn Engine> + Send + Sync>;
+
+/// How a Tournament plays its games.
+#[derive(Debug, Clone, Default)]
+pub struct TournamentOptions {
+    /// How every two engines play each other: pairing.pairs game pairs with the colors swapped
+    /// within every pair, like in run_match.
+    pub pairing: MatchOptions,
+    /// The number of games played at the same time, each on its own thread. 0 and 1 both play
+    /// the games one after the other on the calling thread.
+    pub threads: usize,
+}
+
+/// A round robin between engines: every engine plays every other the same number of games,
+/// as often with White as with Black.
+pub struct Tournament {
+    names: Vec<String>,
+    factories: Vec<EngineFactory>,
+    // the options, with the openings as FEN strings since a Game can't be shared between the
+    // threads
+    pairs: u32,
+    limits: SearchLimits,
+    max_plies: u32,
+    openings: Vec<String>,
+    adjudication: Option<AdjudicationRules>,
+    threads: usize,
+}
+
+/// The result of a Tournament.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TournamentResult {
+    /// The names of the engines, in the order they were given.
+    pub names: Vec<String>,
+    /// crosstable[a][b] is the points engine a scored against engine b, a win counting 1 and
+    /// a draw (or a game without a result) 1/2.
+    pub crosstable: Vec<Vec<f64>>,
+    /// Every game in the order of the schedule, whatever order they were played in.
+    pub games: Vec<GameRecord>,
+}
+
+impl TournamentResult {
+    /// The total points of every engine, in the order of names.
+    pub fn scores(&self) -> Vec<f64> {
+        return self.crosstable.iter().map(|row| row.iter().sum()).collect();
+    }
+
+    /// The names and total points of the engines, the most points first.
+    pub fn standings(&self) -> Vec<(String, f64)> {
+        let mut standings: Vec<(String, f64)> = self.names.iter().cloned().zip(self.scores()).collect();
+        standings.sort_by(|a, b| b.1.total_cmp(&a.1));
+        return standings;
+    }
+
+    /// Every game in Portable Game Notation, one after the other, numbered as rounds of the event.
+    pub fn pgn(&self, event: &str) -> String {
+        let games: Vec<String> = self.games.iter().enumerate().map(|(index, game)| game.to_pgn(event, index as u32 + 1)).collect();
+        return games.join("\n");
+    }
+}
+
+impl Tournament {
+    /// A tournament between the engines, each with a name and a factory that makes an instance
+    /// of it for every game.
+    pub fn new(engines: Vec<(String, EngineFactory)>, opts: TournamentOptions) -> Tournament {
+        let (names, factories) = engines.into_iter().unzip();
+        let pairing = opts.pairing;
+        return Tournament {
+            names,
+            factories,
+            pairs: pairing.pairs,
+            limits: pairing.limits,
+            max_plies: pairing.max_plies,
+            openings: pairing.openings.iter().map(|opening| opening.get_fen()).collect(),
+            adjudication: pairing.adjudication,
+            threads: opts.threads
+        };
+    }
+
+    /// The games of the tournament as (white, black, opening) with the engines as indexes
+    /// into names: for every two engines, game pairs with the colors swapped.
+    fn schedule(&self) -> Vec<(usize, usize, usize)> {
+        let mut schedule = Vec::new();
+        for first in 0..self.names.len() {
+            for second in first + 1..self.names.len() {
+                for pair in 0..self.pairs as usize {
+                    schedule.push((first, second, pair));
+                    schedule.push((second, first, pair));
+                }
+            }
+        }
+        return schedule;
+    }
+
+    /// Plays every game of the tournament and returns the crosstable and the games.
+    pub fn run(&self) -> TournamentResult {
+        let schedule = self.schedule();
+        let threads = self.threads.clamp(1, schedule.len().max(1));
+        let games: Vec<GameRecord> = if threads == 1 {
+            schedule.iter().map(|&game| self.play(game)).collect()
+        } else {
+            // the threads take the next game of the schedule until there are none left, and
+            // every record goes to the place of its game
+            let next = AtomicUsize::new(0);
+            let records: Mutex<Vec<Option<GameRecord>>> = Mutex::new(vec![None; schedule.len()]);
+            thread::scope(|scope| {
+                for _ in 0..threads {
+                    scope.spawn(|| {
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&game) = schedule.get(index) else {break};
+                            let record = self.play(game);
+                            records.lock().unwrap()[index] = Some(record);
+                        }
+                    });
+                }
+            });
+            records.into_inner().unwrap().into_iter().map(|record| record.unwrap()).collect()
+        };
+        let mut crosstable = vec![vec![0.0; self.names.len()]; self.names.len()];
+        for (&(white, black, _), record) in schedule.iter().zip(&games) {
+            let (white_points, black_points) = match record.outcome {
+                Some(GameOutcome::Win(Color::White)) => (1.0, 0.0),
+                Some(GameOutcome::Win(Color::Black)) => (0.0, 1.0),
+                _ => (0.5, 0.5)
+            };
+            crosstable[white][black] += white_points;
+            crosstable[black][white] += black_points;
+        }
+        return TournamentResult { names: self.names.clone(), crosstable, games };
+    }
+
+    /// Plays one game of the schedule with new instances of the engines.
+    fn play(&self, (white, black, pair): (usize, usize, usize)) -> GameRecord {
+        let opening = self.openings.get(pair % self.openings.len().max(1)).map_or(Game::new(), |fen| Game::from_fen(fen).unwrap());
+        let (mut white_engine, mut black_engine) = ((self.factories[white])(), (self.factories[black])());
+        let mut record = play_game(white_engine.as_mut(), black_engine.as_mut(), &opening, &self.limits, self.max_plies, self.adjudication);
+        // the names of the tournament tell engines of the same kind apart
+        record.white = self.names[white].clone();
+        record.black = self.names[black].clone();
+        return record;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{EngineFactory, Tournament, TournamentOptions};
+    use crate::engine::{AlphaBetaEngine, Engine, EngineMove, MatchOptions, SearchLimits};
+    use crate::tests::FirstMoveEngine;
+    use crate::Game;
+
+    /// An engine that always plays the last legal move.
+    struct LastMoveEngine;
+
+    impl Engine for LastMoveEngine {
+        fn choose_move(&mut self, game: &Game, _limits: &SearchLimits) -> Option<EngineMove> {
+            let mv = *game.get_all_possible_moves().last()?;
+            return Some(EngineMove { mv, score: None, pv: vec![mv] });
+        }
+
+        fn name(&self) -> &str {
+            return "last move";
+        }
+    }
+
+    fn engines() -> Vec<(String, EngineFactory)> {
+        return vec![
+            ("first".to_string(), Box::new(|| Box::new(FirstMoveEngine) as Box<dyn Engine>) as EngineFactory),
+            ("last".to_string(), Box::new(|| Box::new(LastMoveEngine) as Box<dyn Engine>)),
+            ("alpha-beta".to_string(), Box::new(|| Box::new(AlphaBetaEngine::default()) as Box<dyn Engine>)),
+        ];
+    }
+
+    //check that a round robin plays every game once and the crosstable adds up
+    #[test]
+    fn check_tournament() {
+        let pairing = MatchOptions { pairs: 2, limits: SearchLimits::depth(1), max_plies: 120, ..MatchOptions::default() };
+        let result = Tournament::new(engines(), TournamentOptions { pairing: pairing.clone(), threads: 1 }).run();
+        // 3 pairs of engines, each playing 2 game pairs
+        assert_eq!(result.games.len(), 12);
+        for a in 0..3 {
+            assert_eq!(result.crosstable[a][a], 0.0);
+            for b in 0..3 {
+                if a != b {assert_eq!(result.crosstable[a][b] + result.crosstable[b][a], 4.0);}
+            }
+        }
+        assert_eq!(result.scores().iter().sum::<f64>(), 12.0);
+        assert_eq!(result.standings()[0].0, "alpha-beta");
+        // every engine has White as often as Black
+        for name in ["first", "last", "alpha-beta"] {
+            assert_eq!(result.games.iter().filter(|game| game.white == name).count(), 4);
+            assert_eq!(result.games.iter().filter(|game| game.black == name).count(), 4);
+        }
+        assert_eq!(result.pgn("round robin").matches("[Round ").count(), 12);
+        // playing the games at the same time gives the same games in the same order
+        let threaded = Tournament::new(engines(), TournamentOptions { pairing, threads: 3 }).run();
+        assert_eq!(threaded, result);
+    }
+}
@@ -0,0 +1,470 @@
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::backend::{Engine, EngineOptions, SearchControl, SearchLimits, MAX_SKILL};
+use super::search::{Score, SearchInfo, StopSignal};
+use super::time::Instant;
+use crate::{Color, Game};
+
+// how much of the remaining time is kept back for the moves after this one, and how long
+// it takes to send the move, which is never spent on the search
+const MOVES_TO_GO: u32 = 30;
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// the spin options with their default, lowest and highest values, in the order they are
+// declared to the GUI
+const SPIN_OPTIONS: [(&str, i64, i64, i64); 5] = [
+    ("Hash", 16, 1, 1024),
+    ("Threads", 1, 1, 1),
+    ("MultiPV", 1, 1, 64),
+    ("Skill Level", MAX_SKILL as i64, 0, MAX_SKILL as i64),
+    ("Contempt", 0, -100, 100),
+];
+
+/// The settings a GUI can change with "setoption", see run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UciOptions {
+    /// The size of the hash table in megabytes. The search doesn't have a hash table, so it
+    /// is only remembered, for GUIs that insist on setting it.
+    pub hash: u32,
+    /// The number of search threads. The search runs on one thread, so this is always 1.
+    pub threads: u32,
+    /// How many of the best lines the search reports, each starting with a different move.
+    pub multipv: u32,
+    /// How well the engine plays, see EngineOptions::skill.
+    pub skill: u8,
+    /// The contempt for draws in centipawns, see EvalParams::contempt.
+    pub contempt: i32,
+    /// Whether the GUI lets the engine think on the time of the opponent ("go ponder").
+    pub ponder: bool,
+}
+
+impl Default for UciOptions {
+    fn default() -> UciOptions {
+        UciOptions { hash: 16, threads: 1, multipv: 1, skill: MAX_SKILL, contempt: 0, ponder: false }
+    }
+}
+
+impl UciOptions {
+    /// Sets the option with the name, which is matched ignoring case, to the value. Numbers
+    /// outside the range of the option are clamped to it. Returns an error message if there
+    /// is no such option or the value doesn't fit it, and leaves the options as they were.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if name.eq_ignore_ascii_case("Ponder") {
+            self.ponder = match value.to_ascii_lowercase().as_str() {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("Ponder is true or false, not {}", value))
+            };
+            return Ok(());
+        }
+        let &(name, _, min, max) = SPIN_OPTIONS.iter().find(|option| option.0.eq_ignore_ascii_case(name)).ok_or(format!("unknown option {}", name))?;
+        let value = value.parse::<i64>().map_err(|_| format!("{} is a number, not {}", name, value))?.clamp(min, max);
+        match name {
+            "Hash" => self.hash = value as u32,
+            "Threads" => self.threads = value as u32,
+            "MultiPV" => self.multipv = value as u32,
+            "Skill Level" => self.skill = value as u8,
+            _ => self.contempt = value as i32
+        }
+        return Ok(());
+    }
+
+    /// The settings of the engine that plays with these options.
+    pub fn engine_options(&self) -> EngineOptions {
+        let mut options = EngineOptions { skill: self.skill, ..EngineOptions::default() };
+        options.eval.contempt = self.contempt;
+        return options;
+    }
+
+    /// Reads the arguments of "setoption name <name> [value <value>]", where both the name
+    /// and the value can be several words.
+    fn setoption<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let tokens: Vec<&str> = tokens.collect();
+        if tokens.first() != Some(&"name") {return Err("setoption without a name".to_string());}
+        let split = tokens.iter().position(|&token| token == "value").unwrap_or(tokens.len());
+        let value = tokens.get(split + 1..).unwrap_or_default().join(" ");
+        return self.set(&tokens[1..split].join(" "), &value);
+    }
+
+    /// The "option" lines to declare the options to the GUI.
+    fn declarations(&self) -> String {
+        let mut lines: Vec<String> = SPIN_OPTIONS.iter().map(|&(name, default, min, max)| {
+            format!("option name {} type spin default {} min {} max {}", name, default, min, max)
+        }).collect();
+        lines.push("option name Ponder type check default false".to_string());
+        return lines.join("\n");
+    }
+}
+
+/// Talks the Universal Chess Interface (UCI) protocol with a GUI: reads the commands from
+/// `input` one line at a time and writes the answers to `output`, until the "quit" command
+/// or the end of the input, where a running search is finished first. Understands "uci",
+/// "isready", "ucinewgame", "position [startpos | fen <fen>] [moves <moves>]", "go" with
+/// "depth", "movetime", "wtime", "btime", "winc", "binc" and "infinite", "stop" and "quit",
+/// and ignores everything else. Also understands "go ponder" and "ponderhit", and
+/// "setoption" for the options of UciOptions, which are declared in answer to "uci"; an
+/// unknown option or a bad value is reported with an "info string" line and ignored.
+/// The engine searches with Engine::search on a worker thread, so "stop" and "isready" are
+/// answered while it searches; the Skill Level and Contempt options are passed on to it with
+/// Engine::configure. An AlphaBetaEngine writes an "info" line every time a depth is finished,
+/// and every engine writes "bestmove" when it is done. Returns an error only if the input
+/// can't be read or the output written.
+pub fn run(input: impl BufRead, output: impl Write + Send + 'static, engine: impl Engine + Send + 'static) -> io::Result<()> {
+    let mut session = Session {
+        game: Game::new(),
+        options: UciOptions::default(),
+        engine: Arc::new(Mutex::new(engine)),
+        output: Arc::new(Mutex::new(output)),
+        search: None,
+        ponder_budget: None
+    };
+    for line in input.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => session.write(&format!("id name eliassam_chess\nid author eliassam\n{}\nuciok", session.options.declarations()))?,
+            Some("isready") => session.write("readyok")?,
+            Some("ucinewgame") => {
+                session.stop();
+                session.game = Game::new();
+            }
+            Some("position") => {
+                session.stop();
+                if let Some(game) = parse_position(tokens) {session.game = game;}
+            }
+            Some("go") => {
+                session.stop();
+                session.go(tokens);
+            }
+            Some("setoption") => {
+                session.stop();
+                let before = session.options;
+                if let Err(message) = session.options.setoption(tokens) {session.write(&format!("info string {}", message))?;}
+                let options = session.options;
+                if (options.skill, options.contempt) != (before.skill, before.contempt) {session.engine.lock().unwrap().configure(options.skill, options.contempt);}
+            }
+            Some("ponderhit") => session.ponder_hit(),
+            Some("stop") => session.stop(),
+            Some("quit") => {
+                session.stop();
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+    // at the end of the input the search is left to finish, so a script can end with "go"
+    session.wait();
+    return Ok(());
+}
+
+/// The state of the protocol between two commands.
+struct Session<W: Write + Send + 'static, E: Engine + Send + 'static> {
+    game: Game,
+    options: UciOptions,
+    /// Shared with the search thread, which has it while it searches.
+    engine: Arc<Mutex<E>>,
+    /// Shared with the search thread, which writes the info lines and the best move.
+    output: Arc<Mutex<W>>,
+    /// The signal and the thread of the running search, if any.
+    search: Option<(Arc<StopSignal>, JoinHandle<()>)>,
+    /// How long the search may go on after "ponderhit", if it is pondering with a time limit.
+    ponder_budget: Option<Duration>,
+}
+
+impl<W: Write + Send + 'static, E: Engine + Send + 'static> Session<W, E> {
+    fn write(&self, text: &str) -> io::Result<()> {
+        let mut output = self.output.lock().unwrap();
+        writeln!(output, "{}", text)?;
+        return output.flush();
+    }
+
+    /// Starts searching the current position on a worker thread, within the limits of the
+    /// arguments of the "go" command.
+    fn go<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let mut depth = None;
+        let mut movetime = None;
+        let mut time = [None, None];
+        let mut increment = [0, 0];
+        let mut ponder = false;
+        while let Some(token) = tokens.next() {
+            // some GUIs send a negative time once the engine is over it, which is no time left
+            let mut value = || tokens.next().and_then(|value| value.parse::<i64>().ok()).map(|value| value.max(0) as u64);
+            match token {
+                "depth" => depth = value().map(|depth| depth as u32),
+                "movetime" => movetime = value(),
+                "wtime" => time[Color::White as usize] = value(),
+                "btime" => time[Color::Black as usize] = value(),
+                "winc" => increment[Color::White as usize] = value().unwrap_or(0),
+                "binc" => increment[Color::Black as usize] = value().unwrap_or(0),
+                "ponder" => ponder = true,
+                _ => {}
+            }
+        }
+        let turn = self.game.get_turn_color() as usize;
+        let budget = match (movetime, time[turn]) {
+            (Some(movetime), _) => Some(Duration::from_millis(movetime)),
+            (None, Some(time)) => Some(time_budget(Duration::from_millis(time), Duration::from_millis(increment[turn]))),
+            (None, None) => None
+        };
+        // while pondering the clock doesn't run for the engine yet, see ponder_hit
+        self.ponder_budget = if ponder {budget} else {None};
+        let signal = Arc::new(StopSignal::default());
+        if !ponder {*signal.deadline.lock().unwrap() = budget.map(|budget| Instant::now() + budget);}
+        let game = self.game.clone();
+        let multipv = self.options.multipv as usize;
+        let engine = Arc::clone(&self.engine);
+        let output = Arc::clone(&self.output);
+        let thread_signal = Arc::clone(&signal);
+        let thread = thread::spawn(move || {
+            // a GUI that went away can't be told anything, so write errors are ignored
+            let write_line = |line: String| {
+                let mut output = output.lock().unwrap();
+                let _ = writeln!(output, "{}", line).and_then(|_| output.flush());
+            };
+            let mut info = |info: &SearchInfo| write_line(info_line(info, multipv > 1));
+            let mut control = SearchControl::new(&thread_signal, &mut info, multipv);
+            // the time limit is the deadline of the signal, which ponderhit can still set
+            let limits = SearchLimits { depth, time: None };
+            let best_move = engine.lock().unwrap().search(&game, &limits, &mut control).map(|chosen| chosen.mv);
+            write_line(format!("bestmove {}", best_move.map_or("0000".to_string(), |mv| mv.to_string())));
+        });
+        self.search = Some((signal, thread));
+    }
+
+    /// The opponent made the move the engine is pondering on: the clock of the engine starts,
+    /// so the search gets the time it was given.
+    fn ponder_hit(&mut self) {
+        if let (Some((signal, _)), Some(budget)) = (&self.search, self.ponder_budget.take()) {
+            *signal.deadline.lock().unwrap() = Some(Instant::now() + budget);
+        }
+    }
+
+    /// Stops the running search, if any, and waits until it has written its best move.
+    fn stop(&mut self) {
+        if let Some((signal, _)) = &self.search {signal.stop.store(true, Ordering::Relaxed);}
+        self.wait();
+    }
+
+    /// Waits until the running search, if any, has written its best move.
+    fn wait(&mut self) {
+        if let Some((_, thread)) = self.search.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Reads the arguments of the "position" command. Returns None if the FEN is invalid, and
+/// stops at the first move that isn't legal.
+fn parse_position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Game> {
+    let mut game = match tokens.next()? {
+        "startpos" => Game::new(),
+        "fen" => {
+            let fen: Vec<&str> = tokens.by_ref().take_while(|&token| token != "moves").collect();
+            let game = Game::from_fen(&fen.join(" ")).ok()?;
+            // the "moves" token was taken with the FEN
+            return Some(play_moves(game, tokens));
+        }
+        _ => return None
+    };
+    if tokens.next() == Some("moves") {game = play_moves(game, tokens);}
+    return Some(game);
+}
+
+/// Plays the moves, given in coordinate notation like "e2e4" or "e7e8q".
+fn play_moves<'a>(mut game: Game, tokens: impl Iterator<Item = &'a str>) -> Game {
+    for token in tokens {
+        let mv = game.get_all_possible_moves().into_iter().find(|mv| mv.to_string() == token);
+        match mv {
+            Some(mv) => {game.play_move(&mv);}
+            None => break
+        }
+    }
+    return game;
+}
+
+/// How long to search with `time` left on the clock and `increment` added after the move:
+/// an even share of the time for the moves to come plus most of the increment, but never
+/// more than is left.
+pub(super) fn time_budget(time: Duration, increment: Duration) -> Duration {
+    return time_budget_to_go(time, increment, MOVES_TO_GO);
+}
+
+/// Like time_budget, but the time left has to last `moves_to_go` moves, like when a time
+/// control adds time after a number of moves.
+pub(super) fn time_budget_to_go(time: Duration, increment: Duration, moves_to_go: u32) -> Duration {
+    let budget = time / moves_to_go.max(1) + increment * 3 / 4;
+    return budget.min(time.saturating_sub(MOVE_OVERHEAD)).max(Duration::from_millis(1));
+}
+
+/// Writes the progress of the search as an "info" line, saying which line it is if the GUI
+/// asked for several.
+fn info_line(info: &SearchInfo, multipv: bool) -> String {
+    let score = score_text(info.score);
+    let rank = if multipv {format!(" multipv {}", info.multipv)} else {String::new()};
+    let pv: Vec<String> = info.pv.iter().map(|mv| mv.to_string()).collect();
+    return format!(
+        "info depth {} seldepth {}{} score {} nodes {} nps {} time {} pv {}",
+        info.depth, info.seldepth, rank, score, info.nodes, info.nps, info.elapsed.as_millis(), pv.join(" ")
+    );
+}
+
+/// Writes the score as "cp <centipawns>" or "mate <moves>".
+fn score_text(score: Score) -> String {
+    return match score {
+        Score::Centipawns(centipawns) => format!("cp {}", centipawns),
+        Score::Mate(moves) => format!("mate {}", moves)
+    };
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use super::{run, time_budget, UciOptions};
+    use crate::engine::{AlphaBetaEngine, Engine, EngineOptions, EvalParams, MctsEngine, MctsOptions};
+    use crate::Game;
+
+    // an output the test can read after run has taken it
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            return self.0.lock().unwrap().write(buf);
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    fn run_script(script: &str) -> Vec<String> {
+        return run_engine(script, AlphaBetaEngine::default());
+    }
+
+    fn run_engine(script: &str, engine: impl Engine + Send + 'static) -> Vec<String> {
+        let output = SharedOutput::default();
+        run(script.as_bytes(), output.clone(), engine).unwrap();
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        return text.lines().map(|line| line.to_string()).collect();
+    }
+
+    //check that the loop answers the handshake and finds the mate in one
+    #[test]
+    fn check_uci_mate_in_one() {
+        let lines = run_script("uci\nisready\nucinewgame\nposition fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+        assert_eq!(lines[..2], ["id name eliassam_chess", "id author eliassam"]);
+        // the options are declared between the name and uciok
+        let uciok = lines.iter().position(|line| line == "uciok").unwrap();
+        assert!(lines[2..uciok].iter().all(|line| line.starts_with("option name ")) && uciok == 8, "{:?}", lines);
+        assert_eq!(lines[uciok + 1], "readyok");
+        assert!(lines.iter().any(|line| line.starts_with("info depth 1 ") && line.contains(" score mate 1 ") && line.ends_with(" pv a1a8")), "{:?}", lines);
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+    }
+
+    //check that the moves after the position are played and stop ends an infinite search
+    #[test]
+    fn check_uci_moves_and_stop() {
+        // after 1.f3 e5 2.g4 black mates with Qh4
+        let lines = run_script("position startpos moves f2f3 e7e5 g2g4\ngo infinite\nisready\nstop\nquit\n");
+        // the mate ends the search, maybe even before the GUI asks if the engine is ready
+        assert!(lines.contains(&"bestmove d8h4".to_string()) && lines.contains(&"readyok".to_string()), "{:?}", lines);
+        // the moves can follow a FEN too, and a game that is over has no move
+        let lines = run_script("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 moves a1a8\ngo movetime 100\n");
+        assert_eq!(lines, ["bestmove 0000"]);
+    }
+
+    //check that the search uses a share of the clock and never all of it
+    #[test]
+    fn check_time_budget() {
+        assert_eq!(time_budget(Duration::from_secs(60), Duration::ZERO), Duration::from_secs(2));
+        assert_eq!(time_budget(Duration::from_secs(60), Duration::from_secs(2)), Duration::from_millis(3500));
+        assert_eq!(time_budget(Duration::from_millis(100), Duration::from_secs(2)), Duration::from_millis(50));
+        // a clock that is over the time gets the least time instead of none at all
+        let start = Instant::now();
+        let lines = run_script("position startpos\ngo wtime -500 btime 60000\n");
+        assert!(lines.last().unwrap().starts_with("bestmove "), "{:?}", lines);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    //check that setoption lines set the options, ignoring the case of the names and clamping numbers
+    #[test]
+    fn check_uci_options() {
+        let mut options = UciOptions::default();
+        for line in ["name Hash value 128", "name skill level value 5", "name CONTEMPT value 400", "name Threads value 8", "name MultiPV value 0", "name Ponder value True"] {
+            options.setoption(line.split_whitespace()).unwrap();
+        }
+        assert_eq!(options, UciOptions { hash: 128, threads: 1, multipv: 1, skill: 5, contempt: 100, ponder: true });
+        let engine_options = options.engine_options();
+        assert_eq!(engine_options, EngineOptions { skill: 5, eval: EvalParams { contempt: 100, ..EvalParams::DEFAULT }, ..EngineOptions::default() });
+        // unknown options, bad values and a missing name change nothing
+        for line in ["name Style value Risky", "name Hash value lots", "name Ponder value yes", "value 3", ""] {
+            assert!(options.setoption(line.split_whitespace()).is_err(), "{}", line);
+        }
+        assert_eq!(options.hash, 128);
+    }
+
+    //check that the loop warns about unknown options and plays with the options it was given
+    #[test]
+    fn check_uci_setoption() {
+        let lines = run_script("setoption name Style value Risky\nsetoption name MultiPV value 3\nposition fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 2\n");
+        assert_eq!(lines[0], "info string unknown option Style");
+        let multipv: Vec<&String> = lines.iter().filter(|line| line.contains(" multipv ")).collect();
+        assert_eq!(multipv.len(), 3, "{:?}", lines);
+        assert!(multipv[0].starts_with("info depth 1 seldepth ") && multipv[0].contains(" multipv 1 score mate 1 ") && multipv[0].ends_with(" pv a1a8"), "{:?}", lines);
+        assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+        // the weakest engine still plays a legal move
+        let lines = run_script("setoption name Skill Level value 0\nposition startpos\ngo depth 5\n");
+        let mv = lines.last().unwrap().strip_prefix("bestmove ").unwrap();
+        assert!(Game::new().get_all_possible_moves().iter().any(|legal| legal.to_string() == mv), "{:?}", lines);
+    }
+
+    //check that a search for several lines keeps to the time and stops when told to, also at a low skill
+    #[test]
+    fn check_uci_multipv_time() {
+        let start = Instant::now();
+        let lines = run_script("setoption name MultiPV value 4\nposition startpos\ngo movetime 200\n");
+        assert!(start.elapsed() < Duration::from_millis(400), "{:?}", start.elapsed());
+        // every finished depth reports all four lines
+        let last_depth: Vec<&String> = lines.iter().rev().skip(1).take(4).collect();
+        assert!(last_depth.iter().all(|line| line.contains(" multipv ")), "{:?}", lines);
+        assert!(lines.last().unwrap().starts_with("bestmove "), "{:?}", lines);
+        let start = Instant::now();
+        let lines = run_script("setoption name Skill Level value 19\nsetoption name MultiPV value 4\nposition startpos\ngo infinite\nstop\n");
+        assert!(start.elapsed() < Duration::from_millis(200), "{:?}", start.elapsed());
+        assert!(lines.last().unwrap().starts_with("bestmove "), "{:?}", lines);
+    }
+
+    //check that any engine can be served, also one chosen at run time, and that stop ends its search
+    #[test]
+    fn check_uci_other_engine() {
+        let engine: Box<dyn Engine + Send> = Box::new(MctsEngine::new(MctsOptions::default()));
+        let lines = run_engine("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo movetime 100\n", engine);
+        assert_eq!(lines, ["bestmove a1a8"]);
+        let start = Instant::now();
+        let lines = run_engine("position startpos\ngo infinite\nisready\nstop\n", MctsEngine::new(MctsOptions::default()));
+        assert!(start.elapsed() < Duration::from_millis(200), "{:?}", start.elapsed());
+        assert_eq!(lines[0], "readyok");
+        let mv = lines[1].strip_prefix("bestmove ").unwrap();
+        assert!(Game::new().get_all_possible_moves().iter().any(|legal| legal.to_string() == mv), "{:?}", lines);
+    }
+
+    //check that pondering searches until ponderhit starts the clock
+    #[test]
+    fn check_uci_ponder() {
+        let lines = run_script("setoption name Ponder value true\nposition startpos\ngo ponder movetime 50\nisready\nponderhit\n");
+        // the best move only comes after ponderhit, which is after the engine said it is ready
+        let ready = lines.iter().position(|line| line == "readyok").unwrap();
+        assert!(lines[..ready].iter().all(|line| line.starts_with("info ")), "{:?}", lines);
+        assert!(lines.last().unwrap().starts_with("bestmove "), "{:?}", lines);
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use super::backend::{Engine, SearchLimits};
+use super::uci::{time_budget, time_budget_to_go};
+use crate::{Color, Game, GameState, Move};
+
+// the time per move until the GUI tells the engine its clock
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(5);
+
+/// Talks the Chess Engine Communication Protocol (CECP, or xboard) with a GUI: reads the
+/// commands from `input` one line at a time and writes the answers to `output`, until the
+/// "quit" command or the end of the input. Understands "xboard", "protover" (answered with
+/// the features), "new", "force", "go", "usermove", "setboard", "level", "time", "sd", "st",
+/// "ping", "result" and "quit", and ignores everything else, like "otim", since only the
+/// clock of the engine matters. The moves come and go in coordinate notation like "e2e4" or
+/// "e7e8q", and the engine chooses them with Engine::choose_move, like the engine of run in
+/// the uci module. It doesn't ponder, and a move is searched on the thread reading the
+/// commands, so nothing is read while the engine thinks.
+pub fn run(input: impl BufRead, mut output: impl Write, engine: impl Engine) -> io::Result<()> {
+    let mut session = Session {
+        game: Game::new(),
+        engine,
+        force: false,
+        engine_color: Color::Black,
+        depth: None,
+        move_time: None,
+        clock: None,
+        increment: Duration::ZERO,
+        moves_per_session: 0
+    };
+    for line in input.lines() {
+        let line = line?;
+        let (command, arguments) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        match command {
+            "protover" => writeln!(output, "feature myname=\"eliassam_chess\" usermove=1 setboard=1 ping=1 colors=0 sigint=0 sigterm=0 analyze=0 done=1")?,
+            "new" => {
+                session.game = Game::new();
+                session.force = false;
+                session.engine_color = Color::Black;
+                session.depth = None;
+                session.move_time = None;
+            }
+            "force" | "result" => session.force = true,
+            "go" => {
+                // the engine plays the side to move from now on
+                session.force = false;
+                session.engine_color = session.game.get_turn_color();
+                session.think(&mut output)?;
+            }
+            "usermove" => {
+                match find_move(&session.game, arguments) {
+                    Some(mv) => {
+                        session.game.play_move(&mv);
+                        session.think(&mut output)?;
+                    }
+                    None => writeln!(output, "Illegal move: {}", arguments)?
+                }
+            }
+            "setboard" => {
+                match Game::from_fen(arguments) {
+                    Ok(game) => session.game = game,
+                    Err(_) => writeln!(output, "tellusererror Illegal position")?
+                }
+            }
+            "level" => {
+                match parse_level(arguments) {
+                    Some((moves, base, increment)) => {
+                        session.moves_per_session = moves;
+                        session.clock = Some(base);
+                        session.increment = increment;
+                    }
+                    None => writeln!(output, "Error (bad time control): level {}", arguments)?
+                }
+            }
+            // the clock of the engine, in centiseconds
+            "time" => session.clock = arguments.parse::<u64>().ok().map(|centiseconds| Duration::from_millis(centiseconds * 10)),
+            "sd" => session.depth = arguments.parse::<u32>().ok(),
+            "st" => session.move_time = arguments.parse::<u64>().ok().map(Duration::from_secs),
+            "ping" => writeln!(output, "pong {}", arguments)?,
+            "quit" => break,
+            _ => {}
+        }
+        output.flush()?;
+    }
+    return Ok(());
+}
+
+/// The state of the protocol between two commands.
+struct Session<E: Engine> {
+    game: Game,
+    engine: E,
+    /// In force mode the engine only checks the moves it is given, and plays none.
+    force: bool,
+    /// The color the engine plays when it isn't in force mode.
+    engine_color: Color,
+    /// The limits set with "sd" and "st".
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    /// The time left on the clock of the engine, once the GUI said so with "level" or "time".
+    clock: Option<Duration>,
+    /// The time control of "level": the time added after every move, and the number of moves
+    /// after which the base time is added again, or 0 if it is for the whole game.
+    increment: Duration,
+    moves_per_session: u32,
+}
+
+impl<E: Engine> Session<E> {
+    /// Plays a move for the engine if it is its turn and the game goes on, and writes it.
+    fn think(&mut self, output: &mut impl Write) -> io::Result<()> {
+        if self.force || self.game.get_turn_color() != self.engine_color {return Ok(());}
+        let time = self.move_time.or(self.clock.map(|clock| match self.moves_per_session {
+            0 => time_budget(clock, self.increment),
+            moves => time_budget_to_go(clock, self.increment, moves - (self.game.position.fullmove - 1) % moves)
+        }));
+        let limits = SearchLimits { depth: self.depth, time: time.or(if self.depth.is_none() {Some(DEFAULT_MOVE_TIME)} else {None}) };
+        let chosen = match self.engine.choose_move(&self.game, &limits) {
+            Some(chosen) => chosen.mv,
+            None => return Ok(())
+        };
+        self.game.play_move(&chosen);
+        writeln!(output, "move {}", chosen)?;
+        match self.game.get_game_state() {
+            GameState::Checkmate if self.engine_color == Color::White => writeln!(output, "1-0 {{White mates}}")?,
+            GameState::Checkmate => writeln!(output, "0-1 {{Black mates}}")?,
+            GameState::Stalemate => writeln!(output, "1/2-1/2 {{Stalemate}}")?,
+            _ => {}
+        }
+        return Ok(());
+    }
+}
+
+/// Reads the arguments of "level <moves> <base> <increment>": the number of moves per time
+/// control (0 for the whole game), the base time in minutes or as minutes:seconds, and the
+/// increment in seconds.
+fn parse_level(arguments: &str) -> Option<(u32, Duration, Duration)> {
+    let arguments: Vec<&str> = arguments.split_whitespace().collect();
+    let [moves, base, increment] = arguments[..] else {return None};
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let base = Duration::from_secs(minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?);
+    let increment = Duration::try_from_secs_f64(increment.parse::<f64>().ok()?).ok()?;
+    return Some((moves.parse().ok()?, base, increment));
+}
+
+/// The legal move written in coordinate notation, if there is one.
+fn find_move(game: &Game, notation: &str) -> Option<Move> {
+    return game.get_all_possible_moves().into_iter().find(|mv| mv.to_string() == notation);
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{parse_level, run};
+    use crate::engine::{AlphaBetaEngine, Engine, MctsEngine, MctsOptions};
+    use crate::Game;
+
+    fn run_script(script: &str) -> Vec<String> {
+        return run_engine(script, AlphaBetaEngine::default());
+    }
+
+    fn run_engine(script: &str, engine: impl Engine) -> Vec<String> {
+        let mut output: Vec<u8> = Vec::new();
+        run(script.as_bytes(), &mut output, engine).unwrap();
+        return String::from_utf8(output).unwrap().lines().map(|line| line.to_string()).collect();
+    }
+
+    //check that the engine takes the moves in force mode and answers with a legal move after go
+    #[test]
+    fn check_xboard_session() {
+        let lines = run_script("xboard\nprotover 2\nnew\nforce\nusermove e2e4\nusermove e7e5\nusermove g1f3\nsd 3\ngo\nping 7\nquit\n");
+        assert!(lines[0].starts_with("feature ") && lines[0].ends_with(" done=1"), "{:?}", lines);
+        assert_eq!(lines.len(), 3, "{:?}", lines);
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "e7e5", "g1f3"] {
+            game1.play_move(&super::find_move(&game1, mv).unwrap()).unwrap();
+        }
+        let reply = lines[1].strip_prefix("move ").unwrap();
+        assert!(super::find_move(&game1, reply).is_some(), "{}", reply);
+        assert_eq!(lines[2], "pong 7");
+    }
+
+    //check that the engine plays black after new, answers right away and rejects illegal moves
+    #[test]
+    fn check_xboard_new_game() {
+        let lines = run_script("new\nsd 2\nusermove e2e5\nusermove e2e4\n");
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        assert_eq!(lines[0], "Illegal move: e2e5");
+        let mut game1 = Game::new();
+        game1.play_move(&super::find_move(&game1, "e2e4").unwrap()).unwrap();
+        assert!(super::find_move(&game1, lines[1].strip_prefix("move ").unwrap()).is_some(), "{:?}", lines);
+        // after 1.f3 e5 2.g4 black mates
+        let lines = run_script("new\nforce\nusermove f2f3\nusermove e7e5\nusermove g2g4\nsd 2\ngo\n");
+        assert_eq!(lines, ["move d8h4", "0-1 {Black mates}"]);
+    }
+
+    //check that setboard sets up the position and the engine finds the mate from it
+    #[test]
+    fn check_xboard_setboard() {
+        let lines = run_script("force\nsetboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\nsd 2\ngo\nsetboard nonsense\n");
+        assert_eq!(lines, ["move a1a8", "1-0 {White mates}", "tellusererror Illegal position"]);
+    }
+
+    //check that level sets the clock the engine plans its time with, and that a bad one is reported
+    #[test]
+    fn check_xboard_level() {
+        assert_eq!(parse_level("40 5 0"), Some((40, Duration::from_secs(300), Duration::ZERO)));
+        assert_eq!(parse_level("0 2:30 1.5"), Some((0, Duration::from_secs(150), Duration::from_millis(1500))));
+        assert_eq!(parse_level("0 2:30"), None);
+        assert_eq!(parse_level("x 5 0"), None);
+        // a second for 40 moves leaves a few milliseconds for the move
+        let start = Instant::now();
+        let lines = run_script("new\nlevel 40 0:01 0\nusermove e2e4\nlevel 0 five 0\n");
+        assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+        let mut game1 = Game::new();
+        game1.play_move(&super::find_move(&game1, "e2e4").unwrap()).unwrap();
+        assert!(super::find_move(&game1, lines[0].strip_prefix("move ").unwrap()).is_some(), "{:?}", lines);
+        assert_eq!(lines[1], "Error (bad time control): level 0 five 0");
+    }
+
+    //check that any engine can play, also one chosen at run time
+    #[test]
+    fn check_xboard_other_engine() {
+        let engine: Box<dyn Engine> = Box::new(MctsEngine::new(MctsOptions::default()));
+        let lines = run_engine("force\nsetboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\nst 1\ngo\n", engine);
+        assert_eq!(lines, ["move a1a8", "1-0 {White mates}"]);
+    }
+}
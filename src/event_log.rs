@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{MonotonicTime, TimeSource};
+use crate::{ChessError, Color, Game, Move};
+
+/// What happened, in an entry of the event log of a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogEntry {
+    /// A move was made, in coordinate notation.
+    Move(String),
+    /// A move was refused: the move as it was given, and why.
+    Rejected { attempt: String, reason: ChessError },
+    DrawOffered,
+    DrawAccepted,
+    Resigned,
+    /// The move was taken back, in coordinate notation.
+    Undo(String),
+    /// The player ran out of time, see Game::check_flag.
+    Flagged,
+    /// See Game::request_takeback.
+    TakebackRequested,
+    TakebackAccepted,
+    TakebackDeclined,
+    /// The position of the FEN string was set up, see Game::load_fen_continue. The player is
+    /// the one to move.
+    PositionSet(String),
+}
+
+/// An entry of the event log of a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoggedEvent {
+    /// When it happened, counted from the start of the log and never going backwards.
+    pub time: Duration,
+    /// The player who did it.
+    pub actor: Color,
+    pub entry: LogEntry,
+}
+
+/// Options for Game::start_event_log. Games start their log with the default options.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EventLogOptions {
+    /// The most entries to keep. The oldest entries are dropped to make room for new ones.
+    /// None keeps every entry.
+    pub max_len: Option<usize>,
+}
+
+/// The event log of a game. Clones of the game copy the entries and share the time source.
+#[derive(Clone)]
+pub(crate) struct EventLog {
+    events: Vec<LoggedEvent>,
+    max_len: Option<usize>,
+    start: Duration,
+    time: Arc<dyn TimeSource>,
+}
+
+impl EventLog {
+    /// An empty log with the options, timed from now by the source.
+    fn new(options: EventLogOptions, time: impl TimeSource + 'static) -> Box<EventLog> {
+        let start = time.now();
+        return Box::new(EventLog { events: Vec::new(), max_len: options.max_len, start, time: Arc::new(time) });
+    }
+
+    /// The log a new game starts with, with the default options and the system's clock.
+    pub(crate) fn started() -> Box<EventLog> {
+        return EventLog::new(EventLogOptions::default(), MonotonicTime::new());
+    }
+}
+
+impl Game {
+    /// Starts the event log of the game over with the options. Every game records every move
+    /// made, every move refused (with the reason), every draw offer, resignation, flag and undo
+    /// in its event log from the start, with the time and the player, e.g. to settle disputes
+    /// about what happened. Starting the log again clears it.
+    pub fn start_event_log(&mut self, options: EventLogOptions) {
+        self.start_event_log_with(options, MonotonicTime::new());
+    }
+
+    /// Like start_event_log, with the time from the source, like a clock::MockTime in tests.
+    pub fn start_event_log_with(&mut self, options: EventLogOptions, time: impl TimeSource + 'static) {
+        self.event_log = Some(EventLog::new(options, time));
+    }
+
+    /// The entries of the event log, the oldest first. Empty if the game was restored from a
+    /// save without a log.
+    pub fn event_log(&self) -> &[LoggedEvent] {
+        return self.event_log.as_ref().map_or(&[], |log| &log.events);
+    }
+
+    /// The options of the event log, or None if the game was restored without one.
+    pub(crate) fn event_log_options(&self) -> Option<EventLogOptions> {
+        return self.event_log.as_ref().map(|log| EventLogOptions { max_len: log.max_len });
+    }
+
+    /// Starts the event log with the entries, going on from the time of the last one.
+    pub(crate) fn restore_event_log(&mut self, options: EventLogOptions, events: Vec<LoggedEvent>) {
+        let time = MonotonicTime::since(events.last().map_or(Duration::ZERO, |event| event.time));
+        self.event_log = Some(Box::new(EventLog { events, max_len: options.max_len, start: Duration::ZERO, time: Arc::new(time) }));
+    }
+
+    /// Removes the entries of the event log, if there is one, which goes on with the same
+    /// options and time.
+    pub(crate) fn clear_event_log(&mut self) {
+        if let Some(log) = self.event_log.as_mut() {log.events.clear();}
+    }
+
+    /// Adds an entry to the event log, if there is one.
+    pub(crate) fn log(&mut self, actor: Color, entry: LogEntry) {
+        let Some(log) = self.event_log.as_mut() else {return};
+        let time = log.time.now().saturating_sub(log.start);
+        log.events.push(LoggedEvent { time, actor, entry });
+        if let Some(max_len) = log.max_len {
+            let excess = log.events.len().saturating_sub(max_len);
+            log.events.drain(..excess);
+        }
+    }
+
+    /// Adds a refused move to the event log. The player is the one the piece on the start
+    /// square belongs to, or the player to move if there is none.
+    pub(crate) fn log_rejection(&mut self, attempt: &str, reason: ChessError) {
+        if self.event_log.is_none() {return;}
+        let actor = crate::parse_square(attempt).and_then(|from| self.position.piece_at(from)).map_or(self.position.turn, |piece| piece.color);
+        self.log(actor, LogEntry::Rejected { attempt: attempt.to_string(), reason });
+    }
+
+    /// Adds a move that was made or taken back to the event log, by the player who made it.
+    pub(crate) fn log_move(&mut self, mv: Move, undo: bool) {
+        if self.event_log.is_none() {return;}
+        let actor = if undo {self.position.turn} else {self.position.turn.opposite()};
+        self.log(actor, if undo {LogEntry::Undo(mv.to_string())} else {LogEntry::Move(mv.to_string())});
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{EventLogOptions, LogEntry, LoggedEvent};
+    use crate::clock::MockTime;
+    use crate::{ChessError, Color, Game, IllegalMoveReason};
+
+    //check the entries of a scripted session, with a refused move and its reason
+    #[test]
+    fn check_event_log() {
+        let time = MockTime::new();
+        let mut game1 = Game::new();
+        // every game has a log from the start, which starting it again clears
+        game1.make_move("d2", "d4").unwrap();
+        assert_eq!(game1.event_log().iter().map(|event| (event.actor, event.entry.clone())).collect::<Vec<_>>(), [(Color::White, LogEntry::Move("d2d4".to_string()))]);
+        game1.start_event_log_with(EventLogOptions::default(), time.clone());
+        time.advance(Duration::from_secs(1));
+        assert!(game1.make_move("e7", "e4").is_none());
+        time.advance(Duration::from_secs(1));
+        game1.make_move("e7", "e5").unwrap();
+        game1.undo().unwrap();
+        game1.offer_draw(Color::Black);
+        time.advance(Duration::from_secs(3));
+        game1.resign(Color::White);
+        let entry = |seconds, actor, entry| LoggedEvent { time: Duration::from_secs(seconds), actor, entry };
+        assert_eq!(game1.event_log(), [
+            entry(1, Color::Black, LogEntry::Rejected { attempt: "e7e4".to_string(), reason: ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay) }),
+            entry(2, Color::Black, LogEntry::Move("e7e5".to_string())),
+            entry(2, Color::Black, LogEntry::Undo("e7e5".to_string())),
+            entry(2, Color::Black, LogEntry::DrawOffered),
+            entry(5, Color::White, LogEntry::Resigned),
+        ]);
+        // the log is copied with the game, and a move after the end is refused
+        let mut refused = game1.clone();
+        assert!(refused.make_move("e7", "e5").is_none());
+        assert_eq!(refused.event_log().len(), 6);
+        assert_eq!(refused.event_log()[5].entry, LogEntry::Rejected { attempt: "e7e5".to_string(), reason: ChessError::IllegalMove(IllegalMoveReason::GameOver) });
+        assert_eq!(game1.event_log().len(), 5);
+    }
+
+    //check that a capped log keeps the newest entries
+    #[test]
+    fn check_event_log_capped() {
+        let mut game1 = Game::new();
+        game1.start_event_log_with(EventLogOptions { max_len: Some(3) }, MockTime::new());
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("b8", "c6"), ("f1", "b5")] {
+            game1.make_move(from, to).unwrap();
+        }
+        let moves: Vec<&LogEntry> = game1.event_log().iter().map(|event| &event.entry).collect();
+        assert_eq!(moves, [&LogEntry::Move("g1f3".to_string()), &LogEntry::Move("b8c6".to_string()), &LogEntry::Move("f1b5".to_string())]);
+        assert_eq!(game1.event_log()[2].actor, Color::White);
+        assert!(Game::new().event_log().is_empty());
+    }
+}
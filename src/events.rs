@@ -0,0 +1,282 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::event_log::LogEntry;
+use crate::{Color, Game, GameOutcome, GameState, IllegalMoveReason, Move, MoveResult, Piece, PieceRole, Square, Variant};
+
+/// Something that happened in a game, given to its subscribers (see Game::subscribe).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A move was made, with what happened. The events the move causes come right after it.
+    MoveMade(MoveResult),
+    /// A pawn was promoted on the square.
+    PromotionOccurred { square: Square, role: PieceRole },
+    /// The king of the color is in check, but not checkmated.
+    CheckGiven(Color),
+    /// The game ended, by a move that checkmated or stalemated, by Game::resign,
+    /// Game::accept_draw or Game::check_flag, or in a draw a GameManager ends the game with.
+    GameEnded(GameOutcome),
+    /// The player offered a draw, see Game::offer_draw.
+    DrawOffered(Color),
+    /// The move was taken back.
+    UndoPerformed(Move),
+    /// The position was set up from a FEN string, see Game::load_fen_reset and
+    /// Game::load_fen_continue.
+    PositionSet,
+    /// The premove of the player is legal after the opponent's move and is played, see
+    /// Game::set_premove. Its MoveMade comes right after.
+    PremovePlayed(Color),
+    /// The premove of the player isn't legal after the opponent's move and was discarded.
+    PremoveDiscarded { color: Color, mv: Move, reason: IllegalMoveReason },
+    /// The player asked for a takeback, see Game::request_takeback.
+    TakebackRequested(Color),
+    /// The player accepted the takeback. The UndoPerformed of the moves come right after.
+    TakebackAccepted(Color),
+    /// The player declined the takeback.
+    TakebackDeclined(Color),
+}
+
+/// Why a game is over, see Game::end_reason.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EndReason {
+    /// The player to move is checkmated.
+    Checkmate,
+    /// The player to move has no legal moves, but isn't in check.
+    Stalemate,
+    /// A player resigned, see Game::resign.
+    Resignation,
+    /// The players agreed to a draw, see Game::accept_draw.
+    Agreement,
+    /// A player ran out of time, see Game::check_flag.
+    Timeout,
+    /// Fifty moves by each player without a capture or a pawn move, which a GameManager ends
+    /// the game on.
+    FiftyMoves,
+    /// The position came up for the third time, which a GameManager ends the game on.
+    Repetition,
+}
+
+/// A function that is called with every event of a game.
+pub type Subscriber = Box<dyn FnMut(&GameEvent) + Send>;
+
+/// The subscribers of a game. A clone of a game starts without subscribers, since the engine
+/// and the analysis functions make clones to try moves out on.
+#[derive(Default)]
+pub(crate) struct Subscribers(Vec<Subscriber>);
+
+impl Clone for Subscribers {
+    fn clone(&self) -> Subscribers {
+        return Subscribers::default();
+    }
+}
+
+impl Subscribers {
+    fn emit(&mut self, event: GameEvent) {
+        for subscriber in self.0.iter_mut() {
+            subscriber(&event);
+        }
+    }
+}
+
+impl Game {
+    /// Calls the subscriber with every event of the game from now on, in the order they happen
+    /// and before the method that caused them returns. Any number of subscribers can be added,
+    /// and they are called in the order they were added. The events come after the game has
+    /// been changed, so a subscriber that panics leaves the game as it should be: the panic
+    /// goes on to the caller of the method, and the later subscribers miss the event.
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.0.push(subscriber);
+    }
+
+    /// The result of the game if it is over: by checkmate or stalemate (a win for the stalemated
+    /// player in Antichess, and for Black in Horde when White has no pieces left), or because a
+    /// player resigned, the players agreed to a draw or a player ran out of time.
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if let Some((outcome, _)) = self.ended {return Some(outcome);}
+        return match self.state {
+            // in Antichess the player without moves wins, whether stalemated or without pieces
+            GameState::Stalemate if self.position.variant == Variant::Antichess => Some(GameOutcome::Win(self.position.turn)),
+            // in Horde Black wins by taking every white piece
+            GameState::Stalemate if self.position.variant == Variant::Horde && self.position.turn == Color::White
+                && !self.position.board.iter().any(|&byte| Piece::from_u8(byte).is_some_and(|piece| piece.color == Color::White)) => Some(GameOutcome::Win(Color::Black)),
+            GameState::Checkmate => Some(GameOutcome::Win(self.position.turn.opposite())),
+            GameState::Stalemate => Some(GameOutcome::Draw),
+            _ => None
+        };
+    }
+
+    /// Why the game is over, or None if it isn't: checkmate or stalemate (also when that wins
+    /// the game in a variant, see outcome), a resignation, an agreed draw, a player who ran out
+    /// of time, whether that lost the game or drew it (see Game::check_flag), or the draw a
+    /// GameManager ends its games with at the fifty-move rule or a threefold repetition.
+    pub fn end_reason(&self) -> Option<EndReason> {
+        if let Some((_, reason)) = self.ended {return Some(reason);}
+        return match self.state {
+            GameState::Checkmate => Some(EndReason::Checkmate),
+            GameState::Stalemate => Some(EndReason::Stalemate),
+            _ => None
+        };
+    }
+
+    /// Ends the game with a win for the opponent of the player. Returns None and does nothing
+    /// if the game is already over.
+    pub fn resign(&mut self, color: Color) -> Option<GameOutcome> {
+        if self.outcome().is_some() {return None;}
+        #[cfg(feature = "std")]
+        self.log(color, LogEntry::Resigned);
+        return Some(self.end(GameOutcome::Win(color.opposite()), EndReason::Resignation));
+    }
+
+    /// Offers a draw to the opponent of the player, which they can take with accept_draw until
+    /// the next move is made. Returns false if the game is already over.
+    pub fn offer_draw(&mut self, color: Color) -> bool {
+        if self.outcome().is_some() {return false;}
+        self.draw_offer = Some(color);
+        #[cfg(feature = "std")]
+        self.log(color, LogEntry::DrawOffered);
+        self.subscribers.emit(GameEvent::DrawOffered(color));
+        return true;
+    }
+
+    /// Takes the draw the opponent of the player offered, which ends the game. Returns None if
+    /// there is no such offer, or the game is already over.
+    pub fn accept_draw(&mut self, color: Color) -> Option<GameOutcome> {
+        if self.outcome().is_some() || self.draw_offer != Some(color.opposite()) {return None;}
+        #[cfg(feature = "std")]
+        self.log(color, LogEntry::DrawAccepted);
+        return Some(self.end(GameOutcome::Draw, EndReason::Agreement));
+    }
+
+    /// Ends the game with the outcome for the reason, which no moves can be made after.
+    pub(crate) fn end(&mut self, outcome: GameOutcome, reason: EndReason) -> GameOutcome {
+        self.ended = Some((outcome, reason));
+        self.draw_offer = None;
+        self.premoves = [None; 2];
+        self.takeback_request = None;
+        self.subscribers.emit(GameEvent::GameEnded(outcome));
+        return outcome;
+    }
+
+    /// Gives the events of a move that has been made.
+    pub(crate) fn emit_move(&mut self, result: MoveResult) {
+        self.draw_offer = None;
+        self.takeback_request = None;
+        #[cfg(feature = "std")]
+        self.log_move(result.mv, false);
+        self.subscribers.emit(GameEvent::MoveMade(result));
+        if let Some(role) = result.mv.promotion.filter(|_| !result.mv.is_drop()) {
+            self.subscribers.emit(GameEvent::PromotionOccurred { square: result.mv.to, role });
+        }
+        match result.state {
+            GameState::Check => self.subscribers.emit(GameEvent::CheckGiven(self.position.turn)),
+            GameState::Checkmate | GameState::Stalemate => self.subscribers.emit(GameEvent::GameEnded(self.outcome().unwrap())),
+            GameState::InProgress => ()
+        }
+    }
+
+    /// Gives the event of a move that has been taken back.
+    pub(crate) fn emit_undo(&mut self, mv: Move) {
+        #[cfg(feature = "std")]
+        self.log_move(mv, true);
+        self.subscribers.emit(GameEvent::UndoPerformed(mv));
+    }
+
+    /// Gives the event of a premove that is about to be played.
+    pub(crate) fn emit_premove_played(&mut self, color: Color) {
+        self.subscribers.emit(GameEvent::PremovePlayed(color));
+    }
+
+    /// Gives the event of a premove that was discarded.
+    pub(crate) fn emit_premove_discarded(&mut self, color: Color, mv: Move, reason: IllegalMoveReason) {
+        self.subscribers.emit(GameEvent::PremoveDiscarded { color, mv, reason });
+    }
+
+    /// Gives the event of a takeback request.
+    pub(crate) fn emit_takeback_requested(&mut self, color: Color) {
+        self.subscribers.emit(GameEvent::TakebackRequested(color));
+    }
+
+    /// Gives the event of the answer to a takeback request.
+    pub(crate) fn emit_takeback_answered(&mut self, color: Color, accept: bool) {
+        self.subscribers.emit(if accept {GameEvent::TakebackAccepted(color)} else {GameEvent::TakebackDeclined(color)});
+    }
+
+    /// Gives the event of a position set up from a FEN string.
+    pub(crate) fn emit_position_set(&mut self) {
+        self.subscribers.emit(GameEvent::PositionSet);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{EndReason, GameEvent};
+    use crate::tests::{coordinate_move, recorded};
+    use crate::{Color, Game, GameOutcome, PieceRole, Square};
+
+    //check the events of a game with a check, a promotion, an undo, a draw offer and a resignation
+    #[test]
+    fn check_game_events() {
+        let (mut game1, events) = recorded("4k3/P7/8/8/8/8/8/4K2R w K - 0 1");
+        let check = game1.play_move(&coordinate_move("h1h8")).unwrap();
+        assert_eq!(game1.undo(), Some(coordinate_move("h1h8")));
+        let promotion = game1.play_move(&coordinate_move("a7a8q")).unwrap();
+        let reply = game1.play_move(&coordinate_move("e8e7")).unwrap();
+        assert!(game1.offer_draw(Color::Black));
+        // White doesn't take the draw, and Black can't take their own offer
+        assert_eq!(game1.accept_draw(Color::Black), None);
+        assert_eq!(game1.resign(Color::Black), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(game1.end_reason(), Some(EndReason::Resignation));
+        // nothing more happens in a game that is over
+        assert!(game1.make_move("e1", "e2").is_none());
+        assert_eq!(game1.resign(Color::White), None);
+        assert!(!game1.offer_draw(Color::White));
+        let events = events.lock().unwrap();
+        let expected = vec![
+            GameEvent::MoveMade(check),
+            GameEvent::CheckGiven(Color::Black),
+            GameEvent::UndoPerformed(coordinate_move("h1h8")),
+            GameEvent::MoveMade(promotion),
+            GameEvent::PromotionOccurred { square: Square::from_algebraic("a8").unwrap(), role: PieceRole::Queen },
+            GameEvent::CheckGiven(Color::Black),
+            GameEvent::MoveMade(reply),
+            GameEvent::DrawOffered(Color::Black),
+            GameEvent::GameEnded(GameOutcome::Win(Color::White)),
+        ];
+        assert_eq!(*events, expected);
+    }
+
+    //check that every subscriber gets the events, a checkmate ends the game and an accepted draw too
+    #[test]
+    fn check_game_events_end() {
+        let (mut game1, events) = recorded("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let second = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&second);
+        game1.subscribe(Box::new(move |_| *counter.lock().unwrap() += 1));
+        // clones don't tell the subscribers about their moves
+        game1.clone().make_move("a1", "b1").unwrap();
+        game1.make_move("a1", "a8").unwrap();
+        assert_eq!(events.lock().unwrap()[1], GameEvent::GameEnded(GameOutcome::Win(Color::White)));
+        assert_eq!(*second.lock().unwrap(), 2);
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(game1.end_reason(), Some(EndReason::Checkmate));
+        let (mut game1, events) = recorded("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        game1.offer_draw(Color::White);
+        assert_eq!(game1.accept_draw(Color::Black), Some(GameOutcome::Draw));
+        assert_eq!(game1.end_reason(), Some(EndReason::Agreement));
+        assert_eq!(*events.lock().unwrap(), vec![GameEvent::DrawOffered(Color::White), GameEvent::GameEnded(GameOutcome::Draw)]);
+        // a move withdraws the offer
+        let mut game1 = Game::new();
+        game1.offer_draw(Color::White);
+        game1.make_move("e2", "e4").unwrap();
+        assert_eq!(game1.accept_draw(Color::Black), None);
+        assert_eq!(game1.end_reason(), None);
+    }
+}
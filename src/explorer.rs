@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::engine::{GameOutcome, GameRecord};
+use crate::{ChessError, Color, Game, Move};
+
+/// How often a move was played from a position and how those games ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExplorerStats {
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl ExplorerStats {
+    /// The number of games the move was played in.
+    pub fn games(&self) -> u32 {
+        return self.white_wins + self.draws + self.black_wins;
+    }
+
+    /// The share of the games White won, in percent.
+    pub fn white_percent(&self) -> f64 {
+        return percent(self.white_wins, self.games());
+    }
+
+    /// The share of the games that were drawn, in percent.
+    pub fn draw_percent(&self) -> f64 {
+        return percent(self.draws, self.games());
+    }
+
+    /// The share of the games Black won, in percent.
+    pub fn black_percent(&self) -> f64 {
+        return percent(self.black_wins, self.games());
+    }
+}
+
+/// A move played from a position, see Explorer::lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerEntry {
+    pub mv: Move,
+    /// The move in standard algebraic notation.
+    pub san: String,
+    pub stats: ExplorerStats,
+}
+
+/// The moves played from every position of a collection of games, with how often they were
+/// played and how the games ended, to look up what was played from a position (an opening
+/// explorer). Positions are the same if the pieces, the player to move, the castling rights
+/// and the en passant square are, however the game got there.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Explorer {
+    /// Only the moves up to this many plies into a game are kept, if set.
+    max_ply: Option<u32>,
+    // the moves of every position, in the order they were first played, by the first four
+    // fields of the FEN of the position
+    positions: HashMap<String, Vec<(Move, ExplorerStats)>>,
+}
+
+impl Explorer {
+    /// An explorer without games. With a max_ply, only the first max_ply moves of every game
+    /// are kept, which bounds how much memory the explorer takes.
+    pub fn new(max_ply: Option<u32>) -> Explorer {
+        return Explorer { max_ply, positions: HashMap::new() };
+    }
+
+    /// Adds the moves of the games. The games are the records of whole games like play_game
+    /// returns. Games without a result are left out, and a game stops counting at the first
+    /// move that isn't legal.
+    pub fn ingest(&mut self, games: impl IntoIterator<Item = GameRecord>) {
+        for record in games {
+            let Some(outcome) = record.outcome else {continue};
+            let Ok(game) = Game::from_fen(&record.start_fen) else {continue};
+            self.add_game(game, &record.moves, outcome);
+        }
+    }
+
+    /// Adds the moves of the games, each with its result, e.g. games read with Game::from_pgn
+    /// with the result of their PGN. The moves are those played since the start of the game.
+    pub fn ingest_games(&mut self, games: impl IntoIterator<Item = (Game, GameOutcome)>) {
+        for (game, outcome) in games {
+            let mut start = game.clone();
+            let mut moves = Vec::new();
+            while let Some(mv) = start.undo() {
+                moves.push(mv);
+            }
+            moves.reverse();
+            // a new game, since one that was resigned or lost on time takes no moves
+            let Ok(start) = Game::from_fen_variant(&start.get_fen(), start.position.variant) else {continue};
+            self.add_game(start, &moves, outcome);
+        }
+    }
+
+    /// Reads a game in PGN with Game::from_pgn and adds its moves. The result is that of the
+    /// Result tag, or of the result after the moves if there is no such tag. A game without a
+    /// result ("*") is left out, and a PGN that can't be read is an error.
+    pub fn ingest_pgn(&mut self, pgn: &str) -> Result<(), ChessError> {
+        let game = Game::from_pgn(pgn)?;
+        if let Some(outcome) = pgn_result(pgn) {self.ingest_games([(game, outcome)]);}
+        return Ok(());
+    }
+
+    // adds the moves of a game from its start position, up to max_ply or the first move that
+    // isn't legal
+    fn add_game(&mut self, mut game: Game, moves: &[Move], outcome: GameOutcome) {
+        let plies = self.max_ply.map_or(moves.len(), |max_ply| moves.len().min(max_ply as usize));
+        for mv in &moves[..plies] {
+            let key = position_key(&game);
+            if game.play_move(mv).is_none() {break;}
+            let moves = self.positions.entry(key).or_default();
+            let index = match moves.iter().position(|&(played, _)| played == *mv) {
+                Some(index) => index,
+                None => {
+                    moves.push((*mv, ExplorerStats::default()));
+                    moves.len() - 1
+                }
+            };
+            let stats = &mut moves[index].1;
+            match outcome {
+                GameOutcome::Win(Color::White) => stats.white_wins += 1,
+                GameOutcome::Win(Color::Black) => stats.black_wins += 1,
+                GameOutcome::Draw => stats.draws += 1
+            }
+        }
+    }
+
+    /// The moves played from the position of the game, the most played first. Moves played
+    /// equally often come in the order they were first played.
+    pub fn lookup(&self, game: &Game) -> Vec<ExplorerEntry> {
+        let Some(moves) = self.positions.get(&position_key(game)) else {return Vec::new()};
+        let mut entries: Vec<ExplorerEntry> = moves.iter().map(|&(mv, stats)| {
+            ExplorerEntry { mv, san: game.to_san(&mv).unwrap_or_default(), stats }
+        }).collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.stats.games()));
+        return entries;
+    }
+
+    /// The number of positions with moves.
+    pub fn len(&self) -> usize {
+        return self.positions.len();
+    }
+
+    /// Returns true if there are no positions with moves.
+    pub fn is_empty(&self) -> bool {
+        return self.positions.is_empty();
+    }
+
+    /// Writes the explorer to a text file, which load reads back. The first line has the
+    /// max_ply (or "-"), and every other line one move of a position: the first four fields
+    /// of the FEN of the position, the move in coordinate notation and the White wins, draws
+    /// and Black wins, separated by ";".
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        writeln!(file, "{}", self.max_ply.map_or("-".to_string(), |max_ply| max_ply.to_string()))?;
+        let mut keys: Vec<&String> = self.positions.keys().collect();
+        keys.sort();
+        for key in keys {
+            for (mv, stats) in &self.positions[key] {
+                writeln!(file, "{};{};{};{};{}", key, mv, stats.white_wins, stats.draws, stats.black_wins)?;
+            }
+        }
+        return file.flush();
+    }
+
+    /// Reads an explorer written by save. Returns an error of kind InvalidData if the file
+    /// isn't one, like one with a move that isn't legal in its position.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Explorer> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("not an explorer line: {}", line));
+        let mut lines = BufReader::new(fs::File::open(path)?).lines();
+        let first = lines.next().ok_or_else(|| invalid(""))??;
+        let max_ply = match first.as_str() {
+            "-" => None,
+            max_ply => Some(max_ply.parse::<u32>().map_err(|_| invalid(max_ply))?)
+        };
+        let mut explorer = Explorer::new(max_ply);
+        for line in lines {
+            let line = line?;
+            let fields: Vec<&str> = line.split(';').collect();
+            let [key, notation, white_wins, draws, black_wins] = fields[..] else {return Err(invalid(&line))};
+            let game = Game::from_fen(&format!("{} 0 1", key)).map_err(|_| invalid(&line))?;
+            let mv = game.get_all_possible_moves().into_iter().find(|mv| mv.to_string() == notation).ok_or_else(|| invalid(&line))?;
+            let count = |field: &str| field.parse::<u32>().map_err(|_| invalid(&line));
+            let stats = ExplorerStats { white_wins: count(white_wins)?, draws: count(draws)?, black_wins: count(black_wins)? };
+            explorer.positions.entry(key.to_string()).or_default().push((mv, stats));
+        }
+        return Ok(explorer);
+    }
+}
+
+/// The first four fields of the FEN: the pieces, the player to move, the castling rights and
+/// the en passant square.
+fn position_key(game: &Game) -> String {
+    return game.get_fen().split(' ').take(4).collect::<Vec<&str>>().join(" ");
+}
+
+/// The result of a PGN game, from its Result tag or else the last word of the text.
+fn pgn_result(pgn: &str) -> Option<GameOutcome> {
+    let tag = pgn.lines().map(str::trim).find_map(|line| line.strip_prefix("[Result \"")?.strip_suffix("\"]"));
+    return match tag.or_else(|| pgn.split_whitespace().last())? {
+        "1-0" => Some(GameOutcome::Win(Color::White)),
+        "0-1" => Some(GameOutcome::Win(Color::Black)),
+        "1/2-1/2" => Some(GameOutcome::Draw),
+        _ => None
+    };
+}
+
+fn percent(part: u32, whole: u32) -> f64 {
+    return if whole == 0 {0.0} else {100.0 * part as f64 / whole as f64};
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{Explorer, ExplorerStats};
+    use crate::engine::{GameOutcome, GameRecord, Termination};
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game};
+
+    fn record(moves: &[&str], outcome: Option<GameOutcome>) -> GameRecord {
+        return GameRecord {
+            white: "white".to_string(),
+            black: "black".to_string(),
+            start_fen: Game::new().get_fen(),
+            moves: moves.iter().map(|mv| coordinate_move(mv)).collect(),
+            outcome,
+            termination: Termination::Checkmate,
+        };
+    }
+
+    fn games() -> Vec<GameRecord> {
+        return vec![
+            record(&["e2e4", "e7e5", "g1f3", "b8c6"], Some(GameOutcome::Win(Color::White))),
+            record(&["e2e4", "c7c5", "g1f3"], Some(GameOutcome::Draw)),
+            record(&["e2e4", "e7e5", "f1c4"], Some(GameOutcome::Win(Color::Black))),
+            record(&["d2d4", "d7d5"], Some(GameOutcome::Draw)),
+            // a game without a result doesn't count
+            record(&["e2e4", "e7e6"], None),
+        ];
+    }
+
+    //check the moves and results after 1.e4
+    #[test]
+    fn check_explorer_lookup() {
+        let mut explorer = Explorer::new(None);
+        explorer.ingest(games());
+        let mut game1 = Game::new();
+        let start = explorer.lookup(&game1);
+        assert_eq!(start.iter().map(|entry| (entry.san.as_str(), entry.stats.games())).collect::<Vec<_>>(), [("e4", 3), ("d4", 1)]);
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        let after_e4 = explorer.lookup(&game1);
+        assert_eq!(after_e4.iter().map(|entry| (entry.san.as_str(), entry.stats.games())).collect::<Vec<_>>(), [("e5", 2), ("c5", 1)]);
+        assert_eq!((after_e4[0].stats.white_percent(), after_e4[0].stats.draw_percent(), after_e4[0].stats.black_percent()), (50.0, 0.0, 50.0));
+        assert_eq!(after_e4[1].stats.draw_percent(), 100.0);
+        // 1.e4 e5 2.Nf3 and 1.e4 c5 2.Nf3 are different positions
+        game1.play_move(&coordinate_move("c7c5")).unwrap();
+        assert_eq!(explorer.lookup(&game1).len(), 1);
+        assert!(explorer.lookup(&Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap()).is_empty());
+    }
+
+    //check that the positions are the same however the game got there and max_ply cuts the games
+    #[test]
+    fn check_explorer_transpositions() {
+        let mut explorer = Explorer::new(Some(2));
+        explorer.ingest([
+            record(&["g1f3", "g8f6", "b1c3"], Some(GameOutcome::Draw)),
+            record(&["b1c3", "g8f6", "g1f3"], Some(GameOutcome::Draw)),
+        ]);
+        // only the first two moves of every game are kept
+        assert_eq!(explorer.len(), 3);
+        let mut explorer = Explorer::new(None);
+        explorer.ingest([
+            record(&["g1f3", "g8f6", "b1c3", "e7e5"], Some(GameOutcome::Draw)),
+            record(&["b1c3", "g8f6", "g1f3", "e7e5"], Some(GameOutcome::Win(Color::White))),
+        ]);
+        let mut game1 = Game::new();
+        for mv in ["g1f3", "g8f6", "b1c3"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(explorer.lookup(&game1)[0].stats.games(), 2);
+    }
+
+    //check that games read from PGN count with the result of their PGN, also a resigned one
+    #[test]
+    fn check_explorer_pgn() {
+        let mut explorer = Explorer::new(None);
+        explorer.ingest_pgn("[Event \"?\"]\n[Result \"0-1\"]\n\n1. e4 e5 2. Nf3 0-1\n").unwrap();
+        explorer.ingest_pgn("1. e4 c5 1/2-1/2").unwrap();
+        // a game in progress doesn't count, and one that can't be read is an error
+        explorer.ingest_pgn("1. d4 d5 *").unwrap();
+        assert!(explorer.ingest_pgn("1. e4 e4 1-0").is_err());
+        let mut resigned = Game::new();
+        resigned.play_move(&coordinate_move("e2e4")).unwrap();
+        resigned.resign(Color::Black);
+        explorer.ingest_games([(resigned, GameOutcome::Win(Color::White))]);
+        let start = explorer.lookup(&Game::new());
+        assert_eq!(start.len(), 1);
+        assert_eq!((start[0].san.as_str(), start[0].stats), ("e4", ExplorerStats { white_wins: 1, draws: 1, black_wins: 1 }));
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        assert_eq!(explorer.lookup(&game1).len(), 2);
+    }
+
+    //check that a saved explorer loads back the same
+    #[test]
+    fn check_explorer_save_load() {
+        let mut explorer = Explorer::new(Some(10));
+        explorer.ingest(games());
+        let path = std::env::temp_dir().join(format!("explorer_{}.txt", std::process::id()));
+        explorer.save(&path).unwrap();
+        let loaded = Explorer::load(&path);
+        std::fs::write(&path, "-\nnot a line\n").unwrap();
+        let broken = Explorer::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), explorer);
+        assert_eq!(broken.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
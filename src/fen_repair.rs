@@ -0,0 +1,209 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{ChessError, Color, FenError, FenField, Game};
+
+/// A defect repair_fen fixed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenFix {
+    /// Spaces before, after or between the fields were removed.
+    Whitespace,
+    /// A row without pieces written other than "8", e.g. "7" or "44", was made "8". The row
+    /// is counted from the top, 1 for rank 8.
+    EmptyRow { row: usize, before: String },
+    /// The active color was written in another way, e.g. "W" or "white".
+    ActiveColor { before: String },
+    /// The castling rights were written in another order, twice or with a '-', e.g. "qkQK"
+    /// or "KQ-".
+    Castling { before: String, after: String },
+    /// The en passant square was in uppercase or on the wrong rank, e.g. the square of the
+    /// pawn that double stepped, and was moved behind the pawn or removed if no pawn double
+    /// stepped on its file.
+    EnPassant { before: String, after: String },
+    /// The halfmove clock (as 0) or the fullmove number (as 1) was missing and was added.
+    MissingField(FenField),
+}
+
+/// A FEN string after repair_fen, with what was fixed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RepairedFen {
+    pub fen: String,
+    /// The fixes in the order of the fields, empty if the FEN string was valid.
+    pub fixes: Vec<FenFix>,
+}
+
+/// Fixes the defects of a FEN string that can only have been meant one way, e.g. one pasted by
+/// a user: extra spaces, the active color in uppercase, castling rights out of order, an en
+/// passant square on the wrong rank, rows without pieces that aren't written "8" and missing
+/// clocks. A row with pieces that doesn't have 8 squares is refused, since it can't be told
+/// where a square is missing, and so are the other errors of from_fen, with the position of
+/// the error in the repaired string.
+pub fn repair_fen(input: &str) -> Result<RepairedFen, FenError> {
+    let mut fixes: Vec<FenFix> = Vec::new();
+    let mut fields: Vec<String> = input.split_whitespace().map(String::from).collect();
+    if fields.join(" ") != input {fixes.push(FenFix::Whitespace);}
+    if fields.is_empty() {return Err(FenError { message: "expected 6 fields, found 0".to_string(), field: None, position: 0 });}
+
+    // rows without pieces, the only rows where the missing squares can only be in one place
+    let mut rows: Vec<String> = fields[0].split('/').map(String::from).collect();
+    for (index, row) in rows.iter_mut().enumerate() {
+        if !row.is_empty() && row.chars().all(|char| char.is_ascii_digit()) && row != "8" {
+            fixes.push(FenFix::EmptyRow { row: index + 1, before: row.clone() });
+            *row = "8".to_string();
+        }
+    }
+    fields[0] = rows.join("/");
+
+    if let Some(color) = fields.get_mut(1) {
+        let repaired = match color.to_ascii_lowercase().as_str() {
+            "w" | "white" => "w",
+            "b" | "black" => "b",
+            _ => color.as_str()
+        }.to_string();
+        if repaired != *color {
+            fixes.push(FenFix::ActiveColor { before: color.clone() });
+            *color = repaired;
+        }
+    }
+
+    if let Some(castling) = fields.get_mut(2) {
+        // the standard letters in the order KQkq, and the files of Shredder-FEN white first
+        let letters: Vec<char> = castling.chars().filter(|&char| char != '-').collect();
+        let mut repaired: String = "KQkq".chars().filter(|char| letters.contains(char)).collect();
+        for white in [true, false] {
+            for char in letters.iter().copied().filter(|char| !"KQkq".contains(*char) && char.is_ascii_uppercase() == white) {
+                if !repaired.contains(char) {repaired.push(char);}
+            }
+        }
+        if repaired.is_empty() {repaired.push('-');}
+        if repaired != *castling {
+            fixes.push(FenFix::Castling { before: castling.clone(), after: repaired.clone() });
+            *castling = repaired;
+        }
+    }
+
+    if let (Some(turn), Some(en_passant)) = (fields.get(1).cloned(), fields.get_mut(3)) {
+        let lower = en_passant.to_ascii_lowercase();
+        let bytes = lower.as_bytes();
+        if lower != "-" && bytes.len() == 2 && (b'a'..=b'h').contains(&bytes[0]) && (b'1'..=b'8').contains(&bytes[1]) {
+            let turn = if turn == "b" {Color::Black} else {Color::White};
+            // the square behind a pawn of the other player that double stepped, and the
+            // squares it came from, which are empty now
+            let (behind, pawn, pawn_letter) = if turn == Color::White {('6', '5', 'p')} else {('3', '4', 'P')};
+            let file = bytes[0] as char;
+            let placement_row = |rank: char| -> String {rows[(b'8' - rank as u8) as usize].clone()};
+            let piece_at = |rank: char| -> Option<char> {
+                if rows.len() != 8 {return None;}
+                let mut column = 0;
+                for char in placement_row(rank).chars() {
+                    // a row past the h-file can't hold the square
+                    if column > 7 {return None;}
+                    match char.to_digit(10) {
+                        Some(digit) => column += digit as u8,
+                        None if char == '~' => (),
+                        None => {
+                            if column == file as u8 - b'a' {return Some(char);}
+                            column += 1;
+                        }
+                    }
+                }
+                return None;
+            };
+            let start = if turn == Color::White {'7'} else {'2'};
+            let double_stepped = piece_at(pawn) == Some(pawn_letter) && piece_at(behind).is_none() && piece_at(start).is_none();
+            let repaired = if double_stepped {format!("{}{}", file, behind)} else if bytes[1] as char == behind {lower.clone()} else {"-".to_string()};
+            if repaired != *en_passant {
+                fixes.push(FenFix::EnPassant { before: en_passant.clone(), after: repaired.clone() });
+                *en_passant = repaired;
+            }
+        }
+    }
+
+    // the clocks, but not the other fields, which can't be told
+    if fields.len() == 4 || fields.len() == 5 {
+        if fields.len() == 4 {
+            fields.push("0".to_string());
+            fixes.push(FenFix::MissingField(FenField::HalfmoveClock));
+        }
+        fields.push("1".to_string());
+        fixes.push(FenFix::MissingField(FenField::FullmoveNumber));
+    }
+
+    let fen = fields.join(" ");
+    return match Game::from_fen(&fen) {
+        Ok(_) => Ok(RepairedFen { fen, fixes }),
+        Err(ChessError::InvalidFen(error)) => Err(error),
+        Err(error) => Err(FenError { message: error.to_string(), field: None, position: 0 })
+    };
+}
+
+impl Game {
+    /// Creates a game from a FEN string like from_fen, after fixing the defects repair_fen
+    /// can fix. Returns an error if the string still isn't valid.
+    pub fn from_fen_lenient(fen_string: &str) -> Result<Game, ChessError> {
+        let repaired = repair_fen(fen_string).map_err(ChessError::InvalidFen)?;
+        return Game::from_fen(&repaired.fen);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{repair_fen, FenFix};
+    use crate::{FenField, Game};
+
+    //check a table of dirty FEN strings and what they are repaired to
+    #[test]
+    fn check_repair_fen() {
+        let table = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            ("  rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR   W KQkq -", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR white qkKQ - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQ- - 5", "r3k2r/8/8/8/8/8/8/R3K2R b KQ - 5 1"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w kKk - 0 1", "r3k2r/8/8/8/8/8/8/R3K2R w Kk - 0 1"),
+            ("rnbqkbnr/pppppppp/7/8/4P3/44/PPPP1PPP/RNBQKBNR b KQkq E3 0 1", "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            // the square of the pawn, the square on the rank of the other player, and no pawn
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e4 0 1", "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            ("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d3 0 3", "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3"),
+            ("4k3/8/8/8/8/8/8/4K3 w - c3 0 1", "4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+        ];
+        for (dirty, repaired) in table {
+            let result = repair_fen(dirty).unwrap();
+            assert_eq!(result.fen, repaired, "{}", dirty);
+            assert_eq!(result.fixes.is_empty(), dirty == repaired, "{}", dirty);
+            assert_eq!(Game::from_fen_lenient(dirty).unwrap().get_fen(), repaired);
+        }
+        let result = repair_fen("  rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR   W KQkq -").unwrap();
+        assert_eq!(result.fixes, vec![
+            FenFix::Whitespace,
+            FenFix::ActiveColor { before: "W".to_string() },
+            FenFix::MissingField(FenField::HalfmoveClock),
+            FenFix::MissingField(FenField::FullmoveNumber),
+        ]);
+        let result = repair_fen("rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQ- - 0 1").unwrap();
+        assert_eq!(result.fixes, vec![
+            FenFix::EmptyRow { row: 3, before: "7".to_string() },
+            FenFix::Castling { before: "KQ-".to_string(), after: "KQ".to_string() },
+        ]);
+    }
+
+    //check that a row where a square is missing among the pieces, and other errors, are refused
+    #[test]
+    fn check_repair_fen_ambiguous() {
+        // rnbqkbnr could have lost an empty square anywhere
+        let error = repair_fen("rnbqkbnr/ppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap_err();
+        assert_eq!((error.field, error.message.as_str()), (Some(FenField::Placement), "row 2 does not have 8 squares"));
+        assert!(Game::from_fen_lenient("rnbqkbnr/ppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+        // a missing active color or castling field can't be told
+        assert!(repair_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").is_err());
+        assert!(repair_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w").is_err());
+        assert!(repair_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").is_err());
+        assert!(repair_fen("").is_err());
+        // nor can a row that is far too long
+        assert!(repair_fen("4k3/8/8/999999999999999999999999999999p/8/8/8/4K3 w - e6 0 1").is_err());
+    }
+}
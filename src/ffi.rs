@@ -0,0 +1,207 @@
+//! Functions to use the library from C, C++ or Python (through ctypes or cffi), declared in
+//! include/eliassam_chess.h.
+//!
+//! Ownership: chess_game_new allocates a game that belongs to the caller until it is given
+//! back to chess_game_free, exactly once. Every other function only borrows the game for the
+//! call. Strings passed in are borrowed too and must be NUL-terminated UTF-8, and strings
+//! come out by being copied into a buffer the caller owns, never as pointers into the game.
+//!
+//! Every function returns CHESS_OK or a count on success and a negative status code on
+//! failure, checks its pointers for null, and catches panics so they never unwind into C.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::Game;
+
+pub const CHESS_OK: i32 = 0;
+/// A pointer was null.
+pub const CHESS_ERR_NULL: i32 = -1;
+/// A string wasn't UTF-8, or not a valid FEN string or square.
+pub const CHESS_ERR_INVALID: i32 = -2;
+/// The move isn't legal, or the game is over.
+pub const CHESS_ERR_ILLEGAL: i32 = -3;
+/// The buffer is too small for the answer.
+pub const CHESS_ERR_BUFFER: i32 = -4;
+/// The library panicked, which is a bug in it.
+pub const CHESS_ERR_PANIC: i32 = -5;
+
+/// Returns a new game from the start position, to be freed with chess_game_free, or null if
+/// it couldn't be made.
+#[no_mangle]
+pub extern "C" fn chess_game_new() -> *mut Game {
+    return panic::catch_unwind(|| Box::into_raw(Box::new(Game::new()))).unwrap_or(ptr::null_mut());
+}
+
+/// Frees a game made by chess_game_new. Null is ignored.
+///
+/// # Safety
+/// The game must come from chess_game_new and not have been freed before, and it can't be
+/// used after this.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_free(game: *mut Game) {
+    if game.is_null() {return;}
+    let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(game))));
+}
+
+/// Sets up the position of the FEN string. Returns CHESS_ERR_INVALID and keeps the position
+/// if it isn't valid.
+///
+/// # Safety
+/// The game must come from chess_game_new, and fen must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_load_fen(game: *mut Game, fen: *const c_char) -> i32 {
+    return guard(|| {
+        let (game, fen) = (game.as_mut().ok_or(CHESS_ERR_NULL)?, read_string(fen)?);
+        *game = Game::from_fen(fen).map_err(|_| CHESS_ERR_INVALID)?;
+        return Ok(CHESS_OK);
+    });
+}
+
+/// Moves the piece on the square `from` (like "e2") to `to`, promoting a pawn that reaches
+/// the last rank to promo ('q', 'r', 'b' or 'n'; 0 for no promotion). Returns
+/// CHESS_ERR_ILLEGAL if the move isn't legal.
+///
+/// # Safety
+/// The game must come from chess_game_new, and from and to must be null or NUL-terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn chess_make_move(game: *mut Game, from: *const c_char, to: *const c_char, promo: c_char) -> i32 {
+    return guard(|| {
+        let game = game.as_mut().ok_or(CHESS_ERR_NULL)?;
+        let (from, to) = (read_string(from)?, read_string(to)?);
+        if from.len() != 2 || to.len() != 2 {return Err(CHESS_ERR_INVALID);}
+        // make_move reads the promotion from the end of the target square
+        let target = match promo as u8 {
+            0 => to.to_string(),
+            letter @ (b'q' | b'r' | b'b' | b'n') => format!("{}{}", to, letter as char),
+            _ => return Err(CHESS_ERR_INVALID)
+        };
+        game.make_move(from, &target).ok_or(CHESS_ERR_ILLEGAL)?;
+        return Ok(CHESS_OK);
+    });
+}
+
+/// Copies the FEN string of the position into buf, NUL-terminated, and returns its length
+/// without the NUL. Returns CHESS_ERR_BUFFER and writes nothing if len is too small.
+///
+/// # Safety
+/// The game must come from chess_game_new, and buf must be null or point to len writable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chess_get_fen(game: *const Game, buf: *mut c_char, len: usize) -> i32 {
+    return guard(|| {
+        let game = game.as_ref().ok_or(CHESS_ERR_NULL)?;
+        return write_string(&game.get_fen(), buf, len);
+    });
+}
+
+/// Copies the squares the piece on the square can move to into buf, separated by spaces and
+/// NUL-terminated (like "e3 e4"), and returns how many there are. Returns CHESS_ERR_INVALID
+/// if there is no piece on the square, and CHESS_ERR_BUFFER if len is too small.
+///
+/// # Safety
+/// The game must come from chess_game_new, square must be null or a NUL-terminated string,
+/// and buf must be null or point to len writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chess_legal_moves(game: *const Game, square: *const c_char, buf: *mut c_char, len: usize) -> i32 {
+    return guard(|| {
+        let (game, square) = (game.as_ref().ok_or(CHESS_ERR_NULL)?, read_string(square)?);
+        let targets = game.get_possible_moves(square).ok_or(CHESS_ERR_INVALID)?;
+        write_string(&targets.join(" "), buf, len)?;
+        return Ok(targets.len() as i32);
+    });
+}
+
+/// Runs f, turning its error and any panic into a status code.
+fn guard(f: impl FnOnce() -> Result<i32, i32>) -> i32 {
+    return match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(result)) | Ok(Err(result)) => result,
+        Err(_) => CHESS_ERR_PANIC
+    };
+}
+
+/// # Safety
+/// The pointer must be null or point to a NUL-terminated string.
+unsafe fn read_string<'a>(string: *const c_char) -> Result<&'a str, i32> {
+    if string.is_null() {return Err(CHESS_ERR_NULL);}
+    return CStr::from_ptr(string).to_str().map_err(|_| CHESS_ERR_INVALID);
+}
+
+/// Copies the text and a NUL into buf and returns the length of the text.
+///
+/// # Safety
+/// buf must be null or point to len writable bytes.
+unsafe fn write_string(text: &str, buf: *mut c_char, len: usize) -> Result<i32, i32> {
+    if buf.is_null() {return Err(CHESS_ERR_NULL);}
+    if text.len() >= len {return Err(CHESS_ERR_BUFFER);}
+    ptr::copy_nonoverlapping(text.as_ptr(), buf as *mut u8, text.len());
+    *buf.add(text.len()) = 0;
+    return Ok(text.len() as i32);
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_char, CStr, CString};
+    use std::ptr;
+
+    use super::*;
+
+    fn text(buf: &[c_char]) -> String {
+        return unsafe {CStr::from_ptr(buf.as_ptr())}.to_str().unwrap().to_string();
+    }
+
+    //check a game played through the C functions
+    #[test]
+    fn check_ffi_game() {
+        let square = |name: &str| CString::new(name).unwrap();
+        let mut buf: [c_char; 128] = [0; 128];
+        unsafe {
+            let game1 = chess_game_new();
+            assert!(!game1.is_null());
+            assert_eq!(chess_legal_moves(game1, square("e2").as_ptr(), buf.as_mut_ptr(), buf.len()), 2);
+            assert_eq!(text(&buf), "e3 e4");
+            assert_eq!(chess_make_move(game1, square("e2").as_ptr(), square("e4").as_ptr(), 0), CHESS_OK);
+            assert_eq!(chess_make_move(game1, square("e2").as_ptr(), square("e4").as_ptr(), 0), CHESS_ERR_ILLEGAL);
+            let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+            assert_eq!(chess_get_fen(game1, buf.as_mut_ptr(), buf.len()), fen.len() as i32);
+            assert_eq!(text(&buf), fen);
+            // the FEN and its NUL don't fit
+            assert_eq!(chess_get_fen(game1, buf.as_mut_ptr(), fen.len()), CHESS_ERR_BUFFER);
+            // a promotion
+            let promotion = CString::new("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+            assert_eq!(chess_load_fen(game1, promotion.as_ptr()), CHESS_OK);
+            assert_eq!(chess_make_move(game1, square("a7").as_ptr(), square("a8").as_ptr(), 'x' as c_char), CHESS_ERR_INVALID);
+            assert_eq!(chess_make_move(game1, square("a7").as_ptr(), square("a8").as_ptr(), 'n' as c_char), CHESS_OK);
+            chess_get_fen(game1, buf.as_mut_ptr(), buf.len());
+            assert_eq!(text(&buf), "N3k3/8/8/8/8/8/8/4K3 b - - 0 1");
+            chess_game_free(game1);
+        }
+    }
+
+    //check that null pointers and bad strings give status codes
+    #[test]
+    fn check_ffi_errors() {
+        let mut buf: [c_char; 8] = [0; 8];
+        let fen = CString::new("not a fen").unwrap();
+        unsafe {
+            assert_eq!(chess_load_fen(ptr::null_mut(), fen.as_ptr()), CHESS_ERR_NULL);
+            assert_eq!(chess_get_fen(ptr::null(), buf.as_mut_ptr(), buf.len()), CHESS_ERR_NULL);
+            chess_game_free(ptr::null_mut());
+            let game1 = chess_game_new();
+            assert_eq!(chess_load_fen(game1, fen.as_ptr()), CHESS_ERR_INVALID);
+            assert_eq!(chess_load_fen(game1, ptr::null()), CHESS_ERR_NULL);
+            assert_eq!(chess_make_move(game1, ptr::null(), ptr::null(), 0), CHESS_ERR_NULL);
+            let empty = CString::new("e4").unwrap();
+            assert_eq!(chess_legal_moves(game1, empty.as_ptr(), buf.as_mut_ptr(), buf.len()), CHESS_ERR_INVALID);
+            assert_eq!(chess_get_fen(game1, buf.as_mut_ptr(), buf.len()), CHESS_ERR_BUFFER);
+            assert_eq!(chess_get_fen(game1, ptr::null_mut(), 100), CHESS_ERR_NULL);
+            chess_game_free(game1);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{parse_square, Color, Game, PieceRole, Square};
+
+/// What fog_fen writes for a square the player can't see.
+const FOG: char = '?';
+
+impl Game {
+    /// The squares the player can see in dark chess (fog of war): the squares of their own
+    /// pieces and every square one of them could move to, taking the opponent's pieces that
+    /// stand there. A pawn also sees the square in front of it when a piece blocks it, but its
+    /// diagonals only when it can take there, en passant included. Check doesn't matter, so a
+    /// pinned piece sees as far as it could move without the pin. Castling adds nothing, since
+    /// the king sees the squares next to it.
+    pub fn visible_squares(&self, color: Color) -> HashSet<Square> {
+        let mut visible = HashSet::new();
+        for index in 0..64 {
+            let Some(piece) = self.position.piece_at(index) else {continue};
+            if piece.color != color {continue;}
+            visible.insert(Square(index as u8));
+            for to in piece.available_moves(&self.position, index, true) {
+                visible.insert(Square(to as u8));
+            }
+            // the piece blocking a pawn is seen, though the pawn can't move there
+            if piece.role == PieceRole::Pawn {
+                let ahead = if color == Color::White {index as isize - 8} else {index as isize + 8};
+                if (0..64).contains(&ahead) {visible.insert(Square(ahead as u8));}
+            }
+        }
+        return visible;
+    }
+
+    /// Writes the position as the player sees it in dark chess, as a FEN string with a ? for
+    /// every square they can't see (see visible_squares), e.g. "rnbqkbnr/pppppppp/8/8/????????/..."
+    /// for Black after 1.e4. Only the player's own castling rights are
+    /// written, and the en passant square only if they can see it. The string is for showing
+    /// the position to the player and can't be loaded with from_fen.
+    pub fn fog_fen(&self, color: Color) -> String {
+        let visible = self.visible_squares(color);
+        let mut placement = String::new();
+        for row in 0..8 {
+            let mut empty_squares = 0;
+            for column in 0..8 {
+                let index = row * 8 + column;
+                let seen = visible.contains(&Square(index as u8));
+                if seen && self.position.board[index] == 0 {
+                    empty_squares += 1;
+                    continue;
+                }
+                if empty_squares > 0 {placement.push_str(&empty_squares.to_string()); empty_squares = 0;}
+                placement.push(if seen {self.position.piece_at(index).unwrap().letter()} else {FOG});
+            }
+            if empty_squares > 0 {placement.push_str(&empty_squares.to_string());}
+            if row < 7 {placement.push('/');}
+        }
+        // the turn and the clocks are known to both players, the rest only in part
+        let fen = self.get_fen();
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let own_rights: String = fields[2].chars().filter(|letter| letter.is_ascii_uppercase() == (color == Color::White)).collect();
+        let castling = if own_rights.is_empty() {"-"} else {&own_rights};
+        let en_passant = match parse_square(fields[3]) {
+            Some(index) if visible.contains(&Square(index as u8)) => fields[3],
+            _ => "-"
+        };
+        return format!("{} {} {} {} {} {}", placement, fields[1], castling, en_passant, fields[4], fields[5]);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, Square};
+
+    // the squares of the ranks, and the other squares
+    fn squares(ranks: &[char], others: &[&str]) -> HashSet<Square> {
+        let mut squares: HashSet<Square> = others.iter().map(|square| Square::from_algebraic(square).unwrap()).collect();
+        for &rank in ranks {
+            for file in 'a'..='h' {
+                squares.insert(Square::from_algebraic(&format!("{}{}", file, rank)).unwrap());
+            }
+        }
+        return squares;
+    }
+
+    //check what both players see in the start position and after 1.e4
+    #[test]
+    fn check_visible_squares() {
+        let mut game1 = Game::new();
+        // the pieces and the squares the pawns and knights can move to
+        assert_eq!(game1.visible_squares(Color::White), squares(&['1', '2', '3', '4'], &[]));
+        assert_eq!(game1.visible_squares(Color::Black), squares(&['5', '6', '7', '8'], &[]));
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        // e2 is empty and seen by the knight, bishop, queen and king, which see further too
+        assert_eq!(game1.visible_squares(Color::White), squares(&['1', '2', '3', '4'], &["e5", "b5", "a6", "h5"]));
+        assert_eq!(game1.visible_squares(Color::Black), squares(&['5', '6', '7', '8'], &[]));
+        assert_eq!(game1.fog_fen(Color::Black), "rnbqkbnr/pppppppp/8/8/????????/????????/????????/???????? b kq - 0 1");
+        assert_eq!(game1.fog_fen(Color::White), "????????/????????/1???????/?1??1??1/4P3/8/PPPP1PPP/RNBQKBNR b KQ e3 0 1");
+    }
+
+    //check that a blocked pawn sees the piece in front of it, but not past it or to its empty diagonals
+    #[test]
+    fn check_visible_blocked_pawn() {
+        let mut game1 = Game::from_fen("4k3/8/8/8/4p3/8/4P3/4K3 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("e2e3")).unwrap();
+        assert_eq!(game1.visible_squares(Color::Black), squares(&[], &["e8", "d8", "f8", "d7", "e7", "f7", "e4", "e3"]));
+        assert_eq!(game1.fog_fen(Color::Black), "???1k1??/???3??/????????/????????/????p???/????P???/????????/???????? b - - 0 1");
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{self, Write};
+
+use crate::engine::find_best_move;
+use crate::{Color, Game, GameOutcome};
+
+/// Options for Game::export_jsonl.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonlOptions {
+    /// Adds the score of a search this many plies deep to every move, in centipawns from the
+    /// point of view of White. None leaves the score out.
+    pub eval_depth: Option<u32>,
+}
+
+impl Game {
+    /// Writes the game in JSON Lines, e.g. for a data pipeline: first a header line with the
+    /// result ("1-0", "0-1", "1/2-1/2" or "*" like to_pgn), the FEN string of the position
+    /// the moves start from and the number of moves, and then one line for every move made
+    /// since then, like {"ply":1,"san":"e4","uci":"e2e4","fen":"..."}. The ply counts the
+    /// moves from 1 and the FEN string is the position after the move. With an eval depth the
+    /// line gets the score of that position as "eval", or null if the game is over there.
+    /// Every line is written as soon as it is made, so the output is never held in memory.
+    pub fn export_jsonl(&self, mut writer: impl Write, opts: JsonlOptions) -> io::Result<()> {
+        let result = match self.outcome() {
+            Some(GameOutcome::Win(Color::White)) => "1-0",
+            Some(GameOutcome::Win(Color::Black)) => "0-1",
+            Some(GameOutcome::Draw) => "1/2-1/2",
+            None => "*"
+        };
+        // go back to the start of the game and play the moves again
+        let mut replay = self.clone();
+        while replay.undo().is_some() {}
+        writeln!(writer, "{{\"result\":\"{}\",\"start_fen\":\"{}\",\"plies\":{}}}", result, replay.get_fen(), self.history.len())?;
+        for (ply, entry) in self.history.iter().enumerate() {
+            let mv = entry.result.mv;
+            let san = replay.position.san(mv);
+            replay.play(mv);
+            write!(writer, "{{\"ply\":{},\"san\":\"{}\",\"uci\":\"{}\",\"fen\":\"{}\"", ply + 1, san, mv, replay.get_fen())?;
+            if let Some(depth) = opts.eval_depth {
+                let eval = find_best_move(&replay, depth.max(1)).map(|(_, score)| if replay.position.turn == Color::White {score} else {-score});
+                match eval {
+                    Some(eval) => write!(writer, ",\"eval\":{}", eval)?,
+                    None => write!(writer, ",\"eval\":null")?
+                }
+            }
+            writeln!(writer, "}}")?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::JsonlOptions;
+    use crate::tests::coordinate_move;
+    use crate::Game;
+    use serde_json::Value;
+
+    fn export(game: &Game, opts: JsonlOptions) -> Vec<Value> {
+        let mut output = Vec::new();
+        game.export_jsonl(&mut output, opts).unwrap();
+        return String::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    }
+
+    //check the lines of a short game and that every FEN string is the position after the move
+    #[test]
+    fn check_export_jsonl() {
+        let mut game1 = Game::new();
+        let moves = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5c6", "d7c6", "e1g1"];
+        for mv in moves {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let lines = export(&game1, JsonlOptions::default());
+        assert_eq!(lines.len(), moves.len() + 1);
+        assert_eq!(lines[0]["result"], "*");
+        assert_eq!(lines[0]["start_fen"], Game::new().get_fen());
+        assert_eq!(lines[0]["plies"], moves.len());
+        assert_eq!(lines[3]["ply"], 3);
+        assert_eq!(lines[3]["san"], "Nf3");
+        assert_eq!(lines[3]["uci"], "g1f3");
+        assert_eq!(lines[7]["san"], "Bxc6");
+        assert_eq!(lines[9]["san"], "O-O");
+        assert!(lines[1].get("eval").is_none());
+        // playing the moves again from the start gives every FEN string
+        let mut replay = Game::from_fen(lines[0]["start_fen"].as_str().unwrap()).unwrap();
+        for line in &lines[1..] {
+            replay.play_move(&coordinate_move(line["uci"].as_str().unwrap())).unwrap();
+            assert_eq!(replay.get_fen(), line["fen"].as_str().unwrap());
+        }
+        assert_eq!(replay.get_fen(), game1.get_fen());
+    }
+
+    //check the evaluation and the result of a finished game from a FEN string
+    #[test]
+    fn check_export_jsonl_eval() {
+        let mut game1 = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("a1a8")).unwrap();
+        let lines = export(&game1, JsonlOptions { eval_depth: Some(2) });
+        assert_eq!(lines[0]["result"], "1-0");
+        assert_eq!(lines[0]["start_fen"], "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        assert_eq!(lines[1]["san"], "Ra8#");
+        assert!(lines[1]["eval"].is_null());
+        // a rook up is good for White, whoever is to move
+        let mut game1 = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("g1f1")).unwrap();
+        game1.play_move(&coordinate_move("g8f8")).unwrap();
+        let lines = export(&game1, JsonlOptions { eval_depth: Some(2) });
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1]["eval"].as_i64().unwrap() > 300);
+        assert!(lines[2]["eval"].as_i64().unwrap() > 300);
+    }
+}
@@ -0,0 +1,207 @@
+use core::fmt;
+
+use crate::{Game, GameState, Move, Piece, PieceRole, Position, Variant};
+
+/// Why a move is refused, see Game::illegal_move_reason.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IllegalMoveReason {
+    /// The game is over.
+    GameOver,
+    /// There is no piece on the square the move starts from.
+    NotYourPiece,
+    /// The piece belongs to the player who isn't to move.
+    WrongTurn,
+    /// The piece never moves like that, e.g. a knight along a file or a pawn diagonally to an
+    /// empty square.
+    PieceCannotMoveThatWay,
+    /// A piece stands in the way, or in front of a pawn.
+    PathBlocked,
+    DestinationOccupiedByOwnPiece,
+    /// The move would put the own king in check, e.g. a pinned piece that moves or the king
+    /// castling onto an attacked square.
+    WouldLeaveKingInCheck,
+    /// The king would castle over a square that is attacked.
+    KingWouldPassThroughAttackedSquare,
+    /// The king or the rook of the castling has moved.
+    CastlingRightsLost,
+    /// The king is in check and the move doesn't get it out of check, castling included.
+    MustRespondToCheck,
+    /// A pawn that reaches the last rank must say what it promotes to.
+    PromotionRequired,
+}
+
+impl fmt::Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            IllegalMoveReason::GameOver => "the game is over",
+            IllegalMoveReason::NotYourPiece => "there is no piece on that square",
+            IllegalMoveReason::WrongTurn => "it isn't the turn of that piece",
+            IllegalMoveReason::PieceCannotMoveThatWay => "the piece can't move that way",
+            IllegalMoveReason::PathBlocked => "a piece is in the way",
+            IllegalMoveReason::DestinationOccupiedByOwnPiece => "an own piece stands on that square",
+            IllegalMoveReason::WouldLeaveKingInCheck => "the king would be in check",
+            IllegalMoveReason::KingWouldPassThroughAttackedSquare => "the king can't castle through check",
+            IllegalMoveReason::CastlingRightsLost => "the king or the rook has already moved",
+            IllegalMoveReason::MustRespondToCheck => "the king is in check",
+            IllegalMoveReason::PromotionRequired => "a pawn on the last rank needs a promotion to q, r, b or n"
+        };
+        return write!(f, "{}", message);
+    }
+}
+
+/// Whether the piece could move from the index to the other on an empty board, castling aside.
+pub(crate) fn reaches(piece: Piece, variant: Variant, from: usize, to: usize) -> bool {
+    let (rows, columns) = ((to / 8) as i8 - (from / 8) as i8, (to % 8) as i8 - (from % 8) as i8);
+    return match piece.role {
+        PieceRole::Knight => rows.abs() * columns.abs() == 2,
+        PieceRole::King => rows.abs().max(columns.abs()) == 1,
+        PieceRole::Rook => (rows == 0) != (columns == 0),
+        PieceRole::Bishop => rows != 0 && rows.abs() == columns.abs(),
+        PieceRole::Queen => (rows == 0) != (columns == 0) || (rows != 0 && rows.abs() == columns.abs()),
+        PieceRole::Pawn => {
+            // White moves up the board, to lower indexes
+            let forward = if piece.color == crate::Color::White {-rows} else {rows};
+            let double_step = !piece.has_moved || variant == Variant::Horde;
+            (forward == 1 && columns.abs() <= 1) || (forward == 2 && columns == 0 && double_step)
+        }
+    };
+}
+
+impl Position {
+    /// Tells why the move isn't legal for the player to move, trying the rules in order: whose
+    /// piece it is, castling, the destination, how the piece moves, what is in the way, the
+    /// promotion and last the own king. Returns None if the move is legal.
+    pub(crate) fn illegal_move_reason(&self, mv: Move) -> Option<IllegalMoveReason> {
+        if self.legal_moves().contains(&mv) {return None;}
+        let (from, to) = (mv.from.index(), mv.to.index());
+        let Some(piece) = self.piece_at(from) else {return Some(IllegalMoveReason::NotYourPiece)};
+        if mv.is_drop() {return Some(IllegalMoveReason::PieceCannotMoveThatWay);}
+        if piece.color != self.turn {return Some(IllegalMoveReason::WrongTurn);}
+        let in_check = self.in_check(self.turn);
+        if piece.role == PieceRole::King && self.variant != Variant::Antichess {
+            if let Some((rook_from, rook_to)) = self.castling_rook(mv) {return Some(self.castling_reason(mv, piece, rook_from, rook_to));}
+        }
+        if self.piece_at(to).is_some_and(|other| other.color == piece.color) {return Some(IllegalMoveReason::DestinationOccupiedByOwnPiece);}
+        if !reaches(piece, self.variant, from, to) {return Some(IllegalMoveReason::PieceCannotMoveThatWay);}
+        if !piece.available_moves(self, from, true).contains(&to) {
+            // a pawn only moves diagonally to take, and every other piece that can't get there
+            // is blocked
+            let diagonal = piece.role == PieceRole::Pawn && from % 8 != to % 8;
+            return Some(if diagonal {IllegalMoveReason::PieceCannotMoveThatWay} else {IllegalMoveReason::PathBlocked});
+        }
+        let last_rank = piece.role == PieceRole::Pawn && (to / 8 == 0 || to / 8 == 7);
+        if last_rank && !self.promotions().contains(&mv.promotion) {return Some(IllegalMoveReason::PromotionRequired);}
+        if !last_rank && mv.promotion.is_some() {return Some(IllegalMoveReason::PieceCannotMoveThatWay);}
+        let mut position_copy = *self;
+        position_copy.apply_move(mv);
+        if position_copy.in_check(piece.color) {
+            return Some(if in_check {IllegalMoveReason::MustRespondToCheck} else {IllegalMoveReason::WouldLeaveKingInCheck});
+        }
+        // the other rules of the variants, e.g. that taking is compulsory in Antichess
+        return Some(IllegalMoveReason::PieceCannotMoveThatWay);
+    }
+
+    /// Tells why castling with the rook on rook_from isn't legal, see illegal_move_reason.
+    fn castling_reason(&self, mv: Move, king: Piece, rook_from: usize, rook_to: usize) -> IllegalMoveReason {
+        let rook_in_place = self.piece_at(rook_from).is_some_and(|rook| rook.role == PieceRole::Rook && rook.color == king.color && !rook.has_moved);
+        if king.has_moved || !rook_in_place {return IllegalMoveReason::CastlingRightsLost;}
+        if self.in_check(king.color) {return IllegalMoveReason::MustRespondToCheck;}
+        let (from, target) = (mv.from.index(), self.move_target(mv));
+        // every square the king or the rook passes or ends on must be empty but for the two of them
+        let (low, high) = (from.min(target).min(rook_from).min(rook_to), from.max(target).max(rook_from).max(rook_to));
+        if (low..=high).any(|index| index != from && index != rook_from && self.piece_at(index).is_some()) {return IllegalMoveReason::PathBlocked;}
+        let mut passed = if target > from {from + 1..target} else {target + 1..from};
+        if passed.any(|index| self.is_attacked(index, king.color.opposite())) {return IllegalMoveReason::KingWouldPassThroughAttackedSquare;}
+        return IllegalMoveReason::WouldLeaveKingInCheck;
+    }
+}
+
+impl Game {
+    /// Tells why the move isn't legal, e.g. to show the player who tried it, or returns None if
+    /// it is legal. The rules are tried in order, so a move that breaks several gets the
+    /// first: that the game is over, whose piece it is, castling rights, check and the
+    /// squares of castling, the destination, how the piece moves, what is in the way, the
+    /// promotion and last whether the own king would be in check.
+    pub fn illegal_move_reason(&self, mv: &Move) -> Option<IllegalMoveReason> {
+        if self.ended.is_some() || self.state == GameState::Checkmate || self.state == GameState::Stalemate {return Some(IllegalMoveReason::GameOver);}
+        if self.is_legal_move(mv) {return None;}
+        return self.position.illegal_move_reason(*mv);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::IllegalMoveReason;
+    use crate::tests::coordinate_move;
+    use crate::{ChessError, Game, Variant};
+
+    fn reason(fen: &str, mv: &str) -> Option<IllegalMoveReason> {
+        return Game::from_fen(fen).unwrap().illegal_move_reason(&coordinate_move(mv));
+    }
+
+    //check every reason with a position made for it
+    #[test]
+    fn check_illegal_move_reasons() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(reason(start, "e2e4"), None);
+        assert_eq!(reason(start, "e4e5"), Some(IllegalMoveReason::NotYourPiece));
+        assert_eq!(reason(start, "e7e5"), Some(IllegalMoveReason::WrongTurn));
+        assert_eq!(reason(start, "g1g3"), Some(IllegalMoveReason::PieceCannotMoveThatWay));
+        assert_eq!(reason(start, "e2e5"), Some(IllegalMoveReason::PieceCannotMoveThatWay));
+        assert_eq!(reason(start, "e2d3"), Some(IllegalMoveReason::PieceCannotMoveThatWay));
+        assert_eq!(reason(start, "f1c4"), Some(IllegalMoveReason::PathBlocked));
+        assert_eq!(reason("rnbqkbnr/pppp1ppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4"), None);
+        assert_eq!(reason("4k3/8/8/8/8/4p3/4P3/4K3 w - - 0 1", "e2e4"), Some(IllegalMoveReason::PathBlocked));
+        assert_eq!(reason(start, "d1d2"), Some(IllegalMoveReason::DestinationOccupiedByOwnPiece));
+        // a pinned knight, and a king that steps into check
+        assert_eq!(reason("4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1", "e2c3"), Some(IllegalMoveReason::WouldLeaveKingInCheck));
+        assert_eq!(reason("3r2k1/8/8/8/8/8/8/4K3 w - - 0 1", "e1d1"), Some(IllegalMoveReason::WouldLeaveKingInCheck));
+        // castling through and into an attacked square, and without the rights
+        assert_eq!(reason("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"), None);
+        assert_eq!(reason("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"), Some(IllegalMoveReason::KingWouldPassThroughAttackedSquare));
+        assert_eq!(reason("4k1r1/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"), Some(IllegalMoveReason::WouldLeaveKingInCheck));
+        assert_eq!(reason("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1", "e1g1"), Some(IllegalMoveReason::CastlingRightsLost));
+        assert_eq!(reason("4k3/8/8/8/8/8/8/R3KN1R w KQ - 0 1", "e1g1"), Some(IllegalMoveReason::PathBlocked));
+        // a king two files over off its back rank isn't castling
+        assert_eq!(reason("4k3/8/8/8/4K3/8/8/8 w - - 0 1", "e4g4"), Some(IllegalMoveReason::PieceCannotMoveThatWay));
+        // nor is a king onto an own piece other than an unmoved rook in Chess960
+        let reason_960 = |fen: &str, mv: &str| Game::from_fen_variant(fen, Variant::Chess960).unwrap().illegal_move_reason(&coordinate_move(mv));
+        assert_eq!(reason_960("4k3/8/8/8/8/8/4N3/4K3 w - - 0 1", "e1e2"), Some(IllegalMoveReason::DestinationOccupiedByOwnPiece));
+        assert_eq!(reason_960("4k3/8/8/8/8/8/8/3NK3 w - - 0 1", "e1d1"), Some(IllegalMoveReason::DestinationOccupiedByOwnPiece));
+        // in check, a move that doesn't help and castling out of check
+        assert_eq!(reason("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1", "a1a2"), Some(IllegalMoveReason::MustRespondToCheck));
+        assert_eq!(reason("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1", "e1g1"), Some(IllegalMoveReason::MustRespondToCheck));
+        assert_eq!(reason("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1", "e1e2"), None);
+        // a pawn on the last rank must promote
+        assert_eq!(reason("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8"), Some(IllegalMoveReason::PromotionRequired));
+        assert_eq!(reason("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q"), None);
+        // a game that is over
+        assert_eq!(reason("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), None);
+        assert_eq!(reason("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", "g8h8"), Some(IllegalMoveReason::GameOver));
+    }
+
+    //check that make_move refuses moves with the reason
+    #[test]
+    fn check_illegal_move_errors() {
+        let mut game1 = Game::new();
+        game1.start_event_log(Default::default());
+        assert_eq!(game1.make_move("e2", "e5"), None);
+        assert_eq!(game1.make_move("e7", "e5"), None);
+        assert_eq!(game1.make_move("i9", "e5"), None);
+        let reasons: Vec<ChessError> = game1.event_log().iter().filter_map(|event| match &event.entry {
+            crate::LogEntry::Rejected { reason, .. } => Some(reason.clone()),
+            _ => None
+        }).collect();
+        assert_eq!(reasons, [
+            ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay),
+            ChessError::IllegalMove(IllegalMoveReason::WrongTurn),
+            ChessError::InvalidSquare("i9".to_string()),
+        ]);
+        assert_eq!(reasons[1].to_string(), "illegal move: it isn't the turn of that piece");
+    }
+}
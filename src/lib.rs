@@ -1,4 +1,105 @@
-use std::fmt;
+// explicit returns and single-arm matches are the house style
+#![allow(clippy::needless_return, clippy::single_match, clippy::collapsible_match)]
+// without the std feature only alloc is needed, see Cargo.toml
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use event_log::EventLog;
+use events::Subscribers;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+mod annotated;
+mod char_board;
+mod chess960;
+mod control;
+mod crazyhouse;
+mod diff;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "std")]
+pub mod engine;
+#[cfg(feature = "std")]
+mod event_log;
+mod events;
+#[cfg(feature = "std")]
+pub mod explorer;
+mod fen_repair;
+#[cfg(feature = "std")]
+mod fog;
+#[cfg(feature = "std")]
+mod jsonl;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod legality;
+#[cfg(feature = "std")]
+pub mod manager;
+mod pgn;
+mod playout;
+mod premove;
+pub mod puzzle;
+mod random_position;
+mod render;
+mod rng;
+mod san;
+#[cfg(feature = "std")]
+mod session;
+mod material;
+#[cfg(feature = "std")]
+mod metrics;
+mod odds;
+mod snapshot;
+mod spoken;
+mod svg;
+mod takeback;
+#[cfg(feature = "std")]
+mod tactics;
+mod validate;
+#[cfg(feature = "std")]
+pub mod training;
+mod transform;
+mod variant;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use annotated::AnnotatedMove;
+#[cfg(feature = "std")]
+pub use event_log::{EventLogOptions, LogEntry, LoggedEvent};
+#[cfg(feature = "std")]
+pub use jsonl::JsonlOptions;
+pub use control::{empty_board_attacks, SquareControl};
+pub use diff::{PositionDiff, SquareChange};
+pub use events::{EndReason, GameEvent, Subscriber};
+pub use fen_repair::{repair_fen, FenFix, RepairedFen};
+pub use legality::IllegalMoveReason;
+pub use takeback::TakebackError;
+pub use material::MaterialKey;
+#[cfg(feature = "std")]
+pub use metrics::{DevelopmentReport, HeatmapOptions};
+pub use odds::Odds;
+pub use pgn::{MoveComment, PgnError, PgnEval};
+pub use playout::{PlayoutOutcome, PlayoutResult};
+pub use random_position::RandomPositionOptions;
+pub use san::{LanCastling, PieceLetters, SanStyle};
+#[cfg(feature = "std")]
+pub use session::RestoreError;
+pub use render::Highlight;
+pub use rng::XorShift;
+pub use snapshot::BoardSnapshot;
+pub use spoken::Verbosity;
+pub use svg::{fen_to_svg, SvgOptions};
+#[cfg(feature = "std")]
+pub use tactics::{CctMove, CctReport, ForkReport, LineReport};
+pub use variant::Variant;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 
@@ -19,255 +120,1108 @@ pub enum PieceRole {
     King
 }
 
+impl PieceRole {
+    /// Returns the standard value of the role in centipawns. The king can never be taken, so it is worth 0.
+    pub fn value(&self) -> i32 {
+        match self {
+            PieceRole::Pawn => 100,
+            PieceRole::Knight => 320,
+            PieceRole::Bishop => 330,
+            PieceRole::Rook => 500,
+            PieceRole::Queen => 900,
+            PieceRole::King => 0
+        }
+    }
+
+    /// The name of the role in lowercase English, e.g. "knight".
+    pub(crate) fn name(self) -> &'static str {
+        return match self {
+            PieceRole::Pawn => "pawn",
+            PieceRole::Rook => "rook",
+            PieceRole::Knight => "knight",
+            PieceRole::Bishop => "bishop",
+            PieceRole::Queen => "queen",
+            PieceRole::King => "king"
+        };
+    }
+}
+
+/// The stages of the staged move generation, see Game::generate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MoveStage {
+    Captures,
+    Checks,
+    Quiets
+}
+
+/// The result of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOutcome {
+    Win(Color),
+    Draw,
+}
+
+/// Errors returned by the fallible parts of the library.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChessError {
+    /// The FEN string could not be parsed, with what was wrong and where.
+    InvalidFen(FenError),
+    /// The PGN text could not be read, with what was wrong and where.
+    InvalidPgn(PgnError),
+    /// The position breaks the rules of chess, with a description of what is wrong.
+    InvalidPosition(String),
+    /// A move was refused, with why.
+    IllegalMove(IllegalMoveReason),
+    /// A string that should name a square, like "e4", doesn't.
+    InvalidSquare(String),
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChessError::InvalidFen(error) => write!(f, "invalid FEN: {}", error),
+            ChessError::InvalidPgn(error) => write!(f, "invalid PGN: {}", error),
+            ChessError::InvalidPosition(message) => write!(f, "invalid position: {}", message),
+            ChessError::IllegalMove(reason) => write!(f, "illegal move: {}", reason),
+            ChessError::InvalidSquare(square) => write!(f, "invalid square: '{}'", square)
+        }
+    }
+}
+
+impl core::error::Error for ChessError {}
+
+/// The six fields of a FEN string, see FenError.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FenField {
+    Placement,
+    ActiveColor,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            FenField::Placement => "placement",
+            FenField::ActiveColor => "active color",
+            FenField::Castling => "castling",
+            FenField::EnPassant => "en passant",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number"
+        };
+        return write!(f, "{}", name);
+    }
+}
+
+/// What is wrong with a FEN string and where, e.g. "invalid piece character 'x' at position 12
+/// in placement field".
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FenError {
+    /// What is wrong, e.g. "invalid piece character 'x'".
+    pub message: String,
+    /// The field the problem is in, or None if the string doesn't have six fields.
+    pub field: Option<FenField>,
+    /// The offset of the problem in characters from the start of the string, 0 for the first.
+    pub position: usize,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self.field {
+            Some(field) => write!(f, "{} at position {} in {} field", self.message, self.position, field),
+            None => write!(f, "{} at position {}", self.message, self.position)
+        };
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Copy, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     White,
     Black
 }
 
+impl Color {
+    /// Returns the other color.
+    pub fn opposite(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White
+        }
+    }
+}
+
+// direction vectors as (row, column) offsets, clockwise starting from up
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(-1, 1), (1, 1), (1, -1), (-1, -1)];
+const QUEEN_DIRECTIONS: [(i8, i8); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [(-2, 1), (-1, 2), (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1)];
+
+/// Returns true if the row and column are inside the board.
+fn move_okay(row: i8, column: i8) -> bool {
+    return (0..=7).contains(&row) && (0..=7).contains(&column);
+}
+
+/// Converts a square in algebraic notation (e.g. "e4") to a board index. Any characters after
+/// the first two are ignored, so that promotion suffixes like "d8Q" can be passed directly.
+fn parse_square(square: &str) -> Option<usize> {
+    let mut chars = square.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?;
+    if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {return None;}
+    return Some((56 - rank as usize) * 8 + (file as usize - 97));
+}
+
+/// Converts a board index to a square in algebraic notation.
+fn square_name(index: usize) -> String {
+    return format!("{}{}", (97 + index % 8) as u8 as char, (56 - index / 8) as u8 as char);
+}
+
+/// A square on the board. Squares are indexed the same way as the board, i.e. a8 is 0 and h1 is 63.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    /// Converts a square in algebraic notation, e.g. "e4", to a Square.
+    pub fn from_algebraic(square: &str) -> Option<Square> {
+        if square.len() != 2 {return None;}
+        return Some(Square(parse_square(square)? as u8));
+    }
+
+    /// Converts a board index (0-63) to a Square.
+    pub fn from_index(index: usize) -> Option<Square> {
+        if index > 63 {return None;}
+        return Some(Square(index as u8));
+    }
+
+    /// Get the board index of the square.
+    pub fn index(self) -> usize {
+        return self.0 as usize;
+    }
+
+    /// Get the color of the square, White for the light squares and Black for the dark ones.
+    /// a1 is dark and h1 is light.
+    pub fn color(self) -> Color {
+        // the row and column of the light squares add up to an even number, as for a8
+        return if (self.0 / 8 + self.0 % 8).is_multiple_of(2) {Color::White} else {Color::Black};
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", square_name(self.index()))
+    }
+}
+
+/// A move from one square to another, with the role a pawn promotes to if any. Castling is
+/// written as the king moving two squares, or in Chess960 as the king taking its own rook. A
+/// drop in Crazyhouse is written as a move from and to the square the piece is dropped on, with
+/// its role in promotion (see Move::new_drop).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceRole>,
+}
+
+impl Move {
+    fn new(from: usize, to: usize, promotion: Option<PieceRole>) -> Move {
+        Move { from: Square(from as u8), to: Square(to as u8), promotion }
+    }
+
+    /// A drop of a piece of the role from the pocket onto the square, in Crazyhouse.
+    pub fn new_drop(role: PieceRole, square: Square) -> Move {
+        return Move { from: square, to: square, promotion: Some(role) };
+    }
+
+    /// Returns true if the move drops a piece from the pocket, see Move::new_drop.
+    pub fn is_drop(&self) -> bool {
+        return self.from == self.to;
+    }
+}
+
+impl fmt::Display for Move {
+    /// Writes the move in coordinate notation, e.g. "e2e4" or "e7e8q", and a drop as e.g. "N@f3".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_drop() {
+            let letter = match self.promotion {
+                Some(PieceRole::Knight) => "N",
+                Some(PieceRole::Bishop) => "B",
+                Some(PieceRole::Rook) => "R",
+                Some(PieceRole::Queen) => "Q",
+                _ => "P"
+            };
+            return write!(f, "{}@{}", letter, self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        match self.promotion {
+            Some(PieceRole::Queen) => write!(f, "q"),
+            Some(PieceRole::Rook) => write!(f, "r"),
+            Some(PieceRole::Knight) => write!(f, "n"),
+            Some(PieceRole::Bishop) => write!(f, "b"),
+            Some(PieceRole::King) => write!(f, "k"),
+            _ => Ok(())
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Position {
+    /* board, clocks, potential en passant square, turn, variant and the pockets of Crazyhouse
+    board is 64 pieces packed into bytes (see Piece::to_u8), where 0 is an empty square. A square is indexed by row*8 + column,
+    where row 0 is rank 8 and column 0 is the a-file, i.e the bottom right square is 63. ep_square is the index of the en passant square.
+    pawn_key is the Zobrist key of the pawns only (see PAWN_KEYS), kept up to date by set_piece.
+    pockets counts the pieces of every color (White first) that can be dropped, in the order of crazyhouse::POCKET_ROLES,
+    and promoted has a bit set for every index with a promoted piece, which goes back into a pocket as a pawn.
+    */
+    board: [u8; 64],
+    halfmove: u32,
+    fullmove: u32,
+    pawn_key: u64,
+    ep_square: Option<u8>,
+    turn: Color,
+    variant: Variant,
+    pockets: [[u8; 5]; 2],
+    promoted: u64,
+}
+
+/// Random keys for a pawn of every color on every square. The pawn key of a position is the
+/// xor of the keys of its pawns (Zobrist hashing), so it only changes when a pawn does.
+/// Generated at compile time with splitmix64, so they are the same in every build.
+const PAWN_KEYS: [[u64; 64]; 2] = pawn_keys();
+
+const fn pawn_keys() -> [[u64; 64]; 2] {
+    let mut keys = [[0; 64]; 2];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 128 {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        keys[i / 64][i % 64] = z ^ (z >> 31);
+        i += 1;
+    }
+    return keys;
+}
+
+/// The pawn key of a packed piece on the index, 0 if it isn't a pawn.
+fn pawn_square_key(byte: u8, index: usize) -> u64 {
+    if byte & 7 != 1 {return 0;}
+    return PAWN_KEYS[(byte >> 3 & 1) as usize][index];
+}
+
+impl Position {
+    /// Returns the piece on the given index, if any.
+    fn piece_at(&self, index: usize) -> Option<Piece> {
+        return Piece::from_u8(self.board[index]);
+    }
+
+    /// The indexes with a piece and their pieces, in board order (a8 first).
+    fn occupied(&self) -> impl Iterator<Item = (usize, Piece)> + '_ {
+        return self.board.iter().enumerate().filter_map(|(index, &byte)| Some((index, Piece::from_u8(byte)?)));
+    }
+
+    /// Writes the castling rights for FEN, e.g. "KQk", or "-" if nobody can castle.
+    fn castling_fen(&self) -> String {
+        let mut castling_availability: String = String::new();
+        let unmoved = |index: usize, role: PieceRole, color: Color| -> bool {
+            match self.piece_at(index) {
+                Some(piece) => piece.role == role && piece.color == color && !piece.has_moved,
+                None => false
+            }
+        };
+        if self.variant == Variant::Chess960 {
+            // the files of the unmoved rooks beside an unmoved king, the kingside first
+            for (color, row) in [(Color::White, 56), (Color::Black, 0)] {
+                if !(row..row + 8).any(|index| unmoved(index, PieceRole::King, color)) {continue;}
+                for index in (row..row + 8).rev().filter(|&index| unmoved(index, PieceRole::Rook, color)) {
+                    let file = (b'A' + (index % 8) as u8) as char;
+                    castling_availability.push(if color == Color::White {file} else {file.to_ascii_lowercase()});
+                }
+            }
+        }
+        // there is no castling in Antichess
+        else if self.variant != Variant::Antichess {
+            if unmoved(60, PieceRole::King, Color::White) {
+                if unmoved(63, PieceRole::Rook, Color::White) {castling_availability.push('K');}
+                if unmoved(56, PieceRole::Rook, Color::White) {castling_availability.push('Q');}
+            }
+            if unmoved(4, PieceRole::King, Color::Black) {
+                if unmoved(7, PieceRole::Rook, Color::Black) {castling_availability.push('k');}
+                if unmoved(0, PieceRole::Rook, Color::Black) {castling_availability.push('q');}
+            }
+        }
+        if castling_availability.is_empty() {castling_availability.push('-');}
+        return castling_availability;
+    }
+
+    /// Puts a piece on (or removes a piece from) the given index.
+    fn set_piece(&mut self, index: usize, piece: Option<Piece>) {
+        self.pawn_key ^= pawn_square_key(self.board[index], index);
+        self.board[index] = match piece {
+            Some(piece) => piece.to_u8(),
+            None => 0
+        };
+        self.pawn_key ^= pawn_square_key(self.board[index], index);
+    }
+
+    /// Returns the index of the king of the given color, if there is one.
+    fn king_square(&self, color: Color) -> Option<usize> {
+        return self.squares_of(PieceRole::King, Some(color)).next().map(Square::index);
+    }
+
+    /// Returns true if any piece of color `by` attacks the given index.
+    fn is_attacked(&self, index: usize, by: Color) -> bool {
+        return self.find_attacker(index, by, |_| true);
+    }
+
+    /// Calls `found` with the index of every piece of color `by` that attacks the given index,
+    /// the sliding pieces only if nothing stands in between, until `found` returns true.
+    /// Returns true if it did, so that is_attacked stops at the first attacker.
+    pub(crate) fn find_attacker(&self, index: usize, by: Color, mut found: impl FnMut(usize) -> bool) -> bool {
+        let (row, column) = ((index / 8) as i8, (index % 8) as i8);
+        let mut is = |row: i8, column: i8, roles: &[PieceRole]| -> bool {
+            if !move_okay(row, column) {return false;}
+            let square = (row * 8 + column) as usize;
+            return self.piece_at(square).is_some_and(|piece| piece.color == by && roles.contains(&piece.role)) && found(square);
+        };
+        // pawns attack diagonally forward, so look one row behind the square from their view
+        let pawn_row = if by == Color::White {row + 1} else {row - 1};
+        for column_offset in [-1, 1] {
+            if is(pawn_row, column + column_offset, &[PieceRole::Pawn]) {return true;}
+        }
+        for (row_offset, column_offset) in KNIGHT_OFFSETS {
+            if is(row + row_offset, column + column_offset, &[PieceRole::Knight]) {return true;}
+        }
+        for (row_offset, column_offset) in QUEEN_DIRECTIONS {
+            if is(row + row_offset, column + column_offset, &[PieceRole::King]) {return true;}
+        }
+        // sliding pieces, stop at the first piece in every direction
+        for (directions, roles) in [(ROOK_DIRECTIONS, [PieceRole::Rook, PieceRole::Queen]), (BISHOP_DIRECTIONS, [PieceRole::Bishop, PieceRole::Queen])] {
+            for (row_offset, column_offset) in directions {
+                let (mut r, mut c) = (row + row_offset, column + column_offset);
+                while move_okay(r, c) {
+                    if self.board[(r * 8 + c) as usize] != 0 {
+                        if is(r, c, &roles) {return true;}
+                        break;
+                    }
+                    r += row_offset;
+                    c += column_offset;
+                }
+            }
+        }
+        return false;
+    }
+
+    /// Returns either true or false if the given color is in check. There is no check in Antichess.
+    fn in_check(&self, color: Color) -> bool {
+        if self.variant == Variant::Antichess {return false;}
+        match self.king_square(color) {
+            Some(index) => self.is_attacked(index, color.opposite()),
+            None => false
+        }
+    }
+
+    /// Returns all legal moves for the side to move, with every promotion as a separate move.
+    fn legal_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        for (from, piece) in self.occupied().filter(|(_, piece)| piece.color == self.turn) {
+            for to in piece.available_moves(self, from, false) {
+                if piece.role == PieceRole::Pawn && (to / 8 == 0 || to / 8 == 7) {
+                    for &role in self.promotions() {
+                        moves.push(Move::new(from, to, role));
+                    }
+                }
+                else {
+                    moves.push(Move::new(from, to, None));
+                }
+            }
+        }
+        moves.extend(self.drop_moves());
+        // taking is compulsory in Antichess
+        if self.variant == Variant::Antichess && moves.iter().any(|&mv| self.is_capture(mv)) {
+            moves.retain(|&mv| self.is_capture(mv));
+        }
+        return moves;
+    }
+
+    /// Returns the roles a pawn can promote to, which in Antichess includes the king.
+    fn promotions(&self) -> &'static [Option<PieceRole>] {
+        if self.variant == Variant::Antichess {
+            return &[Some(PieceRole::Queen), Some(PieceRole::Rook), Some(PieceRole::Bishop), Some(PieceRole::Knight), Some(PieceRole::King)];
+        }
+        return &[Some(PieceRole::Queen), Some(PieceRole::Rook), Some(PieceRole::Bishop), Some(PieceRole::Knight)];
+    }
+
+    /// Returns true if the move takes a piece, including en passant.
+    fn is_capture(&self, mv: Move) -> bool {
+        // the only move to a square with a piece of the own color is castling in Chess960
+        if self.board[mv.to.index()] != 0 {return (self.board[mv.to.index()] ^ self.board[mv.from.index()]) & 8 != 0;}
+        return self.ep_square == Some(mv.to.0) && self.piece_at(mv.from.index()).is_some_and(|piece| piece.role == PieceRole::Pawn);
+    }
+
+    /// Returns true if the move puts the opponent in check.
+    fn gives_check(&self, mv: Move) -> bool {
+        let mut position_copy = *self;
+        position_copy.apply_move(mv);
+        return position_copy.in_check(position_copy.turn);
+    }
+
+    /// Returns which stage of the staged move generation the move belongs to.
+    fn move_stage(&self, mv: Move) -> MoveStage {
+        if self.is_capture(mv) {return MoveStage::Captures;}
+        if self.gives_check(mv) {return MoveStage::Checks;}
+        return MoveStage::Quiets;
+    }
+
+    /// Returns the legal moves of one stage. Captures only test the legality of capturing
+    /// moves, which is cheaper than generating every legal move first.
+    fn stage_moves(&self, stage: MoveStage) -> Vec<Move> {
+        // whether a move is legal in Antichess depends on the captures of every other piece, and
+        // drops aren't moves of a piece
+        if self.variant == Variant::Antichess || self.variant == Variant::Crazyhouse {
+            return self.legal_moves().into_iter().filter(|&mv| self.move_stage(mv) == stage).collect();
+        }
+        let mut moves: Vec<Move> = Vec::new();
+        for (from, piece) in self.occupied().filter(|(_, piece)| piece.color == self.turn) {
+            // castling never captures, so captures can start from the moves that ignore check
+            let targets = piece.available_moves(self, from, stage == MoveStage::Captures);
+            for to in targets {
+                let promotions: &[Option<PieceRole>] = if piece.role == PieceRole::Pawn && (to / 8 == 0 || to / 8 == 7) {
+                    self.promotions()
+                } else {
+                    &[None]
+                };
+                for &promotion in promotions {
+                    let mv = Move::new(from, to, promotion);
+                    if stage == MoveStage::Captures {
+                        if !self.is_capture(mv) {continue;}
+                        // the cheap moves still have to be checked for leaving the king in check
+                        let mut position_copy = *self;
+                        position_copy.apply_move(mv);
+                        if position_copy.in_check(self.turn) {continue;}
+                    }
+                    else if self.move_stage(mv) != stage {continue;}
+                    moves.push(mv);
+                }
+            }
+        }
+        return moves;
+    }
+
+    /// Returns the index of the piece the move takes, which is not the target square for en passant.
+    fn capture_square(&self, mv: Move) -> Option<usize> {
+        if !self.is_capture(mv) {return None;}
+        if self.board[mv.to.index()] != 0 {return Some(mv.to.index());}
+        return Some(mv.from.index() / 8 * 8 + mv.to.index() % 8);
+    }
+
+    /// Returns the indexes the rook moves from and to if the move is castling: a king on its
+    /// back rank that moves along it, two files over from the e-file, or onto an unmoved rook
+    /// of its own in Chess960. Whether the castling is legal isn't checked.
+    fn castling_rook(&self, mv: Move) -> Option<(usize, usize)> {
+        let (from, to) = (mv.from.index(), mv.to.index());
+        let king = self.piece_at(from).filter(|piece| piece.role == PieceRole::King)?;
+        let back_row = if king.color == Color::White {7} else {0};
+        if from / 8 != back_row || to / 8 != back_row {return None;}
+        if self.variant == Variant::Chess960 {
+            // the king takes its own rook, which ends on the f- or d-file
+            if !self.piece_at(to).is_some_and(|rook| rook.role == PieceRole::Rook && rook.color == king.color && !rook.has_moved) {return None;}
+            return Some((to, to / 8 * 8 + if to > from {5} else {3}));
+        }
+        if from % 8 != 4 || (to % 8).abs_diff(4) != 2 {return None;}
+        return Some(if to > from {(from + 3, to - 1)} else {(from - 4, to + 1)});
+    }
+
+    /// Returns the index the moving piece ends on, which for castling in Chess960 is the g- or
+    /// c-file instead of the square of the rook.
+    fn move_target(&self, mv: Move) -> usize {
+        let to = mv.to.index();
+        if self.variant == Variant::Chess960 && self.castling_rook(mv).is_some() {return to / 8 * 8 + if to > mv.from.index() {6} else {2};}
+        return to;
+    }
+
+    /// Returns the indexes of the rooks the king on the index can castle with in Chess960. The
+    /// king and the rook must not have moved, every square either of them passes or ends on must
+    /// be empty but for the two of them, and the king must not be in check or pass through or
+    /// end on an attacked square.
+    fn castling_rooks_960(&self, king: usize) -> Vec<usize> {
+        let color = self.piece_at(king).unwrap().color;
+        let row = king / 8 * 8;
+        let mut rooks: Vec<usize> = Vec::new();
+        for rook in row..row + 8 {
+            if !self.piece_at(rook).is_some_and(|piece| piece.role == PieceRole::Rook && piece.color == color && !piece.has_moved) {continue;}
+            let (king_to, rook_to) = if rook > king {(row + 6, row + 5)} else {(row + 2, row + 3)};
+            let (low, high) = (king.min(king_to).min(rook).min(rook_to), king.max(king_to).max(rook).max(rook_to));
+            if (low..=high).any(|index| index != king && index != rook && self.board[index] != 0) {continue;}
+            if (king.min(king_to)..=king.max(king_to)).any(|index| self.is_attacked(index, color.opposite())) {continue;}
+            rooks.push(rook);
+        }
+        return rooks;
+    }
+
+    /// Mutates the position to match the move, without checking if it is legal.
+    fn apply_move(&mut self, mv: Move) {
+        if mv.is_drop() {return self.apply_drop(mv);}
+        let (from, to) = (mv.from.index(), mv.to.index());
+        let piece = self.piece_at(from).unwrap();
+        let (from_row, to_row) = ((from / 8) as i8, (to / 8) as i8);
+        let castling_rook = self.castling_rook(mv);
+        let target = self.move_target(mv);
+
+        // check if to reset the halfmove clock
+        self.halfmove += 1;
+        if piece.role == PieceRole::Pawn || self.is_capture(mv) {
+            self.halfmove = 0;
+        }
+
+        // if en passant, remove the pawn beside the moving pawn
+        if let Some(captured) = self.capture_square(mv) {
+            if self.variant == Variant::Crazyhouse {self.pocket_capture(captured);}
+            self.set_piece(captured, None);
+        }
+        // a promoted piece stays promoted when it moves
+        if self.variant == Variant::Crazyhouse && (self.promoted >> from & 1 == 1 || mv.promotion.is_some()) {
+            self.promoted = self.promoted & !(1 << from) | 1 << target;
+        }
+
+        // move piece, after taking the castling rook off since in Chess960 the king can end on its square
+        self.set_piece(from, None);
+        if let Some((rook_from, _)) = castling_rook {self.set_piece(rook_from, None);}
+        self.set_piece(target, Some(Piece::new(mv.promotion.unwrap_or(piece.role), piece.color, true)));
+
+        // if pawn double stepped, but not from the first rank in Horde
+        if piece.role == PieceRole::Pawn && (to_row - from_row).abs() == 2 && (from_row == 1 || from_row == 6) {
+            // set en passant square to the square behind the pawn
+            self.ep_square = Some(((from + to) / 2) as u8);
+        }
+        else {
+            // reset en passant square
+            self.ep_square = None;
+        }
+
+        // if castling, put the rook on the other side of the king
+        if let Some((_, rook_to)) = castling_rook {
+            self.set_piece(rook_to, Some(Piece::new(PieceRole::Rook, piece.color, true)));
+        }
+
+        // change fullmove clock after every black turn
+        if self.turn == Color::Black {self.fullmove += 1;}
+        self.turn = self.turn.opposite();
+    }
+
+    /// Counts the leaf nodes of the legal move tree at the given depth.
+    fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {return 1;}
+        let moves = self.legal_moves();
+        if depth == 1 {return moves.len() as u64;}
+        let mut nodes: u64 = 0;
+        for mv in moves {
+            let mut position_copy = *self;
+            position_copy.apply_move(mv);
+            nodes += position_copy.perft(depth - 1);
+        }
+        return nodes;
+    }
+
+    /// A copy of the position that equals the copy of another position exactly when the two
+    /// count as the same position for the repetition rule: same pieces, turn, castling rights
+    /// and en passant square. The clocks are zeroed, and only kings and rooks keep whether
+    /// they have moved, since that is what the castling rights depend on.
+    fn repetition_key(&self) -> Position {
+        let mut key = *self;
+        key.halfmove = 0;
+        key.fullmove = 0;
+        for byte in key.board.iter_mut() {
+            if !matches!(*byte & 7, 2 | 6) {*byte &= !16;}
+        }
+        return key;
+    }
+}
+
+/// What happened when a move was made.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveResult {
+    pub mv: Move,
+    /// The piece that moved, as it was before the move.
+    pub piece: Piece,
+    /// The piece that was taken, if any.
+    pub captured: Option<Piece>,
+    /// The square the taken piece stood on, which is not the target square for en passant.
+    pub capture_square: Option<Square>,
+    /// The move of the rook if the move was castling.
+    pub rook_move: Option<Move>,
+    /// The game state after the move.
+    pub state: GameState,
+}
+
+/// Everything needed to undo a move.
+#[derive(Clone)]
+struct HistoryEntry {
+    position: Position,
+    ids: [u8; 64],
+    state: GameState,
+    last_move: Option<Move>,
+    result: MoveResult,
+    /// The comment of the move in a PGN game, see Game::move_comment.
+    comment: Option<MoveComment>,
+}
+
+/// Counts how many times the last of the repetition keys (see Position::repetition_key) occurs
+/// before it. Only the positions since the last capture or pawn move (the last `halfmove`
+/// plies) can be the same, and only every other one has the same player to move.
+fn repetitions(keys: &[Position], halfmove: u32) -> usize {
+    let last = keys[keys.len() - 1];
+    let earlier = keys.iter().rev().take(halfmove as usize + 1).step_by(2).skip(1);
+    return earlier.filter(|&&key| key == last).count();
+}
+
+/// Gives every piece on the board its own id, counting from 1 in board order.
+fn assign_ids(position: &Position) -> [u8; 64] {
+    let mut ids: [u8; 64] = [0; 64];
+    let mut next_id: u8 = 1;
+    for (index, &byte) in position.board.iter().enumerate() {
+        if byte != 0 {
+            ids[index] = next_id;
+            next_id += 1;
+        }
+    }
+    return ids;
+}
+
 #[derive(Clone)]
 pub struct Game {
-    /* state, chessboard, turn, potential en passant square, the halfmove clock, and fullmove clock 
-    chessboard is represented as a 2d vector of structs Piece. ep_square is a vector of length 2, which represent the coordinates of the en passant square.
-    The coordinates are the row and column indexes of the square, i.e the bottom right square is [7,7].
+    /* state, position, piece ids, the last move made, the history and the cached legal moves
+    The position is kept small and copyable so that moves can be tried out on a copy, see Position.
+    ids holds the id of the piece on every square (0 for empty squares), which follows the piece when it moves.
+    history holds what is needed to undo every move made since the game was created or loaded.
+    legal_moves caches every legal move for the side to move. It is filled the first time it is needed
+    and must be reset whenever the position changes.
+    ended is the result of a game that ended by resignation, an agreed draw or on time and why, after which no
+    moves can be made, and flagged the player who ran out of time (see Game::check_flag).
+    draw_offer is the player whose draw offer stands until the next move, see Game::offer_draw.
+    premoves holds the move each player queued to make after the opponent's move, see Game::set_premove.
+    takeback_request is the player whose takeback request stands until the next move, see Game::request_takeback.
+    subscribers are called with the events of the game, see Game::subscribe. Clones don't have them.
+    event_log records what happened in the game, see Game::start_event_log. Only a game restored from a save
+    without a log has none.
     */
     state: GameState,
-    pub chessboard: Vec<Vec<Option<Piece>>>,
-    pub turn: Color,
-    ep_square: Option<Vec<i8>>,
-    halfmove: u64,
-    fullmove: u64,
+    position: Position,
+    ids: [u8; 64],
+    last_move: Option<Move>,
+    history: Vec<HistoryEntry>,
+    legal_moves: OnceCell<Vec<Move>>,
+    ended: Option<(GameOutcome, EndReason)>,
+    #[cfg(feature = "std")]
+    flagged: Option<Color>,
+    draw_offer: Option<Color>,
+    premoves: [Option<Move>; 2],
+    takeback_request: Option<Color>,
+    subscribers: Subscribers,
+    #[cfg(feature = "std")]
+    event_log: Option<Box<EventLog>>,
 }
 
 impl Game {
     /// Initialises a new board with pieces.
     pub fn new() -> Game {
+        return Game::with_back_row([PieceRole::Rook, PieceRole::Knight, PieceRole::Bishop, PieceRole::Queen, PieceRole::King, PieceRole::Bishop, PieceRole::Knight, PieceRole::Rook], Variant::Standard);
+    }
+
+    /// A new game with the pieces of the back row from the a-file to the h-file, the same for
+    /// both players, and the pawns in front of them. No piece has moved.
+    pub(crate) fn with_back_row(back_row: [PieceRole; 8], variant: Variant) -> Game {
         // Start with empty board
-        let mut chessboard: Vec<Vec<Option<Piece>>> = vec![vec![None; 8]; 8];
-        let back_row: Vec<PieceRole> = vec![PieceRole::Rook, PieceRole::Knight, PieceRole::Bishop, PieceRole::Queen, PieceRole::King, PieceRole::Bishop, PieceRole::Knight, PieceRole::Rook];
+        let mut position = Position { board: [0; 64], halfmove: 0, fullmove: 1, pawn_key: 0, ep_square: None, turn: Color::White, variant, pockets: [[0; 5]; 2], promoted: 0 };
         // Add pieces
-        for i in 0..=7 {
-            chessboard[0][i] = Some(Piece::new(back_row[i], Color::Black, false));
-            chessboard[1][i] = Some(Piece::new(PieceRole::Pawn, Color::Black, false));
-            chessboard[6][i] = Some(Piece::new(PieceRole::Pawn, Color::White, false));
-            chessboard[7][i] = Some(Piece::new(back_row[i], Color::White, false));
+        for (i, &role) in back_row.iter().enumerate() {
+            position.set_piece(i, Some(Piece::new(role, Color::Black, false)));
+            position.set_piece(8 + i, Some(Piece::new(PieceRole::Pawn, Color::Black, false)));
+            position.set_piece(48 + i, Some(Piece::new(PieceRole::Pawn, Color::White, false)));
+            position.set_piece(56 + i, Some(Piece::new(role, Color::White, false)));
         }
         Game {
             state: GameState::InProgress,
-            chessboard: chessboard,
-            turn: Color::White,
-            ep_square: None,
-            halfmove: 0,
-            fullmove: 1
+            ids: assign_ids(&position),
+            position,
+            last_move: None,
+            history: Vec::new(),
+            legal_moves: OnceCell::new(),
+            ended: None,
+            #[cfg(feature = "std")]
+            flagged: None,
+            draw_offer: None,
+            premoves: [None; 2],
+            takeback_request: None,
+            subscribers: Subscribers::default(),
+            #[cfg(feature = "std")]
+            event_log: Some(EventLog::started())
         }
-
     }
 
-    /// Mutates the current board to match the given FEN (Forsyth–Edwards Notation) string.
-    pub fn load_fen(&mut self, fen_string: String) -> Option<GameState> {
-        // split fen string into chapters separated by spaces
-        let mut placement_data: String = String::new();
-        let mut active_color: String = String::new();
-        let mut castling_availability: String = String::new();
-        let mut en_passant: String = String::new();
-        let mut halfmove_clock: String = String::new();
-        let mut fullmove_clock: String = String::new();
-        for (index, chapter) in fen_string.split(" ").enumerate() {
-            match index {
-                0 => placement_data = chapter.to_string(),
-                1 => active_color = chapter.to_string(),
-                2 => castling_availability = chapter.to_string(),
-                3 => en_passant = chapter.to_string(),
-                4 => halfmove_clock = chapter.to_string(),
-                5 => fullmove_clock = chapter.to_string(),
-                _ => return None
+    /// Creates a game from the given FEN (Forsyth–Edwards Notation) string, or returns an error
+    /// describing what is wrong with the string and where, see FenError.
+    pub fn from_fen(fen_string: &str) -> Result<Game, ChessError> {
+        let invalid = |message: String, field: Option<FenField>, position: usize| -> Result<Game, ChessError> {
+            Err(ChessError::InvalidFen(FenError { message, field, position }))
+        };
+        // split fen string into chapters separated by spaces, with the offsets they start at
+        let mut chapters: Vec<(usize, &str)> = Vec::new();
+        let mut start: Option<(usize, usize)> = None;
+        for (position, (byte, char)) in fen_string.char_indices().chain([(fen_string.len(), ' ')]).enumerate() {
+            match (start, char.is_whitespace()) {
+                (None, false) => start = Some((position, byte)),
+                (Some((chapter_position, chapter_byte)), true) => {
+                    chapters.push((chapter_position, &fen_string[chapter_byte..byte]));
+                    start = None;
+                }
+                _ => ()
             }
         }
+        if chapters.len() != 6 {
+            let position = chapters.get(6).map_or(fen_string.chars().count(), |&(position, _)| position);
+            return invalid(format!("expected 6 fields, found {}", chapters.len()), None, position);
+        }
+        let [(placement_start, placement_data), (color_start, active_color), (castling_start, castling_availability), (en_passant_start, en_passant), (halfmove_start, halfmove_clock), (fullmove_start, fullmove_clock)] =
+            chapters[..] else {unreachable!()};
+        let mut position = Position { board: [0; 64], halfmove: 0, fullmove: 1, pawn_key: 0, ep_square: None, turn: Color::White, variant: Variant::Standard, pockets: [[0; 5]; 2], promoted: 0 };
 
-        // placement data
-        for (row_index, row) in placement_data.split("/").enumerate() {
+        // placement data, with the pockets of Crazyhouse after it, e.g. [QNp]
+        let placement = |message: String, position: usize| -> Result<Game, ChessError> {invalid(message, Some(FenField::Placement), position)};
+        let (placement_data, pockets) = match placement_data.split_once('[') {
+            Some((placement_data, pockets)) => (placement_data, Some(pockets)),
+            None => (placement_data, None)
+        };
+        let pockets_start = placement_start + placement_data.chars().count();
+        if let Some(pockets) = pockets {
+            let Some(pockets) = pockets.strip_suffix(']') else {return placement(format!("pockets '[{}' without ']'", pockets), pockets_start)};
+            position.variant = Variant::Crazyhouse;
+            for (offset, char) in pockets.chars().enumerate() {
+                let slot = match char.to_ascii_lowercase() {
+                    'p' => 0,
+                    'n' => 1,
+                    'b' => 2,
+                    'r' => 3,
+                    'q' => 4,
+                    _ => return placement(format!("invalid piece character '{}' in the pockets", char), pockets_start + 1 + offset)
+                };
+                position.pockets[if char.is_ascii_uppercase() {0} else {1}][slot] += 1;
+            }
+        }
+        let rows: Vec<&str> = placement_data.split('/').collect();
+        if rows.len() != 8 {
+            // the slash before the ninth row, or the end of the rows
+            let position = if rows.len() > 8 {placement_start + rows[..8].iter().map(|row| row.chars().count() + 1).sum::<usize>() - 1} else {pockets_start};
+            return placement(format!("expected 8 rows, found {}", rows.len()), position);
+        }
+        let mut row_start = placement_start;
+        for (row_index, row) in rows.iter().enumerate() {
             let mut column_index: usize = 0;
-            for char in row.chars() {
-                if char.is_digit(10) {
-                    for _ in 0..char.to_digit(10).unwrap() {
-                        self.chessboard[row_index][column_index] = None;
-                        column_index += 1;
-                    }
+            let mut after_piece = false;
+            for (offset, char) in row.chars().enumerate() {
+                // a promoted piece in Crazyhouse
+                if char == '~' && after_piece {
+                    position.promoted |= 1 << (row_index * 8 + column_index - 1);
+                    after_piece = false;
+                    continue;
+                }
+                if column_index > 7 {return placement(format!("row {} is longer than 8 squares", row_index + 1), row_start + offset);}
+                after_piece = !('1'..='8').contains(&char);
+                if ('1'..='8').contains(&char) {
+                    column_index += char.to_digit(10).unwrap() as usize;
                 }
                 else {
-                    self.chessboard[row_index][column_index] = Some(Piece::new(
-                        match char {
-                            'p'|'P' => PieceRole::Pawn,
-                            'r'|'R' => PieceRole::Rook,
-                            'n'|'N' => PieceRole::Knight,
-                            'b'|'B' => PieceRole::Bishop,
-                            'q'|'Q' => PieceRole::Queen,
-                            'k'|'K' => PieceRole::King,
-                            _ => return None
-                        },
-                        if char.is_uppercase() {Color::White} else {Color::Black},
-                        true
-                    ));
-                    let piece_clone = self.chessboard[row_index][column_index].clone().unwrap();
-                        if piece_clone.role == PieceRole::Pawn {
-                        match piece_clone.color {
-                            Color::White => {
-                                if row_index == 6 {self.chessboard[row_index][column_index].as_mut().unwrap().has_moved = false}
-                            }
-                            Color::Black => {
-                                if row_index == 1 {self.chessboard[row_index][column_index].as_mut().unwrap().has_moved = false}
-                            }
-                        }
-                    }
+                    let role = match char {
+                        'p'|'P' => PieceRole::Pawn,
+                        'r'|'R' => PieceRole::Rook,
+                        'n'|'N' => PieceRole::Knight,
+                        'b'|'B' => PieceRole::Bishop,
+                        'q'|'Q' => PieceRole::Queen,
+                        'k'|'K' => PieceRole::King,
+                        _ => return placement(format!("invalid piece character '{}'", char), row_start + offset)
+                    };
+                    let color = if char.is_uppercase() {Color::White} else {Color::Black};
+                    // pawns on their starting row have not moved yet
+                    let has_moved = role != PieceRole::Pawn || row_index != if color == Color::White {6} else {1};
+                    position.set_piece(row_index * 8 + column_index, Some(Piece::new(role, color, has_moved)));
                     column_index += 1;
                 }
             }
+            row_start += row.chars().count() + 1;
+            // the square after the row, where the slash or the end is
+            if column_index != 8 {return placement(format!("row {} does not have 8 squares", row_index + 1), row_start - 1);}
         }
 
         // active color
-        self.turn = match active_color.as_str() {
+        position.turn = match active_color {
             "w" => Color::White,
             "b" => Color::Black,
-            _ => return None
+            _ => return invalid(format!("invalid active color '{}'", active_color), Some(FenField::ActiveColor), color_start)
         };
 
-        // castling availability
-        for char in castling_availability.chars() {
-            match char {
-                'K' => {self.chessboard[7][4].as_mut().unwrap().has_moved = false; self.chessboard[7][7].as_mut().unwrap().has_moved = false}
-                'Q' => {self.chessboard[7][4].as_mut().unwrap().has_moved = false; self.chessboard[7][0].as_mut().unwrap().has_moved = false}
-                'k' => {self.chessboard[0][4].as_mut().unwrap().has_moved = false; self.chessboard[0][7].as_mut().unwrap().has_moved = false} 
-                'q' => {self.chessboard[0][4].as_mut().unwrap().has_moved = false; self.chessboard[0][0].as_mut().unwrap().has_moved = false}
-                '-' => (),
-                _ => return None
+        // castling availability, with the files of the rooks for Chess960 (Shredder-FEN)
+        for (offset, char) in castling_availability.chars().enumerate() {
+            let castling = |message: String| -> Result<Game, ChessError> {invalid(message, Some(FenField::Castling), castling_start + offset)};
+            let (indexes, color): ([usize; 2], Color) = match char {
+                'K' => ([60, 63], Color::White),
+                'Q' => ([60, 56], Color::White),
+                'k' => ([4, 7], Color::Black),
+                'q' => ([4, 0], Color::Black),
+                'A'..='H' | 'a'..='h' => {
+                    let (color, row) = if char.is_ascii_uppercase() {(Color::White, 56)} else {(Color::Black, 0)};
+                    let king = position.king_square(color).filter(|&king| king / 8 * 8 == row).unwrap_or(row + 4);
+                    position.variant = Variant::Chess960;
+                    ([king, row + (char.to_ascii_lowercase() as u8 - b'a') as usize], color)
+                }
+                '-' if castling_availability.len() == 1 => continue,
+                _ => return castling(format!("invalid castling availability '{}'", castling_availability))
+            };
+            // castling is only possible with the king and rook on their starting squares
+            for (index, role) in indexes.into_iter().zip([PieceRole::King, PieceRole::Rook]) {
+                match position.piece_at(index) {
+                    Some(mut piece) if piece.role == role && piece.color == color => {piece.has_moved = false; position.set_piece(index, Some(piece))}
+                    _ => return castling(format!("castling availability '{}' without king and rook in place", char))
+                }
             }
         }
 
         // en passant
         if en_passant != "-" {
-            self.ep_square = Some(vec![56-en_passant.chars().nth(1).unwrap() as i8, en_passant.chars().nth(0).unwrap() as i8 - 97]);
-        }
-        else {
-            self.ep_square = None;
+            position.ep_square = match Square::from_algebraic(en_passant) {
+                // the en passant square is always behind a pawn that just double stepped
+                Some(square) if square.index() / 8 == if position.turn == Color::White {2} else {5} => Some(square.0),
+                _ => return invalid(format!("invalid en passant square '{}'", en_passant), Some(FenField::EnPassant), en_passant_start)
+            };
         }
 
         // halfmove clock
-        self.halfmove = halfmove_clock.parse::<u64>().unwrap();
+        position.halfmove = match halfmove_clock.parse::<u32>() {
+            Ok(halfmove) => halfmove,
+            Err(_) => return invalid(format!("invalid halfmove clock '{}'", halfmove_clock), Some(FenField::HalfmoveClock), halfmove_start)
+        };
 
         // fullmove clock
-        self.fullmove = fullmove_clock.parse::<u64>().unwrap();
+        position.fullmove = match fullmove_clock.parse::<u32>() {
+            Ok(fullmove) if fullmove > 0 => fullmove,
+            _ => return invalid(format!("invalid fullmove clock '{}'", fullmove_clock), Some(FenField::FullmoveNumber), fullmove_start)
+        };
 
-        return None;
+        let mut game = Game {
+            state: GameState::InProgress,
+            ids: assign_ids(&position),
+            position,
+            last_move: None,
+            history: Vec::new(),
+            legal_moves: OnceCell::new(),
+            ended: None,
+            #[cfg(feature = "std")]
+            flagged: None,
+            draw_offer: None,
+            premoves: [None; 2],
+            takeback_request: None,
+            subscribers: Subscribers::default(),
+            #[cfg(feature = "std")]
+            event_log: Some(EventLog::started())
+        };
+        game.update_state();
+        return Ok(game);
     }
 
-    // make_move calls make_move_internal so we can have an option parameter
-    pub fn make_move(&mut self, _from: &str, _to: &str) -> Option<GameState> {
-        return self.make_move_internal(_from, _to, false);
+    /// Mutates the current board to match the given FEN (Forsyth–Edwards Notation) string and
+    /// returns the new game state, or returns None and leaves the board as it was if the string
+    /// is not valid. Works like load_fen_reset.
+    #[deprecated(note = "use load_fen_reset to start over, or load_fen_continue to keep the event log")]
+    pub fn load_fen(&mut self, fen_string: String) -> Option<GameState> {
+        return self.load_fen_reset(&fen_string).ok();
     }
 
-    /// If the current game state is "InProgress" or "Check" and the move is legal, mutate the 
-    /// chessboard to match the new position and return the new game state. 
-    fn make_move_internal(&mut self, _from: &str, _to: &str, skip_move_check: bool) -> Option<GameState> {
-        // Check that state is allowed
-        if self.state == GameState::Checkmate || self.state == GameState::Stalemate {return None;}
-        // Check if piece is on square, if not return None
-        if self.chessboard[56-_from.chars().nth(1).unwrap() as usize][_from.chars().nth(0).unwrap() as usize - 97].is_none() {return None;}
-        // Convert algebraic notation to vectors from_pos and to_pos
-        let from_pos = vec![56-_from.chars().nth(1).unwrap() as i8, _from.chars().nth(0).unwrap() as i8 - 97];
-        let to_pos = vec![56-_to.chars().nth(1).unwrap() as i8, _to.chars().nth(0).unwrap() as i8 - 97];
-        // Clone piece, check if it's the right color, and if the move is legal
-        
-        let piece = self.chessboard[from_pos[0] as usize][from_pos[1] as usize].clone().unwrap();
-        if !skip_move_check && piece.color != self.turn {return None;}
-        if !skip_move_check && !piece.available_moves(self, from_pos.clone(), false, false).unwrap().contains(&to_pos) {return None;}
-        
-        // check for promotion
-        if self.chessboard[from_pos[0] as usize][from_pos[1] as usize].as_ref().unwrap().role == PieceRole::Pawn && (to_pos[0] == 0 || to_pos[0] == 7) {
-            if _to.len() < 3 {return None;}
-            let new_role = match _to.chars().nth(2).unwrap() {
-                'q'|'Q' => PieceRole::Queen,
-                'r'|'R' => PieceRole::Rook,
-                'n'|'N' => PieceRole::Knight,
-                'b'|'B' => PieceRole::Bishop,
-                _ => return None
-            };
-            self.chessboard[to_pos[0] as usize][to_pos[1] as usize] = Some(Piece::new(new_role, piece.color, true));
-            self.chessboard[from_pos[0] as usize][from_pos[1] as usize] = None;
-            self.ep_square = None;
-            self.halfmove = 0;
-        }
-        else {
-            // check if to reset the halfmove clock
-            self.halfmove += 1;
-            if piece.clone().role == PieceRole::Pawn || self.chessboard[to_pos[0] as usize][to_pos[1] as usize].is_some() {
-                self.halfmove = 0;
-            }
-            // move piece
-            self.chessboard[to_pos[0] as usize][to_pos[1] as usize] = Some(Piece::new(piece.role, piece.color, true));
-            self.chessboard[from_pos[0] as usize][from_pos[1] as usize] = None;
-
-            
-            // if en passant
-            if self.ep_square != None && self.ep_square == Some(to_pos.clone()) && piece.role == PieceRole::Pawn {
-                self.chessboard[((self.ep_square.clone().unwrap()[0]+7)/3) as usize][self.ep_square.clone().unwrap()[1] as usize] = None;
-            }
-            // if pawn double stepped
-            if piece.clone().role == PieceRole::Pawn && (to_pos[0] - from_pos[0]).abs() == 2 {
-                // set en passant square to the square behind the pawn
-                self.ep_square = Some(vec![from_pos[0] + (to_pos[0] - from_pos[0])/2, from_pos[1]]);
-            }
-            else {
-                // reset en passant square
-                self.ep_square = None;
-            }
+    /// Starts the game over from the position of the FEN string and returns the new game
+    /// state. The moves made so far can no longer be undone or repeat the position, the
+    /// clocks are those of the FEN and the event log is emptied. The subscribers stay and get
+    /// a PositionSet event. If the string isn't valid, the game is left as it was and the
+    /// error tells what is wrong, like from_fen.
+    pub fn load_fen_reset(&mut self, fen_string: &str) -> Result<GameState, ChessError> {
+        self.set_position(Game::from_fen(fen_string)?);
+        #[cfg(feature = "std")]
+        self.clear_event_log();
+        self.emit_position_set();
+        return Ok(self.state);
+    }
 
-            // if castling
-            if piece.clone().role == PieceRole::King && (to_pos[1] - from_pos[1]).abs() == 2 {
-                self.chessboard[to_pos[0] as usize][(to_pos[1] - (to_pos[1] - from_pos[1]).signum()) as usize] = Some(Piece::new(PieceRole::Rook, piece.color, true));
-                self.chessboard[to_pos[0] as usize][((7*to_pos[1]-14)/4) as usize] = None;
+    /// Sets up the position of the FEN string in the middle of the game, e.g. after a
+    /// correction by an arbiter, and returns the new game state. Like load_fen_reset the moves
+    /// before can't be undone and repetitions are counted from the new position on, but the
+    /// event log keeps its entries and records the position as a PositionSet entry. The
+    /// subscribers get a PositionSet event.
+    pub fn load_fen_continue(&mut self, fen_string: &str) -> Result<GameState, ChessError> {
+        self.set_position(Game::from_fen(fen_string)?);
+        #[cfg(feature = "std")]
+        self.log(self.position.turn, LogEntry::PositionSet(String::from(fen_string)));
+        self.emit_position_set();
+        return Ok(self.state);
+    }
+
+    /// Replaces the game with the new one, keeping the subscribers and the event log.
+    fn set_position(&mut self, game: Game) {
+        *self = Game {
+            subscribers: core::mem::take(&mut self.subscribers),
+            #[cfg(feature = "std")]
+            event_log: self.event_log.take(),
+            ..game
+        };
+    }
 
+    /// If the current game state is "InProgress" or "Check" and the move is legal, mutate the
+    /// chessboard to match the new position and return the new game state. If the opponent has
+    /// a premove (see set_premove) it is played or discarded right after, and the state is the
+    /// one after it.
+    pub fn make_move(&mut self, _from: &str, _to: &str) -> Option<GameState> {
+        return match self.find_move(_from, _to) {
+            Ok(mv) => {
+                self.play(mv);
+                self.play_premove();
+                Some(self.state)
             }
+            Err(reason) => {self.reject(&format!("{}{}", _from, _to), reason); None}
+        };
+    }
 
+    /// Finds the legal move from _from to _to for make_move, or says why there is none.
+    fn find_move(&self, _from: &str, _to: &str) -> Result<Move, ChessError> {
+        // Convert algebraic notation to board indexes
+        let Some(from) = parse_square(_from) else {return Err(ChessError::InvalidSquare(_from.to_string()))};
+        let Some(to) = parse_square(_to) else {return Err(ChessError::InvalidSquare(_to.to_string()))};
+        // a pawn on the last rank promotes to the piece of the letter after the square, e.g. "e8q"
+        let mut promotion: Option<PieceRole> = None;
+        if self.position.piece_at(from).is_some_and(|piece| piece.role == PieceRole::Pawn) && (to / 8 == 0 || to / 8 == 7) {
+            promotion = match _to.chars().nth(2) {
+                Some('q'|'Q') => Some(PieceRole::Queen),
+                Some('r'|'R') => Some(PieceRole::Rook),
+                Some('n'|'N') => Some(PieceRole::Knight),
+                Some('b'|'B') => Some(PieceRole::Bishop),
+                Some('k'|'K') => Some(PieceRole::King),
+                _ => None
+            };
         }
+        let mv = Move::new(from, to, promotion);
+        return match self.illegal_move_reason(&mv) {
+            Some(reason) => Err(ChessError::IllegalMove(reason)),
+            None => Ok(mv)
+        };
+    }
 
-        if skip_move_check {return None;}
-        
-        // change state depending on check
-        if Game::in_check(&self, if self.turn == Color::White {Color::Black} else {Color::White}) {
-            self.state = GameState::Check;
-        } else {    
-            self.state = GameState::InProgress;
+    /// If the move is legal, make it and return what happened, including the ids of the pieces
+    /// involved. Returns None for illegal moves or if the game is over. A premove of the
+    /// opponent is played or discarded right after, like in make_move.
+    pub fn play_move(&mut self, mv: &Move) -> Option<MoveResult> {
+        if let Some(reason) = self.illegal_move_reason(mv) {
+            self.reject(&mv.to_string(), ChessError::IllegalMove(reason));
+            return None;
         }
+        let result = self.play(*mv);
+        self.play_premove();
+        return Some(result);
+    }
 
-        // look for checkmate and stalemate
-        for (row_index, row) in self.chessboard.iter().enumerate() {
-            for (column_index, piece) in row.iter().enumerate() {
-                match piece {
-                    Some(piece) => {
-                        if piece.color != self.turn && piece.available_moves(self, vec![row_index as i8, column_index as i8], false, false).unwrap().len() > 0 {
-                            // change fullmove clock after every black turn
-                            if self.turn == Color::Black {self.fullmove += 1;}
-                            self.turn = if self.turn == Color::White {Color::Black} else {Color::White};
-                            return Some(self.state);
-                        }
-                    }
-                    None => ()
-                }
-            }
-        }
-        // no moves are available, meaning that the game is either checkmate or stalemate
-        if Game::in_check(&self, if self.turn == Color::White {Color::Black} else {Color::White}) {
-            self.state = GameState::Checkmate;
-        } else {
-            self.state = GameState::Stalemate;
+    /// Records a move that was refused in the event log, if there is one (see start_event_log).
+    fn reject(&mut self, attempt: &str, reason: ChessError) {
+        #[cfg(feature = "std")]
+        self.log_rejection(attempt, reason);
+        #[cfg(not(feature = "std"))]
+        let _ = (attempt, reason);
+    }
+
+    /// Takes back the last move and returns it, or returns None if there is no move to take back.
+    /// Moves made before the game was loaded from FEN can't be taken back. The premoves and a
+    /// takeback request are cancelled.
+    pub fn undo(&mut self) -> Option<Move> {
+        let entry = self.history.pop()?;
+        self.premoves = [None; 2];
+        self.takeback_request = None;
+        self.position = entry.position;
+        self.ids = entry.ids;
+        self.state = entry.state;
+        self.last_move = entry.last_move;
+        self.legal_moves = OnceCell::new();
+        self.emit_undo(entry.result.mv);
+        return Some(entry.result.mv);
+    }
+
+    /// Makes a move that is known to be legal and returns what happened.
+    fn play(&mut self, mv: Move) -> MoveResult {
+        let mut piece = match mv.promotion {
+            Some(role) if mv.is_drop() => Piece::new(role, self.position.turn, true),
+            _ => self.position.piece_at(mv.from.index()).unwrap()
+        };
+        // a dropped piece gets a new id
+        piece.id = if mv.is_drop() {self.next_id()} else {self.ids[mv.from.index()]};
+        let capture_square = self.position.capture_square(mv);
+        let captured = capture_square.map(|index| {
+            let mut captured = self.position.piece_at(index).unwrap();
+            captured.id = self.ids[index];
+            captured
+        });
+        let castling_rook = self.position.castling_rook(mv);
+        let mut entry = HistoryEntry {
+            position: self.position,
+            ids: self.ids,
+            state: self.state,
+            last_move: self.last_move,
+            result: MoveResult {
+                mv,
+                piece,
+                captured,
+                capture_square: capture_square.map(|index| Square(index as u8)),
+                rook_move: castling_rook.map(|(rook_from, rook_to)| Move::new(rook_from, rook_to, None)),
+                state: self.state
+            },
+            comment: None
+        };
+
+        // the ids follow the pieces, and the id of a captured piece is retired
+        if let Some(index) = capture_square {self.ids[index] = 0;}
+        self.ids[mv.from.index()] = 0;
+        let id = piece.id;
+        if let Some((rook_from, rook_to)) = castling_rook {
+            self.ids[rook_to] = core::mem::take(&mut self.ids[rook_from]);
         }
-        if self.turn == Color::Black {self.fullmove += 1};
-        return Some(self.state);
+        self.ids[self.position.move_target(mv)] = id;
+
+        self.position.apply_move(mv);
+        self.last_move = Some(mv);
+        self.update_state();
+        entry.result.state = self.state;
+        let result = entry.result;
+        self.history.push(entry);
+        self.emit_move(result);
+        return result;
+    }
+
+    /// Resets the cached moves and sets the game state to match the current position.
+    fn update_state(&mut self) {
+        self.legal_moves = OnceCell::new();
+        self.state = GameState::InProgress;
+        // change state depending on check, and look for checkmate and stalemate
+        let in_check = self.position.in_check(self.position.turn);
+        self.state = match (self.legal_move_count() > 0, in_check) {
+            (true, false) => GameState::InProgress,
+            (true, true) => GameState::Check,
+            // no moves are available, meaning that the game is either checkmate or stalemate
+            (false, true) => GameState::Checkmate,
+            (false, false) => GameState::Stalemate
+        };
     }
 
     /// Get the halfmove clock.
     pub fn get_halfmove(&self) -> u64 {
-        return self.halfmove;
+        return self.position.halfmove as u64;
     }
 
     /// Get the current game state.
@@ -277,12 +1231,77 @@ impl Game {
 
     /// Get the current turn.
     pub fn get_turn(&self) -> &str {
-        match self.turn {
+        match self.position.turn {
             Color::White => "White",
             Color::Black => "Black"
         }
     }
 
+    /// Get the color of the player whose turn it is.
+    pub fn get_turn_color(&self) -> Color {
+        return self.position.turn;
+    }
+
+    /// Get the rules the game is played by.
+    pub fn variant(&self) -> Variant {
+        return self.position.variant;
+    }
+
+    /// Get the last move that was made, if any.
+    pub fn get_last_move(&self) -> Option<Move> {
+        return self.last_move;
+    }
+
+    /// Get the piece standing on the given square, if any.
+    pub fn get_piece(&self, _position: &str) -> Option<Piece> {
+        return self.piece_with_id(parse_square(_position)?);
+    }
+
+    /// Get the piece on the given index with its id filled in.
+    fn piece_with_id(&self, index: usize) -> Option<Piece> {
+        let mut piece = self.position.piece_at(index)?;
+        piece.id = self.ids[index];
+        return Some(piece);
+    }
+
+    /// Iterates over the squares with a piece and their pieces (with their ids), in board order:
+    /// a8 to h8, then a7 to h7 and so on down to h1.
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        return self.position.occupied().map(|(index, mut piece)| {
+            piece.id = self.ids[index];
+            return (Square(index as u8), piece);
+        });
+    }
+
+    /// Get the whole board as rows of squares, where the first row is rank 8 and the first
+    /// column is the a-file.
+    pub fn get_board(&self) -> Vec<Vec<Option<Piece>>> {
+        return (0..8).map(|row| (0..8).map(|column| self.piece_with_id(row * 8 + column)).collect()).collect();
+    }
+
+    /// The board as the public chessboard field of Game used to hold it, before the board was
+    /// packed into bytes. Works like get_board.
+    #[deprecated(note = "the chessboard field is gone, use get_board or pieces")]
+    pub fn chessboard(&self) -> Vec<Vec<Option<Piece>>> {
+        return self.get_board();
+    }
+
+    /// The player to move, as the public turn field of Game used to hold it. Works like
+    /// get_turn_color.
+    #[deprecated(note = "the turn field is gone, use get_turn_color")]
+    pub fn turn(&self) -> Color {
+        return self.get_turn_color();
+    }
+
+    /// Get the material balance in centipawns, positive if White has more material.
+    pub fn material_balance(&self) -> i32 {
+        let mut balance: i32 = 0;
+        for piece in self.position.board.iter().filter_map(|&byte| Piece::from_u8(byte)) {
+            balance += if piece.color == Color::White {piece.role.value()} else {-piece.role.value()};
+        }
+        return balance;
+    }
+
     /// Get the FEN (Forsyth–Edwards Notation) string of the current board position.
     pub fn get_fen(&self) -> String {
         // split fen string into chapters
@@ -293,321 +1312,346 @@ impl Game {
         let mut halfmove_clock: String = String::new();
         let mut fullmove_clock: String = String::new();
 
-        // placement data
-        for row in self.chessboard.iter() {
-            let mut row_string: String = String::new();
-            let mut empty_squares: i8 = 0;
-            for piece in row.iter() {
-                if piece.is_some() {
-                    let piece = piece.clone().unwrap();
-                    if empty_squares > 0 {row_string.push_str(&empty_squares.to_string()); empty_squares = 0;}
-                    row_string.push_str(
-                match piece.role {
-                        PieceRole::Pawn => if piece.color == Color::White {"P"} else {"p"},
-                        PieceRole::Rook => if piece.color == Color::White {"R"} else {"r"},
-                        PieceRole::Knight => if piece.color == Color::White {"N"} else {"n"},
-                        PieceRole::Bishop => if piece.color == Color::White {"B"} else {"b"},
-                        PieceRole::Queen => if piece.color == Color::White {"Q"} else {"q"},
-                        PieceRole::King => if piece.color == Color::White {"K"} else {"k"}
-                        }
-                    );
-                }
-                else {
-                    empty_squares += 1;
-                }
+        // placement data, with the empty squares up to every piece and up to the end counted a
+        // row at a time, and a slash before every row but the first
+        let mut next: usize = 0;
+        for (index, piece) in self.pieces().map(|(square, piece)| (square.index(), Some(piece))).chain([(64, None)]) {
+            while next < index {
+                let end = index.min(next / 8 * 8 + 8);
+                if next.is_multiple_of(8) && next > 0 {placement_data.push('/');}
+                placement_data.push_str(&(end - next).to_string());
+                next = end;
             }
-            if empty_squares > 0 {row_string.push_str(&empty_squares.to_string())}
-            row_string.push_str("/");
-            placement_data.push_str(&row_string);
+            let Some(piece) = piece else {break};
+            if index.is_multiple_of(8) && index > 0 {placement_data.push('/');}
+            placement_data.push(piece.letter());
+            // promoted pieces are marked in Crazyhouse
+            if self.position.promoted >> index & 1 == 1 {placement_data.push('~');}
+            next = index + 1;
         }
-        placement_data.pop();
+        if self.position.variant == Variant::Crazyhouse {placement_data.push_str(&self.position.pocket_fen());}
 
         // active color
-        active_color.push_str(match self.turn {
+        active_color.push_str(match self.position.turn {
             Color::White => "w",
             Color::Black => "b"
         });
 
         // castling availability
-        if self.chessboard[7][4].is_some() && self.chessboard[7][4].as_ref().unwrap().role == PieceRole::King && !self.chessboard[7][4].as_ref().unwrap().has_moved {  
-            if self.chessboard[7][7].is_some() && self.chessboard[7][7].as_ref().unwrap().role == PieceRole::Rook && !self.chessboard[7][7].as_ref().unwrap().has_moved {
-                castling_availability.push_str("K");
-            }
-            if self.chessboard[7][0].is_some() && self.chessboard[7][0].as_ref().unwrap().role == PieceRole::Rook && !self.chessboard[7][0].as_ref().unwrap().has_moved {
-                castling_availability.push_str("Q");
-            }
-        }
-        if self.chessboard[0][4].is_some() && self.chessboard[0][4].as_ref().unwrap().role == PieceRole::King && !self.chessboard[0][4].as_ref().unwrap().has_moved {  
-            if self.chessboard[0][7].is_some() && self.chessboard[0][7].as_ref().unwrap().role == PieceRole::Rook && !self.chessboard[0][7].as_ref().unwrap().has_moved {
-                castling_availability.push_str("k");
-            }
-            if self.chessboard[0][0].is_some() && self.chessboard[0][0].as_ref().unwrap().role == PieceRole::Rook && !self.chessboard[0][0].as_ref().unwrap().has_moved {
-                castling_availability.push_str("q");
-            }
-        }
-        if castling_availability.len() == 0 {castling_availability.push_str("-");}
+        castling_availability.push_str(&self.position.castling_fen());
 
         // en passant
-        if self.ep_square != None {
-            en_passant.push_str(&format!("{}{}", (97+self.ep_square.clone().unwrap()[1]) as u8 as char, (56-self.ep_square.clone().unwrap()[0]) as u8 as char));
-        }
-        else {
-            en_passant.push_str("-");
+        match self.position.ep_square {
+            Some(index) => en_passant.push_str(&square_name(index as usize)),
+            None => en_passant.push('-')
         }
 
         // halfmove clock
-        halfmove_clock.push_str(&self.halfmove.to_string());
+        halfmove_clock.push_str(&self.position.halfmove.to_string());
 
         // fullmove clock
-        fullmove_clock.push_str(&self.fullmove.to_string());
+        fullmove_clock.push_str(&self.position.fullmove.to_string());
 
         // add all chapters together separated by spaces
 
         return format!("{} {} {} {} {} {}", placement_data, active_color, castling_availability, en_passant, halfmove_clock, fullmove_clock);
 
     }
-    
-    /// If a piece is standing on the given tile, return all possible 
+
+    /// If a piece is standing on the given tile, return all possible
     /// new positions of that piece.
     pub fn get_possible_moves(&self, _position: &str) -> Option<Vec<String>> {
         // Check if state is allowed
         if self.state == GameState::Checkmate || self.state == GameState::Stalemate {return None;}
-        // Convert from algebraic notation to vector
-        let pos = vec![56-_position.chars().nth(1).unwrap() as i8, _position.chars().nth(0).unwrap() as i8 - 97]; 
+        // Convert from algebraic notation to board index
+        let pos = parse_square(_position)?;
         // Check that piece is on square
-        if self.chessboard[pos[0] as usize][pos[1] as usize].is_none() {return None;}
-        // Clone piece
-        let piece = self.chessboard[pos[0] as usize][pos[1] as usize].as_ref().unwrap();
+        let piece = self.position.piece_at(pos)?;
 
         // convert all possible moves to algebraic notation
         let mut moves_algebraic: Vec<String> = Vec::new();
-        for move_vec in piece.available_moves(&self, pos.clone(), false, false).unwrap() {
-            moves_algebraic.push(format!("{}{}", (97+move_vec[1]) as u8 as char, (56-move_vec[0] as u8) as char));
+        if piece.color == self.position.turn {
+            // promotions are listed once per square, not once per role
+            for mv in self.legal_moves().iter().filter(|mv| mv.from.index() == pos) {
+                if mv.promotion.is_none() || mv.promotion == Some(PieceRole::Queen) {
+                    moves_algebraic.push(mv.to.to_string());
+                }
+            }
+        }
+        else {
+            // the cache only holds moves for the side to move
+            for index in piece.available_moves(&self.position, pos, false) {
+                moves_algebraic.push(square_name(index));
+            }
         }
         return Some(moves_algebraic);
     }
 
-    /// Returns either true or false if the given color is in check.
-    fn in_check(board: &Game, _turn: Color) -> bool {
-        // find king position
-        let mut king_pos: Vec<i8> = Vec::new();
-        'find_king: for (row_index, row) in board.chessboard.iter().enumerate() {
-            for (column_index, piece) in row.iter().enumerate() {
-                match piece {
-                    Some(piece) => {
-                        if piece.color == _turn && piece.role == PieceRole::King {
-                            king_pos = vec![row_index as i8, column_index as i8];
-                            break 'find_king;
-                        }
-                    }
-                    None => ()
-                }
-            }
+    /// Get every legal move for the player whose turn it is. Promotions are listed once for
+    /// every role the pawn can promote to.
+    pub fn get_all_possible_moves(&self) -> Vec<Move> {
+        return self.legal_moves().clone();
+    }
+
+    /// Counts the legal moves of every piece of the player whose turn it is, in board order
+    /// (a8 first), also the pieces that can't move. Every promotion counts as a move, and drops
+    /// aren't counted. The counts are taken from the move list the game keeps (the one
+    /// get_all_possible_moves copies), which is only generated if it isn't there yet.
+    pub fn legal_move_counts(&self) -> Vec<(Square, u32)> {
+        let mut counts = [0u32; 64];
+        for mv in self.legal_moves().iter().filter(|mv| !mv.is_drop()) {
+            counts[mv.from.index()] += 1;
         }
+        return self.position.occupied().filter(|(_, piece)| piece.color == self.position.turn)
+            .map(|(index, _)| (Square(index as u8), counts[index])).collect();
+    }
 
-        // check if any enemy piece can attack the king position, if so return true
-        for (row_index, row) in board.chessboard.iter().enumerate() {
-            for (column_index, piece) in row.iter().enumerate() {
-                match piece {
-                    Some(piece) => {
-                        if piece.color != _turn {
-                            for pos in  piece.available_moves(&board, vec![row_index as i8, column_index as i8], true, true).unwrap() {
-                                if pos == king_pos {
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                    None => ()
-                }
-            }
+    /// Counts the legal moves of the player whose turn it is, 0 after checkmate or stalemate.
+    pub fn legal_move_count(&self) -> u32 {
+        return self.legal_moves().len() as u32;
+    }
+
+    /// Returns true if the move is legal for the player whose turn it is.
+    pub fn is_legal_move(&self, mv: &Move) -> bool {
+        return self.legal_moves().contains(mv);
+    }
+
+    /// Get the legal moves of one stage for the player whose turn it is. Captures (including en
+    /// passant and capturing promotions) are ordered so that captures of pieces worth at least as
+    /// much as the capturing piece come first. Checks are the non-capturing moves that give check,
+    /// directly or by discovery, and Quiets are all remaining moves. Every legal move is in
+    /// exactly one stage.
+    pub fn generate(&self, stage: MoveStage) -> Vec<Move> {
+        if self.state == GameState::Checkmate || self.state == GameState::Stalemate {return Vec::new();}
+        let mut moves: Vec<Move> = match self.legal_moves.get() {
+            // no need to generate anything if all moves are already known
+            Some(legal_moves) => legal_moves.iter().copied().filter(|&mv| self.position.move_stage(mv) == stage).collect(),
+            None => self.position.stage_moves(stage)
+        };
+        if stage == MoveStage::Captures {
+            // winning captures first, sort_by_key is stable so ties keep the generation order
+            moves.sort_by_key(|mv| {
+                let victim = self.position.piece_at(mv.to.index()).map_or(PieceRole::Pawn.value(), |piece| piece.role.value());
+                let attacker = self.position.piece_at(mv.from.index()).unwrap().role.value();
+                victim < attacker
+            });
         }
-        return false
+        return moves;
+    }
+
+    /// Get all legal moves for the player whose turn it is, one stage at a time: captures, then
+    /// checks, then quiet moves. A stage is only generated once the previous one is used up.
+    pub fn ordered_moves(&self) -> impl Iterator<Item = Move> + '_ {
+        return [MoveStage::Captures, MoveStage::Checks, MoveStage::Quiets].into_iter().flat_map(move |stage| self.generate(stage));
     }
 
+    /// The repetition keys of every position of the game so far, the current position last.
+    fn repetition_keys(&self) -> Vec<Position> {
+        let mut keys: Vec<Position> = self.history.iter().map(|entry| entry.position.repetition_key()).collect();
+        keys.push(self.position.repetition_key());
+        return keys;
+    }
+
+    /// Get the cached legal moves, generating them if the position has changed since last time.
+    fn legal_moves(&self) -> &Vec<Move> {
+        return self.legal_moves.get_or_init(|| {
+            if self.state == GameState::Checkmate || self.state == GameState::Stalemate {Vec::new()} else {self.position.legal_moves()}
+        });
+    }
+
+    /// Counts the number of positions reachable with exactly `depth` legal moves (perft).
+    /// Every promotion is counted as a separate move. Used to validate the move generation.
+    pub fn perft(&self, depth: u32) -> u64 {
+        return self.position.perft(depth);
+    }
+
+}
 
+impl Default for Game {
+    fn default() -> Game {
+        Game::new()
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Piece {
     pub role: PieceRole,
     pub color: Color,
     has_moved: bool,
+    id: u8,
 }
 
 impl Piece {
-    // Every piece has an enum role, color, and a bool if it has moved or not, which is only used for castling
+    // Every piece has an enum role, color, and a bool if it has moved or not, which is used for castling and pawn double steps
     fn new(role: PieceRole, color: Color, has_moved: bool) -> Piece {
         Piece {
             role,
             color,
             has_moved,
+            id: 0,
         }
     }
-    fn available_moves(&self, game:&Game, pos: Vec<i8>, only_attack_moves: bool, ignore_check: bool) -> Option<Vec<Vec<i8>>> {
-        //println!("huh");
-        fn move_okay(move_vec: Vec<i8>) -> bool {return move_vec[0] >= 0 && move_vec[0] <= 7 && move_vec[1] >= 0 && move_vec[1] <= 7;}
-        let board = &game.chessboard;
-        let mut moves: Vec<Vec<i8>> = Vec::new();
-        // color as i8
+
+    /// Get the id of the piece. Every piece gets its own id when the game is created or loaded
+    /// from FEN, and keeps it when it moves, castles or promotes, so a GUI can tell which piece
+    /// went where. The id of a captured piece is never reused. Pieces that are not read from
+    /// a game have the id 0.
+    pub fn id(&self) -> u8 {
+        return self.id;
+    }
+
+    /// The FEN letter of the piece, in capitals for White.
+    pub(crate) fn letter(self) -> char {
+        let letter = match self.role {
+            PieceRole::Pawn => 'p',
+            PieceRole::Rook => 'r',
+            PieceRole::Knight => 'n',
+            PieceRole::Bishop => 'b',
+            PieceRole::Queen => 'q',
+            PieceRole::King => 'k'
+        };
+        return if self.color == Color::White {letter.to_ascii_uppercase()} else {letter};
+    }
+
+    /// Packs the piece into a byte: bits 0-2 hold the role (1-6), bit 3 is set for black and
+    /// bit 4 is set if the piece has moved. 0 is never a piece, so it marks an empty square.
+    fn to_u8(self) -> u8 {
+        let role: u8 = match self.role {
+            PieceRole::Pawn => 1,
+            PieceRole::Rook => 2,
+            PieceRole::Knight => 3,
+            PieceRole::Bishop => 4,
+            PieceRole::Queen => 5,
+            PieceRole::King => 6
+        };
+        return role | if self.color == Color::Black {8} else {0} | if self.has_moved {16} else {0};
+    }
+
+    /// Unpacks a byte made by to_u8, or returns None for an empty square.
+    fn from_u8(byte: u8) -> Option<Piece> {
+        let role = match byte & 7 {
+            1 => PieceRole::Pawn,
+            2 => PieceRole::Rook,
+            3 => PieceRole::Knight,
+            4 => PieceRole::Bishop,
+            5 => PieceRole::Queen,
+            6 => PieceRole::King,
+            _ => return None
+        };
+        return Some(Piece::new(role, if byte & 8 != 0 {Color::Black} else {Color::White}, byte & 16 != 0));
+    }
+
+    /// Returns the board indexes the piece on index `pos` can move to. Unless ignore_check is
+    /// set, moves that would leave the own king in check are removed and castling is included.
+    fn available_moves(&self, position: &Position, pos: usize, ignore_check: bool) -> Vec<usize> {
+        let board = &position.board;
+        let (row, column) = ((pos / 8) as i8, (pos % 8) as i8);
+        let index = |row: i8, column: i8| -> usize {(row * 8 + column) as usize};
+        let mut moves: Vec<usize> = Vec::new();
+        let mut castling: Vec<usize> = Vec::new();
+        // sliding pieces move in their directions until they hit a piece or the edge of the board
+        let mut slide = |directions: &[(i8, i8)]| {
+            let mut dir_bools: Vec<bool> = vec![true; directions.len()];
+            for offset in 1..=7 {
+                for dir_index in 0..directions.len() {
+                    if !dir_bools[dir_index] {continue}
+                    let (new_row, new_column) = (row + offset * directions[dir_index].0, column + offset * directions[dir_index].1);
+                    // check if move is okay
+                    if move_okay(new_row, new_column) {
+                        // add move
+                        moves.push(index(new_row, new_column));
+                        // if piece is in the way, stop checking that direction
+                        if board[index(new_row, new_column)] != 0 {
+                            dir_bools[dir_index] = false;
+                        }
+                    }
+                    else {
+                        dir_bools[dir_index] = false;
+                    }
+                }
+            }
+        };
         match self.role {
             PieceRole::Pawn => {
                 // -1 for white, 1 for black
                 let white_black: i8 = if self.color == Color::White {-1} else {1};
-                // check diagonally left
-                if move_okay(vec![pos[0]+white_black, pos[1] - 1]) && (board[(pos[0]+white_black) as usize][(pos[1] - 1) as usize].is_some() || game.ep_square == Some(vec![pos[0]+white_black,pos[1]-1])) {moves.push(vec![pos[0]+white_black, pos[1] - 1])}
-                
-                // check diagonally right
-                if move_okay(vec![pos[0]+white_black, pos[1] + 1]) && (board[(pos[0]+white_black) as usize][(pos[1] + 1) as usize].is_some()|| game.ep_square == Some(vec![pos[0]+white_black,pos[1]+1])) {moves.push(vec![pos[0]+white_black, pos[1] + 1])}
-                
-                // check one ahead
-                if !only_attack_moves && move_okay(vec![pos[0]+white_black, pos[1]]) && board[(pos[0]+white_black) as usize][(pos[1]) as usize].is_none() {
-                    moves.push(vec![pos[0]+white_black, pos[1]]);
-                    // check two ahead
-                    if !self.has_moved && move_okay(vec![pos[0]+2*white_black,pos[1]]) && board[(pos[0]+2*white_black) as usize][(pos[1]) as usize].is_none() {
-                        moves.push(vec![pos[0]+2*white_black, pos[1]]);
+                // check diagonally left and right
+                for column_offset in [-1, 1] {
+                    if move_okay(row + white_black, column + column_offset) && (board[index(row + white_black, column + column_offset)] != 0 || position.ep_square == Some(index(row + white_black, column + column_offset) as u8)) {
+                        moves.push(index(row + white_black, column + column_offset));
                     }
                 }
-            }
-            PieceRole::Rook => {
-                // directions are up, right, down, left (clockwise)
-                let mut dir_bools: Vec<bool> = vec![true, true, true, true];
-                let dir_vectors: Vec<Vec<i8>> = vec![vec![-1, 0], vec![0, 1], vec![1, 0], vec![0, -1]];  
-                for offset in 1..=7 {
-                    for dir_index in 0..=3 {
-                        if dir_bools[dir_index] == false {continue}
-                        // check if move is okay
-                        if move_okay(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]) {
-                            // add move
-                            moves.push(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]);
-                            // if piece is in the way, stop checking that direction
-                            if board[(pos[0]+offset*dir_vectors[dir_index][0]) as usize][(pos[1]+offset*dir_vectors[dir_index][1]) as usize].is_some() {
-                                dir_bools[dir_index] = false;
-                            }
-                        }
+                // check one ahead
+                if move_okay(row + white_black, column) && board[index(row + white_black, column)] == 0 {
+                    moves.push(index(row + white_black, column));
+                    // check two ahead, which the white pawns on the first two ranks of Horde always can
+                    let horde_pawn = position.variant == Variant::Horde && self.color == Color::White && row >= 6;
+                    if (!self.has_moved || horde_pawn) && move_okay(row + 2 * white_black, column) && board[index(row + 2 * white_black, column)] == 0 {
+                        moves.push(index(row + 2 * white_black, column));
                     }
                 }
             }
-            PieceRole::Knight => {
+            PieceRole::Rook => slide(&ROOK_DIRECTIONS),
+            PieceRole::Bishop => slide(&BISHOP_DIRECTIONS),
+            PieceRole::Queen => slide(&QUEEN_DIRECTIONS),
+            PieceRole::Knight | PieceRole::King => {
                 // check all squares clockwise
-                if move_okay(vec![pos[0]-2, pos[1]+1]) {moves.push(vec![pos[0]-2, pos[1]+1])}
-                if move_okay(vec![pos[0]-1, pos[1]+2]) {moves.push(vec![pos[0]-1, pos[1]+2])}
-                if move_okay(vec![pos[0]+1, pos[1]+2]) {moves.push(vec![pos[0]+1, pos[1]+2])}
-                if move_okay(vec![pos[0]+2, pos[1]+1]) {moves.push(vec![pos[0]+2, pos[1]+1])}
-                if move_okay(vec![pos[0]+2, pos[1]-1]) {moves.push(vec![pos[0]+2, pos[1]-1])}
-                if move_okay(vec![pos[0]+1, pos[1]-2]) {moves.push(vec![pos[0]+1, pos[1]-2])}
-                if move_okay(vec![pos[0]-1, pos[1]-2]) {moves.push(vec![pos[0]-1, pos[1]-2])}
-                if move_okay(vec![pos[0]-2, pos[1]-1]) {moves.push(vec![pos[0]-2, pos[1]-1])}
-            }
-            PieceRole::Bishop => {
-                // directions are up-right, down-right, down-left, up-left (clockwise)
-                let mut dir_bools: Vec<bool> = vec![true, true, true, true];
-                let dir_vectors: Vec<Vec<i8>> = vec![vec![-1, 1], vec![1, 1], vec![1, -1], vec![-1, -1]];
-                for offset in 1..=7 {
-                    for dir_index in 0..=3 {
-                        if dir_bools[dir_index] == false {continue}
-                        // check if move is okay
-                        if move_okay(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]) {
-                            // add move
-                            moves.push(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]);
-                            // if piece is in the way, stop checking that direction
-                            if board[(pos[0]+offset*dir_vectors[dir_index][0]) as usize][(pos[1]+offset*dir_vectors[dir_index][1]) as usize].is_some() {
-                                dir_bools[dir_index] = false;
-                            }
-                        }
-                    }
+                let offsets = if self.role == PieceRole::Knight {KNIGHT_OFFSETS} else {QUEEN_DIRECTIONS};
+                for (row_offset, column_offset) in offsets {
+                    if move_okay(row + row_offset, column + column_offset) {moves.push(index(row + row_offset, column + column_offset))}
                 }
-            }
-            PieceRole::Queen => {
-                // directions are up, up-right, right, down-right, down, down-left, left, up-left (clockwise)
-                let mut dir_bools: Vec<bool> = vec![true, true, true, true, true, true, true, true];
-                let dir_vectors: Vec<Vec<i8>> = vec![vec![-1, 0], vec![-1, 1], vec![0, 1], vec![1, 1], vec![1, 0], vec![1, -1], vec![0, -1], vec![-1, -1]];
-                for offset in 1..=7 {
-                    for dir_index in 0..=7 {
-                        if dir_bools[dir_index] == false {continue}
-                        // check if move is okay
-                        if move_okay(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]) {
-                            // add move
-                            moves.push(vec![pos[0]+offset*dir_vectors[dir_index][0], pos[1]+offset*dir_vectors[dir_index][1]]);
-                            // if piece is in the way, stop checking that direction
-                            if board[(pos[0]+offset*dir_vectors[dir_index][0]) as usize][(pos[1]+offset*dir_vectors[dir_index][1]) as usize].is_some() {
-                                dir_bools[dir_index] = false;
-                            }
-                        }
-                    }
+
+                // in Chess960 the king castles by taking its own rook, see Position::castling_rooks_960
+                if self.role == PieceRole::King && !ignore_check && !self.has_moved && position.variant == Variant::Chess960 {
+                    castling = position.castling_rooks_960(pos);
                 }
-            }
-            PieceRole::King => {
-                // check all squares clockwise
-                //println!("UNO {:?}",self);
-                if move_okay(vec![pos[0]-1, pos[1]]) {moves.push(vec![pos[0]-1, pos[1]])}
-                if move_okay(vec![pos[0]-1, pos[1]+1]) {moves.push(vec![pos[0]-1, pos[1]+1])}
-                if move_okay(vec![pos[0], pos[1]+1]) {moves.push(vec![pos[0], pos[1]+1])}
-                if move_okay(vec![pos[0]+1, pos[1]+1]) {moves.push(vec![pos[0]+1, pos[1]+1])}
-                if move_okay(vec![pos[0]+1, pos[1]]) {moves.push(vec![pos[0]+1, pos[1]])}
-                if move_okay(vec![pos[0]+1, pos[1]-1]) {moves.push(vec![pos[0]+1, pos[1]-1])}
-                if move_okay(vec![pos[0], pos[1]-1]) {moves.push(vec![pos[0], pos[1]-1])}
-                if move_okay(vec![pos[0]-1, pos[1]-1]) {moves.push(vec![pos[0]-1, pos[1]-1])}
-
-                // queenside castling
-                if !ignore_check && !Game::in_check(&Game{state: GameState::InProgress, chessboard: board.clone(), turn: self.color, ep_square:None, halfmove:0, fullmove:1}, self.color) && !self.has_moved && board[pos[0] as usize][(pos[1]-4) as usize].is_some()
-                && board[pos[0] as usize][(pos[1]-4) as usize].as_ref().unwrap().role == PieceRole::Rook && !board[pos[0] as usize][(pos[1]-4) as usize].as_ref().unwrap().has_moved {
-                    for i in 1..=3 {
-                        if board[pos[0] as usize][(pos[1]-i) as usize].is_some() {break;}
-                        if i != 3 {
-                            let board_copy = &mut Game{state: GameState::InProgress, chessboard: board.clone(), turn: self.color, ep_square:None, halfmove:0, fullmove:1};
-                            board_copy.chessboard[pos[0] as usize][pos[1] as usize] = None;
-                            board_copy.chessboard[pos[0] as usize][(pos[1]-i) as usize] = Some(Piece::new(PieceRole::King, self.color, true));
-                            if Game::in_check(board_copy, self.color) {break;}
+                // castling requires an unmoved king on the e-file that is not in check, an unmoved rook in the corner,
+                // empty squares between them and that the king does not pass through check
+                else if self.role == PieceRole::King && !ignore_check && !self.has_moved && column == 4 && position.variant != Variant::Antichess
+                && !position.is_attacked(pos, self.color.opposite()) {
+                    let castling_rook = |rook_index: usize| -> bool {
+                        match position.piece_at(rook_index) {
+                            Some(rook) => rook.role == PieceRole::Rook && rook.color == self.color && !rook.has_moved,
+                            None => false
                         }
-                        else {moves.push(vec![pos[0], (pos[1]-2)])}
+                    };
+                    // queenside castling
+                    if castling_rook(pos - 4) && board[pos - 1] == 0 && board[pos - 2] == 0 && board[pos - 3] == 0
+                    && !position.is_attacked(pos - 1, self.color.opposite()) && !position.is_attacked(pos - 2, self.color.opposite()) {
+                        moves.push(pos - 2);
                     }
-                }
-
-                // kingside castling
-                if !ignore_check && !Game::in_check(&Game{state: GameState::InProgress, chessboard: board.clone(), turn: self.color, ep_square:None, halfmove:0, fullmove:1}, self.color) && !self.has_moved && board[pos[0] as usize][(pos[1]+3) as usize].is_some()
-                && board[pos[0] as usize][(pos[1]+3) as usize].as_ref().unwrap().role == PieceRole::Rook && !board[pos[0] as usize][(pos[1]+3) as usize].as_ref().unwrap().has_moved {
-                    for i in 1..=2 {
-                        if board[pos[0] as usize][(pos[1]+i) as usize].is_some() {break;}
-                        let board_copy = &mut Game{state: GameState::InProgress, chessboard: board.clone(), turn: self.color, ep_square: None, halfmove:0, fullmove:1};
-                        board_copy.chessboard[pos[0] as usize][pos[1] as usize] = None;
-                        board_copy.chessboard[pos[0] as usize][(pos[1]+i) as usize] = Some(Piece::new(PieceRole::King, self.color, true));
-                        if Game::in_check(board_copy, self.color) {break;}
-                        if i == 2 {moves.push(vec![pos[0], (pos[1]+2)])}
+                    // kingside castling
+                    if castling_rook(pos + 3) && board[pos + 1] == 0 && board[pos + 2] == 0
+                    && !position.is_attacked(pos + 1, self.color.opposite()) && !position.is_attacked(pos + 2, self.color.opposite()) {
+                        moves.push(pos + 2);
                     }
                 }
             }
         }
-        
 
-        // remove squares with own color (is_none() prevents error when accessing None)
-        moves.retain(|x| board[x[0] as usize][x[1] as usize].is_none() || board[x[0] as usize][x[1] as usize].as_ref().unwrap().color != self.color);
-        //println!("DOS {:?} {:?}",self, moves);
+        // remove squares with own color, but for the rooks of castling in Chess960
+        moves.retain(|&x| board[x] == 0 || Piece::from_u8(board[x]).unwrap().color != self.color);
+        moves.extend(castling);
         // remove squares that would put king in check
-        if ignore_check {return Some(moves)}
-        let moves_copy = moves.clone();
-        for move_vec in moves_copy {
-            let mut board_copy = game.clone();
-            board_copy.make_move_internal(&format!("{}{}", (97+pos[1]) as u8 as char, (56-pos[0]) as u8 as char), &format!("{}{}", (97+move_vec[1]) as u8 as char, (56-move_vec[0]) as u8 as char), true);
-            println!("{:?}", board_copy);
-            //board_copy[move_vec[0] as usize][move_vec[1] as usize] = Some(Piece::new(self.role, self.color, true));
-            //board_copy[pos[0] as usize][pos[1] as usize] = None;
-            if Game::in_check(&board_copy, self.color) {
-                moves.remove(moves.iter().position(|x| *x == move_vec).unwrap());
-            }
-        }
+        // the king is an ordinary piece in Antichess
+        if ignore_check || position.variant == Variant::Antichess {return moves}
+        moves.retain(|&to| {
+            let mut position_copy = *position;
+            position_copy.apply_move(Move::new(pos, to, None));
+            !position_copy.in_check(self.color)
+        });
 
-        return Some(moves);
+        return moves;
     }
 }
 
 impl fmt::Debug for Game {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut board_string = String::new();
-        for row in self.chessboard.iter() {
+        for row in self.get_board().iter() {
             for piece in row.iter() {
                 match piece {
-                    Some(piece) => {let debug_notation: &str = 
+                    Some(piece) => {let debug_notation: &str =
                         match piece.role {
                             PieceRole::Pawn => "p",
                             PieceRole::Rook => "r",
@@ -617,12 +1661,12 @@ impl fmt::Debug for Game {
                             PieceRole::King => "k"
                         };
                         board_string += &(if piece.color == Color::White {debug_notation.to_uppercase()} else {debug_notation.to_lowercase()});
-                        board_string.push_str(" ");
+                        board_string.push(' ');
                     }
                     None => board_string.push_str("* ")
                 }
             }
-            board_string.push_str("\n");
+            board_string.push('\n');
         }
         write!(f, "{}", board_string)
     }
@@ -636,6 +1680,11 @@ impl fmt::Debug for Game {
 mod tests {
     use super::Game;
     use super::GameState;
+    use std::sync::{Arc, Mutex};
+
+    use super::{ChessError, Color, FenError, FenField, Move, MoveStage, Piece, PieceRole, Position, Square};
+    use super::{EventLogOptions, GameEvent, LogEntry};
+    use crate::clock::MockTime;
 
     // check test framework
     #[test]
@@ -654,6 +1703,7 @@ mod tests {
 
     //check that loading a fen-string works
     #[test]
+    #[allow(deprecated)]
     fn check_load_fen() {
         let game1 = Game::new();
         let mut game2 = Game::new();
@@ -661,8 +1711,62 @@ mod tests {
         assert_eq!(format!("{:?}",game1), format!("{:?}",game2));
     }
 
+    // plays the knights out and back, which repeats the position
+    fn knights_out_and_back(game: &mut Game) {
+        for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            game.play_move(&coordinate_move(mv)).unwrap();
+        }
+    }
+
+    //check that a reset forgets the moves, the repetitions and the event log
+    #[test]
+    fn check_load_fen_reset() {
+        let mut game1 = Game::new();
+        game1.start_event_log_with(EventLogOptions::default(), MockTime::new());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&events);
+        game1.subscribe(Box::new(move |event| recorder.lock().unwrap().push(*event)));
+        knights_out_and_back(&mut game1);
+        assert_eq!(game1.load_fen_reset("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 7 30"), Ok(GameState::InProgress));
+        assert_eq!((game1.get_halfmove(), game1.get_fen()), (7, String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 7 30")));
+        assert_eq!(game1.undo(), None);
+        assert!(game1.event_log().is_empty());
+        assert_eq!(events.lock().unwrap().last(), Some(&GameEvent::PositionSet));
+        // the position before the reset doesn't count, so twice more is the third time
+        knights_out_and_back(&mut game1);
+        assert!(!game1.is_threefold_repetition());
+        knights_out_and_back(&mut game1);
+        assert!(game1.is_threefold_repetition());
+        assert_eq!(game1.event_log().len(), 8);
+        // a bad string changes nothing
+        assert!(matches!(game1.load_fen_reset("8/8/8 w - - 0 1"), Err(ChessError::InvalidFen(_))));
+        assert!(game1.is_threefold_repetition());
+    }
+
+    //check that continuing keeps the event log but counts repetitions from the new position
+    #[test]
+    fn check_load_fen_continue() {
+        let mut game1 = Game::new();
+        game1.start_event_log_with(EventLogOptions::default(), MockTime::new());
+        knights_out_and_back(&mut game1);
+        knights_out_and_back(&mut game1);
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(game1.load_fen_continue(fen), Ok(GameState::InProgress));
+        assert!(!game1.is_threefold_repetition());
+        assert_eq!(game1.undo(), None);
+        assert_eq!(game1.event_log().len(), 9);
+        assert_eq!(game1.event_log()[8].entry, LogEntry::PositionSet(String::from(fen)));
+        assert_eq!(game1.event_log()[8].actor, Color::White);
+        knights_out_and_back(&mut game1);
+        assert_eq!(game1.undo(), Some(coordinate_move("f6g8")));
+        assert_eq!(game1.event_log()[9].entry, LogEntry::Move(String::from("g1f3")));
+        assert!(game1.load_fen_continue("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0").is_err());
+        assert_eq!(game1.event_log().len(), 14);
+    }
+
     //check that making a fen-string works
     #[test]
+    #[allow(deprecated)]
     fn check_get_fen() {
         let game1 = Game::new();
         let mut game2 = Game::new();
@@ -672,6 +1776,7 @@ mod tests {
 
     //check that making a move works
     #[test]
+    #[allow(deprecated)]
     fn check_make_move() {
         let mut game1 = Game::new();
         let mut game2 = Game::new();
@@ -704,10 +1809,420 @@ mod tests {
         assert_eq!(game1.get_possible_moves("b1"), Some(vec!["c3".to_string(),"a3".to_string()]));
     }
 
+    //check the move counts of every piece in the start position and in check
+    #[test]
+    fn check_legal_move_counts() {
+        let game1 = Game::new();
+        assert_eq!(game1.legal_move_count(), 20);
+        let counts = game1.legal_move_counts();
+        assert_eq!(counts.len(), 16);
+        for (square, count) in counts {
+            let expected = match square.to_string().as_str() {
+                "a2" | "b2" | "c2" | "d2" | "e2" | "f2" | "g2" | "h2" | "b1" | "g1" => 2,
+                _ => 0
+            };
+            assert_eq!(count, expected, "{}", square);
+        }
+        // in check only the king and the pieces that can block or take may move
+        let game1 = Game::from_fen("4k3/8/8/8/8/4N3/3PP3/r3K3 w - - 0 1").unwrap();
+        assert_eq!(game1.get_game_state(), GameState::Check);
+        let counts: Vec<(String, u32)> = game1.legal_move_counts().into_iter().map(|(square, count)| (square.to_string(), count)).collect();
+        assert_eq!(counts, [("e3".to_string(), 1), ("d2".to_string(), 0), ("e2".to_string(), 0), ("e1".to_string(), 1)]);
+        assert_eq!(game1.legal_move_count(), 2);
+        assert_eq!(game1.legal_move_count() as usize, game1.get_all_possible_moves().len());
+    }
+
     //check that checking for check works
     #[test]
     fn check_check() {
         let game1 = Game::new();
-        assert_eq!(Game::in_check(&game1, game1.turn), false); 
+        assert!(!game1.position.in_check(game1.get_turn_color()));
+    }
+
+    //check that the packed position stays small: 64 one-byte squares, two u32 clocks, the
+    //u64 pawn key, a two-byte Option<u8> en passant square, a one-byte turn and variant, the
+    //ten pocket counts and the u64 of promoted pieces, padded to 104 bytes
+    #[test]
+    fn check_position_size() {
+        assert_eq!(std::mem::size_of::<Position>(), 104);
+    }
+
+    //check that packing and unpacking a piece gives back the same piece
+    #[test]
+    fn check_piece_packing() {
+        for role in [PieceRole::Pawn, PieceRole::Rook, PieceRole::Knight, PieceRole::Bishop, PieceRole::Queen, PieceRole::King] {
+            for color in [Color::White, Color::Black] {
+                for has_moved in [false, true] {
+                    let piece = Piece::new(role, color, has_moved);
+                    assert_ne!(piece.to_u8(), 0);
+                    assert_eq!(Piece::from_u8(piece.to_u8()), Some(piece));
+                }
+            }
+        }
+        assert_eq!(Piece::from_u8(0), None);
+    }
+
+    //check that getting pieces from the board works
+    #[test]
+    fn check_get_piece() {
+        let game1 = Game::new();
+        assert_eq!(game1.get_piece("e1").map(|piece| (piece.role, piece.color)), Some((PieceRole::King, Color::White)));
+        assert_eq!(game1.get_piece("d8").unwrap().role, PieceRole::Queen);
+        assert_eq!(game1.get_piece("e4"), None);
+        assert_eq!(game1.get_piece("i9"), None);
+        assert_eq!(game1.get_board()[7][4], game1.get_piece("e1"));
     }
-}
\ No newline at end of file
+
+    //check the occupied squares of the start position, of two kings and of a small position
+    #[test]
+    fn check_pieces() {
+        assert_eq!(Game::new().pieces().count(), 32);
+        assert!(Game::new().pieces().all(|(square, piece)| Game::new().get_piece(&square.to_string()) == Some(piece)));
+        assert_eq!(Game::from_fen("8/8/3k4/8/8/4K3/8/8 w - - 0 1").unwrap().pieces().count(), 2);
+        let game1 = Game::from_fen("r3k3/8/8/8/4P3/8/8/4K2R b K e3 0 1").unwrap();
+        let pieces: Vec<(String, char)> = game1.pieces().map(|(square, piece)| (square.to_string(), piece.letter())).collect();
+        assert_eq!(pieces, [("a8".to_string(), 'r'), ("e8".to_string(), 'k'), ("e4".to_string(), 'P'), ("e1".to_string(), 'K'), ("h1".to_string(), 'R')]);
+        // the FEN is written from the pieces, also with full and empty rows at the edges
+        for fen in ["r3k3/8/8/8/4P3/8/8/4K2R b K e3 0 1", "8/8/8/8/8/8/8/K6k w - - 0 1", "k7/8/8/8/8/8/8/7K w - - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"] {
+            assert_eq!(Game::from_fen(fen).unwrap().get_fen(), fen);
+        }
+    }
+
+    //check that perft counts match the reference values for the standard test positions
+    #[test]
+    #[allow(deprecated)]
+    fn check_perft() {
+        let game1 = Game::new();
+        assert_eq!(game1.perft(1), 20);
+        assert_eq!(game1.perft(2), 400);
+        assert_eq!(game1.perft(3), 8902);
+
+        let positions = [
+            // "kiwipete"
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97862),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, 9467),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, 62379),
+        ];
+        for (fen, depth, nodes) in positions {
+            let mut game2 = Game::new();
+            game2.load_fen(fen.to_string());
+            assert_eq!(game2.perft(depth), nodes, "{}", fen);
+        }
+        // positions 4 and 5 promote in the first plies, every promotion counted once per piece
+        let shallow = [
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", [6, 264]),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", [44, 1486]),
+        ];
+        for (fen, nodes) in shallow {
+            let game2 = Game::from_fen(fen).unwrap();
+            assert_eq!([game2.perft(1), game2.perft(2)], nodes, "{}", fen);
+        }
+    }
+
+    //check that asking for moves several times gives the same answer
+    #[test]
+    fn check_cached_moves_repeat() {
+        let game1 = Game::new();
+        assert_eq!(game1.get_all_possible_moves().len(), 20);
+        assert_eq!(game1.get_all_possible_moves(), game1.get_all_possible_moves());
+        for square in ["a2", "b1", "g1", "e2"] {
+            assert_eq!(game1.get_possible_moves(square), game1.get_possible_moves(square));
+        }
+    }
+
+    //check that the cached moves are thrown away when the position changes
+    #[test]
+    #[allow(deprecated)]
+    fn check_cached_moves_invalidated() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.get_possible_moves("f1"), Some(vec![]));
+        game1.make_move("e2", "e4");
+        game1.make_move("e7", "e5");
+        assert_eq!(game1.get_possible_moves("f1"), Some(vec!["e2".to_string(), "d3".to_string(), "c4".to_string(), "b5".to_string(), "a6".to_string()]));
+
+        let castle = Move { from: Square::from_algebraic("e1").unwrap(), to: Square::from_algebraic("g1").unwrap(), promotion: None };
+        assert!(!game1.is_legal_move(&castle));
+        game1.load_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPPBPPP/RNBQK2R w KQkq - 2 3".to_string());
+        assert!(game1.is_legal_move(&castle));
+    }
+
+    //check that promotions are listed once per role but only once per square as a string
+    #[test]
+    #[allow(deprecated)]
+    fn check_promotion_moves() {
+        let mut game1 = Game::new();
+        game1.load_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1".to_string());
+        assert_eq!(game1.get_all_possible_moves().iter().filter(|mv| mv.from.to_string() == "e7").count(), 4);
+        assert_eq!(game1.get_possible_moves("e7"), Some(vec!["e8".to_string()]));
+        assert_eq!(game1.get_all_possible_moves()[0].to_string(), "e7e8q");
+    }
+
+    //check that a game can be sent to another thread
+    #[test]
+    fn check_game_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Game>();
+    }
+
+    // an engine that always plays the first legal move, to play games against
+    pub(crate) struct FirstMoveEngine;
+
+    impl crate::engine::Engine for FirstMoveEngine {
+        fn choose_move(&mut self, game: &Game, _limits: &crate::engine::SearchLimits) -> Option<crate::engine::EngineMove> {
+            let mv = *game.get_all_possible_moves().first()?;
+            return Some(crate::engine::EngineMove { mv, score: None, pv: vec![mv] });
+        }
+
+        fn name(&self) -> &str {
+            return "first move";
+        }
+    }
+
+    // plays a random number of random legal moves from the start position and from "kiwipete",
+    // to get a spread of positions for the property tests
+    #[allow(deprecated)]
+    pub(crate) fn random_games(count: usize, seed: u64) -> Vec<Game> {
+        let mut state = seed;
+        let mut next = move |n: usize| -> usize {
+            // xorshift
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            return (state % n as u64) as usize;
+        };
+        let mut games: Vec<Game> = Vec::new();
+        for i in 0..count {
+            let mut game = Game::new();
+            if i % 2 == 1 {
+                game.load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string());
+            }
+            for _ in 0..next(60) {
+                let moves = game.get_all_possible_moves();
+                if moves.is_empty() {break;}
+                game.play(moves[next(moves.len())]);
+            }
+            games.push(game);
+        }
+        return games;
+    }
+
+    //check that the move stages split the legal moves without overlap
+    #[test]
+    fn check_move_stages() {
+        for game in random_games(60, 0x2545F4914F6CDD1D) {
+            let captures = game.generate(MoveStage::Captures);
+            let checks = game.generate(MoveStage::Checks);
+            let quiets = game.generate(MoveStage::Quiets);
+            let mut staged: Vec<Move> = captures.iter().chain(checks.iter()).chain(quiets.iter()).copied().collect();
+            let mut legal = game.get_all_possible_moves();
+            staged.sort_by_key(|mv| mv.to_string());
+            legal.sort_by_key(|mv| mv.to_string());
+            assert_eq!(staged, legal, "{}", game.get_fen());
+            // the same stages must come out when the legal moves are already cached
+            assert_eq!(game.generate(MoveStage::Captures), captures);
+            assert_eq!(game.generate(MoveStage::Checks), checks);
+            assert_eq!(game.ordered_moves().count(), legal.len());
+        }
+    }
+
+    //check that en passant, capturing promotions and discovered checks end up in the right stage
+    #[test]
+    #[allow(deprecated)]
+    fn check_special_move_stages() {
+        let mut game1 = Game::new();
+        game1.load_fen("1n2k3/P7/8/3pP3/8/8/8/B2RK3 w - d6 0 1".to_string());
+        let captures: Vec<String> = game1.generate(MoveStage::Captures).iter().map(|mv| mv.to_string()).collect();
+        assert!(captures.contains(&"e5d6".to_string()));
+        assert!(captures.contains(&"a7b8n".to_string()));
+        assert!(captures.contains(&"d1d5".to_string()));
+        // the pawn takes the knight before the rook takes the pawn
+        assert_eq!(captures[0].get(0..4), Some("a7b8"));
+
+        // promoting to a queen or rook gives check along the back rank, the others don't
+        game1.load_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string());
+        let checks: Vec<String> = game1.generate(MoveStage::Checks).iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(checks, vec!["a7a8q", "a7a8r"]);
+        assert!(game1.generate(MoveStage::Quiets).iter().any(|mv| mv.to_string() == "a7a8n"));
+
+        // every knight move uncovers the rook, a capture that uncovers it is still a capture
+        game1.load_fen("4k3/8/8/8/8/8/4N3/4RK2 w - - 0 1".to_string());
+        assert_eq!(game1.generate(MoveStage::Checks).iter().filter(|mv| mv.from.to_string() == "e2").count(), 6);
+        assert!(game1.generate(MoveStage::Quiets).iter().all(|mv| mv.from.to_string() != "e2"));
+        game1.load_fen("4k3/8/8/3p4/4P3/8/8/4RK2 w - - 0 1".to_string());
+        assert_eq!(game1.generate(MoveStage::Captures).iter().map(|mv| mv.to_string()).collect::<Vec<String>>(), vec!["e4d5"]);
+        assert!(game1.generate(MoveStage::Checks).is_empty());
+    }
+
+    //check that malformed FEN strings give an error instead of a broken board
+    #[test]
+    #[allow(deprecated)]
+    fn check_from_fen_errors() {
+        let invalid = |fen: &str| -> String {
+            match Game::from_fen(fen) {
+                Err(ChessError::InvalidFen(error)) => error.message,
+                other => panic!("{} gave {:?}", fen, other.map(|game| game.get_fen()))
+            }
+        };
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -"), "expected 6 fields, found 4");
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "expected 8 rows, found 7");
+        assert_eq!(invalid("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "row 2 does not have 8 squares");
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"), "invalid active color 'x'");
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1"), "castling availability 'K' without king and rook in place");
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1"), "invalid en passant square 'e4'");
+        assert_eq!(invalid("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1"), "invalid halfmove clock 'x'");
+
+        // a failed load leaves the game as it was
+        let mut game1 = Game::new();
+        assert_eq!(game1.load_fen("8/8/8/8/8/8/8/8 w - - 0".to_string()), None);
+        assert_eq!(game1.get_fen(), Game::new().get_fen());
+        assert_eq!(game1.load_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1".to_string()), Some(GameState::Stalemate));
+    }
+
+    //check where the errors of malformed FEN strings are, counted in characters
+    #[test]
+    fn check_from_fen_error_positions() {
+        let error = |fen: &str| -> FenError {
+            match Game::from_fen(fen) {
+                Err(ChessError::InvalidFen(error)) => error,
+                other => panic!("{} gave {:?}", fen, other.map(|game| game.get_fen()))
+            }
+        };
+        let error1 = error("rnbqkbnr/pppxpppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!((error1.field, error1.position), (Some(FenField::Placement), 12));
+        assert_eq!(error1.to_string(), "invalid piece character 'x' at position 12 in placement field");
+        assert_eq!(ChessError::InvalidFen(error1).to_string(), "invalid FEN: invalid piece character 'x' at position 12 in placement field");
+        // a short row ends at its slash, a long one at the square too many
+        assert_eq!(error("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").position, 16);
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR1 w KQkq - 0 1").position, 43);
+        assert_eq!(error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1").position, 43);
+        // the other fields, with any whitespace between them
+        let error1 = error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1  w   KQkq - 0 1");
+        assert_eq!((error1.field, error1.position), (Some(FenField::Castling), 49));
+        assert_eq!(error1.to_string(), "castling availability 'K' without king and rook in place at position 49 in castling field");
+        let error1 = error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0");
+        assert_eq!((error1.field, error1.position), (Some(FenField::FullmoveNumber), 55));
+        let error1 = error("8/8/8/8/8/8/8/8 w - -");
+        assert_eq!((error1.field, error1.position), (None, 21));
+        assert_eq!(error1.to_string(), "expected 6 fields, found 4 at position 21");
+        // the pockets of Crazyhouse
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3[Qx] w - - 0 1").position, 21);
+    }
+
+    // swaps the colors of a FEN string: the board is mirrored vertically, the pieces change
+    // color and the other side is to move
+    pub(crate) fn flip_fen(fen: &str) -> String {
+        let swap_case = |text: &str| -> String {text.chars().map(|c| if c.is_uppercase() {c.to_ascii_lowercase()} else {c.to_ascii_uppercase()}).collect()};
+        let chapters: Vec<&str> = fen.split(' ').collect();
+        let placement: Vec<String> = chapters[0].split('/').rev().map(swap_case).collect();
+        let turn = if chapters[1] == "w" {"b"} else {"w"};
+        let mut castling: Vec<char> = swap_case(chapters[2]).chars().collect();
+        castling.sort_by_key(|&c| "KQkq-".find(c));
+        let en_passant = match chapters[3] {
+            "-" => "-".to_string(),
+            square => format!("{}{}", &square[0..1], if &square[1..2] == "3" {"6"} else {"3"})
+        };
+        return format!("{} {} {} {} {} {}", placement.join("/"), turn, castling.iter().collect::<String>(), en_passant, chapters[4], chapters[5]);
+    }
+
+    // makes a Move from coordinate notation, e.g. "e2e4" or "e7e8q"
+    pub(crate) fn coordinate_move(notation: &str) -> Move {
+        let promotion = match notation.chars().nth(4) {
+            Some('q') => Some(PieceRole::Queen),
+            Some('r') => Some(PieceRole::Rook),
+            Some('b') => Some(PieceRole::Bishop),
+            Some('n') => Some(PieceRole::Knight),
+            Some('k') => Some(PieceRole::King),
+            _ => None
+        };
+        return Move { from: Square::from_algebraic(&notation[0..2]).unwrap(), to: Square::from_algebraic(&notation[2..4]).unwrap(), promotion };
+    }
+
+    // a game from the FEN that records its events
+    pub(crate) fn recorded(fen: &str) -> (Game, Arc<Mutex<Vec<GameEvent>>>) {
+        let mut game1 = Game::from_fen(fen).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&events);
+        game1.subscribe(Box::new(move |event| recorder.lock().unwrap().push(*event)));
+        return (game1, events);
+    }
+
+    //check that a knight keeps its id as it moves around the board
+    #[test]
+    fn check_piece_id_follows_moves() {
+        let mut game1 = Game::new();
+        let knight_id = game1.get_piece("g1").unwrap().id();
+        let other_knight_id = game1.get_piece("b1").unwrap().id();
+        assert_ne!(knight_id, 0);
+        assert_ne!(knight_id, other_knight_id);
+        for notation in ["g1f3", "g8f6", "f3e5", "b8c6", "e5c6"] {
+            let result = game1.play_move(&coordinate_move(notation)).unwrap();
+            assert_eq!(result.mv.to_string(), notation);
+        }
+        assert_eq!(game1.get_piece("c6").unwrap().id(), knight_id);
+        assert_eq!(game1.get_piece("b1").unwrap().id(), other_knight_id);
+    }
+
+    //check that a capture retires the id of the taken piece
+    #[test]
+    fn check_piece_id_capture() {
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        let victim_id = game1.play_move(&coordinate_move("d7d5")).unwrap().piece.id();
+        let pawn_id = game1.get_piece("e4").unwrap().id();
+        let result = game1.play_move(&coordinate_move("e4d5")).unwrap();
+        assert_eq!(result.piece.id(), pawn_id);
+        assert_eq!(result.captured.unwrap().id(), victim_id);
+        let ids: Vec<u8> = game1.get_board().iter().flatten().flatten().map(|piece| piece.id()).collect();
+        assert_eq!(ids.len(), 31);
+        assert!(!ids.contains(&victim_id));
+    }
+
+    //check that ids survive promotion, castling and en passant, and that undo restores them
+    #[test]
+    fn check_piece_id_special_moves() {
+        let mut game1 = Game::from_fen("4k3/1P6/8/8/3p4/8/4P3/R3K2R w KQ - 0 1").unwrap();
+        let pawn_id = game1.get_piece("b7").unwrap().id();
+        let rook_id = game1.get_piece("h1").unwrap().id();
+        let before = game1.get_board();
+
+        let result = game1.play_move(&coordinate_move("b7b8n")).unwrap();
+        assert_eq!(result.piece.role, PieceRole::Pawn);
+        assert_eq!(game1.get_piece("b8").unwrap().role, PieceRole::Knight);
+        assert_eq!(game1.get_piece("b8").unwrap().id(), pawn_id);
+
+        game1.play_move(&coordinate_move("e8d8")).unwrap();
+        let result = game1.play_move(&coordinate_move("e1g1")).unwrap();
+        assert_eq!(result.rook_move.unwrap().to_string(), "h1f1");
+        assert_eq!(game1.get_piece("f1").unwrap().id(), rook_id);
+
+        game1.play_move(&coordinate_move("d8e8")).unwrap();
+        let double_step_id = game1.play_move(&coordinate_move("e2e4")).unwrap().piece.id();
+        let result = game1.play_move(&coordinate_move("d4e3")).unwrap();
+        assert_eq!(result.capture_square.unwrap().to_string(), "e4");
+        assert_eq!(result.captured.unwrap().id(), double_step_id);
+
+        for _ in 0..6 {
+            assert!(game1.undo().is_some());
+        }
+        assert_eq!(game1.undo(), None);
+        assert_eq!(game1.get_board(), before);
+        assert_eq!(game1.get_fen(), "4k3/1P6/8/8/3p4/8/4P3/R3K2R w KQ - 0 1");
+    }
+
+    //check that undo gives back the earlier position, state and legal moves
+    #[test]
+    fn check_undo() {
+        let mut game1 = Game::new();
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            game1.make_move(from, to);
+        }
+        assert_eq!(game1.get_game_state(), GameState::Checkmate);
+        assert_eq!(game1.undo().unwrap().to_string(), "d8h4");
+        assert_eq!(game1.get_game_state(), GameState::InProgress);
+        assert_eq!(game1.get_turn(), "Black");
+        assert_eq!(game1.get_last_move().unwrap().to_string(), "g2g4");
+        assert!(game1.get_possible_moves("d8").unwrap().contains(&"h4".to_string()));
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2");
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::engine::GameOutcome;
+use crate::{Color, EndReason, Game, GameEvent, Move, MoveResult, PieceRole, Square};
+
+/// Identifies a game of a GameManager. Ids are never reused, so an id of a game that was
+/// cleaned up doesn't find another game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GameId(u64);
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How GameManager::create sets up a game.
+#[derive(Clone, Debug, Default)]
+pub struct GameOptions {
+    /// The game to start from, the start position by default.
+    pub start: Game,
+}
+
+/// Options for a GameManager.
+#[derive(Clone, Copy, Debug)]
+pub struct ManagerOptions {
+    /// How long a finished game is kept before it is cleaned up.
+    pub retention: Duration,
+}
+
+impl Default for ManagerOptions {
+    fn default() -> ManagerOptions {
+        ManagerOptions { retention: Duration::from_secs(300) }
+    }
+}
+
+/// Errors returned by the methods of a GameManager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ManagerError {
+    /// There is no game with the id, or it has been cleaned up.
+    UnknownGame(GameId),
+    /// The game is over, so no more moves can be made and nobody can resign.
+    GameOver(GameId),
+    /// The move isn't legal, in coordinate notation.
+    IllegalMove(String),
+}
+
+impl fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ManagerError::UnknownGame(id) => write!(f, "unknown game {}", id),
+            ManagerError::GameOver(id) => write!(f, "game {} is over", id),
+            ManagerError::IllegalMove(mv) => write!(f, "illegal move {}", mv)
+        }
+    }
+}
+
+impl std::error::Error for ManagerError {}
+
+/// Called with the id of the game and every event of the game (see Game::subscribe), on the
+/// thread that changed the game.
+pub type EventCallback = Box<dyn FnMut(GameId, &GameEvent) + Send>;
+
+struct ManagedGame {
+    game: Game,
+    /// The result and when the game ended, once it has.
+    finished: Option<(GameOutcome, Instant)>,
+}
+
+impl ManagedGame {
+    /// Ends the game at the fifty-move rule or a threefold repetition, which only a claim
+    /// would end in a Game, and notes when the game ended if it has.
+    fn update_finished(&mut self) {
+        if self.game.outcome().is_none() {
+            if self.game.get_halfmove() >= 100 {self.game.end(GameOutcome::Draw, EndReason::FiftyMoves);}
+            else if self.game.is_threefold_repetition() {self.game.end(GameOutcome::Draw, EndReason::Repetition);}
+        }
+        if self.finished.is_none() {
+            self.finished = self.game.outcome().map(|outcome| (outcome, Instant::now()));
+        }
+    }
+}
+
+/// Keeps any number of games apart by their ids, for servers that play many games at once.
+/// A GameManager is Send, so it can be shared between threads behind a Mutex. Finished games
+/// are cleaned up after ManagerOptions::retention, the next time a game is created or changed.
+pub struct GameManager {
+    options: ManagerOptions,
+    next_id: u64,
+    games: HashMap<GameId, ManagedGame>,
+}
+
+impl GameManager {
+    pub fn new(options: ManagerOptions) -> GameManager {
+        return GameManager { options, next_id: 1, games: HashMap::new() };
+    }
+
+    /// Starts a new game and returns its id.
+    pub fn create(&mut self, options: GameOptions) -> GameId {
+        self.cleanup();
+        let id = GameId(self.next_id);
+        self.next_id += 1;
+        let mut managed = ManagedGame { game: options.start, finished: None };
+        managed.update_finished();
+        self.games.insert(id, managed);
+        return id;
+    }
+
+    /// The game with the id, finished or not, or None if there is none.
+    pub fn get(&self, id: GameId) -> Option<&Game> {
+        return self.games.get(&id).map(|managed| &managed.game);
+    }
+
+    /// The result of the game with the id, or None if it isn't over or there is no such game.
+    /// It is the outcome of the game, see Game::outcome and Game::end_reason.
+    pub fn outcome(&self, id: GameId) -> Option<GameOutcome> {
+        return self.games.get(&id)?.game.outcome();
+    }
+
+    /// Calls the callback with the id and every event of the game from now on, by subscribing
+    /// to the game (see Game::subscribe).
+    pub fn subscribe(&mut self, id: GameId, mut callback: EventCallback) -> Result<(), ManagerError> {
+        let managed = self.games.get_mut(&id).ok_or(ManagerError::UnknownGame(id))?;
+        managed.game.subscribe(Box::new(move |event| callback(id, event)));
+        return Ok(());
+    }
+
+    /// Moves the piece on the square `from` (like "e2") to `to` in the game, promoting a pawn
+    /// that reaches the last rank to promo, and returns what happened. The game ends on
+    /// checkmate, stalemate, the fifty-move rule and threefold repetition, the last two with
+    /// EndReason::FiftyMoves and EndReason::Repetition.
+    pub fn make_move(&mut self, id: GameId, from: &str, to: &str, promo: Option<PieceRole>) -> Result<MoveResult, ManagerError> {
+        self.cleanup();
+        let managed = self.games.get_mut(&id).ok_or(ManagerError::UnknownGame(id))?;
+        if managed.finished.is_some() {return Err(ManagerError::GameOver(id));}
+        let illegal = || ManagerError::IllegalMove(format!("{}{}", from, to));
+        let (from, to) = (Square::from_algebraic(from).ok_or_else(illegal)?, Square::from_algebraic(to).ok_or_else(illegal)?);
+        let result = managed.game.play_move(&Move { from, to, promotion: promo }).ok_or_else(illegal)?;
+        managed.update_finished();
+        return Ok(result);
+    }
+
+    /// Ends the game with a win for the opponent of the player who resigns.
+    pub fn resign(&mut self, id: GameId, color: Color) -> Result<GameOutcome, ManagerError> {
+        self.cleanup();
+        let managed = self.games.get_mut(&id).ok_or(ManagerError::UnknownGame(id))?;
+        if managed.finished.is_some() {return Err(ManagerError::GameOver(id));}
+        let outcome = managed.game.resign(color).ok_or(ManagerError::GameOver(id))?;
+        managed.update_finished();
+        return Ok(outcome);
+    }
+
+    /// The ids of the games that aren't over, the oldest first.
+    pub fn list_active(&self) -> Vec<GameId> {
+        let mut active: Vec<GameId> = self.games.iter().filter(|(_, managed)| managed.finished.is_none()).map(|(&id, _)| id).collect();
+        active.sort();
+        return active;
+    }
+
+    /// The number of games kept, finished or not.
+    pub fn len(&self) -> usize {
+        return self.games.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.games.is_empty();
+    }
+
+    /// Removes the games that ended longer than the retention ago and returns how many there
+    /// were. The other methods that change the manager do this too.
+    pub fn cleanup(&mut self) -> usize {
+        return self.cleanup_at(Instant::now());
+    }
+
+    fn cleanup_at(&mut self, now: Instant) -> usize {
+        let before = self.games.len();
+        let retention = self.options.retention;
+        self.games.retain(|_, managed| !matches!(managed.finished, Some((_, ended)) if now.saturating_duration_since(ended) >= retention));
+        return before - self.games.len();
+    }
+}
+
+impl Default for GameManager {
+    fn default() -> GameManager {
+        GameManager::new(ManagerOptions::default())
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use super::{GameId, GameManager, GameOptions, ManagerError, ManagerOptions};
+    use crate::engine::GameOutcome;
+    use crate::{Color, EndReason, Game, GameEvent, PieceRole, Square};
+
+    //check that moves in interleaved games only change their own game
+    #[test]
+    fn check_manager_isolation() {
+        let manager = Mutex::new(GameManager::default());
+        let ids: Vec<GameId> = (0..100).map(|_| manager.lock().unwrap().create(GameOptions::default())).collect();
+        // every thread plays a different opening in its share of the games, one move at a time
+        let openings = [["e2", "e4", "e7", "e5"], ["d2", "d4", "d7", "d5"], ["g1", "f3", "g8", "f6"], ["c2", "c4", "c7", "c5"]];
+        thread::scope(|scope| {
+            for (index, opening) in openings.iter().enumerate() {
+                let (manager, ids) = (&manager, &ids);
+                scope.spawn(move || {
+                    for ply in 0..2 {
+                        for &id in ids.iter().skip(index).step_by(4) {
+                            let (from, to) = (opening[ply * 2], opening[ply * 2 + 1]);
+                            manager.lock().unwrap().make_move(id, from, to, None).unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        let manager = manager.into_inner().unwrap();
+        let fens: Vec<String> = openings.iter().map(|opening| {
+            let mut game1 = Game::new();
+            game1.make_move(opening[0], opening[1]);
+            game1.make_move(opening[2], opening[3]);
+            game1.get_fen()
+        }).collect();
+        for (index, &id) in ids.iter().enumerate() {
+            assert_eq!(manager.get(id).unwrap().get_fen(), fens[index % 4]);
+        }
+        assert_eq!(manager.list_active(), ids);
+        let mut manager = manager;
+        assert_eq!(manager.make_move(ids[0], "e4", "e5", None), Err(ManagerError::IllegalMove("e4e5".to_string())));
+        assert_eq!(manager.make_move(GameId(1000), "e2", "e4", None), Err(ManagerError::UnknownGame(GameId(1000))));
+    }
+
+    //check that finished games are kept for the retention and then cleaned up
+    #[test]
+    fn check_manager_cleanup() {
+        let mut manager = GameManager::new(ManagerOptions { retention: Duration::from_secs(60) });
+        let ids: Vec<GameId> = (0..100).map(|_| manager.create(GameOptions::default())).collect();
+        for &id in ids.iter().step_by(2) {
+            assert_eq!(manager.resign(id, Color::White), Ok(GameOutcome::Win(Color::Black)));
+        }
+        assert_eq!(manager.resign(ids[0], Color::Black), Err(ManagerError::GameOver(ids[0])));
+        assert_eq!(manager.list_active().len(), 50);
+        assert_eq!(manager.cleanup(), 0);
+        assert_eq!(manager.len(), 100);
+        assert_eq!(manager.outcome(ids[0]), Some(GameOutcome::Win(Color::Black)));
+        assert_eq!(manager.cleanup_at(Instant::now() + Duration::from_secs(61)), 50);
+        assert_eq!(manager.len(), 50);
+        assert!(manager.get(ids[0]).is_none());
+        assert!(manager.get(ids[1]).is_some());
+        // ids aren't reused
+        assert_eq!(manager.create(GameOptions::default()), GameId(101));
+    }
+
+    //check that subscribers of a game see its moves and its end, and only those
+    #[test]
+    fn check_manager_events() {
+        let mut manager = GameManager::new(ManagerOptions { retention: Duration::ZERO });
+        let start = Game::from_fen("6k1/P4ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        let (promotion, other) = (manager.create(GameOptions { start }), manager.create(GameOptions::default()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&events);
+        manager.subscribe(promotion, Box::new(move |id, event| recorder.lock().unwrap().push((id, *event)))).unwrap();
+        manager.make_move(other, "e2", "e4", None).unwrap();
+        let result = manager.make_move(promotion, "a7", "a8", Some(PieceRole::Queen)).unwrap();
+        let events = events.lock().unwrap();
+        assert_eq!(*events, vec![
+            (promotion, GameEvent::MoveMade(result)),
+            (promotion, GameEvent::PromotionOccurred { square: Square::from_algebraic("a8").unwrap(), role: PieceRole::Queen }),
+            (promotion, GameEvent::GameEnded(GameOutcome::Win(Color::White))),
+        ]);
+        // without retention the finished game goes the next time the manager changes
+        assert_eq!(manager.list_active(), vec![other]);
+        manager.make_move(other, "e7", "e5", None).unwrap();
+        assert!(manager.get(promotion).is_none());
+    }
+
+    //check that the draws of the fifty-move rule and a repetition end the game itself
+    #[test]
+    fn check_manager_draws() {
+        let mut manager = GameManager::default();
+        let start = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        let fifty = manager.create(GameOptions { start });
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&events);
+        manager.subscribe(fifty, Box::new(move |_, event| recorder.lock().unwrap().push(*event))).unwrap();
+        manager.make_move(fifty, "a1", "a2", None).unwrap();
+        assert_eq!(manager.outcome(fifty), Some(GameOutcome::Draw));
+        assert_eq!(manager.get(fifty).unwrap().end_reason(), Some(EndReason::FiftyMoves));
+        assert_eq!(events.lock().unwrap().last(), Some(&GameEvent::GameEnded(GameOutcome::Draw)));
+        assert_eq!(manager.make_move(fifty, "e8", "d8", None), Err(ManagerError::GameOver(fifty)));
+        let repetition = manager.create(GameOptions::default());
+        for _ in 0..2 {
+            for (from, to) in [("g1", "f3"), ("g8", "f6"), ("f3", "g1"), ("f6", "g8")] {
+                manager.make_move(repetition, from, to, None).unwrap();
+            }
+        }
+        assert_eq!(manager.get(repetition).unwrap().end_reason(), Some(EndReason::Repetition));
+        assert_eq!(manager.get(repetition).unwrap().outcome(), Some(GameOutcome::Draw));
+    }
+}
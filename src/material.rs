@@ -0,0 +1,329 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Color, Game, PieceRole, Position, Square};
+
+// the roles in the order of a signature, with their letters
+const ROLES: [(PieceRole, char); 6] = [
+    (PieceRole::King, 'K'),
+    (PieceRole::Queen, 'Q'),
+    (PieceRole::Rook, 'R'),
+    (PieceRole::Bishop, 'B'),
+    (PieceRole::Knight, 'N'),
+    (PieceRole::Pawn, 'P'),
+];
+
+/// The number of pieces of every role for both players, to classify endgames by or to use as
+/// the key of a map. It is written as a material signature like "KRPvKR", see
+/// Game::material_signature, and can be read back from one with from_signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialKey {
+    // counts[color][role] in the order of ROLES, White first
+    counts: [[u8; 6]; 2],
+}
+
+impl MaterialKey {
+    /// Reads a material signature like "KQvKRR": the uppercase letters of White's pieces, a
+    /// "v" and those of Black's. The letters can come in any order. Returns None for anything
+    /// else.
+    pub fn from_signature(signature: &str) -> Option<MaterialKey> {
+        let (white, black) = signature.split_once('v')?;
+        let mut counts = [[0u8; 6]; 2];
+        for (side, letters) in [white, black].into_iter().enumerate() {
+            for letter in letters.chars() {
+                let role = ROLES.iter().position(|&(_, role_letter)| role_letter == letter)?;
+                counts[side][role] = counts[side][role].checked_add(1)?;
+            }
+        }
+        return Some(MaterialKey { counts });
+    }
+
+    /// How many pieces of the role the color has.
+    pub fn count(&self, color: Color, role: PieceRole) -> u8 {
+        let role = ROLES.iter().position(|&(r, _)| r == role).unwrap();
+        return self.counts[color as usize][role];
+    }
+
+    /// The same material with the colors swapped.
+    pub fn flipped(&self) -> MaterialKey {
+        return MaterialKey { counts: [self.counts[1], self.counts[0]] };
+    }
+}
+
+impl fmt::Display for MaterialKey {
+    /// Writes the material signature, see Game::material_signature.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (side, counts) in self.counts.iter().enumerate() {
+            if side == 1 {write!(f, "v")?;}
+            for (&(_, letter), &count) in ROLES.iter().zip(counts) {
+                for _ in 0..count {
+                    write!(f, "{}", letter)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl Game {
+    /// Returns the material signature of the position: the pieces of White, a "v" and the
+    /// pieces of Black, each as uppercase letters in the order K, Q, R, B, N, P and repeated
+    /// once for every piece, like "KRPPvKR".
+    pub fn material_signature(&self) -> String {
+        return self.material_key().to_string();
+    }
+
+    /// Returns the material of the position as a MaterialKey, to use as the key of a map.
+    pub fn material_key(&self) -> MaterialKey {
+        return self.position.material_key();
+    }
+
+    /// The squares of the pieces of the player with the role, in board order (a8 first), e.g.
+    /// where Black's rooks are.
+    pub fn pieces_of(&self, color: Color, role: PieceRole) -> Vec<Square> {
+        return self.position.squares_of(role, Some(color)).collect();
+    }
+
+    /// Counts the pieces of the player with the role, e.g. 8 pawns at the start.
+    pub fn count_of(&self, color: Color, role: PieceRole) -> u8 {
+        return self.position.squares_of(role, Some(color)).count() as u8;
+    }
+
+    /// Returns true if the color has bishops on both light and dark squares. Two bishops on
+    /// squares of the same color, which only a promotion can give, are not a pair.
+    pub fn has_bishop_pair(&self, color: Color) -> bool {
+        return self.position.has_bishop_pair(color);
+    }
+
+    /// Returns true if there are bishops on the board and all of them stand on squares of the
+    /// same color, whichever side they belong to. Bishops on the same color can never attack
+    /// anything on the other color, so with only kings and bishops left the game is drawn,
+    /// while a lone bishop of each side on opposite colors tends to a draw even with pawns.
+    pub fn bishops_same_color(&self) -> bool {
+        let colors: Vec<Color> = self.position.squares_of(PieceRole::Bishop, None).map(|square| square.color()).collect();
+        return !colors.is_empty() && colors.iter().all(|&color| color == colors[0]);
+    }
+
+    /// Returns true if neither player can ever checkmate, whatever the moves: when only the
+    /// kings are left, with at most one knight, or with bishops that all stand on squares of
+    /// the same color (see bishops_same_color).
+    pub fn is_insufficient_material(&self) -> bool {
+        let key = self.material_key();
+        let count = |role: PieceRole| -> u8 {key.count(Color::White, role) + key.count(Color::Black, role)};
+        if count(PieceRole::Queen) + count(PieceRole::Rook) + count(PieceRole::Pawn) > 0 {return false;}
+        return match (count(PieceRole::Knight), count(PieceRole::Bishop)) {
+            (0, 0) | (1, 0) => true,
+            (0, _) => self.bishops_same_color(),
+            _ => false
+        };
+    }
+
+    /// Returns true if the player has the pieces to checkmate with, which they don't with only
+    /// their king or their king and a single knight or bishop (the USCF rule for games lost on
+    /// time).
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let key = self.material_key();
+        let count = |role: PieceRole| -> u8 {key.count(color, role)};
+        if count(PieceRole::Queen) + count(PieceRole::Rook) + count(PieceRole::Pawn) > 0 {return true;}
+        return count(PieceRole::Knight) + count(PieceRole::Bishop) >= 2;
+    }
+
+    /// The roles of the pieces the player has taken since the game was created or loaded, in the
+    /// order they were taken. A pawn taken en passant counts, a promotion adds nothing, and undo
+    /// takes the last entry back.
+    pub fn captured_pieces(&self, by: Color) -> Vec<PieceRole> {
+        return self.history.iter().filter(|entry| entry.result.piece.color == by).filter_map(|entry| entry.result.captured).map(|piece| piece.role).collect();
+    }
+
+    /// Writes the captures that aren't traded off, as the pieces White took more of, "vs" and
+    /// the pieces Black took more of, e.g. "♞♟ vs ♜" when White took a knight and a pawn more and
+    /// Black a rook more. Pieces are written with the filled symbols, from the queen down, and a
+    /// side that took no more than the other as "-". Returns "=" when the trades are even.
+    pub fn material_imbalance_display(&self) -> String {
+        let (white, black) = (self.captured_pieces(Color::White), self.captured_pieces(Color::Black));
+        let count = |captures: &[PieceRole], role: PieceRole| -> i32 {captures.iter().filter(|&&captured| captured == role).count() as i32};
+        let mut sides = [String::new(), String::new()];
+        for (role, symbol) in [(PieceRole::Queen, '♛'), (PieceRole::Rook, '♜'), (PieceRole::Bishop, '♝'), (PieceRole::Knight, '♞'), (PieceRole::Pawn, '♟')] {
+            let difference = count(&white, role) - count(&black, role);
+            let side = if difference > 0 {0} else {1};
+            for _ in 0..difference.abs() {
+                sides[side].push(symbol);
+            }
+        }
+        if sides.iter().all(|side| side.is_empty()) {return String::from("=");}
+        let [white, black] = sides.map(|side| if side.is_empty() {String::from("-")} else {side});
+        return format!("{} vs {}", white, black);
+    }
+}
+
+impl Position {
+    pub(crate) fn material_key(&self) -> MaterialKey {
+        let mut counts = [[0u8; 6]; 2];
+        for (_, piece) in self.occupied() {
+            let role = ROLES.iter().position(|&(role, _)| role == piece.role).unwrap();
+            counts[piece.color as usize][role] += 1;
+        }
+        return MaterialKey { counts };
+    }
+
+    /// The squares of the pieces of the role and color, or of both colors for None, in board
+    /// order.
+    pub(crate) fn squares_of(&self, role: PieceRole, color: Option<Color>) -> impl Iterator<Item = Square> + '_ {
+        return self.occupied().filter(move |(_, piece)| piece.role == role && color.is_none_or(|color| piece.color == color))
+            .map(|(index, _)| Square(index as u8));
+    }
+
+    pub(crate) fn has_bishop_pair(&self, color: Color) -> bool {
+        let mut squares = self.squares_of(PieceRole::Bishop, Some(color));
+        let first = match squares.next() {
+            Some(square) => square.color(),
+            None => return false
+        };
+        return squares.any(|square| square.color() != first);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::MaterialKey;
+    use crate::tests::{coordinate_move, flip_fen, random_games};
+    use crate::{Color, Game, PieceRole, Square};
+
+    //check the pieces and counts of the start position, after a promotion and after a capture
+    #[test]
+    fn check_pieces_of() {
+        let game1 = Game::new();
+        for color in [Color::White, Color::Black] {
+            assert_eq!(game1.count_of(color, PieceRole::Pawn), 8);
+            assert_eq!(game1.count_of(color, PieceRole::Rook), 2);
+            assert_eq!(game1.count_of(color, PieceRole::Queen), 1);
+        }
+        let squares = |squares: Vec<Square>| -> Vec<String> {squares.iter().map(|square| square.to_string()).collect()};
+        assert_eq!(squares(game1.pieces_of(Color::Black, PieceRole::Rook)), ["a8", "h8"]);
+        assert_eq!(squares(game1.pieces_of(Color::White, PieceRole::Bishop)), ["c1", "f1"]);
+        // the promotion takes the rook on b8 as well
+        let mut game1 = Game::from_fen("1r2k3/P7/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        game1.play_move(&coordinate_move("a7b8q")).unwrap();
+        assert_eq!(game1.count_of(Color::White, PieceRole::Queen), 2);
+        assert_eq!(game1.count_of(Color::White, PieceRole::Pawn), 0);
+        assert_eq!(game1.count_of(Color::Black, PieceRole::Rook), 0);
+        assert_eq!(squares(game1.pieces_of(Color::White, PieceRole::Queen)), ["b8", "d1"]);
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "d7d5", "e4d5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.count_of(Color::Black, PieceRole::Pawn), 7);
+        assert_eq!(game1.count_of(Color::White, PieceRole::Pawn), 8);
+        assert!(game1.pieces_of(Color::Black, PieceRole::King).iter().eq([&Square::from_algebraic("e8").unwrap()]));
+    }
+
+    //check the signature of the start position and that it reads back to the same key
+    #[test]
+    fn check_material_signature() {
+        let game1 = Game::new();
+        assert_eq!(game1.material_signature(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+        assert_eq!(MaterialKey::from_signature(&game1.material_signature()), Some(game1.material_key()));
+        assert_eq!(game1.material_key().count(Color::Black, PieceRole::Pawn), 8);
+        // the letters can come in any order, but nothing else
+        assert_eq!(MaterialKey::from_signature("KPRvRK"), MaterialKey::from_signature("KRPvKR"));
+        assert_eq!(MaterialKey::from_signature("KRP"), None);
+        assert_eq!(MaterialKey::from_signature("KrvK"), None);
+        assert_eq!(MaterialKey::from_signature("KvKvK"), None);
+        for game1 in random_games(30, 0x3C6EF372FE94F82B) {
+            assert_eq!(MaterialKey::from_signature(&game1.material_signature()), Some(game1.material_key()));
+        }
+    }
+
+    //check that captures and promotions change the signature
+    #[test]
+    fn check_material_signature_moves() {
+        let mut game1 = Game::from_fen("1r6/P4k2/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.material_signature(), "KPvKR");
+        game1.play_move(&coordinate_move("a7a8q")).unwrap();
+        assert_eq!(game1.material_signature(), "KQvKR");
+        game1.play_move(&coordinate_move("b8a8")).unwrap();
+        assert_eq!(game1.material_signature(), "KvKR");
+    }
+
+    //check the trays after a capture, an en passant capture and an undo
+    #[test]
+    fn check_captured_pieces() {
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "d7d5", "e4d5", "e7e5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.captured_pieces(Color::White), vec![PieceRole::Pawn]);
+        assert_eq!(game1.captured_pieces(Color::Black), vec![]);
+        assert_eq!(game1.material_imbalance_display(), "♟ vs -");
+        // en passant takes the pawn beside the empty target square
+        let result = game1.play_move(&coordinate_move("d5e6")).unwrap();
+        assert_eq!(result.captured.unwrap().role, PieceRole::Pawn);
+        game1.play_move(&coordinate_move("f7e6")).unwrap();
+        assert_eq!(game1.captured_pieces(Color::White), vec![PieceRole::Pawn, PieceRole::Pawn]);
+        assert_eq!(game1.captured_pieces(Color::Black), vec![PieceRole::Pawn]);
+        assert_eq!(game1.material_imbalance_display(), "♟ vs -");
+        game1.undo().unwrap();
+        game1.undo().unwrap();
+        assert_eq!(game1.captured_pieces(Color::White), vec![PieceRole::Pawn]);
+        // uneven trades on both sides
+        let mut game1 = Game::from_fen("4k2r/8/8/8/1p6/P7/8/4K2R w - - 0 1").unwrap();
+        assert_eq!(game1.material_imbalance_display(), "=");
+        for mv in ["h1h8", "e8d7", "e1e2", "b4a3"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.material_imbalance_display(), "♜ vs ♟");
+    }
+
+    //check that only bishops on both colors are a pair
+    #[test]
+    fn check_bishop_pair() {
+        assert!(Game::new().has_bishop_pair(Color::White) && Game::new().has_bishop_pair(Color::Black));
+        assert_eq!((Square::from_algebraic("a1").unwrap().color(), Square::from_algebraic("h1").unwrap().color()), (Color::Black, Color::White));
+        // two dark squared bishops after a promotion
+        let game1 = Game::from_fen("4k3/8/8/8/8/B7/8/2B1K3 w - - 0 1").unwrap();
+        assert!(!game1.has_bishop_pair(Color::White));
+        let game1 = Game::from_fen("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").unwrap();
+        assert!(game1.has_bishop_pair(Color::White) && !game1.has_bishop_pair(Color::Black));
+    }
+
+    //check that bishops on the same color are found and make the material insufficient
+    #[test]
+    fn check_bishops_same_color() {
+        // c1 and f8 are both dark
+        let game1 = Game::from_fen("5b2/4k3/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+        assert!(game1.bishops_same_color() && game1.is_insufficient_material());
+        let game1 = Game::from_fen("2b5/4k3/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+        assert!(!game1.bishops_same_color() && !game1.is_insufficient_material());
+        assert!(!Game::new().bishops_same_color() && !Game::new().is_insufficient_material());
+        assert!(Game::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap().is_insufficient_material());
+        assert!(!Game::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap().bishops_same_color());
+        assert!(!Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap().is_insufficient_material());
+    }
+
+    //check which pieces are enough to checkmate with
+    #[test]
+    fn check_mating_material() {
+        assert!(Game::new().has_mating_material(Color::White));
+        let game1 = Game::from_fen("4k3/8/8/8/8/8/8/1N2KB2 w - - 0 1").unwrap();
+        assert!(game1.has_mating_material(Color::White) && !game1.has_mating_material(Color::Black));
+        assert!(!Game::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap().has_mating_material(Color::White));
+        assert!(Game::from_fen("4k3/4p3/8/8/8/8/8/4K3 w - - 0 1").unwrap().has_mating_material(Color::Black));
+    }
+
+    //check that the color flipped position has the mirrored signature
+    #[test]
+    fn check_material_signature_flipped() {
+        for game1 in random_games(30, 0xA54FF53A5F1D36F1) {
+            let flipped = Game::from_fen(&flip_fen(&game1.get_fen())).unwrap();
+            assert_eq!(flipped.material_key(), game1.material_key().flipped());
+            let (white, black) = game1.material_signature().split_once('v').map(|(white, black)| (white.to_string(), black.to_string())).unwrap();
+            assert_eq!(flipped.material_signature(), format!("{}v{}", black, white));
+        }
+    }
+}
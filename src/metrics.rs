@@ -0,0 +1,218 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Color, Game, PieceRole, Position};
+
+// d5, e5, d4 and e4
+const CENTER: [usize; 4] = [27, 28, 35, 36];
+
+/// How far a player has come with the opening, see Game::development.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DevelopmentReport {
+    /// The knights and bishops that are off their starting squares.
+    pub minors_developed: u8,
+    /// Whether the player has castled in this game.
+    pub castled: bool,
+    /// Whether the queen moved while two or more of the knights and bishops were still at home.
+    pub queen_moved_early: bool,
+    /// Whether two rooks protect each other along a rank or file.
+    pub rooks_connected: bool,
+}
+
+/// Which moves Game::activity_heatmap counts, and how much.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeatmapOptions {
+    /// How much the squares of a capture count, instead of 1.
+    pub capture_weight: u32,
+    /// Only count the moves of pieces of this role, castling as a king move.
+    pub role: Option<PieceRole>,
+    /// Only count the moves of this player.
+    pub color: Option<Color>,
+}
+
+impl Default for HeatmapOptions {
+    fn default() -> HeatmapOptions {
+        return HeatmapOptions { capture_weight: 1, role: None, color: None };
+    }
+}
+
+impl Game {
+    /// Counts for every square, indexed like the board (a8 is 0), how often the moves made in
+    /// this game touched it: the squares a piece moved from and to, the two squares of the rook
+    /// when castling and the square of the pawn taken en passant, so castling touches four
+    /// squares and en passant three. A capture counts capture_weight on the squares it touches.
+    /// Only the moves since the game was created or loaded are known.
+    pub fn activity_heatmap(&self, options: HeatmapOptions) -> [u32; 64] {
+        let mut heatmap = [0u32; 64];
+        for entry in self.history.iter() {
+            let result = &entry.result;
+            if options.role.is_some_and(|role| role != result.piece.role) || options.color.is_some_and(|color| color != result.piece.color) {continue;}
+            let weight = if result.captured.is_some() {options.capture_weight} else {1};
+            let mut squares: Vec<usize> = vec![result.mv.from.index(), result.mv.to.index()];
+            if let Some(rook_move) = result.rook_move {squares.extend([rook_move.from.index(), rook_move.to.index()]);}
+            if let Some(capture_square) = result.capture_square {squares.push(capture_square.index());}
+            // a drop, or a king and rook that swap or stay in Chess960, touch a square once
+            squares.sort_unstable();
+            squares.dedup();
+            for index in squares {
+                heatmap[index] += weight;
+            }
+        }
+        return heatmap;
+    }
+
+    /// Counts the attacks of White and of Black on the four center squares d4, d5, e4 and e5.
+    /// Every piece attacking a square counts once for that square, so a knight attacking two
+    /// of them counts twice and two rooks on the same file count once (the one behind is
+    /// blocked). Pawns count, kings don't. It doesn't matter what stands on the squares.
+    /// After 1.e4 White attacks d5 with the pawn, one more attack than in the start position.
+    pub fn center_control(&self) -> (u32, u32) {
+        return self.count_attacks(&CENTER);
+    }
+
+    /// Like center_control, but for the 16 squares of the extended center, c3 to f6.
+    pub fn extended_center_control(&self) -> (u32, u32) {
+        let squares: Vec<usize> = (2..6).flat_map(|row| (2..6).map(move |column| row * 8 + column)).collect();
+        return self.count_attacks(&squares);
+    }
+
+    /// Reports how far the color has developed its pieces. Castling and early queen moves are
+    /// looked up in the moves made in this game, so a game loaded from a FEN only knows about
+    /// the moves made since.
+    pub fn development(&self, color: Color) -> DevelopmentReport {
+        let mut castled = false;
+        let mut queen_moved_early = false;
+        // every entry holds the position before its move
+        for entry in self.history.iter().filter(|entry| entry.result.piece.color == color) {
+            if entry.result.rook_move.is_some() {castled = true;}
+            if entry.result.piece.role == PieceRole::Queen && entry.position.minors_at_home(color) >= 2 {queen_moved_early = true;}
+        }
+        return DevelopmentReport {
+            minors_developed: 4u8.saturating_sub(self.position.minors_at_home(color)),
+            castled,
+            queen_moved_early,
+            rooks_connected: self.position.rooks_connected(color)
+        };
+    }
+
+    /// The attacks of both colors on the squares, without the kings.
+    fn count_attacks(&self, squares: &[usize]) -> (u32, u32) {
+        let count = |color: Color| -> u32 {
+            let mut attacks = 0;
+            for &index in squares {
+                self.position.for_each_attacker(index, color, |attacker| {
+                    if self.position.piece_at(attacker).unwrap().role != PieceRole::King {attacks += 1;}
+                });
+            }
+            return attacks;
+        };
+        return (count(Color::White), count(Color::Black));
+    }
+}
+
+impl Position {
+    /// Counts the knights and bishops of the color on their starting squares.
+    fn minors_at_home(&self, color: Color) -> u8 {
+        let row = if color == Color::White {7} else {0};
+        let homes = [(1, PieceRole::Knight), (2, PieceRole::Bishop), (5, PieceRole::Bishop), (6, PieceRole::Knight)];
+        return homes.iter().filter(|&&(column, role)| self.piece_at(row * 8 + column).is_some_and(|piece| piece.role == role && piece.color == color)).count() as u8;
+    }
+
+    /// Returns true if two rooks of the color stand on the same rank or file with nothing between them.
+    fn rooks_connected(&self, color: Color) -> bool {
+        let rooks: Vec<usize> = (0..64).filter(|&index| self.piece_at(index).is_some_and(|piece| piece.role == PieceRole::Rook && piece.color == color)).collect();
+        return rooks.iter().any(|&rook| self.attackers_of(rook, color).iter().any(|&other| self.piece_at(other).unwrap().role == PieceRole::Rook));
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{coordinate_move, flip_fen, random_games};
+    use super::{DevelopmentReport, HeatmapOptions};
+    use crate::{Color, Game, PieceRole, Square};
+
+    //check that the start position is symmetric and 1.e4 adds the attack on d5
+    #[test]
+    fn check_center_control() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.center_control(), (0, 0));
+        // eight pawn attacks and the two knights on the third rank
+        assert_eq!(game1.extended_center_control(), (10, 10));
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        assert_eq!(game1.center_control(), (1, 0));
+        for game1 in random_games(20, 0x9B05688C2B3E6C1F) {
+            let (white, black) = game1.center_control();
+            assert_eq!(Game::from_fen(&flip_fen(&game1.get_fen())).unwrap().center_control(), (black, white));
+        }
+    }
+
+    //check the activity of a game with castling, en passant and captures on the same square
+    #[test]
+    fn check_activity_heatmap() {
+        let mut game1 = Game::new();
+        let moves = [
+            "e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "e1g1", "g8f6", "d2d4", "e5d4",
+            "e4e5", "d7d5", "e5d6", "d8d6", "f1e1", "c8e6", "c4e6", "f7e6", "e1e6", "d6e6",
+        ];
+        for mv in moves {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let heatmap = game1.activity_heatmap(HeatmapOptions::default());
+        let count = |heatmap: &[u32; 64], square: &str| -> u32 {heatmap[Square::from_algebraic(square).unwrap().index()]};
+        // castling touched e1, f1, g1 and h1, and the rook came back to e1
+        assert_eq!([count(&heatmap, "e1"), count(&heatmap, "f1"), count(&heatmap, "g1"), count(&heatmap, "h1")], [3, 3, 2, 1]);
+        // the pawn taken en passant stood on d5
+        assert_eq!([count(&heatmap, "d5"), count(&heatmap, "d6"), count(&heatmap, "e5")], [2, 3, 4]);
+        assert_eq!(count(&heatmap, "e6"), 5);
+        assert_eq!(heatmap.iter().sum::<u32>(), 20 * 2 + 2 + 1);
+        // captures count three times, and only the moves of one piece or player
+        let weighted = game1.activity_heatmap(HeatmapOptions { capture_weight: 3, ..HeatmapOptions::default() });
+        assert_eq!(count(&weighted, "e6"), 1 + 3 + 3 + 3 + 3);
+        // castling is a king move, the rook only counts for Rfe1 and Rxe6
+        let rooks = game1.activity_heatmap(HeatmapOptions { role: Some(PieceRole::Rook), ..HeatmapOptions::default() });
+        assert_eq!([count(&rooks, "e1"), count(&rooks, "h1"), count(&rooks, "e6")], [2, 0, 1]);
+        let black = game1.activity_heatmap(HeatmapOptions { color: Some(Color::Black), ..HeatmapOptions::default() });
+        assert_eq!([count(&black, "e1"), count(&black, "e6"), count(&black, "d8")], [0, 3, 1]);
+    }
+
+    //check the development of both players after an Italian game opening
+    #[test]
+    fn check_development() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.development(Color::White), DevelopmentReport { minors_developed: 0, castled: false, queen_moved_early: false, rooks_connected: false });
+        for mv in ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "f8c5", "c2c3", "g8f6", "d2d3", "d7d6", "e1g1", "d8e7", "b1d2", "c8g4"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.development(Color::White), DevelopmentReport { minors_developed: 3, castled: true, queen_moved_early: false, rooks_connected: false });
+        // black moved the queen with only the light-squared bishop at home, which isn't early
+        assert_eq!(game1.development(Color::Black), DevelopmentReport { minors_developed: 4, castled: false, queen_moved_early: false, rooks_connected: false });
+        for mv in ["d1b3", "e8c8"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        // the bishop on c1 still stands between the white rooks
+        assert_eq!(game1.development(Color::White), DevelopmentReport { minors_developed: 3, castled: true, queen_moved_early: false, rooks_connected: false });
+        assert_eq!(game1.development(Color::Black), DevelopmentReport { minors_developed: 4, castled: true, queen_moved_early: false, rooks_connected: true });
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "e7e5", "d1h5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert!(game1.development(Color::White).queen_moved_early);
+    }
+
+    //check that a knight on f3 and a pawn on e4 count d4, e5 and d5 once each, and other attackers
+    #[test]
+    fn check_center_control_pieces() {
+        let game1 = Game::from_fen("4k3/8/8/8/4P3/5N2/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.center_control(), (3, 0));
+        // a rook behind a rook is blocked, and a king doesn't count
+        let game1 = Game::from_fen("4k3/8/8/3K4/8/8/3R4/3R4 b - - 0 1").unwrap();
+        assert_eq!(game1.center_control(), (2, 0));
+        // two knights attacking d5 both count
+        let game1 = Game::from_fen("4k3/8/8/8/8/2n1n3/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.center_control(), (0, 3));
+    }
+}
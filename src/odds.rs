@@ -0,0 +1,92 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{assign_ids, Game, PieceRole, Square};
+
+/// The handicap of an odds game, see Game::new_with_odds. The player giving the odds plays
+/// White and gives up a piece, but for pawn and move, where they play Black and give up the
+/// f-pawn, so that the weaker player also gets the first move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Odds {
+    /// Without the queen of White.
+    QueenOdds,
+    /// Without the rook of White on h1, or on a1 if not kingside. White loses that castling right.
+    RookOdds { kingside: bool },
+    /// Without the knight of White on g1, or on b1 if not kingside.
+    KnightOdds { kingside: bool },
+    /// Without the pawn of Black on f7, with White moving first as usual.
+    PawnAndMove,
+    /// Without the pieces on the squares. Kings are never removed.
+    Custom(Vec<Square>),
+}
+
+impl Odds {
+    /// The squares of the pieces removed from the starting position.
+    fn squares(&self) -> Vec<Square> {
+        let square = |name: &str| -> Square {Square::from_algebraic(name).unwrap()};
+        return match self {
+            Odds::QueenOdds => vec![square("d1")],
+            Odds::RookOdds { kingside } => vec![square(if *kingside {"h1"} else {"a1"})],
+            Odds::KnightOdds { kingside } => vec![square(if *kingside {"g1"} else {"b1"})],
+            Odds::PawnAndMove => vec![square("f7")],
+            Odds::Custom(squares) => squares.clone()
+        };
+    }
+}
+
+impl Game {
+    /// Creates a game from the starting position without the pieces the odds take away. The
+    /// castling rights follow from the rooks that are left, so the FEN of the game is consistent.
+    pub fn new_with_odds(odds: Odds) -> Game {
+        let mut game = Game::new();
+        for square in odds.squares() {
+            if game.position.piece_at(square.index()).is_some_and(|piece| piece.role == PieceRole::King) {continue;}
+            game.position.set_piece(square.index(), None);
+        }
+        game.ids = assign_ids(&game.position);
+        game.update_state();
+        return game;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::Odds;
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, GameOutcome, GameState, Square};
+
+    //check the positions of the piece odds and the castling rights left
+    #[test]
+    fn check_odds_positions() {
+        let game1 = Game::new_with_odds(Odds::KnightOdds { kingside: false });
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+        assert!(game1.validate_position().is_ok());
+        // without the rook on a1 White can only castle kingside
+        assert_eq!(Game::new_with_odds(Odds::RookOdds { kingside: false }).get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1");
+        assert_eq!(Game::new_with_odds(Odds::RookOdds { kingside: true }).get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1");
+        assert_eq!(Game::new_with_odds(Odds::QueenOdds).get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1");
+        // the kings stay
+        let squares = ["e1", "e8", "h8"].map(|name| Square::from_algebraic(name).unwrap()).to_vec();
+        assert_eq!(Game::new_with_odds(Odds::Custom(squares)).get_fen(), "rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQq - 0 1");
+    }
+
+    //check that a game from pawn and move can be played to the end
+    #[test]
+    fn check_pawn_and_move_game() {
+        let mut game1 = Game::new_with_odds(Odds::PawnAndMove);
+        assert_eq!(game1.get_fen(), "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(game1.get_turn_color(), Color::White);
+        // the missing f-pawn opens the diagonal to the black king
+        for mv in ["e2e4", "g7g5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.play_move(&coordinate_move("d1h5")).unwrap().state, GameState::Checkmate);
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        game1.undo().unwrap();
+        assert_eq!(game1.get_piece("d1").unwrap().id(), Game::new_with_odds(Odds::PawnAndMove).get_piece("d1").unwrap().id());
+    }
+}
@@ -0,0 +1,427 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+use crate::{ChessError, Color, Game, GameOutcome, Move};
+
+/// What is wrong with a PGN text and where, e.g. "illegal move 'Nf9' at line 7 column 12".
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PgnError {
+    /// What is wrong, e.g. "illegal move".
+    pub message: String,
+    /// The text the problem is in, e.g. the move or the tag.
+    pub token: String,
+    /// The line of the token, 1 for the first.
+    pub line: usize,
+    /// The column of the token in characters, 1 for the first.
+    pub column: usize,
+    /// The offset of the token in characters from the start of the text, 0 for the first.
+    pub offset: usize,
+}
+
+impl PgnError {
+    fn new(chars: &[char], message: String, start: usize, end: usize) -> PgnError {
+        let line_start = chars[..start].iter().rposition(|&char| char == '\n').map_or(0, |newline| newline + 1);
+        return PgnError {
+            message,
+            token: chars[start..end].iter().collect(),
+            line: chars[..start].iter().filter(|&&char| char == '\n').count() + 1,
+            column: start - line_start + 1,
+            offset: start
+        };
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{} '{}' at line {} column {}", self.message, self.token, self.line, self.column);
+    }
+}
+
+/// An evaluation in a PGN comment, like [%eval 0.31] or [%eval #-3], from White's side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PgnEval {
+    /// A score in centipawns, positive if White is better.
+    Centipawns(i32),
+    /// A forced mate in this many moves, negative if White gets mated.
+    Mate(i32),
+}
+
+/// The comment of a move in a PGN game, like { [%clk 0:02:58] [%eval 0.31] } in a lichess
+/// export, see Game::move_comment.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveComment {
+    /// The time the player had left after the move, from [%clk h:mm:ss].
+    pub clock: Option<Duration>,
+    /// The evaluation of the position after the move, from [%eval].
+    pub eval: Option<PgnEval>,
+    /// The other commands, like "[%emt 0:00:03]", as they were written.
+    pub commands: Vec<String>,
+    /// The text of the comment without the commands, e.g. "Inaccuracy.".
+    pub text: String,
+}
+
+impl MoveComment {
+    /// Adds what the text of a comment (without the braces) says to the comment.
+    fn read(&mut self, text: &str) {
+        let mut rest = text;
+        let mut words: Vec<&str> = Vec::new();
+        while let Some(start) = rest.find("[%") {
+            words.extend(rest[..start].split_whitespace());
+            let Some(length) = rest[start..].find(']') else {
+                rest = &rest[start..];
+                break;
+            };
+            let command = &rest[start..start + length + 1];
+            let (name, argument) = command[2..command.len() - 1].trim().split_once(' ').unwrap_or((&command[2..command.len() - 1], ""));
+            match name {
+                "clk" if parse_clock(argument.trim()).is_some() => self.clock = parse_clock(argument.trim()),
+                "eval" if parse_eval(argument.trim()).is_some() => self.eval = parse_eval(argument.trim()),
+                _ => self.commands.push(command.to_string())
+            }
+            rest = &rest[start + length + 1..];
+        }
+        words.extend(rest.split_whitespace());
+        if !words.is_empty() {
+            if !self.text.is_empty() {self.text.push(' ');}
+            self.text.push_str(&words.join(" "));
+        }
+    }
+
+    /// Writes the comment as it goes in PGN, e.g. "{ [%eval 0.31] [%clk 0:02:58] }".
+    fn write(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        match self.eval {
+            Some(PgnEval::Centipawns(centipawns)) => {
+                let sign = if centipawns < 0 {"-"} else {""};
+                parts.push(format!("[%eval {}{}.{:02}]", sign, centipawns.abs() / 100, centipawns.abs() % 100));
+            }
+            Some(PgnEval::Mate(moves)) => parts.push(format!("[%eval #{}]", moves)),
+            None => ()
+        }
+        if let Some(clock) = self.clock {
+            let seconds = clock.as_secs();
+            let mut text = format!("[%clk {}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+            // tenths or less, as far as they are given
+            if clock.subsec_millis() > 0 {text.push_str(format!(".{:03}", clock.subsec_millis()).trim_end_matches('0'));}
+            text.push(']');
+            parts.push(text);
+        }
+        parts.extend(self.commands.iter().cloned());
+        if !self.text.is_empty() {parts.push(self.text.clone());}
+        return format!("{{ {} }}", parts.join(" "));
+    }
+}
+
+/// Reads a clock like "0:02:58" or "1:02:03.4".
+fn parse_clock(text: &str) -> Option<Duration> {
+    let fields: Vec<&str> = text.split(':').collect();
+    let [hours, minutes, seconds] = fields[..] else {return None};
+    let (seconds, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+    if fraction.len() > 3 || !fraction.chars().all(|char| char.is_ascii_digit()) {return None;}
+    let millis = if fraction.is_empty() {0} else {format!("{:0<3}", fraction).parse::<u64>().ok()?};
+    // a clock too long to hold is no clock
+    let seconds = hours.parse::<u64>().ok()?.checked_mul(3600)?.checked_add(minutes.parse::<u64>().ok()?.checked_mul(60)?)?.checked_add(seconds.parse::<u64>().ok()?)?;
+    return Some(Duration::from_millis(seconds.checked_mul(1000)?.checked_add(millis)?));
+}
+
+/// Reads an evaluation like "0.31", "-1.5" or "#-3". Centipawns past the second decimal are cut off.
+fn parse_eval(text: &str) -> Option<PgnEval> {
+    if let Some(moves) = text.strip_prefix('#') {return moves.parse::<i32>().ok().map(PgnEval::Mate);}
+    let (negative, number) = match text.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, text.strip_prefix('+').unwrap_or(text))
+    };
+    let (pawns, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if !fraction.chars().all(|char| char.is_ascii_digit()) {return None;}
+    let centipawns = pawns.parse::<i32>().ok()?.checked_mul(100)?.checked_add(format!("{:0<2}", &fraction[..fraction.len().min(2)]).parse::<i32>().ok()?)?;
+    return Some(PgnEval::Centipawns(if negative {-centipawns} else {centipawns}));
+}
+
+/// The characters that end a symbol (a move, a move number or a result) besides whitespace.
+const DELIMITERS: &str = "{}[]();$";
+
+impl Game {
+    /// Reads a game in Portable Game Notation (PGN) and plays its moves, or returns
+    /// ChessError::InvalidPgn with what is wrong and where, see PgnError. The game starts from
+    /// the FEN tag if there is one, other tags are skipped, as are NAGs like $1, annotations
+    /// like "!?" and variations. The comments of the moves are kept, see move_comment. The
+    /// moves are in SAN, with or without check marks.
+    /// The text ends with the result, or without it for a game in progress, and only the first
+    /// game is read. The result isn't checked against the position.
+    pub fn from_pgn(pgn: &str) -> Result<Game, ChessError> {
+        let chars: Vec<char> = pgn.chars().collect();
+        let error = |message: &str, start: usize, end: usize| -> ChessError {ChessError::InvalidPgn(PgnError::new(&chars, message.to_string(), start, end))};
+        // the end of the line a token starts on, for tokens that are cut short
+        let line_end = |start: usize| -> usize {chars[start..].iter().position(|&char| char == '\n').map_or(chars.len(), |length| start + length)};
+        let mut game = Game::new();
+        let mut moves_started = false;
+        let mut result_read = false;
+        // the starts of the variations that are open
+        let mut variations: Vec<usize> = Vec::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let start = index;
+            match chars[start] {
+                char if char.is_whitespace() => index += 1,
+                // an escaped line, or a comment to the end of the line
+                '%' if start == 0 || chars[start - 1] == '\n' => index = line_end(start),
+                ';' => index = line_end(start),
+                '{' => {
+                    let Some(length) = chars[start..].iter().position(|&char| char == '}') else {return Err(error("comment without '}'", start, line_end(start)))};
+                    index = start + length + 1;
+                    // the comment belongs to the move before it, outside of the variations
+                    let Some(entry) = game.history.last_mut().filter(|_| variations.is_empty() && !result_read) else {continue};
+                    let text: String = chars[start + 1..index - 1].iter().collect();
+                    entry.comment.get_or_insert_with(MoveComment::default).read(&text);
+                }
+                '(' => {
+                    variations.push(start);
+                    index += 1;
+                }
+                ')' => {
+                    if variations.pop().is_none() {return Err(error("')' without '('", start, start + 1));}
+                    index += 1;
+                }
+                '$' => {
+                    index += 1;
+                    while index < chars.len() && chars[index].is_ascii_digit() {index += 1;}
+                    if index == start + 1 {return Err(error("'$' without a number", start, index));}
+                }
+                '[' => {
+                    if moves_started {return Err(error("tag after the moves", start, line_end(start)));}
+                    let (name, value, value_start, end) = read_tag(&chars, start).ok_or_else(|| error("malformed tag", start, line_end(start)))?;
+                    if name == "FEN" {
+                        game = Game::from_fen(&value).map_err(|fen_error| match fen_error {
+                            ChessError::InvalidFen(fen_error) => error(&format!("invalid FEN tag: {}", fen_error), value_start, value_start + value.chars().count()),
+                            _ => error("invalid FEN tag", value_start, value_start + value.chars().count())
+                        })?;
+                    }
+                    index = end;
+                }
+                _ => {
+                    while index < chars.len() && !chars[index].is_whitespace() && !DELIMITERS.contains(chars[index]) {index += 1;}
+                    let mut symbol: String = chars[start..index].iter().collect();
+                    moves_started = true;
+                    if !variations.is_empty() {continue;}
+                    if result_read {return Err(error("text after the result", start, index));}
+                    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&symbol.as_str()) {
+                        result_read = true;
+                        continue;
+                    }
+                    // a move number like "12." or "12...", which may stand right before the move
+                    let digits = symbol.chars().take_while(|char| char.is_ascii_digit()).count();
+                    let mut move_start = start;
+                    if digits > 0 && symbol[digits..].starts_with('.') {
+                        let number_length = digits + symbol[digits..].chars().take_while(|&char| char == '.').count();
+                        symbol = symbol[number_length..].to_string();
+                        move_start += number_length;
+                        if symbol.is_empty() {continue;}
+                    }
+                    let mv = game.parse_san(&symbol).ok_or_else(|| error("illegal move", move_start, index))?;
+                    game.play_move(&mv);
+                }
+            }
+        }
+        if let Some(&start) = variations.last() {return Err(error("'(' without ')'", start, start + 1));}
+        return Ok(game);
+    }
+
+    /// The comment of a move read from PGN, with the clock and evaluation of lichess exports,
+    /// or None if the move had no comment. The ply counts the moves made in the game from 0,
+    /// so the first move of Black is ply 1 in a game from the start position.
+    pub fn move_comment(&self, ply: usize) -> Option<&MoveComment> {
+        return self.history.get(ply)?.comment.as_ref();
+    }
+
+    /// Writes the game in PGN: the seven tags every PGN game has, with "?" for the ones the
+    /// game doesn't know, the start position if the game didn't start from the start
+    /// position, and the moves since then in SAN with their comments (see move_comment). The
+    /// result is that of outcome, or * for a game in progress.
+    pub fn to_pgn(&self) -> String {
+        let result = match self.outcome() {
+            Some(GameOutcome::Win(Color::White)) => "1-0",
+            Some(GameOutcome::Win(Color::Black)) => "0-1",
+            Some(GameOutcome::Draw) => "1/2-1/2",
+            None => "*"
+        };
+        let mut pgn = format!("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n[Black \"?\"]\n[Result \"{}\"]\n", result);
+        let mut start = self.clone();
+        if let Some(entry) = self.history.first() {start.position = entry.position;}
+        let start_fen = start.get_fen();
+        if start_fen != Game::new().get_fen() {pgn.push_str(&format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", start_fen));}
+        pgn.push('\n');
+        // the move numbers go before White's moves, and before Black's moves after a comment or
+        // as the first move
+        let mut tokens: Vec<String> = Vec::new();
+        let (mut fullmove, mut turn) = (start.position.fullmove, start.position.turn);
+        let moves: Vec<Move> = self.history.iter().map(|entry| entry.result.mv).collect();
+        for ((ply, san), entry) in start.position.san_line(&moves).into_iter().enumerate().zip(&self.history) {
+            if turn == Color::White {tokens.push(format!("{}.", fullmove));}
+            else if ply == 0 || self.history[ply - 1].comment.is_some() {tokens.push(format!("{}...", fullmove));}
+            tokens.push(san);
+            if let Some(comment) = &entry.comment {tokens.push(comment.write());}
+            if turn == Color::Black {fullmove += 1;}
+            turn = turn.opposite();
+        }
+        tokens.push(result.to_string());
+        // lines of at most 80 characters, unless a comment is longer
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.chars().count() + 1 + token.chars().count() > 80 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {line.push(' ');}
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        return pgn;
+    }
+}
+
+/// Reads the tag that starts at the '[', like [Event "Casual game"], returning its name, its
+/// value, where the value starts and the index after the ']'.
+fn read_tag(chars: &[char], start: usize) -> Option<(String, String, usize, usize)> {
+    let mut index = start + 1;
+    let skip_spaces = |index: &mut usize| while *index < chars.len() && chars[*index] == ' ' {*index += 1;};
+    skip_spaces(&mut index);
+    let name_start = index;
+    while index < chars.len() && (chars[index].is_ascii_alphanumeric() || chars[index] == '_') {index += 1;}
+    if index == name_start {return None;}
+    let name: String = chars[name_start..index].iter().collect();
+    skip_spaces(&mut index);
+    if chars.get(index) != Some(&'"') {return None;}
+    index += 1;
+    let value_start = index;
+    let mut value = String::new();
+    loop {
+        match chars.get(index)? {
+            '"' => break,
+            '\n' => return None,
+            '\\' => {
+                value.push(*chars.get(index + 1)?);
+                index += 2;
+            }
+            &char => {
+                value.push(char);
+                index += 1;
+            }
+        }
+    }
+    index += 1;
+    skip_spaces(&mut index);
+    if chars.get(index) != Some(&']') {return None;}
+    return Some((name, value, value_start, index + 1));
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{PgnError, PgnEval};
+    use crate::{ChessError, Game, GameState};
+
+    // the error of a PGN text that can't be read
+    fn pgn_error(pgn: &str) -> PgnError {
+        match Game::from_pgn(pgn) {
+            Err(ChessError::InvalidPgn(error)) => return error,
+            other => panic!("{} gave {:?}", pgn, other.map(|game| game.get_fen()))
+        }
+    }
+
+    //check that a game with tags, comments, NAGs and variations is read
+    #[test]
+    fn check_from_pgn() {
+        let pgn = "[Event \"Casual game\"]\n[White \"A \\\"B\\\" C\"]\n\n1. e4 e5 {the open game} 2. Nf3 $1 (2. f4 exf4) Nc6\n3.Bc4 Nd4?? 4. Nxe5 Qg5 5. Nxf7?! Qxg2 6. Rf1 Qxe4+ 7. Be2 Nf3# 0-1\n";
+        let game1 = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game1.get_game_state(), GameState::Checkmate);
+        assert_eq!(game1.get_fen(), "r1b1kbnr/pppp1Npp/8/8/4q3/5n2/PPPPBP1P/RNBQKR2 w Qkq - 2 8");
+        // a FEN tag, and castling written with zeros
+        let game1 = Game::from_pgn("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. 0-0 Kd7 *").unwrap();
+        assert_eq!(game1.get_fen(), "8/3k4/8/8/8/8/8/5RK1 w - - 2 2");
+        assert_eq!(Game::from_pgn("").unwrap().get_fen(), Game::new().get_fen());
+    }
+
+    //check the clocks and evaluations of a lichess export, and that they are written back
+    #[test]
+    fn check_pgn_comments() {
+        let game1 = Game::from_pgn(include_str!("../tests/fixtures/lichess_game.pgn")).unwrap();
+        // White's 10th move is d4 and the 15th a4
+        assert_eq!(game1.move_comment(18).unwrap().clock, Some(Duration::from_secs(144)));
+        assert_eq!(game1.move_comment(28).unwrap().eval, Some(PgnEval::Centipawns(44)));
+        let nb8 = game1.move_comment(17).unwrap();
+        assert_eq!((nb8.text.as_str(), nb8.clock, nb8.commands.len()), ("Inaccuracy. Na5 was best.", Some(Duration::from_secs(146)), 0));
+        assert_eq!(game1.move_comment(24).unwrap().commands, vec!["[%cal Gf1g3]".to_string()]);
+        assert_eq!(game1.move_comment(38), None);
+        let pgn = game1.to_pgn();
+        // the lines are wrapped, also inside the comments
+        let movetext = pgn.split_whitespace().collect::<Vec<&str>>().join(" ");
+        assert!(movetext.contains("10. d4 { [%eval 0.33] [%clk 0:02:24] } 10... Nbd7"), "{}", pgn);
+        assert!(movetext.contains("13. Nf1 { [%eval 0.30] [%clk 0:02:15] [%cal Gf1g3] }"), "{}", pgn);
+        let read = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(read.get_fen(), game1.get_fen());
+        for ply in 0..38 {
+            assert_eq!(read.move_comment(ply), game1.move_comment(ply));
+        }
+    }
+
+    //check the clocks and evaluations the comments can have
+    #[test]
+    fn check_move_comment_values() {
+        let game1 = Game::from_pgn("1. e4 {[%clk 1:02:03.4] [%eval #-3]} {good} e5 { [%eval -1.5] [%clk 0:00:05.25] [%clk x] } *").unwrap();
+        let comment = game1.move_comment(0).unwrap();
+        assert_eq!((comment.clock, comment.eval, comment.text.as_str()), (Some(Duration::from_millis(3723400)), Some(PgnEval::Mate(-3)), "good"));
+        let comment = game1.move_comment(1).unwrap();
+        assert_eq!((comment.clock, comment.eval), (Some(Duration::from_millis(5250)), Some(PgnEval::Centipawns(-150))));
+        assert_eq!(comment.commands, vec!["[%clk x]".to_string()]);
+        let movetext = game1.to_pgn().split("\n\n").nth(1).unwrap().trim_end().replace('\n', " ");
+        assert_eq!(movetext, "1. e4 { [%eval #-3] [%clk 1:02:03.4] good } 1... e5 { [%eval -1.50] [%clk 0:00:05.25] [%clk x] } *");
+        // values too large to hold are kept as unknown commands
+        let game1 = Game::from_pgn("1. e4 { [%eval 99999999] } *").unwrap();
+        let comment = game1.move_comment(0).unwrap();
+        assert_eq!((comment.eval, comment.commands.clone()), (None, vec!["[%eval 99999999]".to_string()]));
+        let game1 = Game::from_pgn("1. e4 { [%clk 9999999999999999:00:00] } *").unwrap();
+        let comment = game1.move_comment(0).unwrap();
+        assert_eq!((comment.clock, comment.commands.clone()), (None, vec!["[%clk 9999999999999999:00:00]".to_string()]));
+        // games that didn't start from the start position have their FEN
+        let game1 = Game::from_pgn("[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 1\"]\n\n1... Kd7 2. Ra7+ *").unwrap();
+        assert!(game1.to_pgn().ends_with("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K3 b - - 0 1\"]\n\n1... Kd7 2. Ra7+ *\n"));
+    }
+
+    //check the locations of the errors, on the line of the move or of the tag
+    #[test]
+    fn check_from_pgn_errors() {
+        let pgn = "[Event \"Casual game\"]\n[Site \"?\"]\n[Date \"2023.05.01\"]\n[White \"A\"]\n[Black \"B\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6 dxc6 5. Nf9 Nf6\n";
+        let error = pgn_error(pgn);
+        assert_eq!((error.line, error.column, error.offset), (7, 47, 124));
+        assert_eq!(error.token, "Nf9");
+        assert_eq!(error.to_string(), "illegal move 'Nf9' at line 7 column 47");
+        assert_eq!(ChessError::InvalidPgn(error).to_string(), "invalid PGN: illegal move 'Nf9' at line 7 column 47");
+        let error = pgn_error("1. e4 e5\n2.Ke3");
+        assert_eq!((error.line, error.column, error.offset, error.token.as_str()), (2, 3, 11, "Ke3"));
+        let error = pgn_error("[Event \"x\"]\n[White A]\n\n1. e4");
+        assert_eq!((error.message.as_str(), error.line, error.column, error.token.as_str()), ("malformed tag", 2, 1, "[White A]"));
+        let error = pgn_error("1. e4 {a comment\n that never ends");
+        assert_eq!((error.message.as_str(), error.offset, error.token.as_str()), ("comment without '}'", 6, "{a comment"));
+        let error = pgn_error("1. e4 (1. d4 d5 2. c4");
+        assert_eq!((error.message.as_str(), error.offset), ("'(' without ')'", 6));
+        let error = pgn_error("1. e4 e5 1-0 2. Nf3");
+        assert_eq!((error.message.as_str(), error.token.as_str()), ("text after the result", "2."));
+        // the FEN error points into the tag
+        let error = pgn_error("[FEN \"4k3/8/8/8/8/8/8/4X3 w - - 0 1\"]\n\n*");
+        assert_eq!(error.message, "invalid FEN tag: invalid piece character 'X' at position 17 in placement field");
+        assert_eq!((error.line, error.column), (1, 7));
+    }
+}
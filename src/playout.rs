@@ -0,0 +1,172 @@
+use alloc::vec::Vec;
+
+use crate::{repetitions, Color, Game, Move, XorShift};
+
+/// How a random playout ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayoutOutcome {
+    Checkmate { winner: Color },
+    Stalemate,
+    /// A draw by the fifty-move rule: 100 plies without a capture or a pawn move.
+    FiftyMoves,
+    /// A draw by threefold repetition.
+    Repetition,
+    /// The ply limit was reached before the game ended.
+    PlyLimit,
+}
+
+/// The result of Game::random_playout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayoutResult {
+    pub outcome: PlayoutOutcome,
+    /// The number of moves played.
+    pub plies: u32,
+    /// The moves played, in order.
+    pub moves: Vec<Move>,
+}
+
+impl Game {
+    /// Picks a legal move at random, every legal move being equally likely, or returns None if
+    /// the game is over. The move only depends on the position and the numbers of the
+    /// generator, so a generator created with the same seed picks the same move.
+    pub fn random_legal_move(&self, rng: &mut XorShift) -> Option<Move> {
+        let moves = self.legal_moves();
+        if moves.is_empty() {return None;}
+        return Some(moves[rng.next_index(moves.len())]);
+    }
+
+    /// Plays random legal moves from the current position until the game ends or `max_plies`
+    /// moves have been played, without changing the game. `choose` is called with the number of
+    /// legal moves n and returns the index (below n) of the move to play, so any source of
+    /// random numbers can be used. Besides checkmate and stalemate the playout ends with a draw
+    /// by the fifty-move rule or threefold repetition, counting the positions of the game
+    /// before the playout too.
+    pub fn random_playout(&self, mut choose: impl FnMut(usize) -> usize, max_plies: u32) -> PlayoutResult {
+        let mut position = self.position;
+        let mut keys = self.repetition_keys();
+        let mut moves: Vec<Move> = Vec::new();
+        let outcome = loop {
+            let legal_moves = position.legal_moves();
+            if legal_moves.is_empty() {
+                break if position.in_check(position.turn) {PlayoutOutcome::Checkmate { winner: position.turn.opposite() }} else {PlayoutOutcome::Stalemate};
+            }
+            if position.halfmove >= 100 {break PlayoutOutcome::FiftyMoves;}
+            if repetitions(&keys, position.halfmove) >= 2 {break PlayoutOutcome::Repetition;}
+            if moves.len() as u32 >= max_plies {break PlayoutOutcome::PlyLimit;}
+            let mv = legal_moves[choose(legal_moves.len())];
+            position.apply_move(mv);
+            keys.push(position.repetition_key());
+            moves.push(mv);
+        };
+        return PlayoutResult { outcome, plies: moves.len() as u32, moves };
+    }
+
+    /// True if the current position has occurred three times in the game, with the same player
+    /// to move and the same castling and en passant rights.
+    // only used by the parts that need std
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn is_threefold_repetition(&self) -> bool {
+        return repetitions(&self.repetition_keys(), self.position.halfmove) >= 2;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::PlayoutOutcome;
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, XorShift};
+
+    //check that a playout from a finished game ends without a move
+    #[test]
+    fn check_playout_game_over() {
+        let game1 = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        let result = game1.random_playout(|_| panic!("no move to choose"), 10);
+        assert_eq!(result.outcome, PlayoutOutcome::Checkmate { winner: Color::White });
+        assert_eq!(result.plies, 0);
+        let game1 = Game::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game1.random_playout(|_| 0, 10).outcome, PlayoutOutcome::Stalemate);
+    }
+
+    //check that playouts from the start end within the ply limit with legal moves
+    #[test]
+    fn check_playout_terminates() {
+        let mut rng = XorShift::new(7);
+        for _ in 0..100 {
+            let result = Game::new().random_playout(|n| rng.next_index(n), 300);
+            assert!(result.plies <= 300);
+            assert_eq!(result.plies as usize, result.moves.len());
+            let mut game1 = Game::new();
+            for mv in &result.moves {
+                assert!(game1.play_move(mv).is_some());
+            }
+            assert_eq!(result.outcome == PlayoutOutcome::PlyLimit, result.plies == 300);
+        }
+    }
+
+    //check that the fifty-move rule and repetitions end a playout
+    #[test]
+    fn check_playout_draw_rules() {
+        let game1 = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        let result = game1.random_playout(|_| 0, 10);
+        assert_eq!(result.outcome, PlayoutOutcome::FiftyMoves);
+        assert_eq!(result.plies, 1);
+        // the knights jump out and back twice
+        let mut game1 = Game::new();
+        let mut choices = Vec::new();
+        for _ in 0..2 {
+            for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                let mv = coordinate_move(mv);
+                choices.push(game1.get_all_possible_moves().iter().position(|&other| other == mv).unwrap());
+                game1.play_move(&mv).unwrap();
+            }
+        }
+        let mut choices = choices.into_iter();
+        let result = Game::new().random_playout(|_| choices.next().unwrap(), 100);
+        assert_eq!(result.outcome, PlayoutOutcome::Repetition);
+        assert_eq!(result.plies, 8);
+    }
+
+    //check that the same seed picks the same move, and that every move gets picked
+    #[test]
+    fn check_random_legal_move_seeded() {
+        let game1 = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let picks = |seed: u64| -> Vec<String> {
+            let mut rng = XorShift::new(seed);
+            return (0..20).map(|_| game1.random_legal_move(&mut rng).unwrap().to_string()).collect();
+        };
+        assert_eq!(picks(1234), picks(1234));
+        assert_ne!(picks(1234), picks(4321));
+        let moves = game1.get_all_possible_moves();
+        let mut counts = vec![0; moves.len()];
+        let mut rng = XorShift::new(99);
+        for _ in 0..moves.len() * 200 {
+            let mv = game1.random_legal_move(&mut rng).unwrap();
+            counts[moves.iter().position(|&other| other == mv).unwrap()] += 1;
+        }
+        // about 200 each
+        assert!(counts.iter().all(|&count| (120..280).contains(&count)));
+        let game1 = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(game1.random_legal_move(&mut rng), None);
+    }
+
+    //check that long random games stay in valid positions
+    #[test]
+    fn check_random_games_valid() {
+        let mut rng = XorShift::new(2024);
+        for _ in 0..20 {
+            let mut game1 = Game::new();
+            for _ in 0..200 {
+                let mv = match game1.random_legal_move(&mut rng) {
+                    Some(mv) => mv,
+                    None => break
+                };
+                game1.play_move(&mv).unwrap();
+                assert_eq!(game1.validate_position(), Ok(()));
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::string::ToString;
+
+use crate::legality::reaches;
+use crate::{parse_square, ChessError, Color, Game, IllegalMoveReason, Move, PieceRole};
+
+impl Game {
+    /// Queues a move for the player to make as soon as the opponent has moved, like clicking a
+    /// move on a board while waiting. A player has at most one premove, and a new one replaces
+    /// the old. The move only has to be one the piece could make on an empty board, since the
+    /// opponent's move can still open a path or bring a piece to take: it is checked again
+    /// after that move and played if it is legal then, or else discarded, see
+    /// GameEvent::PremovePlayed and GameEvent::PremoveDiscarded. Returns an error if the game
+    /// is over, the player is to move (WrongTurn), a square isn't valid, the player has no piece
+    /// on from, the piece can't move like that or a pawn to the last rank has no promotion.
+    pub fn set_premove(&mut self, color: Color, from: &str, to: &str, promotion: Option<PieceRole>) -> Result<(), ChessError> {
+        if self.outcome().is_some() {return Err(ChessError::IllegalMove(IllegalMoveReason::GameOver));}
+        let Some(from_index) = parse_square(from).filter(|_| from.len() == 2) else {return Err(ChessError::InvalidSquare(from.to_string()))};
+        let Some(to_index) = parse_square(to).filter(|_| to.len() == 2) else {return Err(ChessError::InvalidSquare(to.to_string()))};
+        if color == self.position.turn {return Err(ChessError::IllegalMove(IllegalMoveReason::WrongTurn));}
+        let Some(piece) = self.position.piece_at(from_index).filter(|piece| piece.color == color) else {return Err(ChessError::IllegalMove(IllegalMoveReason::NotYourPiece))};
+        let mv = Move::new(from_index, to_index, promotion);
+        let castles = self.position.castling_rook(mv).is_some();
+        if !castles && !reaches(piece, self.position.variant, from_index, to_index) {return Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay));}
+        let last_rank = piece.role == PieceRole::Pawn && (to_index / 8 == 0 || to_index / 8 == 7);
+        if last_rank && promotion.is_none() {return Err(ChessError::IllegalMove(IllegalMoveReason::PromotionRequired));}
+        if !last_rank && promotion.is_some() {return Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay));}
+        self.premoves[color as usize] = Some(mv);
+        return Ok(());
+    }
+
+    /// The premove the player has queued, see set_premove.
+    pub fn premove(&self, color: Color) -> Option<Move> {
+        return self.premoves[color as usize];
+    }
+
+    /// Takes back the premove of the player and returns it, or None if there was none.
+    pub fn cancel_premove(&mut self, color: Color) -> Option<Move> {
+        return self.premoves[color as usize].take();
+    }
+
+    /// Plays the premove of the player to move if it is legal now, or discards it, after the
+    /// opponent has moved. A premove never outlives the move it waited for.
+    pub(crate) fn play_premove(&mut self) {
+        let color = self.position.turn;
+        let Some(mv) = self.premoves[color as usize].take() else {return};
+        match self.illegal_move_reason(&mv) {
+            None => {
+                self.emit_premove_played(color);
+                self.play(mv);
+            }
+            Some(reason) => self.emit_premove_discarded(color, mv, reason)
+        }
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{coordinate_move, recorded};
+    use crate::{ChessError, Color, Game, GameEvent, GameOutcome, GameState, IllegalMoveReason, PieceRole, Variant};
+
+    //check that a premove is played right after the opponent's move once it is legal
+    #[test]
+    fn check_premove_played() {
+        let (mut game1, events) = recorded("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        // the bishop can't get out before the pawn has moved
+        game1.set_premove(Color::Black, "f8", "c5", None).unwrap();
+        assert_eq!(game1.premove(Color::Black), Some(coordinate_move("f8c5")));
+        assert_eq!(game1.make_move("e2", "e4"), Some(GameState::InProgress));
+        assert_eq!(game1.premove(Color::Black), None);
+        assert_eq!(game1.get_turn_color(), Color::Black);
+        game1.make_move("e7", "e5").unwrap();
+        // but may once it has
+        game1.set_premove(Color::Black, "f8", "c5", None).unwrap();
+        let result = game1.play_move(&coordinate_move("g1f3")).unwrap();
+        assert_eq!(result.mv, coordinate_move("g1f3"));
+        assert_eq!(game1.get_turn_color(), Color::White);
+        assert_eq!(game1.get_fen(), "rnbqk1nr/pppp1ppp/8/2b1p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let events = events.lock().unwrap();
+        assert_eq!(events[1], GameEvent::PremoveDiscarded { color: Color::Black, mv: coordinate_move("f8c5"), reason: IllegalMoveReason::PathBlocked });
+        assert_eq!(events[4], GameEvent::PremovePlayed(Color::Black));
+        assert!(matches!(events[5], GameEvent::MoveMade(result) if result.mv == coordinate_move("f8c5")));
+        assert_eq!(events.len(), 6);
+        drop(events);
+        // the premove is taken back like any move
+        assert_eq!(game1.undo(), Some(coordinate_move("f8c5")));
+    }
+
+    //check that a premove the opponent's move makes illegal is discarded, e.g. a piece that is pinned
+    #[test]
+    fn check_premove_discarded() {
+        let (mut game1, events) = recorded("4k3/4n3/8/8/8/8/8/R5K1 w - - 0 1");
+        game1.set_premove(Color::Black, "e7", "c6", None).unwrap();
+        game1.make_move("a1", "e1").unwrap();
+        assert_eq!(game1.get_turn_color(), Color::Black);
+        assert_eq!(game1.premove(Color::Black), None);
+        assert_eq!(game1.get_fen(), "4k3/4n3/8/8/8/8/8/4R1K1 b - - 1 1");
+        let discarded = GameEvent::PremoveDiscarded { color: Color::Black, mv: coordinate_move("e7c6"), reason: IllegalMoveReason::WouldLeaveKingInCheck };
+        assert_eq!(events.lock().unwrap().last(), Some(&discarded));
+    }
+
+    //check that a premove capture is discarded when the opponent moves the piece away
+    #[test]
+    fn check_premove_capture_vacated() {
+        let (mut game1, events) = recorded("4k3/8/8/3p4/4P3/8/8/4K3 b - - 0 1");
+        game1.make_move("e8", "d7").unwrap();
+        game1.set_premove(Color::Black, "d5", "e4", None).unwrap();
+        game1.make_move("e4", "e5").unwrap();
+        assert_eq!(game1.get_fen(), "8/3k4/8/3pP3/8/8/8/4K3 b - - 0 2");
+        assert_eq!(game1.premove(Color::Black), None);
+        let discarded = GameEvent::PremoveDiscarded { color: Color::Black, mv: coordinate_move("d5e4"), reason: IllegalMoveReason::PieceCannotMoveThatWay };
+        assert_eq!(events.lock().unwrap().last(), Some(&discarded));
+        // a knight that was to take goes to the square all the same
+        let mut game1 = Game::from_fen("4k3/8/8/8/4P3/2n5/8/K7 w - - 0 1").unwrap();
+        game1.set_premove(Color::Black, "c3", "e4", None).unwrap();
+        game1.make_move("e4", "e5").unwrap();
+        assert_eq!(game1.get_turn_color(), Color::White);
+        assert_eq!(game1.get_fen(), "4k3/8/8/4P3/4n3/8/8/K7 w - - 1 2");
+    }
+
+    //check the errors of set_premove, cancelling and that no premove is played after the game ends
+    #[test]
+    fn check_premove_rules() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.set_premove(Color::White, "e2", "e4", None), Err(ChessError::IllegalMove(IllegalMoveReason::WrongTurn)));
+        assert_eq!(game1.set_premove(Color::Black, "e2", "e4", None), Err(ChessError::IllegalMove(IllegalMoveReason::NotYourPiece)));
+        assert_eq!(game1.set_premove(Color::Black, "g8", "g6", None), Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay)));
+        assert_eq!(game1.set_premove(Color::Black, "e7", "e9", None), Err(ChessError::InvalidSquare("e9".to_string())));
+        assert_eq!(game1.set_premove(Color::Black, "e7", "e6", Some(PieceRole::Queen)), Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay)));
+        // castling is queued like any king move
+        assert!(game1.set_premove(Color::Black, "e8", "g8", None).is_ok());
+        // but a king two files over off its back rank, or onto a rook that has moved in
+        // Chess960, can't get there
+        let mut away = Game::from_fen("4k3/8/8/8/4K3/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(away.set_premove(Color::White, "e4", "g6", None), Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay)));
+        let mut moved_rook = Game::from_fen_variant("4k3/8/8/8/8/8/8/1R2K3 b - - 0 1", Variant::Chess960).unwrap();
+        assert_eq!(moved_rook.set_premove(Color::White, "e1", "b1", None), Err(ChessError::IllegalMove(IllegalMoveReason::PieceCannotMoveThatWay)));
+        let mut unmoved_rook = Game::from_fen_variant("4k3/8/8/8/8/8/8/1R2K3 b B - 0 1", Variant::Chess960).unwrap();
+        assert!(unmoved_rook.set_premove(Color::White, "e1", "b1", None).is_ok());
+        game1.set_premove(Color::Black, "e7", "e5", None).unwrap();
+        assert_eq!(game1.cancel_premove(Color::Black), Some(coordinate_move("e7e5")));
+        assert_eq!(game1.cancel_premove(Color::Black), None);
+        game1.make_move("e2", "e4").unwrap();
+        assert_eq!(game1.get_turn_color(), Color::Black);
+        let mut game1 = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(game1.set_premove(Color::White, "b7", "b8", None), Err(ChessError::IllegalMove(IllegalMoveReason::PromotionRequired)));
+        game1.set_premove(Color::White, "b7", "b8", Some(PieceRole::Queen)).unwrap();
+        game1.make_move("e8", "e7").unwrap();
+        assert_eq!(game1.get_fen(), "1Q6/4k3/8/8/8/8/8/4K3 b - - 0 2");
+        // a resignation ends the game and the premove with it
+        game1.set_premove(Color::White, "b8", "b1", None).unwrap();
+        game1.resign(Color::Black);
+        assert_eq!(game1.premove(Color::White), None);
+        assert_eq!(game1.set_premove(Color::White, "b8", "b1", None), Err(ChessError::IllegalMove(IllegalMoveReason::GameOver)));
+        // and a checkmate doesn't let the mated player's premove through
+        let mut game1 = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        game1.set_premove(Color::Black, "g8", "h8", None).unwrap();
+        assert_eq!(game1.make_move("a1", "a8"), Some(GameState::Checkmate));
+        assert_eq!(game1.premove(Color::Black), None);
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(game1.get_fen(), "R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1");
+    }
+}
@@ -0,0 +1,152 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{ChessError, Game, GameState, Move};
+
+/// Errors returned by Puzzle::parse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleError {
+    /// The FEN string of the puzzle isn't valid.
+    InvalidFen(ChessError),
+    /// A move of the solution isn't legal, with its number (0 for the setup move) and the move.
+    IllegalMove { index: usize, mv: String },
+    /// There is no move to solve after the setup move.
+    NoSolution,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::InvalidFen(error) => write!(f, "{}", error),
+            PuzzleError::IllegalMove { index, mv } => write!(f, "move {} of the puzzle ({}) isn't legal", index, mv),
+            PuzzleError::NoSolution => write!(f, "the puzzle has no solution")
+        }
+    }
+}
+
+impl core::error::Error for PuzzleError {}
+
+/// How an attempt at a puzzle went, see Puzzle::check_attempt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttemptResult {
+    /// The move of the solution.
+    Correct,
+    /// Another move that checkmates, which solves the puzzle as well.
+    AlternateMateAccepted,
+    Wrong,
+}
+
+/// A puzzle in the format of the lichess puzzle database: a position, the move of the opponent
+/// that sets up the puzzle and the moves of the solution.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    /// The position after the setup move.
+    game: Game,
+    /// The moves of the solver and the replies of the opponent, the solver's first.
+    solution: Vec<Move>,
+}
+
+impl Puzzle {
+    /// Reads a puzzle from the FEN and moves columns of the lichess puzzle database. The moves
+    /// are in coordinate notation separated by spaces, e.g. "f2g3 e6e7 b2b1": the first is the
+    /// opponent's move that sets up the puzzle, and the rest alternate between the moves of
+    /// the player who solves it and the replies of the opponent.
+    pub fn parse(fen: &str, uci_moves: &str) -> Result<Puzzle, PuzzleError> {
+        let mut game = Game::from_fen(fen).map_err(PuzzleError::InvalidFen)?;
+        let start = game.clone();
+        let mut moves: Vec<Move> = Vec::new();
+        for (index, notation) in uci_moves.split_whitespace().enumerate() {
+            let Some(mv) = game.get_all_possible_moves().into_iter().find(|mv| mv.to_string() == notation) else {
+                return Err(PuzzleError::IllegalMove { index, mv: notation.to_string() });
+            };
+            game.play_move(&mv);
+            moves.push(mv);
+        }
+        if moves.len() < 2 {return Err(PuzzleError::NoSolution);}
+        let mut game = start;
+        game.play_move(&moves[0]);
+        return Ok(Puzzle { game, solution: moves.split_off(1) });
+    }
+
+    /// The position the solver starts from, after the setup move.
+    pub fn game(&self) -> &Game {
+        return &self.game;
+    }
+
+    /// The moves of the solution, the solver's first and then every other one.
+    pub fn solution(&self) -> &[Move] {
+        return &self.solution;
+    }
+
+    /// Checks the move the solver tries after the moves so far, which are the moves played
+    /// from the puzzle position: the solver's correct moves and the opponent's replies from
+    /// the solution. Like on lichess, a move that checkmates is accepted even if it isn't the
+    /// one of the solution. After moves that left the solution, or when it is the opponent's
+    /// turn or the puzzle is solved, every attempt is wrong.
+    pub fn check_attempt(&self, moves_so_far: &[Move], attempt: Move) -> AttemptResult {
+        let ply = moves_so_far.len();
+        if ply % 2 == 1 || ply >= self.solution.len() || moves_so_far != &self.solution[..ply] {return AttemptResult::Wrong;}
+        if attempt == self.solution[ply] {return AttemptResult::Correct;}
+        let mut game = self.game.clone();
+        for mv in moves_so_far {
+            game.play_move(mv);
+        }
+        return match game.play_move(&attempt) {
+            Some(result) if result.state == GameState::Checkmate => AttemptResult::AlternateMateAccepted,
+            _ => AttemptResult::Wrong
+        };
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{AttemptResult, Puzzle, PuzzleError};
+    use crate::tests::coordinate_move;
+
+    //check a puzzle row of the lichess database that wins material
+    #[test]
+    fn check_puzzle() {
+        let puzzle = Puzzle::parse("r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2R1/PqP2bPP/7K b - - 0 24", "f2g3 e6e7 b2b1 b3c1 b1c1 h6c1").unwrap();
+        assert_eq!(puzzle.game().get_fen(), "r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2b1/PqP3PP/7K w - - 0 25");
+        assert_eq!(puzzle.solution().len(), 5);
+        let moves: Vec<_> = puzzle.solution().to_vec();
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("e6e7")), AttemptResult::Correct);
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("h6h7")), AttemptResult::Wrong);
+        assert_eq!(puzzle.check_attempt(&moves[..2], coordinate_move("b3c1")), AttemptResult::Correct);
+        assert_eq!(puzzle.check_attempt(&moves[..4], coordinate_move("h6c1")), AttemptResult::Correct);
+        // the opponent's move, a solved puzzle and moves that left the solution
+        assert_eq!(puzzle.check_attempt(&moves[..1], coordinate_move("b2b1")), AttemptResult::Wrong);
+        assert_eq!(puzzle.check_attempt(&moves, coordinate_move("h8g8")), AttemptResult::Wrong);
+        assert_eq!(puzzle.check_attempt(&[coordinate_move("h6h7"), moves[1]], coordinate_move("b3c1")), AttemptResult::Wrong);
+        // another row, where Black solves it, and a check that isn't mate
+        let puzzle = Puzzle::parse("5rk1/1p3ppp/pq3b2/8/8/1P1Q1N2/P4PPP/3R2K1 w - - 2 27", "d3d6 f8d8 d6d8 f6d8").unwrap();
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("f8d8")), AttemptResult::Correct);
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("b6f2")), AttemptResult::Wrong);
+    }
+
+    //check that another mate than the one of the solution is accepted, in a back rank mate with two rooks
+    #[test]
+    fn check_puzzle_alternate_mate() {
+        let puzzle = Puzzle::parse("6k1/5ppp/8/8/8/p7/5PPP/1R1R2K1 b - - 0 30", "a3a2 d1d8").unwrap();
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("d1d8")), AttemptResult::Correct);
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("b1b8")), AttemptResult::AlternateMateAccepted);
+        // a move that doesn't mate, and a move that isn't legal
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("d1d7")), AttemptResult::Wrong);
+        assert_eq!(puzzle.check_attempt(&[], coordinate_move("d1c3")), AttemptResult::Wrong);
+    }
+
+    //check that broken rows are refused
+    #[test]
+    fn check_puzzle_errors() {
+        assert!(matches!(Puzzle::parse("8/8/8 w - - 0 1", "e2e4 e7e5"), Err(PuzzleError::InvalidFen(_))));
+        let fen = "r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2R1/PqP2bPP/7K b - - 0 24";
+        assert_eq!(Puzzle::parse(fen, "f2g3 e6e8").unwrap_err(), PuzzleError::IllegalMove { index: 1, mv: "e6e8".to_string() });
+        assert_eq!(Puzzle::parse(fen, "f2g3").unwrap_err(), PuzzleError::NoSolution);
+        assert_eq!(Puzzle::parse(fen, "f2g3 e6e8").unwrap_err().to_string(), "move 1 of the puzzle (e6e8) isn't legal");
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{Color, Game, XorShift};
+
+/// How Game::random_position makes a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomPositionOptions {
+    /// Plays from 0 up to max_plies random legal moves from the start position, fewer if the
+    /// game ends, so the position can be reached in a game. The moves can be undone.
+    Played { max_plies: u32 },
+    /// Places the two kings and from 0 up to max_pieces other pieces (at most 30) on random
+    /// squares, with random material a player could have after promotions, and a random
+    /// player to move. Nobody can castle or take en passant.
+    Placed { max_pieces: u32 },
+}
+
+impl Default for RandomPositionOptions {
+    fn default() -> RandomPositionOptions {
+        return RandomPositionOptions::Played { max_plies: 100 };
+    }
+}
+
+/// Whether a player with the pawns, knights, bishops, rooks and queens of counts can have one
+/// more piece of the role (its index in that order): at most 8 pawns, and a pawn missing for
+/// every piece more than at the start.
+fn material_allows(mut counts: [u32; 5], role: usize) -> bool {
+    counts[role] += 1;
+    let promoted = counts[1].saturating_sub(2) + counts[2].saturating_sub(2) + counts[3].saturating_sub(2) + counts[4].saturating_sub(1);
+    return counts[0] + promoted <= 8;
+}
+
+impl Game {
+    /// Makes a random position that validate_position accepts, see RandomPositionOptions, e.g.
+    /// for property tests. The position only depends on the options and the numbers of the
+    /// generator, so a generator created with the same seed makes the same position.
+    pub fn random_position(rng: &mut XorShift, options: RandomPositionOptions) -> Game {
+        return match options {
+            RandomPositionOptions::Played { max_plies } => {
+                let mut game = Game::new();
+                for _ in 0..rng.next_index(max_plies as usize + 1) {
+                    let Some(mv) = game.random_legal_move(rng) else {break};
+                    game.play_move(&mv);
+                }
+                game
+            }
+            RandomPositionOptions::Placed { max_pieces } => loop {
+                let mut board = [['.'; 8]; 8];
+                let mut place = |letter: char, rng: &mut XorShift| {
+                    // pawns stay off the first and last rank
+                    let squares = if letter.eq_ignore_ascii_case(&'p') {8..56} else {0..64};
+                    loop {
+                        let index = squares.start + rng.next_index(squares.len());
+                        let square = &mut board[index / 8][index % 8];
+                        if *square == '.' {*square = letter; return;}
+                    }
+                };
+                place('K', rng);
+                place('k', rng);
+                let mut counts = [[0u32; 5]; 2];
+                for _ in 0..rng.next_index(max_pieces.min(30) as usize + 1) {
+                    let (color, role) = (rng.next_index(2), rng.next_index(5));
+                    if !material_allows(counts[color], role) {continue;}
+                    counts[color][role] += 1;
+                    let letter = ['p', 'n', 'b', 'r', 'q'][role];
+                    place(if color == 0 {letter.to_ascii_uppercase()} else {letter}, rng);
+                }
+                let turn = if rng.next_index(2) == 0 {Color::White} else {Color::Black};
+                // the kings may stand next to each other, or the player who just moved in check
+                if let Ok(game) = Game::from_char_board(board, turn, None, None) {
+                    if game.validate_position().is_ok() {break game;}
+                }
+            }
+        };
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::RandomPositionOptions;
+    use crate::{Game, XorShift};
+
+    //check that generated positions are valid and read back from their FEN to the same position
+    #[test]
+    fn check_random_position_fen_round_trip() {
+        let mut rng = XorShift::new(0x510E527FADE682D1);
+        for i in 0..1000 {
+            let options = if i % 2 == 0 {RandomPositionOptions::Played { max_plies: 120 }} else {RandomPositionOptions::Placed { max_pieces: 30 }};
+            let game1 = Game::random_position(&mut rng, options);
+            assert!(game1.validate_position().is_ok(), "{}", game1.get_fen());
+            let read = Game::from_fen(&game1.get_fen()).unwrap();
+            assert_eq!(read.get_fen(), game1.get_fen());
+            assert_eq!(read.get_game_state(), game1.get_game_state());
+            let moves = |game: &Game| -> Vec<String> {
+                let mut moves: Vec<String> = game.get_all_possible_moves().iter().map(|mv| mv.to_string()).collect();
+                moves.sort();
+                return moves;
+            };
+            assert_eq!(moves(&read), moves(&game1), "{}", game1.get_fen());
+        }
+    }
+
+    //check that the same seed makes the same positions, and that placed material is possible
+    #[test]
+    fn check_random_position_seeded() {
+        let fens = |seed: u64, options: RandomPositionOptions| -> Vec<String> {
+            let mut rng = XorShift::new(seed);
+            return (0..20).map(|_| Game::random_position(&mut rng, options).get_fen()).collect();
+        };
+        for options in [RandomPositionOptions::default(), RandomPositionOptions::Placed { max_pieces: 12 }] {
+            assert_eq!(fens(7, options), fens(7, options));
+            assert_ne!(fens(7, options), fens(8, options));
+        }
+        let mut rng = XorShift::new(11);
+        for _ in 0..200 {
+            let fen = Game::random_position(&mut rng, RandomPositionOptions::Placed { max_pieces: 30 }).get_fen();
+            let placement = fen.split(' ').next().unwrap();
+            for pawn in ['P', 'p'] {
+                assert!(placement.chars().filter(|&char| char == pawn).count() <= 8, "{}", fen);
+            }
+            assert!(placement.chars().filter(|char| char.is_ascii_alphabetic()).count() <= 32, "{}", fen);
+            assert!(fen.ends_with(" - - 0 1"), "{}", fen);
+        }
+        // no moves to play gives the start position
+        assert_eq!(Game::random_position(&mut rng, RandomPositionOptions::Played { max_plies: 0 }).get_fen(), Game::new().get_fen());
+    }
+}
@@ -0,0 +1,210 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{parse_square, ChessError, Color, Game};
+
+/// How Game::render_with_highlights marks a square, by the characters around what stands on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Highlight {
+    /// The piece the player picked up, e.g. (N).
+    Selected,
+    /// A square the selected piece can move to, e.g. [.] or [p] for a capture.
+    LegalTarget,
+    /// The square the last move came from, e.g. <.>.
+    LastMoveFrom,
+    /// The square the last move went to, e.g. {N}.
+    LastMoveTo,
+    /// The king in check, e.g. !K!.
+    Check,
+    /// The square of a pawn taken en passant, e.g. x.x.
+    Captured,
+}
+
+impl Highlight {
+    fn brackets(self) -> (char, char) {
+        return match self {
+            Highlight::Selected => ('(', ')'),
+            Highlight::LegalTarget => ('[', ']'),
+            Highlight::LastMoveFrom => ('<', '>'),
+            Highlight::LastMoveTo => ('{', '}'),
+            Highlight::Check => ('!', '!'),
+            Highlight::Captured => ('x', 'x')
+        };
+    }
+}
+
+impl Game {
+    /// Draws the board as text from the side of the player, with the ranks and files written
+    /// beside it: FEN letters for the pieces (capitals for White) and . for empty squares.
+    pub fn render(&self, perspective: Color) -> String {
+        return self.render_with_highlights(&[], perspective).unwrap();
+    }
+
+    /// Draws the board like render, with the highlighted squares bracketed, see Highlight. A
+    /// square highlighted more than once gets the last of its highlights. Returns an error if
+    /// one of the squares isn't a square.
+    pub fn render_with_highlights(&self, highlights: &[(&str, Highlight)], perspective: Color) -> Result<String, ChessError> {
+        let mut marks: [Option<Highlight>; 64] = [None; 64];
+        for &(square, highlight) in highlights {
+            let Some(index) = parse_square(square).filter(|_| square.len() == 2) else {return Err(ChessError::InvalidSquare(String::from(square)))};
+            marks[index] = Some(highlight);
+        }
+        // the rows and columns in the order they are drawn
+        let order: Vec<usize> = if perspective == Color::White {(0..8).collect()} else {(0..8).rev().collect()};
+        let mut text = String::new();
+        for &row in order.iter() {
+            let mut line = String::new();
+            line.push((b'8' - row as u8) as char);
+            line.push(' ');
+            for &column in order.iter() {
+                let index = row * 8 + column;
+                let (open, close) = marks[index].map_or((' ', ' '), Highlight::brackets);
+                line.push(open);
+                line.push(self.position.piece_at(index).map_or('.', |piece| piece.letter()));
+                line.push(close);
+            }
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text.push_str("  ");
+        for &column in order.iter() {
+            text.push(' ');
+            text.push((b'a' + column as u8) as char);
+            text.push(' ');
+        }
+        text.truncate(text.trim_end().len());
+        text.push('\n');
+        return Ok(text);
+    }
+
+    /// Draws the board like render from the side of White, with the last move marked: the
+    /// square it came from as LastMoveFrom, the square it went to as LastMoveTo, the squares of
+    /// the rook when castling as well and the square of a pawn taken en passant as Captured.
+    /// A caption line follows with the move in SAN, and for a capture what took what, e.g.
+    /// "Nxe5: knight takes pawn". Without a move since the game was created or loaded it is
+    /// the board of render.
+    pub fn render_last_move(&self) -> String {
+        let Some(entry) = self.history.last() else {return self.render(Color::White)};
+        let result = &entry.result;
+        let mut marks: Vec<(String, Highlight)> = Vec::new();
+        // the king's squares last, as a Chess960 king can move to the square of its rook
+        if let Some(rook_move) = result.rook_move {
+            marks.push((rook_move.from.to_string(), Highlight::LastMoveFrom));
+            marks.push((rook_move.to.to_string(), Highlight::LastMoveTo));
+        }
+        if let Some(square) = result.capture_square.filter(|&square| square != result.mv.to) {marks.push((square.to_string(), Highlight::Captured));}
+        if !result.mv.is_drop() {marks.push((result.mv.from.to_string(), Highlight::LastMoveFrom));}
+        marks.push((result.mv.to.to_string(), Highlight::LastMoveTo));
+        let highlights: Vec<(&str, Highlight)> = marks.iter().map(|(square, highlight)| (square.as_str(), *highlight)).collect();
+        let mut text = self.render_with_highlights(&highlights, Color::White).unwrap();
+        text.push_str(&entry.position.san(result.mv));
+        if let Some(captured) = result.captured {
+            text.push_str(&format!(": {} takes {}", result.piece.role.name(), captured.role.name()));
+            if result.capture_square != Some(result.mv.to) {text.push_str(" en passant");}
+        }
+        text.push('\n');
+        return text;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::Highlight;
+    use crate::tests::coordinate_move;
+    use crate::{ChessError, Color, Game};
+
+    //check the start position with the knight on g1 selected and its moves highlighted, from both sides
+    #[test]
+    fn check_render_highlights() {
+        let game1 = Game::new();
+        let highlights = [("g1", Highlight::Selected), ("f3", Highlight::LegalTarget), ("h3", Highlight::LegalTarget)];
+        let expected = "\
+8  r  n  b  q  k  b  n  r
+7  p  p  p  p  p  p  p  p
+6  .  .  .  .  .  .  .  .
+5  .  .  .  .  .  .  .  .
+4  .  .  .  .  .  .  .  .
+3  .  .  .  .  . [.] . [.]
+2  P  P  P  P  P  P  P  P
+1  R  N  B  Q  K  B (N) R
+   a  b  c  d  e  f  g  h
+";
+        assert_eq!(game1.render_with_highlights(&highlights, Color::White).unwrap(), expected);
+        let expected = "\
+1  R (N) B  K  Q  B  N  R
+2  P  P  P  P  P  P  P  P
+3 [.] . [.] .  .  .  .  .
+4  .  .  .  .  .  .  .  .
+5  .  .  .  .  .  .  .  .
+6  .  .  .  .  .  .  .  .
+7  p  p  p  p  p  p  p  p
+8  r  n  b  k  q  b  n  r
+   h  g  f  e  d  c  b  a
+";
+        assert_eq!(game1.render_with_highlights(&highlights, Color::Black).unwrap(), expected);
+        assert_eq!(game1.render(Color::White), game1.render_with_highlights(&[], Color::White).unwrap());
+    }
+
+    //check the last move and check markers, and that bad squares are refused
+    #[test]
+    fn check_render_last_move_and_check() {
+        let mut game1 = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        game1.make_move("a1", "a8").unwrap();
+        let highlights = [("a1", Highlight::LastMoveFrom), ("a8", Highlight::LastMoveTo), ("e8", Highlight::Check)];
+        let text = game1.render_with_highlights(&highlights, Color::White).unwrap();
+        assert_eq!(text.lines().next().unwrap(), "8 {R} .  .  . !k! .  .  .");
+        assert_eq!(text.lines().nth(7).unwrap(), "1 <.> .  .  .  K  .  .  .");
+        assert_eq!(game1.render_with_highlights(&[("i9", Highlight::Check)], Color::White), Err(ChessError::InvalidSquare("i9".to_string())));
+        assert!(game1.render_with_highlights(&[("e88", Highlight::Check)], Color::White).is_err());
+    }
+
+    //check the last move of a capture, a castle and an en passant capture
+    #[test]
+    fn check_render_last_move() {
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "e7e5", "g1f3", "d7d6", "f3e5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let expected = "\
+8  r  n  b  q  k  b  n  r
+7  p  p  p  .  .  p  p  p
+6  .  .  .  p  .  .  .  .
+5  .  .  .  . {N} .  .  .
+4  .  .  .  .  P  .  .  .
+3  .  .  .  .  . <.> .  .
+2  P  P  P  P  .  P  P  P
+1  R  N  B  Q  K  B  .  R
+   a  b  c  d  e  f  g  h
+Nxe5: knight takes pawn
+";
+        assert_eq!(game1.render_last_move(), expected);
+        // the king and the rook are marked when castling
+        let mut game1 = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        game1.play_move(&coordinate_move("e8c8")).unwrap();
+        let text = game1.render_last_move();
+        assert_eq!(text.lines().next().unwrap(), "8 <.> . {k}{r}<.> .  .  r");
+        assert_eq!(text.lines().last().unwrap(), "O-O-O");
+        // the pawn taken en passant is marked on its own square
+        let mut game1 = Game::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
+        game1.play_move(&coordinate_move("d5e6")).unwrap();
+        let expected = "\
+8  .  .  .  .  k  .  .  .
+7  .  .  .  .  .  .  .  .
+6  .  .  .  . {P} .  .  .
+5  .  .  . <.>x.x .  .  .
+4  .  .  .  .  .  .  .  .
+3  .  .  .  .  .  .  .  .
+2  .  .  .  .  .  .  .  .
+1  .  .  .  .  K  .  .  .
+   a  b  c  d  e  f  g  h
+dxe6: pawn takes pawn en passant
+";
+        assert_eq!(game1.render_last_move(), expected);
+        assert_eq!(Game::new().render_last_move(), Game::new().render(Color::White));
+    }
+}
@@ -0,0 +1,35 @@
+/// A small random number generator (xorshift64) for the parts of the library that make random
+/// choices. The same seed always gives the same numbers on every platform, so random games can
+/// be replayed. It is not suitable for anything that has to be unpredictable.
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// Creates a generator that gives the numbers of the seed.
+    pub fn new(seed: u64) -> XorShift {
+        // xorshift never leaves 0, so that seed is replaced
+        return XorShift { state: if seed == 0 {0x2545F4914F6CDD1D} else {seed} };
+    }
+
+    /// The next number, from 1 up to u64::MAX.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return self.state;
+    }
+
+    /// A number from 0 up to but not including n, which must not be 0. Every number is equally
+    /// likely.
+    pub fn next_index(&mut self, n: usize) -> usize {
+        let n = n as u64;
+        // numbers from the incomplete last round of n are drawn again, so no number is favoured
+        let limit = u64::MAX - u64::MAX % n;
+        loop {
+            let number = self.next_u64();
+            if number < limit {return (number % n) as usize;}
+        }
+    }
+}
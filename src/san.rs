@@ -0,0 +1,334 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{square_name, Color, Game, Move, PieceRole, Position};
+
+/// The letters of the pieces in SAN, see SanStyle. Pawns have no letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PieceLetters {
+    pub king: char,
+    pub queen: char,
+    pub rook: char,
+    pub bishop: char,
+    pub knight: char,
+}
+
+impl PieceLetters {
+    /// K, Q, R, B and N.
+    pub const ENGLISH: PieceLetters = PieceLetters { king: 'K', queen: 'Q', rook: 'R', bishop: 'B', knight: 'N' };
+    /// K (König), D (Dame), T (Turm), L (Läufer) and S (Springer).
+    pub const GERMAN: PieceLetters = PieceLetters { king: 'K', queen: 'D', rook: 'T', bishop: 'L', knight: 'S' };
+    /// The figurines of the white pieces, for the pieces of both players as in most books.
+    pub const FIGURINE: PieceLetters = PieceLetters { king: '♔', queen: '♕', rook: '♖', bishop: '♗', knight: '♘' };
+
+    /// Letters for another language, e.g. PieceLetters::custom('R', 'D', 'T', 'F', 'C') for French.
+    pub fn custom(king: char, queen: char, rook: char, bishop: char, knight: char) -> PieceLetters {
+        return PieceLetters { king, queen, rook, bishop, knight };
+    }
+
+    fn letter(&self, role: PieceRole) -> Option<char> {
+        return match role {
+            PieceRole::Pawn => None,
+            PieceRole::King => Some(self.king),
+            PieceRole::Queen => Some(self.queen),
+            PieceRole::Rook => Some(self.rook),
+            PieceRole::Bishop => Some(self.bishop),
+            PieceRole::Knight => Some(self.knight)
+        };
+    }
+}
+
+/// How Game::to_san_styled writes the pieces of a move, e.g. "Nf3", "Sf3" or "♘f3". Castling,
+/// captures, checks and checkmates are written the same in every style, and drops in
+/// coordinate notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SanStyle {
+    pub letters: PieceLetters,
+    /// Whether the pieces are written as figurines in the color of the player who moves,
+    /// ♘ for White and ♞ for Black, instead of the letters.
+    pub figurine: bool,
+}
+
+/// How Game::move_to_lan writes castling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LanCastling {
+    /// "O-O" and "O-O-O", as in SAN.
+    Symbols,
+    /// The squares of the king, e.g. "e1-g1".
+    KingMove,
+}
+
+impl Default for SanStyle {
+    fn default() -> SanStyle {
+        return SanStyle { letters: PieceLetters::ENGLISH, figurine: false };
+    }
+}
+
+impl SanStyle {
+    /// The letter of a role moved by the player. Pawns have no letter.
+    fn letter(&self, role: PieceRole, color: Color) -> Option<char> {
+        if !self.figurine {return self.letters.letter(role);}
+        let white = PieceLetters::FIGURINE.letter(role)?;
+        // the black figurines come six code points after the white ones
+        return if color == Color::White {Some(white)} else {char::from_u32(white as u32 + 6)};
+    }
+}
+
+impl Position {
+    /// Writes a legal move in standard algebraic notation (SAN), e.g. "Nf3", "exd5", "O-O",
+    /// "e8=Q+" or the drop "N@f3". The file and/or rank of the piece is added when another
+    /// piece of the same role can move to the same square.
+    pub(crate) fn san(&self, mv: Move) -> String {
+        return self.san_styled(mv, SanStyle::default());
+    }
+
+    /// Writes a legal move in SAN like san, with the pieces written in the style.
+    pub(crate) fn san_styled(&self, mv: Move, style: SanStyle) -> String {
+        let (from, to) = (mv.from.index(), mv.to.index());
+        let mut san = String::new();
+        if mv.is_drop() {
+            // a drop is written like a move in coordinate notation, e.g. N@f3
+            san.push_str(&mv.to_string());
+        } else if self.castling_rook(mv).is_some() {
+            san.push_str(if to > from {"O-O"} else {"O-O-O"});
+        } else {
+            let piece = self.piece_at(from).unwrap();
+            let capture = self.is_capture(mv);
+            if piece.role == PieceRole::Pawn {
+                // pawn captures are written with the file the pawn came from
+                if capture {san.push_str(&square_name(from)[..1]);}
+            } else {
+                san.extend(style.letter(piece.role, piece.color));
+                let others: Vec<usize> = self.legal_moves().iter()
+                    .filter(|other| other.to.index() == to && other.from.index() != from)
+                    .filter(|other| self.piece_at(other.from.index()).is_some_and(|other_piece| other_piece.role == piece.role))
+                    .map(|other| other.from.index())
+                    .collect();
+                if !others.is_empty() {
+                    let name = square_name(from);
+                    if others.iter().all(|&other| other % 8 != from % 8) {san.push_str(&name[..1]);}
+                    else if others.iter().all(|&other| other / 8 != from / 8) {san.push_str(&name[1..]);}
+                    else {san.push_str(&name);}
+                }
+            }
+            if capture {san.push('x');}
+            san.push_str(&square_name(to));
+            if let Some(role) = mv.promotion {
+                san.push('=');
+                san.extend(style.letter(role, self.turn));
+            }
+        }
+        san.extend(self.check_mark(mv));
+        return san;
+    }
+
+    /// Writes a legal move in long algebraic notation (LAN) with the pieces written in the
+    /// style: the square the piece comes from, a '-' or an 'x' for a capture and the square it
+    /// goes to, e.g. "Ng1-f3", "e7xd8=Q+" or "O-O", or "e1-g1" when castling is written with
+    /// the squares of the king.
+    pub(crate) fn lan(&self, mv: Move, style: SanStyle, castling: LanCastling) -> String {
+        let (from, to) = (mv.from.index(), self.move_target(mv));
+        let mut lan = String::new();
+        if mv.is_drop() {
+            lan.push_str(&mv.to_string());
+        } else if self.castling_rook(mv).is_some() {
+            // the king move is written with the squares only, like in coordinate notation
+            match castling {
+                LanCastling::Symbols => lan.push_str(if mv.to.index() > from {"O-O"} else {"O-O-O"}),
+                LanCastling::KingMove => lan.push_str(&format!("{}-{}", square_name(from), square_name(to)))
+            }
+        } else {
+            let piece = self.piece_at(from).unwrap();
+            lan.extend(style.letter(piece.role, piece.color));
+            lan.push_str(&square_name(from));
+            lan.push(if self.is_capture(mv) {'x'} else {'-'});
+            lan.push_str(&square_name(to));
+            if let Some(role) = mv.promotion {
+                lan.push('=');
+                lan.extend(style.letter(role, self.turn));
+            }
+        }
+        lan.extend(self.check_mark(mv));
+        return lan;
+    }
+
+    /// The '+' or '#' written after a legal move that checks or checkmates.
+    fn check_mark(&self, mv: Move) -> Option<char> {
+        let mut position_copy = *self;
+        position_copy.apply_move(mv);
+        if !position_copy.in_check(position_copy.turn) {return None;}
+        return Some(if position_copy.legal_moves().is_empty() {'#'} else {'+'});
+    }
+
+    /// Writes a line of legal moves in SAN, playing each move before writing the next.
+    // only used by the parts that need std
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) fn san_line(&self, moves: &[Move]) -> Vec<String> {
+        let mut position = *self;
+        let mut line: Vec<String> = Vec::new();
+        for &mv in moves {
+            line.push(position.san(mv));
+            position.apply_move(mv);
+        }
+        return line;
+    }
+}
+
+impl Game {
+    /// Writes a legal move in standard algebraic notation (SAN), e.g. "Nf3", "exd5", "O-O" or
+    /// "e8=Q+". Returns None if the move isn't legal.
+    pub fn to_san(&self, mv: &Move) -> Option<String> {
+        if !self.is_legal_move(mv) {return None;}
+        return Some(self.position.san(*mv));
+    }
+
+    /// Writes a legal move in SAN like to_san, with the pieces written in the style, e.g.
+    /// "Sf3" with German letters or "♘f3" with figurines. Returns None if the move isn't legal.
+    pub fn to_san_styled(&self, mv: &Move, style: SanStyle) -> Option<String> {
+        if !self.is_legal_move(mv) {return None;}
+        return Some(self.position.san_styled(*mv, style));
+    }
+
+    /// Writes a legal move in long algebraic notation (LAN), the notation of some
+    /// correspondence servers and older books, with the square the piece comes from, e.g.
+    /// "Ng1-f3", "e7xd8=Q+" or "O-O". Returns None if the move isn't legal.
+    pub fn move_to_lan(&self, mv: &Move) -> Option<String> {
+        return self.move_to_lan_styled(mv, SanStyle::default(), LanCastling::Symbols);
+    }
+
+    /// Writes a legal move in LAN like move_to_lan, with the pieces written in the style and
+    /// castling written as LanCastling says, e.g. "e1-g1". Returns None if the move isn't legal.
+    pub fn move_to_lan_styled(&self, mv: &Move, style: SanStyle, castling: LanCastling) -> Option<String> {
+        if !self.is_legal_move(mv) {return None;}
+        return Some(self.position.lan(*mv, style, castling));
+    }
+
+    /// Finds the legal move written in SAN, e.g. "Nf3" or "exd5", or in LAN, e.g. "Ng1-f3" or
+    /// "e1-g1" for castling. The check and checkmate marks and annotations like "!?" may be
+    /// left out or added, and castling may be written with zeros. Returns None if no legal
+    /// move is written so.
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        return self.parse_san_styled(san, SanStyle::default());
+    }
+
+    /// Finds the legal move written in SAN or LAN like parse_san, with the pieces written in
+    /// the style, e.g. "Sf3" with German letters.
+    pub fn parse_san_styled(&self, san: &str, style: SanStyle) -> Option<Move> {
+        let bare = |san: &str| -> String {san.trim_end_matches(['+', '#', '!', '?']).replace('0', "O")};
+        let wanted = bare(san);
+        return self.legal_moves().iter().copied().find(|&mv| {
+            return bare(&self.position.san_styled(mv, style)) == wanted
+                || [LanCastling::Symbols, LanCastling::KingMove].iter().any(|&castling| bare(&self.position.lan(mv, style, castling)) == wanted);
+        });
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::coordinate_move;
+    use crate::{Game, LanCastling, PieceLetters, SanStyle};
+
+    fn san(fen: &str, mv: &str) -> String {
+        return Game::from_fen(fen).unwrap().to_san(&coordinate_move(mv)).unwrap();
+    }
+
+    //check that pieces, pawns, captures and castling are written in SAN
+    #[test]
+    fn check_san() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(start, "e2e4"), "e4");
+        assert_eq!(san(start, "g1f3"), "Nf3");
+        assert_eq!(san("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5"), "exd5");
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"), "O-O-O");
+        assert_eq!(san("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2", "d5e6"), "dxe6");
+    }
+
+    //check that moves are disambiguated by file, rank or both
+    #[test]
+    fn check_san_disambiguation() {
+        assert_eq!(san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"), "Rad1");
+        assert_eq!(san("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", "a1a3"), "R1a3");
+        assert_eq!(san("k7/8/8/8/8/2Q1Q3/8/4Q2K w - - 0 1", "e3d2"), "Qe3d2");
+        // a piece that is pinned can't move there, so it doesn't count
+        assert_eq!(san("4k3/4r3/8/8/8/2N1N3/8/4K3 w - - 0 1", "c3d5"), "Nd5");
+    }
+
+    //check that promotions, checks and checkmates are marked
+    #[test]
+    fn check_san_promotion_check_mate() {
+        assert_eq!(san("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q"), "e8=Q");
+        assert_eq!(san("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8n"), "e8=N");
+        assert_eq!(san("3k4/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+    }
+
+    //check the same moves in English, German and figurine SAN, and German SAN read back
+    #[test]
+    fn check_san_styles() {
+        let english = SanStyle::default();
+        let german = SanStyle { letters: PieceLetters::GERMAN, figurine: false };
+        let figurine = SanStyle { letters: PieceLetters::ENGLISH, figurine: true };
+        let game1 = Game::new();
+        let knight = coordinate_move("g1f3");
+        assert_eq!(game1.to_san_styled(&knight, english).unwrap(), "Nf3");
+        assert_eq!(game1.to_san_styled(&knight, german).unwrap(), "Sf3");
+        assert_eq!(game1.to_san_styled(&knight, figurine).unwrap(), "♘f3");
+        assert_eq!(game1.to_san_styled(&coordinate_move("e2e4"), german).unwrap(), "e4");
+        // promotion with check, castling and the figurines of Black
+        let game1 = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = coordinate_move("b7b8q");
+        assert_eq!(game1.to_san_styled(&promotion, english).unwrap(), "b8=Q+");
+        assert_eq!(game1.to_san_styled(&promotion, german).unwrap(), "b8=D+");
+        assert_eq!(game1.to_san_styled(&promotion, figurine).unwrap(), "b8=♕+");
+        let game1 = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        for style in [english, german, figurine] {
+            assert_eq!(game1.to_san_styled(&coordinate_move("e8g8"), style).unwrap(), "O-O");
+        }
+        assert_eq!(game1.to_san_styled(&coordinate_move("a8a1"), figurine).unwrap(), "♜xa1+");
+        assert_eq!(game1.to_san_styled(&coordinate_move("a8a1"), SanStyle { letters: PieceLetters::FIGURINE, figurine: false }).unwrap(), "♖xa1+");
+        let french = SanStyle { letters: PieceLetters::custom('R', 'D', 'T', 'F', 'C'), figurine: false };
+        assert_eq!(game1.to_san_styled(&coordinate_move("e8d8"), french).unwrap(), "Rd8");
+        // German SAN is read back to the same moves
+        let game1 = Game::from_fen("4k3/1P6/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        assert_eq!(game1.parse_san_styled("b8=D+", german), Some(promotion));
+        assert_eq!(game1.parse_san_styled("Sf3", german), Some(knight));
+        assert_eq!(game1.parse_san_styled("Nf3", german), None);
+        assert_eq!(game1.parse_san("Nf3"), Some(knight));
+        assert_eq!(game1.parse_san_styled("♘f3", figurine), Some(knight));
+    }
+
+    //check moves written in LAN, and read back
+    #[test]
+    fn check_lan() {
+        let lan = |fen: &str, mv: &str| -> String {Game::from_fen(fen).unwrap().move_to_lan(&coordinate_move(mv)).unwrap()};
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(lan(start, "g1f3"), "Ng1-f3");
+        assert_eq!(lan(start, "e2e4"), "e2-e4");
+        assert_eq!(lan("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5"), "e4xd5");
+        assert_eq!(lan("3qk3/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8q"), "e7xd8=Q+");
+        assert_eq!(lan("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra1-a8#");
+        assert_eq!(Game::new().move_to_lan(&coordinate_move("e2e5")), None);
+        // both castles, as symbols and as king moves
+        let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(lan(castles, "e1g1"), "O-O");
+        assert_eq!(lan(castles, "e1c1"), "O-O-O");
+        let game1 = Game::from_fen(castles).unwrap();
+        assert_eq!(game1.move_to_lan_styled(&coordinate_move("e1g1"), SanStyle::default(), LanCastling::KingMove).unwrap(), "e1-g1");
+        assert_eq!(game1.move_to_lan_styled(&coordinate_move("e1c1"), SanStyle::default(), LanCastling::KingMove).unwrap(), "e1-c1");
+        // every form is read back
+        for (notation, mv) in [("O-O", "e1g1"), ("O-O-O", "e1c1"), ("e1-g1", "e1g1"), ("e1-c1", "e1c1"), ("Ra1xa8+", "a1a8"), ("Ke1-d1", "e1d1")] {
+            assert_eq!(game1.parse_san(notation), Some(coordinate_move(mv)), "{}", notation);
+        }
+        let game1 = Game::from_fen("3qk3/4P3/8/8/8/8/8/4K1N1 w - - 0 1").unwrap();
+        for (notation, mv) in [("Ng1-f3", "g1f3"), ("e7xd8=Q+", "e7d8q"), ("e7xd8=Q", "e7d8q"), ("Ng1-h3", "g1h3")] {
+            assert_eq!(game1.parse_san(notation), Some(coordinate_move(mv)), "{}", notation);
+        }
+        assert_eq!(game1.parse_san("Ng1xf3"), None);
+    }
+}
@@ -0,0 +1,550 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use crate::clock::{Clock, ClockedGame, TimeControl};
+use crate::event_log::{EventLogOptions, LogEntry, LoggedEvent};
+use crate::pgn::{MoveComment, PgnEval};
+use crate::{ChessError, Color, EndReason, Game, GameOutcome, IllegalMoveReason, Move, PieceRole, Square, Variant};
+
+/// The first bytes of a saved game.
+const MAGIC: &[u8; 4] = b"ECSG";
+/// The version of the layout, written after the magic bytes.
+const VERSION: u8 = 1;
+
+// the codes of the enums in a saved game are their indexes in these lists
+const VARIANTS: [Variant; 5] = [Variant::Standard, Variant::Chess960, Variant::Antichess, Variant::Horde, Variant::Crazyhouse];
+const ROLES: [PieceRole; 6] = [PieceRole::Pawn, PieceRole::Rook, PieceRole::Knight, PieceRole::Bishop, PieceRole::Queen, PieceRole::King];
+const REASONS: [IllegalMoveReason; 11] = [
+    IllegalMoveReason::GameOver, IllegalMoveReason::NotYourPiece, IllegalMoveReason::WrongTurn, IllegalMoveReason::PieceCannotMoveThatWay,
+    IllegalMoveReason::PathBlocked, IllegalMoveReason::DestinationOccupiedByOwnPiece, IllegalMoveReason::WouldLeaveKingInCheck,
+    IllegalMoveReason::KingWouldPassThroughAttackedSquare, IllegalMoveReason::CastlingRightsLost, IllegalMoveReason::MustRespondToCheck,
+    IllegalMoveReason::PromotionRequired,
+];
+
+/// Errors returned by Game::restore and ClockedGame::restore.
+#[derive(Debug)]
+pub enum RestoreError {
+    /// The data ends before the game does, e.g. a file that wasn't written to the end.
+    Truncated,
+    /// The data doesn't start with the magic bytes of a saved game.
+    NotASavedGame,
+    /// The game was saved in a later version of the layout.
+    UnsupportedVersion(u8),
+    /// The starting position isn't valid.
+    InvalidFen(ChessError),
+    /// A move of the history isn't legal where it is made, with its number counted from 0.
+    IllegalMove { ply: usize, mv: Move, reason: IllegalMoveReason },
+    /// A value can't be what the data says, e.g. a draw offer in a game that is over.
+    Corrupt(&'static str),
+    /// Reading failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RestoreError::Truncated => write!(f, "the saved game is truncated"),
+            RestoreError::NotASavedGame => write!(f, "not a saved game"),
+            RestoreError::UnsupportedVersion(version) => write!(f, "unsupported version {} of the saved game", version),
+            RestoreError::InvalidFen(error) => write!(f, "invalid starting position: {}", error),
+            RestoreError::IllegalMove { ply, mv, reason } => write!(f, "move {} ({}) is illegal: {}", ply, mv, reason),
+            RestoreError::Corrupt(what) => write!(f, "the saved game is corrupt: {}", what),
+            RestoreError::Io(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+impl std::error::Error for RestoreError {}
+
+impl From<io::Error> for RestoreError {
+    fn from(error: io::Error) -> RestoreError {
+        return if error.kind() == io::ErrorKind::UnexpectedEof {RestoreError::Truncated} else {RestoreError::Io(error)};
+    }
+}
+
+impl Game {
+    /// Saves the whole game in a binary layout that restore reads back: the variant and the
+    /// position the moves start from, every move made since then with its PGN comment, the
+    /// result, who ran out of time, a standing draw offer, takeback request and premoves, and
+    /// the event log with the time of every entry. The subscribers aren't saved. The layout
+    /// starts with the magic bytes "ECSG" and a version, and numbers are little-endian.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        write_game(&mut writer, self)?;
+        // no clock
+        writer.write_all(&[0])?;
+        return writer.flush();
+    }
+
+    /// Reads a game written by save. The moves are played again from the starting position,
+    /// so data that was changed or cut off gives an error instead of a game that breaks the
+    /// rules. A restored event log goes on from the time of its last entry.
+    pub fn restore(mut reader: impl Read) -> Result<Game, RestoreError> {
+        let game = read_game(&mut reader)?;
+        // the clock of a ClockedGame is left out
+        read_clock(&mut reader)?;
+        return Ok(game);
+    }
+}
+
+impl ClockedGame {
+    /// Game::save with the time control and the time each player has left.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        write_game(&mut writer, self.game())?;
+        let clock = self.clock();
+        writer.write_all(&[1])?;
+        let (tag, base, extra) = match clock.time_control() {
+            TimeControl::SuddenDeath(base) => (0, base, Duration::ZERO),
+            TimeControl::Increment { base, increment } => (1, base, increment),
+            TimeControl::Delay { base, delay } => (2, base, delay)
+        };
+        writer.write_all(&[tag])?;
+        for duration in [base, extra, clock.remaining(Color::White), clock.remaining(Color::Black)] {
+            write_duration(&mut writer, duration)?;
+        }
+        return writer.flush();
+    }
+
+    /// Reads a game written by ClockedGame::save, see Game::restore. The clock of the player
+    /// to move starts running with the system's clock. A game saved by Game::save gets the
+    /// error Corrupt, since it has no clock.
+    pub fn restore(mut reader: impl Read) -> Result<ClockedGame, RestoreError> {
+        let game = read_game(&mut reader)?;
+        let Some(clock) = read_clock(&mut reader)? else {return Err(RestoreError::Corrupt("no clock"))};
+        return Ok(ClockedGame::new(game, clock));
+    }
+}
+
+fn write_game(writer: &mut impl Write, game: &Game) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, code(&VARIANTS, game.variant())])?;
+    // go back to the start of the game for its position
+    let mut start = game.clone();
+    while start.undo().is_some() {}
+    write_str(writer, &start.get_fen())?;
+    writer.write_all(&(game.history.len() as u32).to_le_bytes())?;
+    for entry in &game.history {
+        write_move(writer, Some(entry.result.mv))?;
+        write_comment(writer, entry.comment.as_ref())?;
+    }
+    let ended = match game.ended.map(|(outcome, _)| outcome) {
+        None => 0,
+        Some(GameOutcome::Win(Color::White)) => 1,
+        Some(GameOutcome::Win(Color::Black)) => 2,
+        Some(GameOutcome::Draw) => 3
+    };
+    writer.write_all(&[ended, color_code(game.flagged), color_code(game.draw_offer), color_code(game.takeback_request)])?;
+    write_move(writer, game.premoves[0])?;
+    write_move(writer, game.premoves[1])?;
+    let Some(options) = game.event_log_options() else {return writer.write_all(&[0])};
+    writer.write_all(&[1])?;
+    write_len(writer, options.max_len)?;
+    writer.write_all(&(game.event_log().len() as u32).to_le_bytes())?;
+    for event in game.event_log() {
+        write_duration(writer, event.time)?;
+        writer.write_all(&[color_code(Some(event.actor))])?;
+        write_entry(writer, &event.entry)?;
+    }
+    return Ok(());
+}
+
+fn read_game(reader: &mut impl Read) -> Result<Game, RestoreError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {return Err(RestoreError::NotASavedGame);}
+    let version = read_u8(reader)?;
+    if version != VERSION {return Err(RestoreError::UnsupportedVersion(version));}
+    let variant = decode(&VARIANTS, read_u8(reader)?, "unknown variant")?;
+    let fen = read_str(reader)?;
+    let mut game = Game::from_fen_variant(&fen, variant).map_err(RestoreError::InvalidFen)?;
+    // play the moves again, so only a game that follows the rules comes out
+    let plies = read_u32(reader)? as usize;
+    for ply in 0..plies {
+        let Some(mv) = read_move(reader)? else {return Err(RestoreError::Corrupt("missing move"))};
+        if let Some(reason) = game.illegal_move_reason(&mv) {return Err(RestoreError::IllegalMove { ply, mv, reason });}
+        game.play(mv);
+        game.history.last_mut().unwrap().comment = read_comment(reader)?;
+    }
+    let ended = match read_u8(reader)? {
+        0 => None,
+        1 => Some(GameOutcome::Win(Color::White)),
+        2 => Some(GameOutcome::Win(Color::Black)),
+        3 => Some(GameOutcome::Draw),
+        _ => return Err(RestoreError::Corrupt("unknown result"))
+    };
+    let flagged = read_color(reader)?;
+    let draw_offer = read_color(reader)?;
+    let takeback_request = read_color(reader)?;
+    let premoves = [read_move(reader)?, read_move(reader)?];
+    if ended.is_some() && game.outcome().is_some() {return Err(RestoreError::Corrupt("a game that ended twice"));}
+    if flagged.is_some() && ended.is_none() {return Err(RestoreError::Corrupt("a flag in a game that isn't over"));}
+    if let Some(outcome) = ended {
+        // the reason isn't saved: a flag is a timeout, a win is a resignation, and a draw is the
+        // one a GameManager ends the game with at the fifty-move rule or a repetition, or agreed
+        let reason = match outcome {
+            _ if flagged.is_some() => EndReason::Timeout,
+            GameOutcome::Win(_) => EndReason::Resignation,
+            GameOutcome::Draw if game.get_halfmove() >= 100 => EndReason::FiftyMoves,
+            GameOutcome::Draw if game.is_threefold_repetition() => EndReason::Repetition,
+            GameOutcome::Draw => EndReason::Agreement
+        };
+        game.end(outcome, reason);
+    }
+    game.flagged = flagged;
+    let over = game.outcome().is_some();
+    if draw_offer.is_some() && over {return Err(RestoreError::Corrupt("a draw offer in a game that is over"));}
+    game.draw_offer = draw_offer;
+    // a takeback request and premoves are checked by making them again
+    if let Some(color) = takeback_request {
+        game.request_takeback(color).map_err(|_| RestoreError::Corrupt("a takeback request that can't be made"))?;
+    }
+    for (color, premove) in [Color::White, Color::Black].into_iter().zip(premoves) {
+        let Some(mv) = premove else {continue};
+        if mv.is_drop() {return Err(RestoreError::Corrupt("a premove that drops a piece"));}
+        game.set_premove(color, &mv.from.to_string(), &mv.to.to_string(), mv.promotion)
+            .map_err(|_| RestoreError::Corrupt("a premove that can't be made"))?;
+    }
+    if read_u8(reader)? == 1 {
+        let max_len = read_len(reader)?;
+        let count = read_u32(reader)? as usize;
+        let mut events: Vec<LoggedEvent> = Vec::new();
+        for _ in 0..count {
+            let time = read_duration(reader)?;
+            let Some(actor) = read_color(reader)? else {return Err(RestoreError::Corrupt("an event log entry without a player"))};
+            if events.last().is_some_and(|last| last.time > time) {return Err(RestoreError::Corrupt("an event log that goes back in time"));}
+            events.push(LoggedEvent { time, actor, entry: read_entry(reader)? });
+        }
+        game.restore_event_log(EventLogOptions { max_len }, events);
+    } else {
+        // the replayed moves aren't what was recorded, and nothing was
+        game.event_log = None;
+    }
+    return Ok(game);
+}
+
+/// Reads the clock after the game, if there is one.
+fn read_clock(reader: &mut impl Read) -> Result<Option<Clock>, RestoreError> {
+    if read_u8(reader)? == 0 {return Ok(None);}
+    let tag = read_u8(reader)?;
+    let (base, extra) = (read_duration(reader)?, read_duration(reader)?);
+    let control = match tag {
+        0 => TimeControl::SuddenDeath(base),
+        1 => TimeControl::Increment { base, increment: extra },
+        2 => TimeControl::Delay { base, delay: extra },
+        _ => return Err(RestoreError::Corrupt("unknown time control"))
+    };
+    let remaining = [read_duration(reader)?, read_duration(reader)?];
+    return Ok(Some(Clock::with_remaining(control, remaining)));
+}
+
+fn code<T: PartialEq>(list: &[T], value: T) -> u8 {
+    return list.iter().position(|item| *item == value).unwrap() as u8;
+}
+
+fn decode<T: Copy>(list: &[T], code: u8, what: &'static str) -> Result<T, RestoreError> {
+    return list.get(code as usize).copied().ok_or(RestoreError::Corrupt(what));
+}
+
+fn color_code(color: Option<Color>) -> u8 {
+    return match color {
+        None => 0,
+        Some(Color::White) => 1,
+        Some(Color::Black) => 2
+    };
+}
+
+fn read_color(reader: &mut impl Read) -> Result<Option<Color>, RestoreError> {
+    return match read_u8(reader)? {
+        0 => Ok(None),
+        1 => Ok(Some(Color::White)),
+        2 => Ok(Some(Color::Black)),
+        _ => Err(RestoreError::Corrupt("unknown color"))
+    };
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, RestoreError> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    return Ok(bytes[0]);
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, RestoreError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u32::from_le_bytes(bytes));
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, RestoreError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u64::from_le_bytes(bytes));
+}
+
+/// A string is its length in bytes and then the bytes in UTF-8.
+fn write_str(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    writer.write_all(&(string.len() as u32).to_le_bytes())?;
+    return writer.write_all(string.as_bytes());
+}
+
+fn read_str(reader: &mut impl Read) -> Result<String, RestoreError> {
+    let len = read_u32(reader)? as usize;
+    // only what is there is read, so a corrupt length can't ask for a huge buffer
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {return Err(RestoreError::Truncated);}
+    return String::from_utf8(bytes).map_err(|_| RestoreError::Corrupt("a string that isn't UTF-8"));
+}
+
+/// A duration is whole seconds and then nanoseconds.
+fn write_duration(writer: &mut impl Write, duration: Duration) -> io::Result<()> {
+    writer.write_all(&duration.as_secs().to_le_bytes())?;
+    return writer.write_all(&duration.subsec_nanos().to_le_bytes());
+}
+
+fn read_duration(reader: &mut impl Read) -> Result<Duration, RestoreError> {
+    let seconds = read_u64(reader)?;
+    let nanos = read_u32(reader)?;
+    if nanos >= 1_000_000_000 {return Err(RestoreError::Corrupt("a duration with too many nanoseconds"));}
+    return Ok(Duration::new(seconds, nanos));
+}
+
+fn write_len(writer: &mut impl Write, len: Option<usize>) -> io::Result<()> {
+    return match len {
+        Some(len) => {writer.write_all(&[1])?; writer.write_all(&(len as u64).to_le_bytes())}
+        None => writer.write_all(&[0])
+    };
+}
+
+fn read_len(reader: &mut impl Read) -> Result<Option<usize>, RestoreError> {
+    if read_u8(reader)? == 0 {return Ok(None);}
+    return Ok(Some(read_u64(reader)? as usize));
+}
+
+/// A move is a flag, the two squares and the promotion (0 for none), or the dropped piece.
+fn write_move(writer: &mut impl Write, mv: Option<Move>) -> io::Result<()> {
+    let Some(mv) = mv else {return writer.write_all(&[0])};
+    let promotion = mv.promotion.map_or(0, |role| code(&ROLES, role) + 1);
+    return writer.write_all(&[1, mv.from.0, mv.to.0, promotion]);
+}
+
+fn read_move(reader: &mut impl Read) -> Result<Option<Move>, RestoreError> {
+    if read_u8(reader)? == 0 {return Ok(None);}
+    let (from, to, promotion) = (read_u8(reader)?, read_u8(reader)?, read_u8(reader)?);
+    if from >= 64 || to >= 64 {return Err(RestoreError::Corrupt("a square off the board"));}
+    let promotion = if promotion == 0 {None} else {Some(decode(&ROLES, promotion - 1, "unknown piece")?)};
+    return Ok(Some(Move { from: Square(from), to: Square(to), promotion }));
+}
+
+fn write_comment(writer: &mut impl Write, comment: Option<&MoveComment>) -> io::Result<()> {
+    let Some(comment) = comment else {return writer.write_all(&[0])};
+    writer.write_all(&[1])?;
+    match comment.clock {
+        Some(clock) => {writer.write_all(&[1])?; write_duration(writer, clock)?;}
+        None => writer.write_all(&[0])?
+    }
+    match comment.eval {
+        Some(PgnEval::Centipawns(score)) => {writer.write_all(&[1])?; writer.write_all(&score.to_le_bytes())?;}
+        Some(PgnEval::Mate(moves)) => {writer.write_all(&[2])?; writer.write_all(&moves.to_le_bytes())?;}
+        None => writer.write_all(&[0])?
+    }
+    writer.write_all(&(comment.commands.len() as u32).to_le_bytes())?;
+    for command in &comment.commands {
+        write_str(writer, command)?;
+    }
+    return write_str(writer, &comment.text);
+}
+
+fn read_comment(reader: &mut impl Read) -> Result<Option<MoveComment>, RestoreError> {
+    if read_u8(reader)? == 0 {return Ok(None);}
+    let clock = if read_u8(reader)? == 1 {Some(read_duration(reader)?)} else {None};
+    let eval = match read_u8(reader)? {
+        0 => None,
+        1 => Some(PgnEval::Centipawns(read_u32(reader)? as i32)),
+        2 => Some(PgnEval::Mate(read_u32(reader)? as i32)),
+        _ => return Err(RestoreError::Corrupt("unknown evaluation"))
+    };
+    let count = read_u32(reader)?;
+    let commands = (0..count).map(|_| read_str(reader)).collect::<Result<Vec<String>, RestoreError>>()?;
+    return Ok(Some(MoveComment { clock, eval, commands, text: read_str(reader)? }));
+}
+
+/// An entry of the event log is the index of its kind and then what it holds. Refused moves
+/// only get an IllegalMove or InvalidSquare from make_move and play_move, and other errors keep
+/// their message as an InvalidPosition.
+fn write_entry(writer: &mut impl Write, entry: &LogEntry) -> io::Result<()> {
+    match entry {
+        LogEntry::Move(mv) => {writer.write_all(&[0])?; write_str(writer, mv)}
+        LogEntry::Rejected { attempt, reason } => {
+            writer.write_all(&[1])?;
+            write_str(writer, attempt)?;
+            match reason {
+                ChessError::IllegalMove(reason) => writer.write_all(&[0, code(&REASONS, *reason)]),
+                ChessError::InvalidSquare(square) => {writer.write_all(&[1])?; write_str(writer, square)}
+                other => {writer.write_all(&[2])?; write_str(writer, &other.to_string())}
+            }
+        }
+        LogEntry::DrawOffered => writer.write_all(&[2]),
+        LogEntry::DrawAccepted => writer.write_all(&[3]),
+        LogEntry::Resigned => writer.write_all(&[4]),
+        LogEntry::Undo(mv) => {writer.write_all(&[5])?; write_str(writer, mv)}
+        LogEntry::Flagged => writer.write_all(&[6]),
+        LogEntry::PositionSet(fen) => {writer.write_all(&[7])?; write_str(writer, fen)}
+        LogEntry::TakebackRequested => writer.write_all(&[8]),
+        LogEntry::TakebackAccepted => writer.write_all(&[9]),
+        LogEntry::TakebackDeclined => writer.write_all(&[10])
+    }
+}
+
+fn read_entry(reader: &mut impl Read) -> Result<LogEntry, RestoreError> {
+    return Ok(match read_u8(reader)? {
+        0 => LogEntry::Move(read_str(reader)?),
+        1 => {
+            let attempt = read_str(reader)?;
+            let reason = match read_u8(reader)? {
+                0 => ChessError::IllegalMove(decode(&REASONS, read_u8(reader)?, "unknown reason")?),
+                1 => ChessError::InvalidSquare(read_str(reader)?),
+                2 => ChessError::InvalidPosition(read_str(reader)?),
+                _ => return Err(RestoreError::Corrupt("unknown error"))
+            };
+            LogEntry::Rejected { attempt, reason }
+        }
+        2 => LogEntry::DrawOffered,
+        3 => LogEntry::DrawAccepted,
+        4 => LogEntry::Resigned,
+        5 => LogEntry::Undo(read_str(reader)?),
+        6 => LogEntry::Flagged,
+        7 => LogEntry::PositionSet(read_str(reader)?),
+        8 => LogEntry::TakebackRequested,
+        9 => LogEntry::TakebackAccepted,
+        10 => LogEntry::TakebackDeclined,
+        _ => return Err(RestoreError::Corrupt("unknown event log entry"))
+    });
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RestoreError;
+    use crate::clock::{Clock, ClockedGame, MockTime, TimeControl};
+    use crate::tests::coordinate_move;
+    use crate::{Color, EndReason, EventLogOptions, Game, GameOutcome, IllegalMoveReason, Move, PieceRole, Square, Variant};
+
+    fn saved(game: &Game) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        game.save(&mut bytes).unwrap();
+        return bytes;
+    }
+
+    // the positions after every move, to compare a game with the restored one
+    fn fens(game: &Game) -> Vec<String> {
+        let mut game = game.clone();
+        let mut fens = vec![game.get_fen()];
+        while game.undo().is_some() {fens.push(game.get_fen());}
+        return fens;
+    }
+
+    //check a game with castling on both sides, en passant, promotions, a refused move and a draw offer
+    #[test]
+    fn check_save_restore() {
+        let mut game1 = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/6p1/R3K2R w KQkq d6 0 1").unwrap();
+        let time = MockTime::new();
+        game1.start_event_log_with(EventLogOptions::default(), time.clone());
+        for mv in ["e5d6", "e8g8", "e1c1", "g2h1n", "b7a8q", "f8a8", "d6d7", "g8f7", "d7d8r", "a8d8"] {
+            time.advance(Duration::from_secs(3));
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert!(game1.make_move("a1", "a9").is_none());
+        game1.offer_draw(Color::White);
+        let restored = Game::restore(saved(&game1).as_slice()).unwrap();
+        assert_eq!(fens(&restored), fens(&game1));
+        assert_eq!(restored.get_all_possible_moves(), game1.get_all_possible_moves());
+        assert_eq!(restored.event_log(), game1.event_log());
+        assert_eq!(restored.event_log().last().unwrap().time, Duration::from_secs(30));
+        // the draw offer still stands
+        let mut restored = restored;
+        assert_eq!(restored.accept_draw(Color::Black), Some(GameOutcome::Draw));
+        // and the result of a game that is over comes back
+        let restored = Game::restore(saved(&restored).as_slice()).unwrap();
+        assert_eq!(restored.outcome(), Some(GameOutcome::Draw));
+        assert_eq!(restored.end_reason(), Some(EndReason::Agreement));
+        // and a loss on time
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(Duration::from_secs(1)), time.clone());
+        clock.start_turn(Color::White);
+        time.advance(Duration::from_secs(2));
+        let mut flagged = Game::new();
+        flagged.check_flag(&clock).unwrap();
+        let restored = Game::restore(saved(&flagged).as_slice()).unwrap();
+        assert_eq!(restored.end_reason(), Some(EndReason::Timeout));
+        assert_eq!(restored.flagged(), Some(Color::White));
+        assert_eq!(saved(&restored), saved(&Game::restore(saved(&restored).as_slice()).unwrap()));
+        // the comments of a PGN game too
+        let game1 = Game::from_pgn("1. e4 { [%clk 0:02:58] [%eval 0.31] [%emt 0:00:03] Good. } e5 { [%eval #-4] } *").unwrap();
+        let restored = Game::restore(saved(&game1).as_slice()).unwrap();
+        assert_eq!(restored.move_comment(0), game1.move_comment(0));
+        assert_eq!(restored.move_comment(1), game1.move_comment(1));
+        assert!(restored.move_comment(0).is_some());
+    }
+
+    //check a Crazyhouse game with a drop, the premoves, a takeback request and a clock
+    #[test]
+    fn check_save_restore_state() {
+        let mut game1 = Game::new_variant(Variant::Crazyhouse);
+        for mv in ["e2e4", "d7d5", "e4d5", "d8d5"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        game1.play_move(&Move::new_drop(PieceRole::Pawn, Square::from_algebraic("e4").unwrap())).unwrap();
+        assert_eq!(game1.get_fen(), "rnb1kbnr/ppp1pppp/8/3q4/4P3/8/PPPP1PPP/RNBQKBNR[p] b KQkq - 1 3");
+        game1.request_takeback(Color::White).unwrap();
+        game1.set_premove(Color::White, "g1", "f3", None).unwrap();
+        let mut restored = Game::restore(saved(&game1).as_slice()).unwrap();
+        assert_eq!(restored.variant(), Variant::Crazyhouse);
+        assert_eq!(fens(&restored), fens(&game1));
+        assert_eq!(restored.premove(Color::White), Some(coordinate_move("g1f3")));
+        assert_eq!(restored.respond_takeback(Color::Black, true).unwrap().len(), 1);
+        // a clock keeps the time of both players
+        let time = MockTime::new();
+        let clock = Clock::with_time_source(TimeControl::Increment { base: Duration::from_secs(60), increment: Duration::from_secs(2) }, time.clone());
+        let mut clocked = ClockedGame::new(Game::new(), clock);
+        time.advance(Duration::from_secs(10));
+        clocked.make_move("e2", "e4").unwrap();
+        let mut bytes = Vec::new();
+        clocked.save(&mut bytes).unwrap();
+        let restored = ClockedGame::restore(bytes.as_slice()).unwrap();
+        assert_eq!(restored.clock().time_control(), clocked.clock().time_control());
+        assert_eq!(restored.clock().remaining(Color::White), Duration::from_secs(52));
+        assert_eq!(restored.clock().running(), Some(Color::Black));
+        assert_eq!(restored.game().get_fen(), clocked.game().get_fen());
+        // a game without a clock can be read from a clocked one, but not the other way
+        assert_eq!(Game::restore(bytes.as_slice()).unwrap().get_fen(), clocked.game().get_fen());
+        assert!(matches!(ClockedGame::restore(saved(&game1).as_slice()), Err(RestoreError::Corrupt(_))));
+    }
+
+    //check that a truncated or changed file gives an error
+    #[test]
+    fn check_restore_errors() {
+        let mut game1 = Game::new();
+        for mv in ["e2e4", "e7e5", "g1f3"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let bytes = saved(&game1);
+        for len in 0..bytes.len() {
+            assert!(matches!(Game::restore(&bytes[..len]), Err(RestoreError::Truncated)), "{}", len);
+        }
+        assert!(matches!(Game::restore(&b"PGN1 and more"[..]), Err(RestoreError::NotASavedGame)));
+        let mut later = bytes.clone();
+        later[4] = 2;
+        assert!(matches!(Game::restore(later.as_slice()), Err(RestoreError::UnsupportedVersion(2))));
+        // the knight of the last move jumps to g4 instead, which it can't
+        let mut changed = bytes.clone();
+        let knight_move = [Square::from_algebraic("g1").unwrap().0, Square::from_algebraic("f3").unwrap().0, 0];
+        let knight = changed.windows(3).position(|bytes| bytes == knight_move).unwrap() + 2;
+        changed[knight - 1] = Square::from_algebraic("g4").unwrap().0;
+        let error = Game::restore(changed.as_slice()).unwrap_err();
+        assert!(matches!(error, RestoreError::IllegalMove { ply: 2, reason: IllegalMoveReason::PieceCannotMoveThatWay, .. }), "{}", error);
+    }
+}
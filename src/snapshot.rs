@@ -0,0 +1,107 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::{Color, Game, GameState, Move, Piece, Square};
+
+/// An immutable copy of what is needed to draw a game: the pieces, turn, state, last move,
+/// clocks and legal moves. It is Send + Sync and cloning it only copies the board, since the
+/// legal moves are shared behind an Arc.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardSnapshot {
+    board: [u8; 64],
+    ids: [u8; 64],
+    pub turn: Color,
+    pub state: GameState,
+    pub last_move: Option<Move>,
+    pub halfmove: u64,
+    pub fullmove: u64,
+    legal_moves: Arc<[Move]>,
+}
+
+impl BoardSnapshot {
+    /// Get the piece standing on the given square, if any, with its id filled in.
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        let mut piece = Piece::from_u8(self.board[square.index()])?;
+        piece.id = self.ids[square.index()];
+        return Some(piece);
+    }
+
+    /// Get every legal move for the player whose turn it is.
+    pub fn legal_moves(&self) -> &[Move] {
+        return &self.legal_moves;
+    }
+
+    /// Get the legal moves of the piece standing on the given square.
+    pub fn moves_from(&self, square: Square) -> Vec<Move> {
+        return self.legal_moves.iter().copied().filter(|mv| mv.from == square && !mv.is_drop()).collect();
+    }
+}
+
+impl Game {
+    /// Takes a snapshot of the current game that can be handed to another thread, e.g. a
+    /// renderer. Later changes to the game do not affect the snapshot.
+    pub fn snapshot(&self) -> BoardSnapshot {
+        return BoardSnapshot {
+            board: self.position.board,
+            ids: self.ids,
+            turn: self.position.turn,
+            state: self.state,
+            last_move: self.last_move,
+            halfmove: self.position.halfmove as u64,
+            fullmove: self.position.fullmove as u64,
+            legal_moves: Arc::from(self.legal_moves().as_slice()),
+        };
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::BoardSnapshot;
+    use crate::{Color, Game, PieceRole, Square};
+
+    //check that a snapshot can be shared between threads
+    #[test]
+    fn check_snapshot_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BoardSnapshot>();
+
+        let snapshot = Arc::new(Game::new().snapshot());
+        let shared = Arc::clone(&snapshot);
+        let moves = thread::spawn(move || shared.legal_moves().len()).join().unwrap();
+        assert_eq!(moves, 20);
+    }
+
+    //check that cloning the game doesn't change what a snapshot looks like
+    #[test]
+    fn check_snapshot_equality() {
+        let mut game1 = Game::new();
+        game1.make_move("g1", "f3");
+        let snapshot = game1.snapshot();
+        let game2 = game1.clone();
+        assert_eq!(snapshot, game1.snapshot());
+        assert_eq!(snapshot, game2.snapshot());
+        assert_eq!(snapshot.last_move.unwrap().to_string(), "g1f3");
+        assert_eq!(snapshot.turn, Color::Black);
+        assert_eq!(snapshot.moves_from(Square::from_algebraic("b8").unwrap()).len(), 2);
+    }
+
+    //check that a snapshot stays the same when the game moves on
+    #[test]
+    fn check_snapshot_unaffected_by_moves() {
+        let mut game1 = Game::new();
+        let snapshot = game1.snapshot();
+        game1.make_move("e2", "e4");
+        assert_eq!(snapshot.piece_at(Square::from_algebraic("e2").unwrap()).unwrap().role, PieceRole::Pawn);
+        assert_eq!(snapshot.piece_at(Square::from_algebraic("e4").unwrap()), None);
+        assert_eq!(snapshot.last_move, None);
+        assert_eq!(snapshot.turn, Color::White);
+        assert_ne!(snapshot, game1.snapshot());
+    }
+}
@@ -0,0 +1,208 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{square_name, Game, Move, PieceRole};
+
+/// How much Game::describe_move says about a move.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Verbosity {
+    /// Like SAN read aloud: the square the piece comes from only when another piece of the same
+    /// kind could go there too, e.g. "the b-file knight to d2" or "pawn takes on d5".
+    #[default]
+    Brief,
+    /// Always the square the piece comes from and the piece that is taken, e.g. "knight from
+    /// g1 to f3" or "pawn from e4 takes knight on d5".
+    Full,
+}
+
+/// The parts of a description. The text of each is in one place (see english), so that other
+/// languages can be added by giving them other texts.
+#[derive(Clone, Copy)]
+enum Phrase {
+    MoveTo,
+    MoveFromTo,
+    TakesOn,
+    TakesFromOn,
+    Drop,
+    CastlesKingside,
+    CastlesQueenside,
+    /// A piece told apart from the others by its file, rank or square.
+    FilePiece,
+    RankPiece,
+    SquarePiece,
+    EnPassant,
+    PromotesTo,
+    Check,
+    Checkmate,
+    Stalemate,
+}
+
+/// The English text of the phrase, where {piece}, {from}, {to}, {captured}, {file} and {rank}
+/// are filled in.
+fn english(phrase: Phrase) -> &'static str {
+    return match phrase {
+        Phrase::MoveTo => "{piece} to {to}",
+        Phrase::MoveFromTo => "{piece} from {from} to {to}",
+        Phrase::TakesOn => "{piece} takes on {to}",
+        Phrase::TakesFromOn => "{piece} from {from} takes {captured} on {to}",
+        Phrase::Drop => "{piece} dropped on {to}",
+        Phrase::CastlesKingside => "castles kingside",
+        Phrase::CastlesQueenside => "castles queenside",
+        Phrase::FilePiece => "the {file}-file {piece}",
+        Phrase::RankPiece => "the {rank}-rank {piece}",
+        Phrase::SquarePiece => "the {piece} on {from}",
+        Phrase::EnPassant => "en passant",
+        Phrase::PromotesTo => "promotes to {piece}",
+        Phrase::Check => "check",
+        Phrase::Checkmate => "checkmate",
+        Phrase::Stalemate => "stalemate"
+    };
+}
+
+fn english_piece(role: PieceRole) -> &'static str {
+    return match role {
+        PieceRole::Pawn => "pawn",
+        PieceRole::Knight => "knight",
+        PieceRole::Bishop => "bishop",
+        PieceRole::Rook => "rook",
+        PieceRole::Queen => "queen",
+        PieceRole::King => "king"
+    };
+}
+
+/// The ranks as ordinals, from the first rank up.
+const ENGLISH_RANKS: [&str; 8] = ["first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth"];
+
+/// The text of the phrase with the values filled in.
+fn fill(phrase: Phrase, values: &[(&str, &str)]) -> String {
+    let mut text = english(phrase).to_string();
+    for (key, value) in values {
+        text = text.replace(&["{", key, "}"].concat(), value);
+    }
+    return text;
+}
+
+impl Game {
+    /// Describes a legal move in words, e.g. to read it aloud to a player who can't see the
+    /// board: "knight to f3", "pawn takes on d5, en passant", "pawn to e8, promotes to queen,
+    /// check", "queen to h7, checkmate", "castles kingside" or "knight dropped on e4". The
+    /// verbosity says whether the square the piece comes from is always said, see Verbosity.
+    /// The same move in the same position is always described the same way. Returns the move
+    /// in coordinate notation if it isn't legal.
+    pub fn describe_move(&self, mv: &Move, verbosity: Verbosity) -> String {
+        if !self.is_legal_move(mv) {return mv.to_string();}
+        let position = &self.position;
+        let (from, to) = (mv.from.index(), mv.to.index());
+        let (from_name, to_name) = (square_name(from), square_name(to));
+        let mut clauses: Vec<String> = Vec::new();
+        if mv.is_drop() {
+            let role = mv.promotion.unwrap();
+            clauses.push(fill(Phrase::Drop, &[("piece", english_piece(role)), ("to", &to_name)]));
+        } else if position.castling_rook(*mv).is_some() {
+            clauses.push(fill(if to > from {Phrase::CastlesKingside} else {Phrase::CastlesQueenside}, &[]));
+        } else {
+            let piece = position.piece_at(from).unwrap();
+            let capture = position.capture_square(*mv);
+            let name = english_piece(piece.role);
+            let clause = match verbosity {
+                Verbosity::Full => match capture {
+                    Some(captured) => {
+                        let captured = english_piece(position.piece_at(captured).unwrap().role);
+                        fill(Phrase::TakesFromOn, &[("piece", name), ("from", &from_name), ("captured", captured), ("to", &to_name)])
+                    }
+                    None => fill(Phrase::MoveFromTo, &[("piece", name), ("from", &from_name), ("to", &to_name)])
+                },
+                Verbosity::Brief => {
+                    // the other pieces of the same kind that could go to the square, like in SAN
+                    let others: Vec<usize> = self.legal_moves().iter()
+                        .filter(|other| other.to.index() == to && other.from.index() != from && !other.is_drop())
+                        .filter(|other| position.piece_at(other.from.index()).is_some_and(|other_piece| other_piece.role == piece.role))
+                        .map(|other| other.from.index())
+                        .collect();
+                    let subject = if others.is_empty() {
+                        name.to_string()
+                    } else if others.iter().all(|&other| other % 8 != from % 8) {
+                        fill(Phrase::FilePiece, &[("file", &from_name[..1]), ("piece", name)])
+                    } else if others.iter().all(|&other| other / 8 != from / 8) {
+                        fill(Phrase::RankPiece, &[("rank", ENGLISH_RANKS[7 - from / 8]), ("piece", name)])
+                    } else {
+                        fill(Phrase::SquarePiece, &[("piece", name), ("from", &from_name)])
+                    };
+                    fill(if capture.is_some() {Phrase::TakesOn} else {Phrase::MoveTo}, &[("piece", &subject), ("to", &to_name)])
+                }
+            };
+            clauses.push(clause);
+            if capture.is_some_and(|captured| captured != to) {clauses.push(fill(Phrase::EnPassant, &[]));}
+            if let Some(role) = mv.promotion {clauses.push(fill(Phrase::PromotesTo, &[("piece", english_piece(role))]));}
+        }
+        let mut after = *position;
+        after.apply_move(*mv);
+        let check = after.in_check(after.turn);
+        if after.legal_moves().is_empty() {
+            clauses.push(fill(if check {Phrase::Checkmate} else {Phrase::Stalemate}, &[]));
+        } else if check {
+            clauses.push(fill(Phrase::Check, &[]));
+        }
+        return clauses.join(", ");
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::Verbosity;
+    use crate::tests::coordinate_move;
+    use crate::{Game, Move, PieceRole, Square, Variant};
+
+    fn describe(fen: &str, mv: &str, verbosity: Verbosity) -> String {
+        return Game::from_fen(fen).unwrap().describe_move(&coordinate_move(mv), verbosity);
+    }
+
+    //check the words of every kind of move, briefly and in full
+    #[test]
+    fn check_describe_move() {
+        let table = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "g1f3", "knight to f3", "knight from g1 to f3"),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "e2e4", "pawn to e4", "pawn from e2 to e4"),
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", "e4d5", "pawn takes on d5", "pawn from e4 takes pawn on d5"),
+            ("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 2", "d5e6", "pawn takes on e6, en passant", "pawn from d5 takes pawn on e6, en passant"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", "castles kingside", "castles kingside"),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", "castles queenside", "castles queenside"),
+            ("3k4/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q", "pawn to a8, promotes to queen, check", "pawn from a7 to a8, promotes to queen, check"),
+            ("1r1k4/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n", "pawn takes on b8, promotes to knight", "pawn from a7 takes rook on b8, promotes to knight"),
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8", "rook to a8, checkmate", "rook from a1 to a8, checkmate"),
+            ("k7/8/1Q6/8/8/8/8/7K w - - 0 1", "b6c7", "queen to c7, stalemate", "queen from b6 to c7, stalemate"),
+            // told apart by the file, the rank or the square
+            ("4k3/8/8/8/8/8/4K3/1N3N2 w - - 0 1", "b1d2", "the b-file knight to d2", "knight from b1 to d2"),
+            ("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", "a1a3", "the first-rank rook to a3", "rook from a1 to a3"),
+            ("k7/8/8/8/8/2Q1Q3/8/4Q2K w - - 0 1", "e3d2", "the queen on e3 to d2", "queen from e3 to d2"),
+            ("4k3/8/8/3p4/2P1P3/8/8/4K3 w - - 0 1", "e4d5", "the e-file pawn takes on d5", "pawn from e4 takes pawn on d5"),
+        ];
+        for (fen, mv, brief, full) in table {
+            assert_eq!(describe(fen, mv, Verbosity::Brief), brief, "{} {}", fen, mv);
+            assert_eq!(describe(fen, mv, Verbosity::Full), full, "{} {}", fen, mv);
+        }
+        // a drop in Crazyhouse, and an illegal move
+        let game1 = Game::from_fen_variant("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1", Variant::Crazyhouse).unwrap();
+        let drop = Move::new_drop(PieceRole::Knight, Square::from_algebraic("d6").unwrap());
+        assert_eq!(game1.describe_move(&drop, Verbosity::Brief), "knight dropped on d6, check");
+        assert_eq!(Game::new().describe_move(&coordinate_move("e2e5"), Verbosity::Full), "e2e5");
+    }
+
+    //check that every move of random games gets a description that tells it apart from the others
+    #[test]
+    fn check_describe_move_unique() {
+        for game1 in crate::tests::random_games(20, 0x3C6EF372FE94F82B) {
+            let moves = game1.get_all_possible_moves();
+            for verbosity in [Verbosity::Brief, Verbosity::Full] {
+                let mut descriptions: Vec<String> = moves.iter().map(|mv| game1.describe_move(mv, verbosity)).collect();
+                descriptions.sort();
+                descriptions.dedup();
+                assert_eq!(descriptions.len(), moves.len(), "{}", game1.get_fen());
+            }
+        }
+    }
+}
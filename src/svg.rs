@@ -0,0 +1,225 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{ChessError, Color, Game, Move, Piece, PieceRole, Square};
+
+// the board is drawn 800 units wide, 100 for every square, and scaled to the size
+const SQUARE: usize = 100;
+const LIGHT: &str = "#f0d9b5";
+const DARK: &str = "#b58863";
+
+/// How Game::to_svg draws the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// The width and height of the image in pixels.
+    pub size: u32,
+    /// The player whose pieces are drawn at the bottom.
+    pub orientation: Color,
+    /// Whether the files and ranks are written in the squares at the edge.
+    pub coordinates: bool,
+    /// The move whose squares are highlighted, e.g. Game::get_last_move.
+    pub last_move: Option<Move>,
+    /// Whether the square of the king of the player to move is highlighted if it is in check.
+    pub check: bool,
+    /// Arrows drawn from the first square to the second, e.g. for a suggested move.
+    pub arrows: Vec<(Square, Square)>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        return SvgOptions { size: 400, orientation: Color::White, coordinates: true, last_move: None, check: true, arrows: Vec::new() };
+    }
+}
+
+/// The glyph of the piece, the filled one for both colors so that it can be painted.
+fn glyph(piece: Piece) -> char {
+    return match piece.role {
+        PieceRole::King => '♚',
+        PieceRole::Queen => '♛',
+        PieceRole::Rook => '♜',
+        PieceRole::Bishop => '♝',
+        PieceRole::Knight => '♞',
+        PieceRole::Pawn => '♟'
+    };
+}
+
+/// Draws the FEN position like Game::to_svg, without a game. Returns an error if the FEN
+/// string isn't valid.
+pub fn fen_to_svg(fen: &str, opts: SvgOptions) -> Result<String, ChessError> {
+    return Ok(Game::from_fen(fen)?.to_svg(opts));
+}
+
+impl Game {
+    /// Draws the board as a self-contained SVG image, e.g. to share a position on a web page
+    /// or in a chat: the checkered squares, the pieces as Unicode text, and the coordinates,
+    /// highlights and arrows of the options. Every piece is a text element with the class
+    /// "piece", and the highlights are rectangles with the classes "highlight last-move" and
+    /// "highlight check", so they can be styled or found.
+    pub fn to_svg(&self, opts: SvgOptions) -> String {
+        // the column and row a board index is drawn at
+        let place = |index: usize| -> (usize, usize) {
+            let (row, column) = (index / 8, index % 8);
+            return if opts.orientation == Color::White {(column, row)} else {(7 - column, 7 - row)};
+        };
+        let center = |index: usize| -> (usize, usize) {
+            let (x, y) = place(index);
+            return (x * SQUARE + SQUARE / 2, y * SQUARE + SQUARE / 2);
+        };
+        let mut svg = String::new();
+        let _ = write!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {1} {1}\">", opts.size, 8 * SQUARE);
+        if !opts.arrows.is_empty() {
+            svg.push_str("<defs><marker id=\"arrowhead\" markerWidth=\"4\" markerHeight=\"4\" refX=\"2\" refY=\"2\" orient=\"auto\"><path d=\"M0,0 L4,2 L0,4 z\" fill=\"#15781b\"/></marker></defs>");
+        }
+        for index in 0..64 {
+            let (x, y) = place(index);
+            let (class, fill) = if (index / 8 + index % 8) % 2 == 0 {("light", LIGHT)} else {("dark", DARK)};
+            let _ = write!(svg, "<rect class=\"square {0}\" x=\"{1}\" y=\"{2}\" width=\"{3}\" height=\"{3}\" fill=\"{4}\"/>", class, x * SQUARE, y * SQUARE, SQUARE, fill);
+        }
+        let mut highlights: Vec<(usize, &str, &str)> = Vec::new();
+        if let Some(mv) = opts.last_move {
+            highlights.push((mv.from.index(), "last-move", "#cdd26a"));
+            highlights.push((mv.to.index(), "last-move", "#cdd26a"));
+        }
+        let turn = self.position.turn;
+        if opts.check && self.position.in_check(turn) {
+            if let Some(index) = self.position.king_square(turn) {highlights.push((index, "check", "#e0413a"));}
+        }
+        for (index, class, fill) in highlights {
+            let (x, y) = place(index);
+            let _ = write!(svg, "<rect class=\"highlight {0}\" x=\"{1}\" y=\"{2}\" width=\"{3}\" height=\"{3}\" fill=\"{4}\" fill-opacity=\"0.75\"/>", class, x * SQUARE, y * SQUARE, SQUARE, fill);
+        }
+        if opts.coordinates {
+            // the files along the bottom and the ranks along the left, in the color of the other squares
+            for index in 0..64 {
+                let (x, y) = place(index);
+                let fill = if (index / 8 + index % 8) % 2 == 0 {DARK} else {LIGHT};
+                if y == 7 {
+                    let file = (b'a' + (index % 8) as u8) as char;
+                    let _ = write!(svg, "<text class=\"coordinate\" x=\"{}\" y=\"{}\" font-size=\"20\" font-family=\"sans-serif\" fill=\"{}\">{}</text>", x * SQUARE + SQUARE - 16, 8 * SQUARE - 6, fill, file);
+                }
+                if x == 0 {
+                    let rank = (b'8' - (index / 8) as u8) as char;
+                    let _ = write!(svg, "<text class=\"coordinate\" x=\"4\" y=\"{}\" font-size=\"20\" font-family=\"sans-serif\" fill=\"{}\">{}</text>", y * SQUARE + 20, fill, rank);
+                }
+            }
+        }
+        for index in 0..64 {
+            let Some(piece) = self.position.piece_at(index) else {continue};
+            let (x, y) = center(index);
+            let (color, fill) = if piece.color == Color::White {("white", "#ffffff")} else {("black", "#000000")};
+            // the text variation selector keeps the glyph from being drawn as an emoji
+            let _ = write!(svg, "<text class=\"piece {} {}\" x=\"{}\" y=\"{}\" font-size=\"80\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\" stroke=\"#000000\" stroke-width=\"2\">{}\u{FE0E}</text>", color, piece.role.name(), x, y, fill, glyph(piece));
+        }
+        for &(from, to) in opts.arrows.iter() {
+            let ((x1, y1), (x2, y2)) = (center(from.index()), center(to.index()));
+            let _ = write!(svg, "<line class=\"arrow\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#15781b\" stroke-width=\"15\" stroke-opacity=\"0.8\" marker-end=\"url(#arrowhead)\"/>", x1, y1, x2, y2);
+        }
+        svg.push_str("</svg>");
+        return svg;
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{fen_to_svg, SvgOptions};
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, Square};
+
+    /// Reads the elements of the XML document and returns their names and attributes, in the
+    /// order they are opened, and panics if the document isn't well-formed.
+    fn parse_xml(xml: &str) -> Vec<(String, Vec<(String, String)>)> {
+        let mut elements: Vec<(String, Vec<(String, String)>)> = Vec::new();
+        let mut open: Vec<String> = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            assert!(!open.is_empty() || rest[..start].trim().is_empty(), "text outside the root element");
+            assert!(!rest[..start].contains('>'), "stray '>'");
+            let end = start + rest[start..].find('>').expect("tag without '>'");
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(name), "mismatched closing tag");
+                continue;
+            }
+            assert!(elements.is_empty() || !open.is_empty(), "more than one root element");
+            let (tag, empty) = match tag.strip_suffix('/') {Some(tag) => (tag, true), None => (tag, false)};
+            let name = tag.split_whitespace().next().expect("tag without a name").to_string();
+            // name="value" pairs, every value quoted
+            let mut attributes: Vec<(String, String)> = Vec::new();
+            let mut text = tag[name.len()..].trim_start();
+            while !text.is_empty() {
+                let (key, value) = text.split_once("=\"").expect("attribute without a quoted value");
+                let (value, after) = value.split_once('"').expect("attribute without a closing quote");
+                assert!(!key.is_empty() && !key.contains(char::is_whitespace), "bad attribute name");
+                assert!(!attributes.iter().any(|(other, _)| other == key), "duplicate attribute");
+                assert!(!value.contains('<') && !value.contains('&'), "unescaped attribute value");
+                attributes.push((key.to_string(), value.to_string()));
+                text = after.trim_start();
+            }
+            if !empty {open.push(name.clone());}
+            elements.push((name, attributes));
+        }
+        assert!(rest.trim().is_empty() && open.is_empty(), "unclosed elements");
+        return elements;
+    }
+
+    fn with_class<'a>(elements: &'a [(String, Vec<(String, String)>)], class: &str) -> Vec<&'a Vec<(String, String)>> {
+        return elements.iter().map(|(_, attributes)| attributes)
+            .filter(|attributes| attributes.iter().any(|(key, value)| key == "class" && value.split(' ').next() == Some(class)))
+            .collect();
+    }
+
+    //check that the start position is well-formed with 64 squares and 32 pieces
+    #[test]
+    fn check_svg_start_position() {
+        let game1 = Game::new();
+        let svg = game1.to_svg(SvgOptions::default());
+        let elements = parse_xml(&svg);
+        assert_eq!(elements[0].0, "svg");
+        assert!(elements[0].1.contains(&("width".to_string(), "400".to_string())));
+        assert_eq!(with_class(&elements, "square").len(), 64);
+        assert_eq!(with_class(&elements, "piece").len(), 32);
+        assert_eq!(with_class(&elements, "coordinate").len(), 16);
+        assert!(with_class(&elements, "highlight").is_empty());
+        assert_eq!(svg.matches('♚').count(), 2);
+        // without coordinates, and the same from a FEN
+        let options = SvgOptions { coordinates: false, ..SvgOptions::default() };
+        assert!(with_class(&parse_xml(&game1.to_svg(options.clone())), "coordinate").is_empty());
+        assert_eq!(fen_to_svg(&game1.get_fen(), options.clone()).unwrap(), game1.to_svg(options.clone()));
+        assert!(fen_to_svg("8/8/8 w - - 0 1", options).is_err());
+    }
+
+    //check the highlights of the last move and of check, and the arrows
+    #[test]
+    fn check_svg_highlights() {
+        let mut game1 = Game::new();
+        for mv in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        let options = SvgOptions { last_move: game1.get_last_move(), ..SvgOptions::default() };
+        let elements = parse_xml(&game1.to_svg(options));
+        let highlights = with_class(&elements, "highlight");
+        assert_eq!(highlights.len(), 3);
+        // d8 in the top row and h4 and e1 from the side of White
+        let position = |attributes: &Vec<(String, String)>| -> (String, String) {
+            let get = |name: &str| attributes.iter().find(|(key, _)| key == name).unwrap().1.clone();
+            return (get("x"), get("y"));
+        };
+        assert_eq!(position(highlights[0]), ("300".to_string(), "0".to_string()));
+        assert_eq!(position(highlights[1]), ("700".to_string(), "400".to_string()));
+        assert_eq!(position(highlights[2]), ("400".to_string(), "700".to_string()));
+        assert!(highlights[2].contains(&("class".to_string(), "highlight check".to_string())));
+        // turned around, with an arrow and without the check
+        let arrow = (Square::from_algebraic("e1").unwrap(), Square::from_algebraic("f2").unwrap());
+        let options = SvgOptions { orientation: Color::Black, check: false, arrows: vec![arrow], ..SvgOptions::default() };
+        let elements = parse_xml(&game1.to_svg(options));
+        assert!(with_class(&elements, "highlight").is_empty());
+        assert_eq!(with_class(&elements, "arrow").len(), 1);
+        assert_eq!(position(with_class(&elements, "square")[0]), ("700".to_string(), "700".to_string()));
+    }
+}
@@ -0,0 +1,244 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{move_okay, Color, Game, Move, MoveStage, Piece, PieceRole, Position, Square, BISHOP_DIRECTIONS, QUEEN_DIRECTIONS, ROOK_DIRECTIONS};
+
+/// A piece that attacks two or more enemy pieces at once, see Game::forks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkReport {
+    /// The square and role of the forking piece.
+    pub square: Square,
+    pub role: PieceRole,
+    /// The squares of the attacked pieces, from a8 to h1.
+    pub targets: Vec<Square>,
+}
+
+/// A bishop, rook or queen lined up with two enemy pieces, see Game::tactical_pins and
+/// Game::skewers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineReport {
+    /// The square of the bishop, rook or queen.
+    pub attacker: Square,
+    /// The square of the enemy piece it attacks, and of the one behind it.
+    pub front: Square,
+    pub rear: Square,
+    /// The squares from the one next to the attacker up to the rear piece.
+    pub line: Vec<Square>,
+}
+
+/// A move of a CctReport, also in standard algebraic notation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CctMove {
+    pub mv: Move,
+    pub san: String,
+    /// The static exchange value of a capture (see engine's see), 0 for other moves.
+    pub see: i32,
+}
+
+/// The checks, captures and threats of the player to move, see Game::cct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CctReport {
+    pub checks: Vec<CctMove>,
+    pub captures: Vec<CctMove>,
+    pub threats: Vec<CctMove>,
+}
+
+impl Game {
+    /// Finds the pieces of the color that attack two or more enemy pieces that are worth
+    /// attacking: the king, and every piece the attacker wins material by taking according to
+    /// the static exchange (see engine's see), so pieces that are undefended or worth more
+    /// than the attacker. Defended pieces worth as much or less don't count, so a knight
+    /// attacking two defended pawns is no fork. Whether the forking piece can be taken itself
+    /// is not looked at. The forks are listed by the square of the forking piece, from a8 to h1.
+    pub fn forks(&self, color: Color) -> Vec<ForkReport> {
+        let mut targets: Vec<Vec<Square>> = vec![Vec::new(); 64];
+        for target in 0..64 {
+            let victim = match self.position.piece_at(target) {
+                Some(piece) if piece.color != color => piece,
+                _ => continue
+            };
+            self.position.for_each_attacker(target, color, |attacker| {
+                if victim.role == PieceRole::King || self.position.see(Move::new(attacker, target, None)) > 0 {
+                    targets[attacker].push(Square(target as u8));
+                }
+            });
+        }
+        return targets.into_iter().enumerate().filter(|(_, targets)| targets.len() >= 2).map(|(index, targets)| {
+            ForkReport { square: Square(index as u8), role: self.position.piece_at(index).unwrap().role, targets }
+        }).collect();
+    }
+
+    /// Finds the pins of the bishops, rooks and queens of the color: an enemy piece that can't
+    /// move off the line without exposing the piece behind it. The pin is absolute when the
+    /// piece behind is the king, so the pinned piece may not leave the line at all, and
+    /// relative when it is a queen or rook worth more than the pinned piece. The pins are
+    /// listed by the square of the attacker, from a8 to h1.
+    pub fn tactical_pins(&self, color: Color) -> Vec<LineReport> {
+        return self.line_tactics(color, |front, rear| {
+            front.role != PieceRole::King && (rear.role == PieceRole::King || (matches!(rear.role, PieceRole::Queen | PieceRole::Rook) && rear.role.value() > front.role.value()))
+        });
+    }
+
+    /// Finds the skewers of the bishops, rooks and queens of the color: an enemy king, or a
+    /// piece worth more than the one behind it, that has to move off the line and leave the
+    /// piece behind it to be taken. Listed like tactical_pins.
+    pub fn skewers(&self, color: Color) -> Vec<LineReport> {
+        return self.line_tactics(color, |front, rear| {
+            rear.role != PieceRole::King && (front.role == PieceRole::King || front.role.value() > rear.role.value())
+        });
+    }
+
+    /// Lists the checks, captures and threats of the player to move, the moves to look at
+    /// first: every move that gives check (captures too), every capture with its static
+    /// exchange value, and every other move after which an undefended enemy piece other than
+    /// the king is attacked that wasn't attacked before. A move can be in more than one list.
+    /// The moves come in the order of generate, so the captures that win material first.
+    pub fn cct(&self) -> CctReport {
+        let entry = |mv: Move, see: i32| -> CctMove {CctMove { mv, san: self.position.san(mv), see }};
+        let captures: Vec<CctMove> = self.generate(MoveStage::Captures).into_iter().map(|mv| entry(mv, self.position.see(mv))).collect();
+        let mut checks: Vec<CctMove> = captures.iter().filter(|capture| self.position.gives_check(capture.mv)).cloned().collect();
+        let mut threats: Vec<CctMove> = Vec::new();
+        let attacked_before = self.position.loose_targets(self.position.turn);
+        for stage in [MoveStage::Checks, MoveStage::Quiets] {
+            for mv in self.generate(stage) {
+                if stage == MoveStage::Checks {checks.push(entry(mv, 0));}
+                let mut child = self.position;
+                child.apply_move(mv);
+                if child.loose_targets(self.position.turn).iter().any(|target| !attacked_before.contains(target)) {threats.push(entry(mv, 0));}
+            }
+        }
+        return CctReport { checks, captures, threats };
+    }
+
+    /// The lines from a bishop, rook or queen of the color through an enemy piece to the next
+    /// piece, if that is an enemy piece too and the pieces pass the test.
+    fn line_tactics(&self, color: Color, test: impl Fn(Piece, Piece) -> bool) -> Vec<LineReport> {
+        let mut reports: Vec<LineReport> = Vec::new();
+        for attacker in 0..64 {
+            let directions: &[(i8, i8)] = match self.position.piece_at(attacker) {
+                Some(piece) if piece.color == color && piece.role == PieceRole::Bishop => &BISHOP_DIRECTIONS,
+                Some(piece) if piece.color == color && piece.role == PieceRole::Rook => &ROOK_DIRECTIONS,
+                Some(piece) if piece.color == color && piece.role == PieceRole::Queen => &QUEEN_DIRECTIONS,
+                _ => continue
+            };
+            for &(row_offset, column_offset) in directions {
+                // the first two pieces along the line
+                let mut pieces: Vec<(usize, Piece)> = Vec::new();
+                let mut line: Vec<Square> = Vec::new();
+                let (mut row, mut column) = ((attacker / 8) as i8 + row_offset, (attacker % 8) as i8 + column_offset);
+                while move_okay(row, column) && pieces.len() < 2 {
+                    let index = (row * 8 + column) as usize;
+                    line.push(Square(index as u8));
+                    if let Some(piece) = self.position.piece_at(index) {pieces.push((index, piece));}
+                    row += row_offset;
+                    column += column_offset;
+                }
+                if let [(front, front_piece), (rear, rear_piece)] = pieces[..] {
+                    if front_piece.color == color || rear_piece.color == color || !test(front_piece, rear_piece) {continue;}
+                    reports.push(LineReport { attacker: Square(attacker as u8), front: Square(front as u8), rear: Square(rear as u8), line });
+                }
+            }
+        }
+        return reports;
+    }
+}
+
+impl Position {
+    /// The indexes of the enemy pieces other than the king that the color attacks and their
+    /// own pieces don't defend.
+    fn loose_targets(&self, color: Color) -> Vec<usize> {
+        return (0..64).filter(|&index| {
+            self.piece_at(index).is_some_and(|piece| piece.color != color && piece.role != PieceRole::King)
+                && !self.attackers_of(index, color).is_empty()
+                && self.attackers_of(index, color.opposite()).is_empty()
+        }).collect();
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::{CctMove, ForkReport, LineReport};
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, PieceRole, Square};
+
+    fn squares(names: &[&str]) -> Vec<Square> {
+        return names.iter().map(|name| Square::from_algebraic(name).unwrap()).collect();
+    }
+
+    //check that a knight checking the king and attacking a rook is a fork
+    #[test]
+    fn check_royal_fork() {
+        let game1 = Game::from_fen("r3k3/2N5/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        let fork = ForkReport { square: squares(&["c7"])[0], role: PieceRole::Knight, targets: squares(&["a8", "e8"]) };
+        assert_eq!(game1.forks(Color::White), [fork]);
+        assert_eq!(game1.forks(Color::Black), []);
+        assert_eq!(Game::new().forks(Color::White), []);
+    }
+
+    //check that a pawn attacking two defended pieces worth more is a fork
+    #[test]
+    fn check_pawn_fork() {
+        let game1 = Game::from_fen("4k3/1p3p2/2n1b3/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        let fork = ForkReport { square: squares(&["d5"])[0], role: PieceRole::Pawn, targets: squares(&["c6", "e6"]) };
+        assert_eq!(game1.forks(Color::White), [fork]);
+    }
+
+    //check that attacking two defended pawns with a knight is no fork
+    #[test]
+    fn check_defended_targets() {
+        let game1 = Game::from_fen("4k3/3p1p2/8/4N3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.forks(Color::White), []);
+        // without the king beside them the pawns are loose
+        let game1 = Game::from_fen("7k/3p1p2/8/4N3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.forks(Color::White).len(), 1);
+    }
+
+    fn line(attacker: &str, front: &str, rear: &str, line: &[&str]) -> LineReport {
+        let square = |name: &str| -> Square {Square::from_algebraic(name).unwrap()};
+        return LineReport { attacker: square(attacker), front: square(front), rear: square(rear), line: squares(line) };
+    }
+
+    //check an absolute pin to the king and a relative pin to the queen
+    #[test]
+    fn check_tactical_pins() {
+        let game1 = Game::from_fen("4k3/8/2n5/1B6/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.tactical_pins(Color::White), [line("b5", "c6", "e8", &["c6", "d7", "e8"])]);
+        assert_eq!(game1.skewers(Color::White), []);
+        let game1 = Game::from_fen("3qk3/8/5n2/6B1/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.tactical_pins(Color::White), [line("g5", "f6", "d8", &["f6", "e7", "d8"])]);
+        // a knight in front of a bishop is no pin
+        let game1 = Game::from_fen("3bk3/8/5n2/6B1/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.tactical_pins(Color::White), []);
+    }
+
+    //check a skewer of the king and a rook, and that a piece in between blocks it
+    #[test]
+    fn check_skewers() {
+        let game1 = Game::from_fen("8/8/8/8/R2k3r/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(game1.skewers(Color::White), [line("a4", "d4", "h4", &["b4", "c4", "d4", "e4", "f4", "g4", "h4"])]);
+        assert_eq!(game1.tactical_pins(Color::White), []);
+        let game1 = Game::from_fen("8/8/8/8/R1Nk3r/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game1.skewers(Color::White), []);
+        assert_eq!(game1.tactical_pins(Color::White), []);
+    }
+
+    //check the checks, captures and threats of a crafted position
+    #[test]
+    fn check_cct() {
+        let game1 = Game::from_fen("6k1/2n2ppp/2b5/4p3/6N1/2N2Q2/p5PP/6K1 w - - 0 1").unwrap();
+        let report = game1.cct();
+        let names = |moves: &[CctMove]| -> Vec<String> {moves.iter().map(|entry| entry.san.clone()).collect()};
+        assert_eq!(names(&report.checks), ["Qxf7+", "Nh6+", "Nf6+"]);
+        assert_eq!(names(&report.captures), ["Nxe5", "Nxa2", "Qxc6", "Qxf7+"]);
+        // only taking the pawn beside the king loses material
+        assert_eq!(report.captures.iter().map(|entry| entry.see).collect::<Vec<i32>>(), [100, 100, 330, -800]);
+        // both knight moves attack the undefended knight on c7
+        assert_eq!(names(&report.threats), ["Nd5", "Nb5"]);
+        assert_eq!(report.threats[0].mv, coordinate_move("c3d5"));
+    }
+}
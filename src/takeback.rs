@@ -0,0 +1,146 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "std")]
+use crate::event_log::LogEntry;
+use crate::{Color, Game, Move};
+
+/// Errors returned by Game::request_takeback and Game::respond_takeback.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TakebackError {
+    /// The game is over.
+    GameOver,
+    /// The player hasn't made a move that could be taken back.
+    NoMoves,
+    /// A takeback has already been asked for and not answered.
+    AlreadyRequested,
+    /// The opponent hasn't asked for a takeback.
+    NoRequest,
+}
+
+impl fmt::Display for TakebackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TakebackError::GameOver => write!(f, "the game is over"),
+            TakebackError::NoMoves => write!(f, "there is no move to take back"),
+            TakebackError::AlreadyRequested => write!(f, "a takeback has already been requested"),
+            TakebackError::NoRequest => write!(f, "no takeback has been requested")
+        }
+    }
+}
+
+impl core::error::Error for TakebackError {}
+
+impl Game {
+    /// Asks the opponent of the player to take back the player's last move, which they can
+    /// answer with respond_takeback until the next move is made. If the opponent has already
+    /// replied to it, their reply is taken back too. Returns an error if the game is over, the
+    /// player hasn't moved yet or a takeback has already been asked for.
+    pub fn request_takeback(&mut self, color: Color) -> Result<(), TakebackError> {
+        if self.outcome().is_some() {return Err(TakebackError::GameOver);}
+        if self.takeback_request.is_some() {return Err(TakebackError::AlreadyRequested);}
+        if self.history.len() < self.takeback_plies(color) {return Err(TakebackError::NoMoves);}
+        self.takeback_request = Some(color);
+        #[cfg(feature = "std")]
+        self.log(color, LogEntry::TakebackRequested);
+        self.emit_takeback_requested(color);
+        return Ok(());
+    }
+
+    /// Answers the takeback the opponent of the player asked for. If the player accepts, the
+    /// moves are taken back like with undo and returned, the last move first; if they decline
+    /// nothing is taken back and the list is empty. Either way the request is answered.
+    /// Returns an error if the opponent hasn't asked for a takeback.
+    pub fn respond_takeback(&mut self, color: Color, accept: bool) -> Result<Vec<Move>, TakebackError> {
+        if self.takeback_request != Some(color.opposite()) {return Err(TakebackError::NoRequest);}
+        self.takeback_request = None;
+        #[cfg(feature = "std")]
+        self.log(color, if accept {LogEntry::TakebackAccepted} else {LogEntry::TakebackDeclined});
+        self.emit_takeback_answered(color, accept);
+        if !accept {return Ok(Vec::new());}
+        let plies = self.takeback_plies(color.opposite());
+        return Ok((0..plies).filter_map(|_| self.undo()).collect());
+    }
+
+    /// The number of moves to take back for the player: their last move, and the reply to it if
+    /// the opponent has made it.
+    fn takeback_plies(&self, color: Color) -> usize {
+        return if color == self.position.turn {2} else {1};
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::TakebackError;
+    use crate::tests::{coordinate_move, recorded};
+    use crate::{Color, Game, GameEvent};
+
+    //check a takeback before the opponent has replied, which takes back one move
+    #[test]
+    fn check_takeback_one_ply() {
+        let (mut game1, events) = recorded("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        game1.make_move("e2", "e4").unwrap();
+        game1.make_move("e7", "e5").unwrap();
+        game1.make_move("f2", "f4").unwrap();
+        let fen = game1.get_fen();
+        game1.request_takeback(Color::White).unwrap();
+        // White can't take back their own request
+        assert_eq!(game1.respond_takeback(Color::White, true), Err(TakebackError::NoRequest));
+        assert_eq!(game1.respond_takeback(Color::Black, false), Ok(Vec::new()));
+        assert_eq!(game1.get_fen(), fen);
+        // the declined request is over, and a new one can be accepted
+        assert_eq!(game1.respond_takeback(Color::Black, true), Err(TakebackError::NoRequest));
+        game1.request_takeback(Color::White).unwrap();
+        assert_eq!(game1.respond_takeback(Color::Black, true), Ok(vec![coordinate_move("f2f4")]));
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        let events = events.lock().unwrap();
+        assert_eq!(events[3..], [
+            GameEvent::TakebackRequested(Color::White),
+            GameEvent::TakebackDeclined(Color::Black),
+            GameEvent::TakebackRequested(Color::White),
+            GameEvent::TakebackAccepted(Color::Black),
+            GameEvent::UndoPerformed(coordinate_move("f2f4")),
+        ]);
+    }
+
+    //check a takeback after the opponent has replied, which takes back the reply too
+    #[test]
+    fn check_takeback_two_plies() {
+        let mut game1 = Game::new();
+        game1.make_move("e2", "e4").unwrap();
+        game1.make_move("e7", "e5").unwrap();
+        game1.make_move("d1", "h5").unwrap();
+        game1.make_move("g7", "g6").unwrap();
+        // White asks after Black has replied, so both moves are taken back
+        assert_eq!(game1.request_takeback(Color::White), Ok(()));
+        assert_eq!(game1.request_takeback(Color::Black), Err(TakebackError::AlreadyRequested));
+        assert_eq!(game1.respond_takeback(Color::Black, true), Ok(vec![coordinate_move("g7g6"), coordinate_move("d1h5")]));
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        assert_eq!(game1.get_turn_color(), Color::White);
+    }
+
+    //check that a request expires when a move is made instead, and the errors
+    #[test]
+    fn check_takeback_expiry() {
+        let mut game1 = Game::new();
+        assert_eq!(game1.request_takeback(Color::White), Err(TakebackError::NoMoves));
+        game1.make_move("e2", "e4").unwrap();
+        // Black has no move to take back, only White
+        assert_eq!(game1.request_takeback(Color::Black), Err(TakebackError::NoMoves));
+        game1.request_takeback(Color::White).unwrap();
+        game1.make_move("e7", "e5").unwrap();
+        assert_eq!(game1.respond_takeback(Color::Black, true), Err(TakebackError::NoRequest));
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        // an undo answers it too
+        game1.request_takeback(Color::Black).unwrap();
+        game1.undo();
+        assert_eq!(game1.respond_takeback(Color::White, true), Err(TakebackError::NoRequest));
+        // and nothing can be taken back once the game is over
+        game1.resign(Color::Black);
+        assert_eq!(game1.request_takeback(Color::White), Err(TakebackError::GameOver));
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::{self, Write};
+
+use crate::engine::find_best_move;
+use crate::{Color, Game, GameOutcome, GameState};
+
+/// The file format export_training_data writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrainingFormat {
+    /// Comma-separated values with a header line.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// Options for export_training_data.
+#[derive(Clone, Copy, Debug)]
+pub struct TrainingOptions {
+    pub format: TrainingFormat,
+    /// The number of positions to leave out at the start of every game, since the openings of
+    /// most games are the same.
+    pub skip_plies: u32,
+    /// Leaves out positions where the player to move is in check, since their moves are forced.
+    pub skip_check: bool,
+    /// Adds the score of a search this many plies deep to every row, in centipawns from the
+    /// point of view of the player to move. None leaves the score out.
+    pub eval_depth: Option<u32>,
+}
+
+impl Default for TrainingOptions {
+    fn default() -> TrainingOptions {
+        TrainingOptions { format: TrainingFormat::Csv, skip_plies: 0, skip_check: false, eval_depth: None }
+    }
+}
+
+/// Writes one row for every position a move was made from in the games: the FEN string, the
+/// player to move ("w" or "b") and the result of the game from the point of view of that
+/// player (1 for a win, 0.5 for a draw and 0 for a loss). The result is empty in CSV and null
+/// in JSON Lines if the game isn't over. The games are read one at a time and every row is
+/// written as soon as it is made, so any number of games can be exported. Returns the number
+/// of rows written.
+pub fn export_training_data(games: impl Iterator<Item = Game>, mut writer: impl Write, opts: TrainingOptions) -> io::Result<usize> {
+    if opts.format == TrainingFormat::Csv {
+        let eval = if opts.eval_depth.is_some() {",eval"} else {""};
+        writeln!(writer, "fen,turn,result{}", eval)?;
+    }
+    let mut rows = 0;
+    for game in games {
+        let winner = result(&game);
+        // go back to the start of the game and play the moves again, in a new game since one
+        // that was resigned or lost on time takes no moves
+        let mut start = game.clone();
+        let mut moves = Vec::new();
+        while let Some(mv) = start.undo() {
+            moves.push(mv);
+        }
+        let mut replay = Game::from_fen_variant(&start.get_fen(), start.position.variant).unwrap();
+        for (ply, mv) in moves.iter().rev().enumerate() {
+            let skipped = (ply as u32) < opts.skip_plies || (opts.skip_check && replay.get_game_state() == GameState::Check);
+            if !skipped {
+                write_row(&mut writer, &replay, winner, &opts)?;
+                rows += 1;
+            }
+            replay.play_move(mv);
+        }
+    }
+    writer.flush()?;
+    return Ok(rows);
+}
+
+/// The result of the game, as Some(winner) or Some(None) for a draw, or None if the game
+/// isn't over. Besides the outcome of the game (which covers resignations, agreed draws and
+/// lost time), a game stopped at the fifty-move rule or a threefold repetition is a draw,
+/// since either player could have claimed it.
+fn result(game: &Game) -> Option<Option<Color>> {
+    return match game.outcome() {
+        Some(GameOutcome::Win(color)) => Some(Some(color)),
+        Some(GameOutcome::Draw) => Some(None),
+        None if game.get_halfmove() >= 100 || game.is_threefold_repetition() => Some(None),
+        None => None
+    };
+}
+
+fn write_row(writer: &mut impl Write, game: &Game, winner: Option<Option<Color>>, opts: &TrainingOptions) -> io::Result<()> {
+    let turn = game.get_turn_color();
+    let result = match winner {
+        Some(Some(color)) if color == turn => "1",
+        Some(Some(_)) => "0",
+        Some(None) => "0.5",
+        None => ""
+    };
+    let eval = opts.eval_depth.map(|depth| find_best_move(game, depth.max(1)).map_or(0, |(_, score)| score));
+    let (fen, turn) = (game.get_fen(), if turn == Color::White {"w"} else {"b"});
+    return match opts.format {
+        TrainingFormat::Csv => {
+            let eval = eval.map_or(String::new(), |eval| format!(",{}", eval));
+            writeln!(writer, "{},{},{}{}", fen, turn, result, eval)
+        }
+        TrainingFormat::JsonLines => {
+            let result = if result.is_empty() {"null"} else {result};
+            let eval = eval.map_or(String::new(), |eval| format!(",\"eval\":{}", eval));
+            writeln!(writer, "{{\"fen\":\"{}\",\"turn\":\"{}\",\"result\":{}{}}}", fen, turn, result, eval)
+        }
+    };
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{export_training_data, TrainingFormat, TrainingOptions};
+    use crate::clock::{Clock, MockTime, TimeControl};
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, GameOutcome};
+
+    const FIXTURE: &str = include_str!("../tests/fixtures/training_games.txt");
+
+    // the games of the fixture, one line of moves in coordinate notation per game
+    fn games() -> impl Iterator<Item = Game> {
+        return FIXTURE.lines().map(|line| {
+            let mut game1 = Game::new();
+            for mv in line.split_whitespace() {
+                game1.play_move(&coordinate_move(mv)).unwrap();
+            }
+            game1
+        });
+    }
+
+    //check the rows of a CSV export and that their FEN strings are the positions of the game
+    #[test]
+    fn check_export_csv() {
+        let mut output = Vec::new();
+        let rows = export_training_data(games(), &mut output, TrainingOptions::default()).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        // a fool's mate of 4 plies and an unfinished game of 6
+        assert_eq!(rows, 10);
+        assert_eq!(lines.len(), 11);
+        assert_eq!(lines[0], "fen,turn,result");
+        assert_eq!(lines[1], "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1,w,0");
+        assert!(lines[2].ends_with(",b,1"));
+        assert!(lines[5].ends_with(",w,"));
+        // the fourth position of the second game is the one after its first three moves
+        let fen = lines[8].split(',').next().unwrap();
+        let mut game1 = Game::new();
+        for mv in FIXTURE.lines().nth(1).unwrap().split_whitespace().take(3) {
+            game1.play_move(&coordinate_move(mv)).unwrap();
+        }
+        assert_eq!(game1.get_fen(), fen);
+        assert_eq!(Game::from_fen(fen).unwrap().get_fen(), fen);
+    }
+
+    //check that skipped plies and checks are left out, and the JSON Lines rows with an eval
+    #[test]
+    fn check_export_json_lines() {
+        let opts = TrainingOptions { format: TrainingFormat::JsonLines, skip_plies: 2, skip_check: true, eval_depth: Some(1) };
+        let mut output = Vec::new();
+        let rows = export_training_data(games(), &mut output, opts).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // 2 + 4 positions after the skipped ones, two of them in check
+        assert_eq!(rows, 4);
+        assert_eq!(output.lines().count(), 4);
+        let first = output.lines().next().unwrap();
+        assert!(first.starts_with("{\"fen\":\"rnbqkbnr/pppp1ppp/8/4p3/8/5P2/PPPPP1PP/RNBQKBNR w KQkq e6 0 2\",\"turn\":\"w\",\"result\":0,\"eval\":"), "{}", first);
+        assert!(output.lines().last().unwrap().contains("\"result\":null"));
+    }
+
+    //check the results of games that ended without a checkmate: a resignation, an agreed draw and a loss on time
+    #[test]
+    fn check_export_other_endings() {
+        let export = |game: Game| -> Vec<String> {
+            let mut output = Vec::new();
+            export_training_data(std::iter::once(game), &mut output, TrainingOptions::default()).unwrap();
+            return String::from_utf8(output).unwrap().lines().skip(1).map(|line| line.rsplit(',').next().unwrap().to_string()).collect();
+        };
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        game1.play_move(&coordinate_move("e7e5")).unwrap();
+        let mut resigned = game1.clone();
+        resigned.resign(Color::Black);
+        assert_eq!(export(resigned), ["1", "0"]);
+        let mut agreed = game1.clone();
+        agreed.offer_draw(Color::White);
+        agreed.accept_draw(Color::Black);
+        assert_eq!(export(agreed), ["0.5", "0.5"]);
+        // White is out of time
+        let time = MockTime::new();
+        let mut clock = Clock::with_time_source(TimeControl::SuddenDeath(Duration::from_secs(10)), time.clone());
+        clock.start_turn(Color::White);
+        time.advance(Duration::from_secs(11));
+        let mut flagged = game1.clone();
+        assert_eq!(flagged.check_flag(&clock), Some(GameOutcome::Win(Color::Black)));
+        assert_eq!(export(flagged), ["0", "1"]);
+    }
+}
@@ -0,0 +1,140 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::Game;
+
+/// The squares of a row of FEN placement data, from the a-file on: the letter of the piece,
+/// with a ~ after it if it was promoted in Crazyhouse, or nothing for an empty square.
+fn row_cells(row: &str) -> Vec<String> {
+    let mut cells: Vec<String> = Vec::new();
+    for char in row.chars() {
+        match char {
+            '1'..='8' => cells.extend((0..char as u8 - b'0').map(|_| String::new())),
+            '~' => cells.last_mut().unwrap().push(char),
+            _ => cells.push(char.to_string())
+        }
+    }
+    return cells;
+}
+
+/// Writes the squares of a row back as FEN placement data, see row_cells.
+fn write_row(cells: &[String]) -> String {
+    let mut row = String::new();
+    let mut empty_squares = 0;
+    for cell in cells {
+        if cell.is_empty() {
+            empty_squares += 1;
+            continue;
+        }
+        if empty_squares > 0 {row.push_str(&empty_squares.to_string()); empty_squares = 0;}
+        row.push_str(cell);
+    }
+    if empty_squares > 0 {row.push_str(&empty_squares.to_string());}
+    return row;
+}
+
+/// Swaps the case of the letters, and with it the colors of the pieces or castling rights.
+fn swap_case(text: &str) -> String {
+    return text.chars().map(|char| if char.is_ascii_uppercase() {char.to_ascii_lowercase()} else {char.to_ascii_uppercase()}).collect();
+}
+
+impl Game {
+    /// Returns the same position with the colors swapped: the board is mirrored from the
+    /// first rank to the eighth, every piece changes color, the other player is to move and
+    /// the castling rights and en passant square go along. The clocks and the variant stay.
+    /// Flipping twice gives back the position. The new game has no moves to undo and its
+    /// pieces get new ids.
+    pub fn flip_colors(&self) -> Game {
+        let fen = self.get_fen();
+        let fields: Vec<&str> = fen.split(' ').collect();
+        // the pockets of Crazyhouse stay behind the pieces
+        let (placement, pockets) = match fields[0].split_once('[') {
+            Some((placement, pockets)) => (placement, format!("[{}", swap_case(pockets))),
+            None => (fields[0], String::new())
+        };
+        let rows: Vec<String> = placement.split('/').rev().map(swap_case).collect();
+        let turn = if fields[1] == "w" {"b"} else {"w"};
+        // castling rights in the order White, Black
+        let mut castling = swap_case(fields[2]);
+        castling = castling.chars().filter(|char| char.is_ascii_uppercase()).chain(castling.chars().filter(|char| !char.is_ascii_uppercase())).collect();
+        let en_passant = match fields[3] {
+            "-" => String::from("-"),
+            square => format!("{}{}", &square[0..1], if &square[1..2] == "3" {"6"} else {"3"})
+        };
+        let flipped = format!("{}{} {} {} {} {} {}", rows.join("/"), pockets, turn, castling, en_passant, fields[4], fields[5]);
+        return Game::from_fen_variant(&flipped, self.position.variant).unwrap();
+    }
+
+    /// Returns the position mirrored from the a-file to the h-file, with the same player to
+    /// move. The kings and rooks end up where castling is impossible, so there are no
+    /// castling rights. The en passant square is mirrored too. The new game has no moves to
+    /// undo and its pieces get new ids.
+    pub fn mirror_horizontal(&self) -> Game {
+        let fen = self.get_fen();
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let (placement, pockets) = match fields[0].split_once('[') {
+            Some((placement, pockets)) => (placement, format!("[{}", pockets)),
+            None => (fields[0], String::new())
+        };
+        let rows: Vec<String> = placement.split('/').map(|row| {
+            let mut cells = row_cells(row);
+            cells.reverse();
+            write_row(&cells)
+        }).collect();
+        let en_passant = match fields[3] {
+            "-" => String::from("-"),
+            square => format!("{}{}", (b'h' - (square.as_bytes()[0] - b'a')) as char, &square[1..2])
+        };
+        let mirrored = format!("{}{} {} - {} {} {}", rows.join("/"), pockets, fields[1], en_passant, fields[4], fields[5]);
+        return Game::from_fen_variant(&mirrored, self.position.variant).unwrap();
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{coordinate_move, flip_fen, random_games};
+    use crate::{Color, Game, Variant};
+
+    //check a flipped position, and that flipping twice gives back the position in random games
+    #[test]
+    fn check_flip_colors() {
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("e2e4")).unwrap();
+        let flipped = game1.flip_colors();
+        assert_eq!(flipped.get_fen(), "rnbqkbnr/pppp1ppp/8/4p3/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1");
+        assert_eq!(flipped.get_turn_color(), Color::White);
+        assert!(flipped.validate_position().is_ok());
+        for game1 in random_games(40, 0x6A09E667F3BCC908) {
+            let flipped = game1.flip_colors();
+            assert_eq!(flipped.get_fen(), flip_fen(&game1.get_fen()));
+            assert!(flipped.validate_position().is_ok(), "{}", flipped.get_fen());
+            assert_eq!(flipped.flip_colors().get_fen(), game1.get_fen());
+            assert_eq!(flipped.get_all_possible_moves().len(), game1.get_all_possible_moves().len());
+        }
+        // pockets and promoted pieces of Crazyhouse
+        let game1 = Game::from_fen("1Q~2k3/3n4/8/8/8/8/8/4K3[Rp] b - - 0 1").unwrap();
+        assert_eq!(game1.flip_colors().get_fen(), "4k3/8/8/8/8/8/3N4/1q~2K3[Pr] w - - 0 1");
+        assert_eq!(game1.flip_colors().variant(), Variant::Crazyhouse);
+    }
+
+    //check a mirrored position, which loses its castling rights
+    #[test]
+    fn check_mirror_horizontal() {
+        let mut game1 = Game::new();
+        game1.play_move(&coordinate_move("b2b4")).unwrap();
+        let mirrored = game1.mirror_horizontal();
+        assert_eq!(mirrored.get_fen(), "rnbkqbnr/pppppppp/8/8/6P1/8/PPPPPP1P/RNBKQBNR b - g3 0 1");
+        assert!(mirrored.validate_position().is_ok());
+        assert_eq!(mirrored.mirror_horizontal().get_fen(), "rnbqkbnr/pppppppp/8/8/1P6/8/P1PPPPPP/RNBQKBNR b - b3 0 1");
+        for game1 in random_games(40, 0xBB67AE8584CAA73B) {
+            let mirrored = game1.mirror_horizontal();
+            assert!(mirrored.validate_position().is_ok(), "{}", mirrored.get_fen());
+            assert_eq!(mirrored.get_game_state(), game1.get_game_state());
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{square_name, ChessError, Color, Game, GameState, PieceRole, Variant};
+
+impl Game {
+    /// Checks that the game is in a state that the rules of chess allow: one king of each
+    /// color, no pawns on the first or last rank, no check against the player who just moved,
+    /// an en passant square behind a pawn that could just have double stepped, pieces that
+    /// haven't moved on their starting squares, a piece id for every piece and a game state
+    /// that matches the position. Returns an error describing the first problem found.
+    pub fn validate_position(&self) -> Result<(), ChessError> {
+        let invalid = |message: String| -> Result<(), ChessError> {Err(ChessError::InvalidPosition(message))};
+        let position = &self.position;
+        // Antichess has no rules for the kings, and White has no king in Horde
+        for color in [Color::White, Color::Black].into_iter().filter(|&color| position.variant != Variant::Antichess && (position.variant != Variant::Horde || color == Color::Black)) {
+            let kings = (0..64).filter(|&index| position.piece_at(index).is_some_and(|piece| piece.role == PieceRole::King && piece.color == color)).count();
+            if kings != 1 {return invalid(format!("{:?} has {} kings", color, kings));}
+        }
+        let mut ids_seen: Vec<u8> = Vec::new();
+        for index in 0..64 {
+            let id = self.ids[index];
+            let piece = match position.piece_at(index) {
+                Some(piece) => piece,
+                None => {
+                    if id != 0 {return invalid(format!("empty square {} has id {}", square_name(index), id));}
+                    continue;
+                }
+            };
+            if id == 0 || ids_seen.contains(&id) {return invalid(format!("the piece on {} has no id of its own", square_name(index)));}
+            ids_seen.push(id);
+            let row = index / 8;
+            // the white pawns of Horde start on the first rank too
+            let horde_pawn = position.variant == Variant::Horde && piece.color == Color::White && row == 7;
+            if piece.role == PieceRole::Pawn && (row == 0 || row == 7) && !horde_pawn {return invalid(format!("pawn on {}", square_name(index)));}
+            // pieces that haven't moved have to be where they started
+            let starting_row = match (piece.role, piece.color) {
+                (PieceRole::Pawn, Color::White) => 6,
+                (PieceRole::Pawn, Color::Black) => 1,
+                (_, Color::White) => 7,
+                (_, Color::Black) => 0
+            };
+            let on_starting_square = row == starting_row && match piece.role {
+                // the king and the rooks can start on any file in Chess960
+                PieceRole::King | PieceRole::Rook if position.variant == Variant::Chess960 => true,
+                PieceRole::King => index % 8 == 4,
+                PieceRole::Rook => index % 8 == 0 || index % 8 == 7,
+                _ => true
+            };
+            if !piece.has_moved && !on_starting_square {return invalid(format!("the piece on {} hasn't moved", square_name(index)));}
+        }
+        if position.in_check(position.turn.opposite()) {
+            return invalid(format!("{:?} is in check but it's {:?}'s turn", position.turn.opposite(), position.turn));
+        }
+        if let Some(ep_square) = position.ep_square {
+            let ep_square = ep_square as usize;
+            // the pawn of the opponent passed the en passant square from the square behind it
+            let (pawn_square, start_square) = if position.turn == Color::White {(ep_square + 8, ep_square - 8)} else {(ep_square - 8, ep_square + 8)};
+            let pawn_in_place = position.piece_at(pawn_square).is_some_and(|piece| piece.role == PieceRole::Pawn && piece.color != position.turn);
+            if !pawn_in_place || position.board[ep_square] != 0 || position.board[start_square] != 0 {
+                return invalid(format!("en passant square {} without a pawn that double stepped", square_name(ep_square)));
+            }
+        }
+        // the moves are generated again, since the cached moves depend on the state
+        let has_moves = !position.legal_moves().is_empty();
+        let expected_state = match (has_moves, position.in_check(position.turn)) {
+            (true, false) => GameState::InProgress,
+            (true, true) => GameState::Check,
+            (false, true) => GameState::Checkmate,
+            (false, false) => GameState::Stalemate
+        };
+        if self.state != expected_state {return invalid(format!("the game state is {:?} instead of {:?}", self.state, expected_state));}
+        return Ok(());
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::random_games;
+    use crate::{ChessError, Game};
+
+    fn validate(fen: &str) -> Result<(), ChessError> {
+        return Game::from_fen(fen).unwrap().validate_position();
+    }
+
+    //check that positions reached by legal moves are valid
+    #[test]
+    fn check_validate_position() {
+        assert_eq!(Game::new().validate_position(), Ok(()));
+        for game1 in random_games(50, 0x853C49E6748FEA9B) {
+            assert_eq!(game1.validate_position(), Ok(()));
+        }
+        assert_eq!(validate("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3"), Ok(()));
+    }
+
+    //check that positions breaking the rules are found
+    #[test]
+    fn check_validate_position_invalid() {
+        let message = |fen: &str| -> String {
+            match validate(fen) {
+                Err(ChessError::InvalidPosition(message)) => message,
+                other => panic!("{:?}", other)
+            }
+        };
+        assert_eq!(message("4k3/8/8/8/8/8/8/8 w - - 0 1"), "White has 0 kings");
+        assert_eq!(message("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"), "White has 2 kings");
+        assert_eq!(message("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"), "pawn on a8");
+        assert_eq!(message("4k3/8/8/8/8/8/8/4K2r b - - 0 1"), "White is in check but it's Black's turn");
+        assert_eq!(message("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"), "en passant square e6 without a pawn that double stepped");
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{ChessError, Game, PieceRole};
+
+/// The rules a game is played by.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// Normal chess.
+    #[default]
+    Standard,
+    /// Fischer random chess, see Game::new_960. Castling is written as the king taking its own
+    /// rook, and the castling rights in FEN as the files of the rooks (Shredder-FEN), e.g. HAha.
+    Chess960,
+    /// Losing chess: taking is compulsory, there is no check and no castling, the king is an
+    /// ordinary piece that pawns can also promote to, and a player wins by losing all their
+    /// pieces or by being stalemated.
+    Antichess,
+    /// White has 36 pawns and no king against the normal pieces of Black. White wins by
+    /// checkmate and Black by taking every white piece. The white pawns on the first and second
+    /// rank can move two squares, but not en passant from the first rank.
+    Horde,
+    /// Taken pieces go into the pocket of the player who took them, with their color changed,
+    /// and instead of moving a player can drop a piece from their pocket on any empty square
+    /// (see Move::new_drop), but pawns not on the first or last rank. A promoted piece goes back
+    /// into the pocket as a pawn. In FEN the pockets come after the pieces, e.g. [QNp], and
+    /// promoted pieces are marked with a ~.
+    Crazyhouse,
+}
+
+/// The starting position of Horde.
+const HORDE_FEN: &str = "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+impl Game {
+    /// Creates a game of the variant from its usual starting position, which for Chess960 is
+    /// the normal starting position (see Game::new_960 for the others).
+    pub fn new_variant(variant: Variant) -> Game {
+        if variant == Variant::Horde {return Game::from_fen_variant(HORDE_FEN, variant).unwrap();}
+        let back_row = [PieceRole::Rook, PieceRole::Knight, PieceRole::Bishop, PieceRole::Queen, PieceRole::King, PieceRole::Bishop, PieceRole::Knight, PieceRole::Rook];
+        return Game::with_back_row(back_row, variant);
+    }
+
+    /// Creates a game of the variant from the given FEN string, or returns an error describing
+    /// what is wrong with the string. The castling rights are read as for Game::from_fen.
+    pub fn from_fen_variant(fen_string: &str, variant: Variant) -> Result<Game, ChessError> {
+        let mut game = Game::from_fen(fen_string)?;
+        game.position.variant = variant;
+        game.update_state();
+        return Ok(game);
+    }
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::Variant;
+    use crate::tests::coordinate_move;
+    use crate::{Color, Game, GameOutcome, GameState, PieceRole};
+
+    fn antichess(fen: &str) -> Game {
+        return Game::from_fen_variant(fen, Variant::Antichess).unwrap();
+    }
+
+    //check that only captures can be made when there is one, and that the king can be taken
+    #[test]
+    fn check_antichess_forced_capture() {
+        let mut game1 = Game::new_variant(Variant::Antichess);
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+        assert_eq!(game1.get_all_possible_moves().len(), 20);
+        game1.make_move("e2", "e4").unwrap();
+        game1.make_move("d7", "d5").unwrap();
+        // White has to take, with either piece that can
+        assert_eq!(game1.get_all_possible_moves(), vec![coordinate_move("e4d5")]);
+        assert!(game1.make_move("g1", "f3").is_none());
+        game1.make_move("e4", "d5").unwrap();
+        let mut captures = game1.get_all_possible_moves();
+        captures.sort_by_key(|mv| mv.to_string());
+        assert_eq!(captures, vec![coordinate_move("d8d5")]);
+        // the king walks into an attack and is taken, without check or checkmate
+        let game1 = antichess("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1");
+        assert_eq!(game1.get_game_state(), GameState::InProgress);
+        assert_eq!(game1.get_all_possible_moves(), vec![coordinate_move("e1d2")]);
+        let mut game1 = antichess("4k3/8/8/8/8/8/8/r3K3 b - - 0 1");
+        game1.make_move("a1", "e1").unwrap();
+        assert_eq!(game1.get_piece("e1").unwrap().color, Color::Black);
+    }
+
+    //check that a pawn can promote to a king, and that there is no castling
+    #[test]
+    fn check_antichess_promotion() {
+        let mut game1 = antichess("8/P7/8/8/8/8/8/R3K2k w - - 0 1");
+        let promotions: Vec<Option<PieceRole>> = game1.get_all_possible_moves().iter().filter(|mv| mv.from.to_string() == "a7").map(|mv| mv.promotion).collect();
+        assert_eq!(promotions.len(), 5);
+        assert!(game1.to_san(&coordinate_move("a7a8k")).is_some());
+        game1.make_move("a7", "a8k").unwrap();
+        assert_eq!(game1.get_piece("a8").unwrap().role, PieceRole::King);
+        assert_eq!(game1.get_fen(), "K7/8/8/8/8/8/8/R3K2k b - - 0 1");
+        assert!(!Game::from_fen_variant("8/8/8/8/8/8/8/R3K2k w Q - 0 1", Variant::Antichess).unwrap().is_legal_move(&coordinate_move("e1c1")));
+    }
+
+    //check that losing every piece or being stalemated wins
+    #[test]
+    fn check_antichess_end() {
+        // Black has to take the last white piece, which wins the game for White
+        let mut game1 = antichess("8/8/8/8/8/8/1p6/R7 b - - 0 1");
+        assert_eq!(game1.get_all_possible_moves().len(), 5);
+        assert_eq!(game1.make_move("b2", "a1q"), Some(GameState::Stalemate));
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        assert!(game1.make_move("a1", "a2").is_none());
+        // White has a pawn that can't move, so White is stalemated and wins
+        let game1 = antichess("8/8/8/8/8/p7/P7/8 w - - 0 1");
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+        assert_eq!(Game::from_fen("8/8/8/8/8/p7/P7/8 w - - 0 1").unwrap().outcome(), Some(GameOutcome::Draw));
+    }
+
+    //check the starting position of Horde and the double steps of the white pawns
+    #[test]
+    fn check_horde_start() {
+        let game1 = Game::new_variant(Variant::Horde);
+        assert_eq!(game1.get_fen(), "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1");
+        assert!(game1.validate_position().is_ok());
+        assert_eq!(game1.get_all_possible_moves().len(), 8);
+        // a pawn on the first rank double steps, without an en passant square
+        let mut game1 = Game::from_fen_variant("4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde).unwrap();
+        assert_eq!(game1.get_possible_moves("a1").unwrap(), vec!["a2", "a3"]);
+        game1.make_move("a1", "a3").unwrap();
+        assert_eq!(game1.get_fen(), "4k3/8/8/8/8/P7/8/8 b - - 0 1");
+        // and can still double step from the second rank after a single step
+        let mut game1 = Game::from_fen_variant("4k3/8/8/8/8/8/8/P7 w - - 0 1", Variant::Horde).unwrap();
+        game1.make_move("a1", "a2").unwrap();
+        game1.make_move("e8", "d8").unwrap();
+        assert_eq!(game1.get_possible_moves("a2").unwrap(), vec!["a3", "a4"]);
+        game1.make_move("a2", "a4").unwrap();
+        assert_eq!(game1.get_fen(), "3k4/8/8/8/P7/8/8/8 b - a3 0 2");
+    }
+
+    //check that Black wins by taking every white piece, and White by checkmate
+    #[test]
+    fn check_horde_end() {
+        let mut game1 = Game::from_fen_variant("4k3/8/8/8/8/8/8/P2r4 b - - 0 1", Variant::Horde).unwrap();
+        assert_eq!(game1.make_move("d1", "a1"), Some(GameState::Stalemate));
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::Black)));
+        // a blocked horde is only stalemated
+        let game1 = Game::from_fen_variant("4k3/8/8/8/8/p7/P7/8 w - - 0 1", Variant::Horde).unwrap();
+        assert_eq!(game1.outcome(), Some(GameOutcome::Draw));
+        let mut game1 = Game::from_fen_variant("4k3/R7/8/8/8/8/8/7R w - - 0 1", Variant::Horde).unwrap();
+        assert_eq!(game1.make_move("h1", "h8"), Some(GameState::Checkmate));
+        assert_eq!(game1.outcome(), Some(GameOutcome::Win(Color::White)));
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use crate::engine::{find_best_move, find_best_move_timed};
+use crate::{Game, GameState};
+
+// bestMove takes limits up to this as a depth in plies, and larger ones as milliseconds
+const MAX_DEPTH_LIMIT: u32 = 20;
+
+/// A Game for JavaScript, to play against the engine in a browser. Build it with
+/// `wasm-pack build --features wasm` in the bindings directory. Every method takes and returns strings and numbers,
+/// and throws an Error instead of panicking when it is given something it can't use.
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// A game from the start position.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmGame {
+        return WasmGame { game: Game::new() };
+    }
+
+    /// Sets up the position of the FEN string, or throws if it isn't valid and keeps the
+    /// position as it was.
+    #[wasm_bindgen(js_name = loadFen)]
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), JsError> {
+        self.game = Game::from_fen(fen).map_err(|error| JsError::new(&error.to_string()))?;
+        return Ok(());
+    }
+
+    /// Moves the piece on the square `from` (like "e2") to `to`, promoting a pawn to the
+    /// promotion ("q", "r", "b" or "n") if it reaches the last rank, and returns the game state
+    /// after the move. Throws if the move isn't legal.
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, from: &str, to: &str, promotion: Option<String>) -> Result<String, JsError> {
+        return self.try_make_move(from, to, promotion.as_deref()).map_err(|error| JsError::new(&error));
+    }
+
+    /// The squares the piece on the square can move to, as an array of strings like "e4".
+    /// Throws if there is no piece on the square.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self, square: &str) -> Result<Array, JsError> {
+        let targets = self.game.get_possible_moves(square).ok_or_else(|| JsError::new(&format!("no piece that can move on {}", square)))?;
+        return Ok(targets.iter().map(|target| JsValue::from_str(target)).collect());
+    }
+
+    /// The state of the game: "InProgress", "Check", "Checkmate" or "Stalemate".
+    #[wasm_bindgen(js_name = gameState)]
+    pub fn game_state(&self) -> String {
+        return state_name(self.game.get_game_state());
+    }
+
+    /// The position as a FEN string.
+    pub fn fen(&self) -> String {
+        return self.game.get_fen();
+    }
+
+    /// The move the engine would make, in coordinate notation like "e2e4" or "e7e8q", or
+    /// undefined if the game is over. A limit up to 20 is a depth in plies, and a larger one
+    /// the time to think in milliseconds.
+    #[wasm_bindgen(js_name = bestMove)]
+    pub fn best_move(&self, depth_or_millis: u32) -> Option<String> {
+        return self.find_move(depth_or_millis);
+    }
+}
+
+impl Default for WasmGame {
+    fn default() -> WasmGame {
+        WasmGame::new()
+    }
+}
+
+impl WasmGame {
+    fn try_make_move(&mut self, from: &str, to: &str, promotion: Option<&str>) -> Result<String, String> {
+        // make_move reads the promotion from the end of the target square
+        let target = format!("{}{}", to, promotion.unwrap_or(""));
+        return match self.game.make_move(from, &target) {
+            Some(state) => Ok(state_name(state)),
+            None => Err(format!("illegal move {}{}", from, target))
+        };
+    }
+
+    fn find_move(&self, depth_or_millis: u32) -> Option<String> {
+        let mv = if depth_or_millis <= MAX_DEPTH_LIMIT {
+            find_best_move(&self.game, depth_or_millis.max(1)).map(|(mv, _)| mv)
+        } else {
+            find_best_move_timed(&self.game, Duration::from_millis(depth_or_millis as u64)).best_move
+        };
+        return mv.map(|mv| mv.to_string());
+    }
+}
+
+fn state_name(state: GameState) -> String {
+    return format!("{:?}", state);
+}
+
+// --------------------------
+// ######### TESTS ##########
+// --------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::WasmGame;
+
+    //check that moves, promotions and the engine work without going through JavaScript
+    #[test]
+    fn check_wasm_game() {
+        let mut game1 = WasmGame::new();
+        assert_eq!(game1.try_make_move("e2", "e4", None), Ok("InProgress".to_string()));
+        assert!(game1.try_make_move("e2", "e4", None).is_err());
+        assert_eq!(game1.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        game1.game = crate::Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(game1.find_move(3), Some("a1a8".to_string()));
+        assert_eq!(game1.find_move(200), Some("a1a8".to_string()));
+        // a promotion needs a role
+        game1.game = crate::Game::from_fen("6k1/P4ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        assert!(game1.try_make_move("a7", "a8", None).is_err());
+        let mut knight = WasmGame { game: game1.game.clone() };
+        assert_eq!(knight.try_make_move("a7", "a8", Some("n")), Ok("InProgress".to_string()));
+        assert_eq!(game1.try_make_move("a7", "a8", Some("q")), Ok("Checkmate".to_string()));
+        assert_eq!(game1.find_move(3), None);
+    }
+}
@@ -0,0 +1,53 @@
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+
+use std::path::Path;
+use std::process::Command;
+
+// a target without the standard library, used when it is installed
+const NO_STD_TARGET: &str = "thumbv7em-none-eabihf";
+// the target of wasm-pack, used when it is installed
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Runs cargo build with the arguments, into its own target directory so it doesn't wait for
+/// the build of the tests.
+fn build(args: &[&str], target_dir: &str, target: Option<&str>) -> std::process::Output {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let mut command = Command::new(env!("CARGO"));
+    command.current_dir(manifest_dir).arg("build").args(args);
+    command.arg("--target-dir").arg(Path::new(manifest_dir).join("target").join(target_dir));
+    if let Some(target) = target {command.args(["--target", target]);}
+    return command.output().unwrap();
+}
+
+fn target_installed(target: &str) -> bool {
+    let output = Command::new("rustc").args(["--print", "target-libdir", "--target", target]).output();
+    return output.is_ok_and(|output| output.status.success() && Path::new(String::from_utf8_lossy(&output.stdout).trim()).exists());
+}
+
+//check that the board, the move generation and FEN build with only core and alloc
+#[test]
+fn check_no_std_build() {
+    // the library is only an rlib (the cdylib is in the bindings crate), which needs no panic
+    // handler or allocator, so it also builds for the host; but there std could still sneak in
+    // through a dependency, which only a target without std catches
+    let target = if target_installed(NO_STD_TARGET) {
+        Some(NO_STD_TARGET)
+    } else {
+        eprintln!("{} isn't installed, so the build is only checked for the host (rustup target add {})", NO_STD_TARGET, NO_STD_TARGET);
+        None
+    };
+    let output = build(&["--lib", "--no-default-features"], "no_std", target);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+//check that the JavaScript bindings build for the browser, with the clock they use there
+#[test]
+fn check_wasm_build() {
+    if !target_installed(WASM_TARGET) {
+        eprintln!("{} isn't installed, so the wasm build isn't checked (rustup target add {})", WASM_TARGET, WASM_TARGET);
+        return;
+    }
+    let output = build(&["--package", "eliassam_chess", "--features", "wasm"], "wasm", Some(WASM_TARGET));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}